@@ -0,0 +1,130 @@
+//! Derive macros for the [`grafite`](https://docs.rs/grafite) crate.
+//!
+//! This crate should not be used directly. Enable the `derive` feature of `grafite` instead, which
+//! re-exports the macros alongside the traits that they implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Index};
+
+/// Derives `grafite::RangeKey` and `grafite::ToOrderedU64` for a struct or a fieldless enum.
+///
+/// A struct is encoded by packing the encodings of its fields, with the first field in the most
+/// significant bits, so keys are ordered like a derived `Ord` implementation would order them.
+/// Every field must implement `RangeKey`, and the fields may take up at most 128 bits in total.
+///
+/// An enum is encoded as the index of its variant in declaration order, using as few bits as
+/// possible, which matches a derived `Ord` implementation unless the variants have explicit
+/// discriminants that are out of order. Only enums without fields are supported.
+#[proc_macro_derive(RangeKey)]
+pub fn derive_range_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Generates the `RangeKey` and `ToOrderedU64` implementations for the input type.
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (bits, encode) = match &input.data {
+        Data::Struct(data) => {
+            let types: Vec<_> = data.fields.iter().map(|f| f.ty.clone()).collect();
+
+            let where_clause = input.generics.make_where_clause();
+            for ty in &types {
+                where_clause
+                    .predicates
+                    .push(parse_quote!(#ty: ::grafite::RangeKey));
+            }
+
+            let members: Vec<TokenStream2> = match &data.fields {
+                Fields::Named(fields) => fields
+                    .named
+                    .iter()
+                    .map(|f| {
+                        let ident = f.ident.as_ref().expect("named fields have identifiers");
+                        quote!(#ident)
+                    })
+                    .collect(),
+                Fields::Unnamed(fields) => (0..fields.unnamed.len())
+                    .map(|i| {
+                        let index = Index::from(i);
+                        quote!(#index)
+                    })
+                    .collect(),
+                Fields::Unit => Vec::new(),
+            };
+
+            let bits = quote! {{
+                let bits = 0u32 #(+ <#types as ::grafite::RangeKey>::BITS)*;
+                assert!(bits <= 128, "a `RangeKey` can take up at most 128 bits");
+                bits
+            }};
+            let encode = quote! {
+                let key = 0u128;
+                #(
+                    let key = key
+                        .checked_shl(<#types as ::grafite::RangeKey>::BITS)
+                        .unwrap_or(0)
+                        | ::grafite::RangeKey::to_range_key(&self.#members);
+                )*
+                key
+            };
+
+            (bits, encode)
+        }
+        Data::Enum(data) => {
+            if let Some(variant) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+                return Err(Error::new_spanned(
+                    variant,
+                    "`RangeKey` can only be derived for enums without fields",
+                ));
+            }
+
+            let count = data.variants.len() as u128;
+            let bits = u128::BITS - count.saturating_sub(1).leading_zeros();
+            let bits = quote!(#bits);
+
+            let variants = data.variants.iter().map(|v| &v.ident);
+            let indices = (0..count).map(|i| quote!(#i));
+            let encode = if count == 0 {
+                quote!(match *self {})
+            } else {
+                quote! {
+                    match self {
+                        #(Self::#variants => #indices,)*
+                    }
+                }
+            };
+
+            (bits, encode)
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "`RangeKey` cannot be derived for unions",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::grafite::RangeKey for #name #ty_generics #where_clause {
+            const BITS: u32 = #bits;
+
+            fn to_range_key(&self) -> u128 {
+                #encode
+            }
+        }
+
+        impl #impl_generics ::grafite::ToOrderedU64 for #name #ty_generics #where_clause {
+            fn to_ordered_u64(&self) -> u64 {
+                ::grafite::RangeKey::to_range_key_u64(self)
+            }
+        }
+    })
+}
@@ -0,0 +1,370 @@
+//! A `no_std` and allocation-free reader for range filters serialized by the
+//! [`grafite`](https://docs.rs/grafite) crate.
+//!
+//! This crate only answers queries. It reads the binary format written by `RangeFilter::write_to`
+//! directly from a byte slice, such as a filter that is embedded in the flash memory of a
+//! microcontroller, without copying or decoding its hash values. Filters are built and written
+//! with the `grafite` crate itself, which requires `std`.
+//!
+//! ```
+//! use grafite::{OrderPreservingHasher, RangeFilter};
+//! use grafite_query::FilterRef;
+//!
+//! let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+//! let rf = RangeFilter::new([1, 2, 3, 7].into_iter(), hasher);
+//! let mut bytes = Vec::new();
+//! rf.write_to(&mut bytes).unwrap();
+//!
+//! let filter = FilterRef::decode(&bytes).unwrap();
+//! assert!(filter.query(2..5));
+//! assert_eq!(filter.query(10..20), rf.query(10..20));
+//! ```
+//!
+//! Filters with a keyed hash function cannot be read, since their constants are derived from a
+//! secret key, which requires the `grafite` crate.
+
+#![no_std]
+
+use core::fmt;
+use core::ops::{Bound, RangeBounds};
+
+/// The magic bytes at the start of every serialized filter.
+const MAGIC: [u8; 4] = *b"GRAF";
+
+/// The version of the format that this crate reads.
+const FORMAT_VERSION: u32 = 1;
+
+/// Set if the serialized filter has a metadata block.
+const FLAG_METADATA: u32 = 1;
+
+/// Set if the serialized filter has the seed of its hash parameters.
+const FLAG_SEED: u32 = 2;
+
+/// Set if the serialized filter has the build parameters of its hash function.
+const FLAG_BUILD_PARAMS: u32 = 4;
+
+/// Set if the filter was encoded with a dense bit vector, which does not change how its hash
+/// values are stored.
+const FLAG_DENSE: u32 = 8;
+
+/// Set if the hash values are stored as 32-bit integers.
+const FLAG_COMPACT: u32 = 16;
+
+/// Set if the hash function is keyed.
+const FLAG_KEYED: u32 = 32;
+
+/// A range filter that is queried directly from its serialized bytes.
+///
+/// Queries have the same semantics as `RangeFilter::query`, and each one costs up to two binary
+/// searches over the stored hash values.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterRef<'a> {
+    /// The hash function of the filter.
+    hasher: Hasher,
+    /// The stored hash values, as little-endian integers of `width` bytes each.
+    hashes: &'a [u8],
+    /// The number of bytes that every hash value is stored in.
+    width: usize,
+}
+
+impl<'a> FilterRef<'a> {
+    /// Reads a filter that was written with `RangeFilter::write_to` from the given bytes, which
+    /// may be followed by other data.
+    ///
+    /// The checksum and every stored hash value are verified, which takes time linear in the size
+    /// of the filter. If the bytes are not a valid filter, this function will return a
+    /// [`DecodeError`].
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(DecodeError::InvalidMagic);
+        }
+        let version = reader.read_u32()?;
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        let known_flags =
+            FLAG_METADATA | FLAG_SEED | FLAG_BUILD_PARAMS | FLAG_DENSE | FLAG_COMPACT | FLAG_KEYED;
+        let flags = reader.read_u32()?;
+        if flags & !known_flags != 0 {
+            return Err(DecodeError::Corrupt);
+        }
+
+        // Skip the optional fields, which are not needed to answer queries.
+        let mut skipped = 0;
+        for (flag, len) in [(FLAG_METADATA, 32), (FLAG_SEED, 8), (FLAG_BUILD_PARAMS, 16)] {
+            if flags & flag != 0 {
+                skipped += len;
+            }
+        }
+        reader.take(skipped)?;
+
+        let [c1, c2, p, r] = [
+            reader.read_u64()?,
+            reader.read_u64()?,
+            reader.read_u64()?,
+            reader.read_u64()?,
+        ];
+        let len = reader.read_u64()?;
+
+        let width = if flags & FLAG_COMPACT != 0 { 4 } else { 8 };
+        let hashes_len = usize::try_from(len)
+            .ok()
+            .and_then(|len| len.checked_mul(width))
+            .ok_or(DecodeError::Truncated)?;
+        let hashes = reader.take(hashes_len)?;
+
+        let actual = crc32(&bytes[8..reader.pos]);
+        let expected = reader.read_u32()?;
+        if expected != actual {
+            return Err(DecodeError::ChecksumMismatch { expected, actual });
+        }
+
+        // The constants of a keyed hash function are not stored, which is only detected after the
+        // checksum, so that corrupted flags are not reported as a keyed filter.
+        if flags & FLAG_KEYED != 0 {
+            return Err(DecodeError::Keyed);
+        }
+        if r == 0 || p <= r || c1 == 0 || c1 >= p || c2 >= p {
+            return Err(DecodeError::Corrupt);
+        }
+
+        let filter = Self {
+            hasher: Hasher { c1, c2, p, r },
+            hashes,
+            width,
+        };
+
+        // The searches rely on the hash values being strictly increasing.
+        let mut prev = None;
+        for i in 0..filter.len() {
+            let hash = filter.hash_at(i);
+            if hash >= r || prev.is_some_and(|prev| prev >= hash) {
+                return Err(DecodeError::Corrupt);
+            }
+            prev = Some(hash);
+        }
+        if prev.is_none() {
+            return Err(DecodeError::Corrupt);
+        }
+
+        Ok(filter)
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// This has the same semantics as `RangeFilter::query`: it never returns a false negative, and
+    /// empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        // See `OrderPreservingHasher::hash` for why a range is split at block boundaries.
+        let r = self.hasher.r;
+        match end / r - start / r {
+            0 => self.query_window(self.hasher.hash(start), self.hasher.hash(end)),
+            1 => {
+                // The first key of the second block, which is greater than `start`.
+                let boundary = end / r * r;
+                self.query_window(self.hasher.hash(start), self.hasher.hash(boundary - 1))
+                    || self.query_window(self.hasher.hash(boundary), self.hasher.hash(end))
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks if the given key might be among the original input set.
+    pub fn contains(&self, x: u64) -> bool {
+        self.query(x..=x)
+    }
+
+    /// Returns the number of distinct hash values stored in the filter.
+    pub fn len(&self) -> usize {
+        self.hashes.len() / self.width
+    }
+
+    /// Returns `true` if the filter stores no hash values, which a decoded filter never does.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Returns the size `r` of the reduced universe that keys are hashed into.
+    pub fn reduced_universe(&self) -> u64 {
+        self.hasher.r
+    }
+
+    /// Checks if there are any stored hash values within a hashed window.
+    fn query_window(&self, start_hash: u64, end_hash: u64) -> bool {
+        // If the start hash is greater than the end hash, then the window has wrapped around the
+        // reduced universe, so only the smallest and largest hash values need to be checked.
+        if start_hash > end_hash {
+            return self.hash_at(0) <= end_hash || self.hash_at(self.len() - 1) >= start_hash;
+        }
+
+        match self.rank(end_hash) {
+            0 => false,
+            rank => self.hash_at(rank - 1) >= start_hash,
+        }
+    }
+
+    /// Returns the number of stored hash values less than or equal to `hash`.
+    fn rank(&self, hash: u64) -> usize {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.hash_at(mid) <= hash {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Returns the `i`-th stored hash value.
+    fn hash_at(&self, i: usize) -> u64 {
+        let bytes = &self.hashes[i * self.width..(i + 1) * self.width];
+        match *bytes {
+            [a, b, c, d] => u32::from_le_bytes([a, b, c, d]) as u64,
+            [a, b, c, d, e, f, g, h] => u64::from_le_bytes([a, b, c, d, e, f, g, h]),
+            _ => unreachable!("hash values are 4 or 8 bytes"),
+        }
+    }
+}
+
+/// The order-preserving hash function of a filter, which hashes keys exactly like
+/// `OrderPreservingHasher::hash` without its specialized reductions.
+#[derive(Debug, Clone, Copy)]
+struct Hasher {
+    c1: u64,
+    c2: u64,
+    p: u64,
+    r: u64,
+}
+
+impl Hasher {
+    /// Hashes a key into the reduced universe, shifting its offset within its block of `r` keys by
+    /// a hash of the block.
+    fn hash(&self, x: u64) -> u64 {
+        let (block, y) = (x / self.r, x % self.r);
+        // Since `c1, c2 < p < 2^64`, this cannot overflow 128 bits.
+        let q = (self.c1 as u128 * block as u128 + self.c2 as u128) % self.p as u128;
+        let q = (q % self.r as u128) as u64;
+
+        // Compute `(q + y) mod r` without overflowing, since `q, y < r`.
+        match q.checked_add(y) {
+            Some(sum) if sum < self.r => sum,
+            _ => q.wrapping_add(y).wrapping_sub(self.r),
+        }
+    }
+}
+
+/// An error type representing why a serialized filter could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// If the input does not start with the magic bytes `GRAF`.
+    InvalidMagic,
+    /// If the format version is not supported. Stores the version that was read.
+    UnsupportedVersion(u32),
+    /// If the input ends before the end of the filter.
+    Truncated,
+    /// If the stored checksum does not match the checksum of the data that was read.
+    ChecksumMismatch {
+        /// The checksum stored in the input.
+        expected: u32,
+        /// The checksum computed over the data that was read.
+        actual: u32,
+    },
+    /// If the filter has a keyed hash function, whose constants are not stored.
+    Keyed,
+    /// If the checksum is valid but the filter is not, e.g. if the hash values are not strictly
+    /// increasing or are not less than the reduced universe size.
+    Corrupt,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "invalid magic bytes"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {version}")
+            }
+            Self::Truncated => write!(f, "the input ends before the end of the filter"),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {expected:#010x}, got {actual:#010x}"
+            ),
+            Self::Keyed => write!(f, "filters with a keyed hash function cannot be read"),
+            Self::Corrupt => write!(f, "the filter is corrupt"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// A cursor over the bytes of a serialized filter.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Returns the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self.pos.checked_add(len).ok_or(DecodeError::Truncated)?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(DecodeError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        let (low, high) = (self.read_u32()?, self.read_u32()?);
+        Ok(((high as u64) << 32) | low as u64)
+    }
+}
+
+/// Computes the CRC32 (IEEE) checksum of the given bytes, bit by bit, so that no lookup table is
+/// needed.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Returns the inclusive endpoints of a range of keys, or `None` if the range is empty.
+fn range_endpoints<R>(range: &R) -> Option<(u64, u64)>
+where
+    R: RangeBounds<u64>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&s) => s.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&e) => e.checked_sub(1)?,
+        Bound::Unbounded => u64::MAX,
+    };
+
+    (start <= end).then_some((start, end))
+}
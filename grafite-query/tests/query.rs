@@ -0,0 +1,110 @@
+use grafite::{FilterMetadata, OrderPreservingHasher, RangeFilter};
+use grafite_query::{DecodeError, FilterRef};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+fn serialize(rf: &RangeFilter) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn test_queries_match() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+
+    let compact = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let wide = OrderPreservingHasher::new_with_reduced_rng(u64::MAX / 2, &mut rng);
+    let pow2 = OrderPreservingHasher::new_with_reduced_rng(1 << 40, &mut rng);
+    let mersenne =
+        OrderPreservingHasher::from_parts(12_345, 678, (1 << 61) - 1, 1_000_003).unwrap();
+
+    for hasher in [compact, wide, pow2, mersenne] {
+        let rf = RangeFilter::new(keys.iter().copied(), hasher);
+        let bytes = serialize(&rf);
+        let filter = FilterRef::decode(&bytes).unwrap();
+        assert_eq!(filter.len(), rf.iter_hashes().count());
+        assert_eq!(filter.reduced_universe(), hasher.reduced_universe());
+
+        for &key in &keys {
+            assert!(filter.contains(key));
+            assert!(filter.query(key.saturating_sub(20)..=key));
+        }
+        for _ in 0..10_000 {
+            let start = rng.gen::<u64>();
+            let end = start.saturating_add(rng.gen_range(0..100));
+            assert_eq!(filter.query(start..=end), rf.query(start..=end));
+        }
+        assert!(!filter.query(5..5));
+        assert_eq!(filter.query(..), rf.query(..));
+    }
+}
+
+#[test]
+fn test_queries_across_blocks() {
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    // The optional fields of the header are skipped.
+    let mut bytes = Vec::new();
+    rf.write_to_with_metadata(&mut bytes, &FilterMetadata::new(7))
+        .unwrap();
+    let filter = FilterRef::decode(&bytes).unwrap();
+
+    for start in (r - 20..51 * r).step_by(13) {
+        for end in [start, start + 15, start + r, start + 3 * r] {
+            assert_eq!(filter.query(start..=end), rf.query(start..=end));
+        }
+    }
+    assert!(filter.query(r - 3..=r + 3));
+}
+
+#[test]
+fn test_decode_errors() {
+    let hasher = OrderPreservingHasher::new_seeded(4, 0.01, 20, 7).unwrap();
+    let rf = RangeFilter::new([1, 2, 3, 7].into_iter(), hasher);
+    let bytes = serialize(&rf);
+
+    // Trailing bytes are ignored.
+    let mut padded = bytes.clone();
+    padded.extend_from_slice(&[0; 16]);
+    assert!(FilterRef::decode(&padded).unwrap().query(2..5));
+
+    assert_eq!(
+        FilterRef::decode(&bytes[..bytes.len() - 1]).unwrap_err(),
+        DecodeError::Truncated
+    );
+
+    let mut corrupted = bytes.clone();
+    corrupted[0] = b'X';
+    assert_eq!(
+        FilterRef::decode(&corrupted).unwrap_err(),
+        DecodeError::InvalidMagic
+    );
+
+    let mut corrupted = bytes.clone();
+    corrupted[4] = 2;
+    assert_eq!(
+        FilterRef::decode(&corrupted).unwrap_err(),
+        DecodeError::UnsupportedVersion(2)
+    );
+
+    let mut corrupted = bytes.clone();
+    let last_hash = bytes.len() - 5;
+    corrupted[last_hash] ^= 1;
+    assert!(matches!(
+        FilterRef::decode(&corrupted).unwrap_err(),
+        DecodeError::ChecksumMismatch { .. }
+    ));
+
+    let keyed = OrderPreservingHasher::new_keyed(4, 0.01, 20, &[3; 32]).unwrap();
+    let rf = RangeFilter::new([1, 2, 3, 7].into_iter(), keyed);
+    assert_eq!(
+        FilterRef::decode(&serialize(&rf)).unwrap_err(),
+        DecodeError::Keyed
+    );
+}
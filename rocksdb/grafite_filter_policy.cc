@@ -0,0 +1,181 @@
+#include "grafite_filter_policy.h"
+
+#include <algorithm>
+#include <cmath>
+#include <cstring>
+#include <memory>
+#include <utility>
+#include <vector>
+
+#include "grafite.h"
+#include "table/block_based/filter_policy_internal.h"
+
+namespace grafite {
+
+namespace {
+
+// Returns `true` if a call into the C ABI succeeded, which is reported with the status `Ok`, whose
+// value is `0`.
+bool Succeeded(GrafiteStatus status) { return static_cast<int>(status) == 0; }
+
+// Collects the extracted keys of a filter block and builds a filter over them once it is finished.
+class GrafiteFilterBitsBuilder : public rocksdb::FilterBitsBuilder {
+ public:
+  GrafiteFilterBitsBuilder(double epsilon, uint64_t max_interval, uint64_t seed,
+                           const KeyExtractor& extractor)
+      : epsilon_(epsilon), max_interval_(max_interval), seed_(seed), extractor_(extractor) {}
+
+  void AddKey(const rocksdb::Slice& key) override {
+    uint64_t x = extractor_(key);
+    // Consecutive keys often share their extracted key, for example with a short prefix.
+    if (keys_.empty() || keys_.back() != x) {
+      keys_.push_back(x);
+    }
+  }
+
+  void AddKeyAndAlt(const rocksdb::Slice& key, const rocksdb::Slice& alt) override {
+    AddKey(key);
+    AddKey(alt);
+  }
+
+  size_t EstimateEntriesAdded() override { return keys_.size(); }
+
+  rocksdb::Slice Finish(std::unique_ptr<const char[]>* buf) override {
+    rocksdb::Status status;
+    return Finish(buf, &status);
+  }
+
+  // Builds the filter and serializes it into `buf`. A block without keys gets an empty filter,
+  // which matches every key.
+  rocksdb::Slice Finish(std::unique_ptr<const char[]>* buf, rocksdb::Status* status) override {
+    std::vector<uint64_t> keys;
+    keys.swap(keys_);
+    buf->reset();
+    if (keys.empty()) {
+      return rocksdb::Slice();
+    }
+
+    GrafiteFilter* filter = nullptr;
+    if (!Succeeded(grafite_filter_build(keys.data(), keys.size(), epsilon_, max_interval_, seed_,
+                                        &filter))) {
+      *status = rocksdb::Status::InvalidArgument("the Grafite filter could not be built");
+      return rocksdb::Slice();
+    }
+
+    // Querying the size with an empty buffer always fails, but writes the required size.
+    size_t len = 0;
+    grafite_filter_serialize(filter, nullptr, &len);
+    std::unique_ptr<char[]> bytes(new char[len]);
+    bool serialized = len > 0 && Succeeded(grafite_filter_serialize(
+                                     filter, reinterpret_cast<uint8_t*>(bytes.get()), &len));
+    grafite_filter_free(filter);
+
+    if (!serialized) {
+      *status = rocksdb::Status::Corruption("the Grafite filter could not be serialized");
+      return rocksdb::Slice();
+    }
+
+    rocksdb::Slice contents(bytes.get(), len);
+    buf->reset(bytes.release());
+    return contents;
+  }
+
+  size_t ApproximateNumEntries(size_t bytes) override {
+    // The Elias-Fano encoding takes about `2 + log2(max_interval / epsilon)` bits per key, and
+    // the serialized header and select samples take a little more.
+    double bits_per_key = 2.5 + std::log2(static_cast<double>(max_interval_) / epsilon_);
+    return static_cast<size_t>(static_cast<double>(bytes) * 8.0 / std::max(bits_per_key, 1.0));
+  }
+
+ private:
+  double epsilon_;
+  uint64_t max_interval_;
+  uint64_t seed_;
+  KeyExtractor extractor_;
+  std::vector<uint64_t> keys_;
+};
+
+// Looks keys up in a deserialized filter.
+class GrafiteFilterBitsReader : public rocksdb::FilterBitsReader {
+ public:
+  GrafiteFilterBitsReader(GrafiteFilter* filter, const KeyExtractor& extractor)
+      : filter_(filter), extractor_(extractor) {}
+
+  ~GrafiteFilterBitsReader() override { grafite_filter_free(filter_); }
+
+  GrafiteFilterBitsReader(const GrafiteFilterBitsReader&) = delete;
+  GrafiteFilterBitsReader& operator=(const GrafiteFilterBitsReader&) = delete;
+
+  bool MayMatch(const rocksdb::Slice& entry) override {
+    bool found = true;
+    // A failed lookup is answered conservatively.
+    if (!Succeeded(grafite_filter_contains(filter_, extractor_(entry), &found))) {
+      return true;
+    }
+    return found;
+  }
+
+  void MayMatch(int num_keys, rocksdb::Slice** keys, bool* may_match) override {
+    for (int i = 0; i < num_keys; ++i) {
+      may_match[i] = MayMatch(*keys[i]);
+    }
+  }
+
+ private:
+  GrafiteFilter* filter_;
+  KeyExtractor extractor_;
+};
+
+// A reader for a filter block that is empty or cannot be read, which matches every key.
+class AlwaysMatchBitsReader : public rocksdb::FilterBitsReader {
+ public:
+  bool MayMatch(const rocksdb::Slice& /* entry */) override { return true; }
+
+  void MayMatch(int num_keys, rocksdb::Slice** /* keys */, bool* may_match) override {
+    std::fill(may_match, may_match + num_keys, true);
+  }
+};
+
+}  // namespace
+
+uint64_t BigEndianPrefix(const rocksdb::Slice& key) {
+  uint8_t bytes[8] = {0};
+  std::memcpy(bytes, key.data(), std::min<size_t>(key.size(), sizeof(bytes)));
+
+  uint64_t x = 0;
+  for (uint8_t byte : bytes) {
+    x = (x << 8) | byte;
+  }
+  return x;
+}
+
+GrafiteFilterPolicy::GrafiteFilterPolicy(double epsilon, uint64_t max_interval, uint64_t seed,
+                                         KeyExtractor extractor)
+    : epsilon_(epsilon),
+      max_interval_(max_interval),
+      seed_(seed),
+      extractor_(std::move(extractor)) {}
+
+const char* GrafiteFilterPolicy::Name() const { return "grafite.GrafiteFilterPolicy"; }
+
+// Filter blocks written with any parameters, and by any version of this policy, can be read by
+// every other one.
+const char* GrafiteFilterPolicy::CompatibilityName() const { return Name(); }
+
+rocksdb::FilterBitsBuilder* GrafiteFilterPolicy::GetBuilderWithContext(
+    const rocksdb::FilterBuildingContext& /* context */) const {
+  return new GrafiteFilterBitsBuilder(epsilon_, max_interval_, seed_, extractor_);
+}
+
+rocksdb::FilterBitsReader* GrafiteFilterPolicy::GetFilterBitsReader(
+    const rocksdb::Slice& contents) const {
+  GrafiteFilter* filter = nullptr;
+  if (contents.empty() ||
+      !Succeeded(grafite_filter_deserialize(reinterpret_cast<const uint8_t*>(contents.data()),
+                                            contents.size(), &filter))) {
+    return new AlwaysMatchBitsReader();
+  }
+  return new GrafiteFilterBitsReader(filter, extractor_);
+}
+
+}  // namespace grafite
@@ -0,0 +1,70 @@
+// A RocksDB `FilterPolicy` that stores a Grafite range filter in every filter block of a table
+// file, in place of a Bloom or Ribbon filter.
+//
+// The policy is built on the C ABI of the `ffi` feature of the `grafite` crate. The `grafite.h`
+// header is generated with `cbindgen --config cbindgen.toml --output grafite.h` at the root of the
+// repository, and the library is built for linking with
+// `cargo rustc --release --features ffi --crate-type staticlib`.
+//
+// Custom filter policies implement the `FilterBitsBuilder` and `FilterBitsReader` interfaces,
+// which RocksDB declares in an internal header, so this file must be compiled with the root of the
+// RocksDB source tree on the include path.
+//
+// Every filter block holds a filter serialized in the format of `RangeFilter::write_to`, so it can
+// also be read back outside of RocksDB with `grafite_filter_deserialize`, for example to check a
+// range before scanning a table file.
+
+#ifndef GRAFITE_FILTER_POLICY_H
+#define GRAFITE_FILTER_POLICY_H
+
+#include <cstdint>
+#include <functional>
+
+#include "rocksdb/filter_policy.h"
+#include "rocksdb/slice.h"
+
+namespace grafite {
+
+// Maps a RocksDB key, or a prefix of one if the table filters prefixes, onto the `u64` keys that
+// the filter is built over.
+//
+// The mapping must be deterministic. For the filter to be range-aware, it must also be monotone
+// with respect to the comparator of the table, so that keys which are close in the table are close
+// as integers.
+using KeyExtractor = std::function<uint64_t(const rocksdb::Slice&)>;
+
+// Maps a key onto its first 8 bytes read as a big-endian integer, padding shorter keys with zero
+// bytes. This is monotone for the default bytewise comparator.
+uint64_t BigEndianPrefix(const rocksdb::Slice& key);
+
+// A `FilterPolicy` that builds a Grafite range filter over the keys of every filter block.
+//
+// The hash function of every filter is derived deterministically from `seed`, so that table files
+// written by different processes are identical. Keys are looked up with `grafite_filter_contains`,
+// and a filter block that cannot be read matches every key, so a corrupt or foreign block never
+// causes a false negative.
+class GrafiteFilterPolicy : public rocksdb::FilterPolicy {
+ public:
+  // Creates a policy whose filters have the false positive rate `epsilon` for ranges of at most
+  // `max_interval` keys.
+  GrafiteFilterPolicy(double epsilon, uint64_t max_interval, uint64_t seed = 0,
+                      KeyExtractor extractor = BigEndianPrefix);
+
+  const char* Name() const override;
+  const char* CompatibilityName() const override;
+
+  rocksdb::FilterBitsBuilder* GetBuilderWithContext(
+      const rocksdb::FilterBuildingContext& context) const override;
+  rocksdb::FilterBitsReader* GetFilterBitsReader(
+      const rocksdb::Slice& contents) const override;
+
+ private:
+  double epsilon_;
+  uint64_t max_interval_;
+  uint64_t seed_;
+  KeyExtractor extractor_;
+};
+
+}  // namespace grafite
+
+#endif  // GRAFITE_FILTER_POLICY_H
@@ -0,0 +1,108 @@
+//! Support for zero-copy archiving of a [`RangeFilter`](crate::RangeFilter) with [`rkyv`].
+//!
+//! The backends do not implement [`rkyv::Archive`] themselves, so the filter is archived as the
+//! sorted sequence of hash values that its backend represents. An [`ArchivedRangeFilter`] can be
+//! queried directly from the archived bytes (for example, from a memory-mapped file) without
+//! deserializing it first, and deserializing it rebuilds the backend from the stored hash values.
+
+use rkyv::rancor::Fallible;
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::with::{ArchiveWith, DeserializeWith, SerializeWith};
+use rkyv::{Archived, Place};
+use std::ops::RangeBounds;
+
+use crate::filter::{range_endpoints, ArchivedRangeFilter};
+use crate::hash::HashedRange;
+use crate::RangeFilterBackend;
+
+/// An [`ArchiveWith`] wrapper that archives a [`RangeFilterBackend`] as the slice of hash values it
+/// encodes.
+pub struct AsHashes;
+
+impl<B: RangeFilterBackend> ArchiveWith<B> for AsHashes {
+    type Archived = ArchivedVec<Archived<u64>>;
+    type Resolver = VecResolver;
+
+    fn resolve_with(field: &B, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        ArchivedVec::resolve_from_len(field.len(), resolver, out);
+    }
+}
+
+impl<B, S> SerializeWith<B, S> for AsHashes
+where
+    B: RangeFilterBackend,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize_with(field: &B, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        let hashes: Vec<u64> = field.iter().collect();
+        ArchivedVec::serialize_from_slice(&hashes, serializer)
+    }
+}
+
+impl<B, D> DeserializeWith<ArchivedVec<Archived<u64>>, B, D> for AsHashes
+where
+    B: RangeFilterBackend,
+    D: Fallible + ?Sized,
+{
+    fn deserialize_with(
+        field: &ArchivedVec<Archived<u64>>,
+        _deserializer: &mut D,
+    ) -> Result<B, D::Error> {
+        let hashes: Vec<u64> = field.iter().map(|h| h.to_native()).collect();
+        Ok(B::from_sorted(&hashes))
+    }
+}
+
+impl<B: RangeFilterBackend> ArchivedRangeFilter<B> {
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// This has the same semantics as [`RangeFilter::query`](crate::RangeFilter::query), but runs
+    /// directly on the archived hash values with a binary search instead of an Elias-Fano
+    /// predecessor query.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        let hashes = self.ef.as_slice();
+        let (Some(min_hash), Some(max_hash)) = (hashes.first(), hashes.last()) else {
+            return false;
+        };
+        let (min_hash, max_hash) = (min_hash.to_native(), max_hash.to_native());
+
+        let query_window = |start_hash: u64, end_hash: u64| {
+            // See `RangeFilter::query_prehashed` for an explanation of the wrapped case.
+            if start_hash > end_hash {
+                return min_hash <= end_hash || max_hash >= start_hash;
+            }
+
+            // The number of hashes that are less than or equal to the end hash.
+            match hashes.partition_point(|h| h.to_native() <= end_hash) {
+                0 => false,
+                i => hashes[i - 1].to_native() >= start_hash,
+            }
+        };
+
+        match self.hasher.to_hasher().hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => query_window(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                query_window(start1, end1) || query_window(start2, end2)
+            }
+            HashedRange::All => true,
+        }
+    }
+
+    /// Returns the number of distinct hash values stored in the archived filter.
+    pub fn len(&self) -> usize {
+        self.ef.len()
+    }
+
+    /// Returns `true` if the archived filter stores no hash values.
+    pub fn is_empty(&self) -> bool {
+        self.ef.is_empty()
+    }
+}
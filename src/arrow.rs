@@ -0,0 +1,51 @@
+//! Vectorized queries over Apache Arrow arrays, for engines that probe a filter with a whole batch
+//! of range predicates at once.
+
+use arrow_array::{Array, BooleanArray, UInt64Array};
+use arrow_buffer::{BooleanBuffer, NullBuffer};
+use arrow_schema::ArrowError;
+
+use crate::{RangeFilter, RangeFilterBackend};
+
+impl<B: RangeFilterBackend> RangeFilter<B> {
+    /// Checks a batch of inclusive ranges `starts[i]..=ends[i]`, returning whether each range may
+    /// contain an element.
+    ///
+    /// The result is null wherever either endpoint is null. If the arrays have different lengths,
+    /// this function will return an [`ArrowError::InvalidArgumentError`].
+    ///
+    /// ```
+    /// use arrow_array::{BooleanArray, UInt64Array};
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new(3, 0.01, 16).unwrap();
+    /// let rf = RangeFilter::new([10, 20, 30].into_iter(), hasher);
+    ///
+    /// let starts = UInt64Array::from(vec![Some(8), None, Some(29)]);
+    /// let ends = UInt64Array::from(vec![Some(12), Some(5), Some(31)]);
+    /// let results = rf.query_arrow(&starts, &ends).unwrap();
+    ///
+    /// assert_eq!(results, BooleanArray::from(vec![Some(true), None, Some(true)]));
+    /// ```
+    pub fn query_arrow(
+        &self,
+        starts: &UInt64Array,
+        ends: &UInt64Array,
+    ) -> Result<BooleanArray, ArrowError> {
+        if starts.len() != ends.len() {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "range starts and ends must have the same length, got {} and {}",
+                starts.len(),
+                ends.len()
+            )));
+        }
+
+        let (starts_values, ends_values) = (starts.values(), ends.values());
+        let values = BooleanBuffer::collect_bool(starts.len(), |i| {
+            self.query(starts_values[i]..=ends_values[i])
+        });
+        let nulls = NullBuffer::union(starts.nulls(), ends.nulls());
+
+        Ok(BooleanArray::new(values, nulls))
+    }
+}
@@ -0,0 +1,613 @@
+//! This module contains the [`RangeFilterBackend`] trait, which abstracts the succinct sequence
+//! that a [`RangeFilter`](crate::RangeFilter) stores its hash values in, and the default
+//! [`Encoding`] backend.
+//!
+//! The trait is implemented for the in-crate [`EliasFano`] and [`DenseBitVector`] encodings, and
+//! for [`Encoding`], which picks the smaller of the two for every filter. With the
+//! `vers-vecs` feature enabled, it is also implemented for the `EliasFanoVec` type of the
+//! `vers-vecs` crate, and with the `sucds` feature enabled on 64-bit targets, for the `EliasFano`
+//! type of the `sucds` crate.
+
+use crate::dense::DenseBitVectorBuilder;
+use crate::elias_fano::EliasFanoBuilder;
+use crate::{dense, elias_fano, DenseBitVector, EliasFano, MemoryBreakdown};
+
+/// A static, sorted sequence of distinct 64-bit hash values that a
+/// [`RangeFilter`](crate::RangeFilter) can be stored in.
+///
+/// The default backend is [`Encoding`], which stores the hash values either with the Elias-Fano
+/// encoding [`EliasFano`], which is close to the information-theoretic minimum for sparse sets of
+/// integers, or with a [`DenseBitVector`] for dense ones. Implementing this trait for another
+/// encoding allows downstream projects to plug in their own storage, and every query of the filter
+/// is answered through [`Self::predecessor`] and, for hash-space accessors, the provided methods.
+pub trait RangeFilterBackend: Sized {
+    /// Creates the backend from non-empty, strictly increasing hash values.
+    fn from_sorted(hashes: &[u64]) -> Self;
+
+    /// Creates the backend from non-empty, strictly increasing hash values like
+    /// [`Self::from_sorted`], calling `checkpoint` with the number of hash values encoded so far
+    /// after every `interval` of them, except after the last one. If `checkpoint` returns `false`,
+    /// the encoding is abandoned and `None` is returned.
+    ///
+    /// This lets a build report its progress through the encoding and be cancelled while it runs.
+    /// By default, the hash values are encoded with [`Self::from_sorted`] without any checkpoints,
+    /// so the encoding cannot be interrupted once it starts.
+    fn from_sorted_interruptible(
+        hashes: &[u64],
+        interval: usize,
+        checkpoint: &mut dyn FnMut(usize) -> bool,
+    ) -> Option<Self> {
+        let _ = (interval, checkpoint);
+        Some(Self::from_sorted(hashes))
+    }
+
+    /// Returns the number of stored hash values.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no stored hash values.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the hash value at position `index` in increasing order.
+    ///
+    /// The `index` must be less than [`Self::len`], otherwise this may panic or return an
+    /// arbitrary value.
+    fn get(&self, index: usize) -> u64;
+
+    /// Returns the largest stored hash value that is less than or equal to `value`, if any.
+    fn predecessor(&self, value: u64) -> Option<u64>;
+
+    /// Returns the smallest stored hash value that is greater than or equal to `value`, if any.
+    fn successor(&self, value: u64) -> Option<u64> {
+        let rank = self.rank(value);
+        (rank < self.len()).then(|| self.get(rank))
+    }
+
+    /// Returns the number of stored hash values that are less than `value`.
+    fn rank(&self, value: u64) -> usize {
+        // Binary search for the first position whose hash value is at least `value`.
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.get(mid) < value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Returns an iterator over the stored hash values in increasing order.
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    /// Returns the amount of space required to store this backend on the heap, in bytes.
+    fn size_in_bytes(&self) -> usize;
+
+    /// Returns the amount of space that this backend would take on the heap to store `len`
+    /// strictly increasing hash values whose largest value is `max`, in bytes, or `None` if it
+    /// cannot be known before the backend is built.
+    ///
+    /// By default, this returns `None`.
+    fn estimated_size_in_bytes(len: usize, max: u64) -> Option<usize> {
+        let _ = (len, max);
+        None
+    }
+
+    /// Returns the space used by each part of this backend, which adds up to
+    /// [`Self::size_in_bytes`].
+    ///
+    /// By default, all of the space is reported as [`MemoryBreakdown::other`].
+    fn memory_breakdown(&self) -> MemoryBreakdown {
+        MemoryBreakdown {
+            other: self.size_in_bytes(),
+            ..MemoryBreakdown::default()
+        }
+    }
+}
+
+impl<S> RangeFilterBackend for EliasFano<S>
+where
+    S: AsRef<[u64]> + From<Vec<u64>>,
+{
+    fn from_sorted(hashes: &[u64]) -> Self {
+        let words = EliasFano::from_slice(hashes).as_words().to_vec();
+        EliasFano::from_words(S::from(words)).expect("the layout is valid")
+    }
+
+    fn from_sorted_interruptible(
+        hashes: &[u64],
+        interval: usize,
+        checkpoint: &mut dyn FnMut(usize) -> bool,
+    ) -> Option<Self> {
+        let mut builder = EliasFanoBuilder::new(hashes.len(), hashes.last().copied().unwrap_or(0));
+        push_interruptible(hashes, interval, checkpoint, |x| builder.push(x))?;
+
+        let words = builder.finish().as_words().to_vec();
+        Some(EliasFano::from_words(S::from(words)).expect("the layout is valid"))
+    }
+
+    fn len(&self) -> usize {
+        EliasFano::len(self)
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        self.get_unchecked(index)
+    }
+
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        EliasFano::predecessor(self, value)
+    }
+
+    fn successor(&self, value: u64) -> Option<u64> {
+        EliasFano::successor(self, value)
+    }
+
+    fn rank(&self, value: u64) -> usize {
+        EliasFano::rank(self, value)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        EliasFano::iter(self)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.heap_size()
+    }
+
+    fn estimated_size_in_bytes(len: usize, max: u64) -> Option<usize> {
+        Some(8 * elias_fano::layout_words(len, max))
+    }
+
+    fn memory_breakdown(&self) -> MemoryBreakdown {
+        EliasFano::memory_breakdown(self)
+    }
+}
+
+impl RangeFilterBackend for DenseBitVector {
+    fn from_sorted(hashes: &[u64]) -> Self {
+        DenseBitVector::from_slice(hashes)
+    }
+
+    fn from_sorted_interruptible(
+        hashes: &[u64],
+        interval: usize,
+        checkpoint: &mut dyn FnMut(usize) -> bool,
+    ) -> Option<Self> {
+        let mut builder = DenseBitVectorBuilder::new(hashes.len(), hashes.last().copied());
+        push_interruptible(hashes, interval, checkpoint, |x| builder.push(x))?;
+
+        Some(builder.finish())
+    }
+
+    fn len(&self) -> usize {
+        DenseBitVector::len(self)
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        self.get_unchecked(index)
+    }
+
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        DenseBitVector::predecessor(self, value)
+    }
+
+    fn successor(&self, value: u64) -> Option<u64> {
+        DenseBitVector::successor(self, value)
+    }
+
+    fn rank(&self, value: u64) -> usize {
+        DenseBitVector::rank(self, value)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        DenseBitVector::iter(self)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.heap_size()
+    }
+
+    fn estimated_size_in_bytes(_len: usize, max: u64) -> Option<usize> {
+        // The bit vector spans every value up to `max`, no matter how few of them are stored.
+        let words = dense::layout_words(max as u128 + 1);
+        Some(usize::try_from(8 * words).unwrap_or(usize::MAX))
+    }
+
+    fn memory_breakdown(&self) -> MemoryBreakdown {
+        DenseBitVector::memory_breakdown(self)
+    }
+}
+
+/// The default backend of a [`RangeFilter`](crate::RangeFilter), which stores its hash values in
+/// whichever of an [`EliasFano`] encoding or a [`DenseBitVector`] is smaller.
+///
+/// The Elias-Fano encoding needs about `2 + log2(r / n)` bits per hash value for `n` hash values
+/// out of a reduced universe of size `r`, while the bit vector needs about `1.125 * r` bits in
+/// total. The bit vector is therefore chosen when the hash values are dense relative to `r`, which
+/// happens for small false positive rates with small maximum range intervals, where it is both
+/// smaller and faster to query.
+///
+/// ```
+/// use grafite::{Encoding, RangeFilterBackend};
+///
+/// let sparse = Encoding::from_slice(&[3, 1000, 1 << 40]);
+/// assert!(matches!(sparse, Encoding::EliasFano(_)));
+///
+/// let dense: Vec<u64> = (0..1000).filter(|x| x % 3 != 0).collect();
+/// let dense = Encoding::from_slice(&dense);
+/// assert!(matches!(dense, Encoding::Dense(_)));
+/// assert_eq!(dense.predecessor(999), Some(998));
+/// ```
+#[derive(Debug, Clone)]
+pub enum Encoding {
+    /// An Elias-Fano encoding, used for sparse hash values.
+    EliasFano(EliasFano),
+    /// A plain bit vector, used for dense hash values.
+    Dense(DenseBitVector),
+}
+
+impl Encoding {
+    /// Creates a new `Encoding` from the given strictly increasing values, choosing the smaller
+    /// of the two encodings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not strictly increasing.
+    pub fn from_slice(values: &[u64]) -> Self {
+        if Self::prefers_dense(values) {
+            Self::Dense(DenseBitVector::from_slice(values))
+        } else {
+            Self::EliasFano(EliasFano::from_slice(values))
+        }
+    }
+
+    /// Checks if a [`DenseBitVector`] over the given strictly increasing values takes fewer words
+    /// than their Elias-Fano encoding.
+    pub fn prefers_dense(values: &[u64]) -> bool {
+        let Some(&max) = values.last() else {
+            return false;
+        };
+
+        dense_is_smaller(values.len(), max)
+    }
+
+    /// Returns `true` if the values are stored in a [`DenseBitVector`].
+    pub fn is_dense(&self) -> bool {
+        matches!(self, Self::Dense(_))
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::EliasFano(ef) => ef.len(),
+            Self::Dense(bits) => bits.len(),
+        }
+    }
+
+    /// Returns `true` if there are no stored values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the value at position `index`, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        match self {
+            Self::EliasFano(ef) => ef.get(index),
+            Self::Dense(bits) => bits.get(index),
+        }
+    }
+
+    /// Returns the value at position `index`.
+    ///
+    /// The `index` must be less than [`Self::len`], otherwise this may panic or return an
+    /// arbitrary value.
+    pub fn get_unchecked(&self, index: usize) -> u64 {
+        match self {
+            Self::EliasFano(ef) => ef.get_unchecked(index),
+            Self::Dense(bits) => bits.get_unchecked(index),
+        }
+    }
+
+    /// Returns the number of stored values that are less than `value`.
+    pub fn rank(&self, value: u64) -> usize {
+        match self {
+            Self::EliasFano(ef) => ef.rank(value),
+            Self::Dense(bits) => bits.rank(value),
+        }
+    }
+
+    /// Returns the largest stored value that is less than or equal to `value`, if any.
+    pub fn predecessor(&self, value: u64) -> Option<u64> {
+        match self {
+            Self::EliasFano(ef) => ef.predecessor(value),
+            Self::Dense(bits) => bits.predecessor(value),
+        }
+    }
+
+    /// Returns the smallest stored value that is greater than or equal to `value`, if any.
+    pub fn successor(&self, value: u64) -> Option<u64> {
+        match self {
+            Self::EliasFano(ef) => ef.successor(value),
+            Self::Dense(bits) => bits.successor(value),
+        }
+    }
+
+    /// Returns an iterator over the stored values in increasing order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = u64> + '_ {
+        match self {
+            Self::EliasFano(ef) => EncodingIter::EliasFano(ef.iter()),
+            Self::Dense(bits) => EncodingIter::Dense(bits.iter()),
+        }
+    }
+
+    /// Returns the amount of space required to store this `Encoding` on the heap.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            Self::EliasFano(ef) => ef.heap_size(),
+            Self::Dense(bits) => bits.heap_size(),
+        }
+    }
+
+    /// Returns the space used by each part of the encoding, which adds up to
+    /// [`Self::heap_size`].
+    pub fn memory_breakdown(&self) -> MemoryBreakdown {
+        match self {
+            Self::EliasFano(ef) => ef.memory_breakdown(),
+            Self::Dense(bits) => bits.memory_breakdown(),
+        }
+    }
+}
+
+/// Checks if a [`DenseBitVector`] over `len` strictly increasing values whose largest value is
+/// `max` takes fewer words than their Elias-Fano encoding.
+pub(crate) fn dense_is_smaller(len: usize, max: u64) -> bool {
+    let dense_words = dense::layout_words(max as u128 + 1);
+    let ef_words = elias_fano::layout_words(len, max) as u128;
+    dense_words < ef_words
+}
+
+/// Pushes every hash value with `push`, calling `checkpoint` with the number of hash values pushed
+/// so far after every `interval` of them, except after the last one, and returns `None` as soon as
+/// it returns `false`.
+fn push_interruptible(
+    hashes: &[u64],
+    interval: usize,
+    checkpoint: &mut dyn FnMut(usize) -> bool,
+    mut push: impl FnMut(u64),
+) -> Option<()> {
+    let mut done = 0;
+    for chunk in hashes.chunks(interval.max(1)) {
+        if done > 0 && !checkpoint(done) {
+            return None;
+        }
+        chunk.iter().for_each(|&x| push(x));
+        done += chunk.len();
+    }
+
+    Some(())
+}
+
+/// Builds an [`Encoding`] from hash values that are pushed one at a time in strictly increasing
+/// order, choosing the smaller of the two encodings up front from the number of values and the
+/// largest value.
+pub(crate) enum EncodingBuilder {
+    /// Builds an Elias-Fano encoding.
+    EliasFano(EliasFanoBuilder),
+    /// Builds a plain bit vector.
+    Dense(DenseBitVectorBuilder),
+}
+
+impl EncodingBuilder {
+    /// Allocates the smaller encoding of `len` values whose largest value is `max`.
+    pub(crate) fn new(len: usize, max: u64) -> Self {
+        if len > 0 && dense_is_smaller(len, max) {
+            Self::Dense(DenseBitVectorBuilder::new(len, Some(max)))
+        } else {
+            Self::EliasFano(EliasFanoBuilder::new(len, max))
+        }
+    }
+
+    /// Appends the next value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not strictly increasing, or if more values, or larger values, are
+    /// pushed than the encoding was allocated for.
+    pub(crate) fn push(&mut self, x: u64) {
+        match self {
+            Self::EliasFano(builder) => builder.push(x),
+            Self::Dense(builder) => builder.push(x),
+        }
+    }
+
+    /// Returns the finished encoding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer values were pushed than the encoding was allocated for.
+    pub(crate) fn finish(self) -> Encoding {
+        match self {
+            Self::EliasFano(builder) => Encoding::EliasFano(builder.finish()),
+            Self::Dense(builder) => Encoding::Dense(builder.finish()),
+        }
+    }
+}
+
+impl RangeFilterBackend for Encoding {
+    fn from_sorted(hashes: &[u64]) -> Self {
+        Encoding::from_slice(hashes)
+    }
+
+    fn from_sorted_interruptible(
+        hashes: &[u64],
+        interval: usize,
+        checkpoint: &mut dyn FnMut(usize) -> bool,
+    ) -> Option<Self> {
+        let mut builder = EncodingBuilder::new(hashes.len(), hashes.last().copied().unwrap_or(0));
+        push_interruptible(hashes, interval, checkpoint, |x| builder.push(x))?;
+
+        Some(builder.finish())
+    }
+
+    fn len(&self) -> usize {
+        Encoding::len(self)
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        self.get_unchecked(index)
+    }
+
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        Encoding::predecessor(self, value)
+    }
+
+    fn successor(&self, value: u64) -> Option<u64> {
+        Encoding::successor(self, value)
+    }
+
+    fn rank(&self, value: u64) -> usize {
+        Encoding::rank(self, value)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        Encoding::iter(self)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.heap_size()
+    }
+
+    fn estimated_size_in_bytes(len: usize, max: u64) -> Option<usize> {
+        let elias_fano = EliasFano::<Vec<u64>>::estimated_size_in_bytes(len, max)?;
+        let dense = DenseBitVector::estimated_size_in_bytes(len, max)?;
+        Some(elias_fano.min(dense))
+    }
+
+    fn memory_breakdown(&self) -> MemoryBreakdown {
+        Encoding::memory_breakdown(self)
+    }
+}
+
+/// An iterator over the values of an [`Encoding`].
+enum EncodingIter<E, D> {
+    EliasFano(E),
+    Dense(D),
+}
+
+impl<E, D> Iterator for EncodingIter<E, D>
+where
+    E: Iterator<Item = u64>,
+    D: Iterator<Item = u64>,
+{
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        match self {
+            Self::EliasFano(iter) => iter.next(),
+            Self::Dense(iter) => iter.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::EliasFano(iter) => iter.size_hint(),
+            Self::Dense(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl<E, D> ExactSizeIterator for EncodingIter<E, D>
+where
+    E: ExactSizeIterator<Item = u64>,
+    D: ExactSizeIterator<Item = u64>,
+{
+}
+
+#[cfg(feature = "vers-vecs")]
+impl RangeFilterBackend for vers_vecs::EliasFanoVec {
+    fn from_sorted(hashes: &[u64]) -> Self {
+        vers_vecs::EliasFanoVec::from_slice(hashes)
+    }
+
+    fn len(&self) -> usize {
+        vers_vecs::EliasFanoVec::len(self)
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        self.get_unchecked(index)
+    }
+
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        vers_vecs::EliasFanoVec::predecessor(self, value)
+    }
+
+    fn successor(&self, value: u64) -> Option<u64> {
+        vers_vecs::EliasFanoVec::successor(self, value)
+    }
+
+    fn rank(&self, value: u64) -> usize {
+        vers_vecs::EliasFanoVec::rank(self, value) as usize
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        vers_vecs::EliasFanoVec::iter(self)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.heap_size()
+    }
+}
+
+#[cfg(all(feature = "sucds", target_pointer_width = "64"))]
+impl RangeFilterBackend for sucds::mii_sequences::EliasFano {
+    fn from_sorted(hashes: &[u64]) -> Self {
+        let max_hash = *hashes.last().expect("hashes must not be empty");
+
+        let mut builder =
+            sucds::mii_sequences::EliasFanoBuilder::new(max_hash as usize + 1, hashes.len())
+                .expect("hashes must not be empty");
+        builder
+            .extend(hashes.iter().map(|&hash| hash as usize))
+            .expect("hashes must be sorted and less than the universe");
+
+        builder.build().enable_rank()
+    }
+
+    fn len(&self) -> usize {
+        sucds::mii_sequences::EliasFano::len(self)
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        self.select(index)
+            .expect("index must be less than the length") as u64
+    }
+
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        // Unlike the trait, `sucds` has no predecessor for values outside of its universe.
+        let value = value.min(self.universe() as u64 - 1);
+        sucds::mii_sequences::EliasFano::predecessor(self, value as usize).map(|h| h as u64)
+    }
+
+    fn successor(&self, value: u64) -> Option<u64> {
+        sucds::mii_sequences::EliasFano::successor(self, usize::try_from(value).ok()?)
+            .map(|h| h as u64)
+    }
+
+    fn rank(&self, value: u64) -> usize {
+        let value = value.min(self.universe() as u64);
+        sucds::mii_sequences::EliasFano::rank(self, value as usize)
+            .expect("the value is at most the universe")
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        sucds::mii_sequences::EliasFano::iter(self, 0).map(|h| h as u64)
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        sucds::Serializable::size_in_bytes(self)
+    }
+}
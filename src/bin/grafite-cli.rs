@@ -0,0 +1,176 @@
+//! A command line tool for building, querying, and inspecting serialized range filters.
+//!
+//! Keys and queries are read as whitespace-separated decimal `u64` values. Every line of a query
+//! file is either a single key, or the inclusive endpoints of a range, and its answer is printed on
+//! its own line as `true` or `false`.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use grafite::{FilterMetadata, OrderPreservingHasher, RangeFilter};
+
+/// Build, query, and inspect Grafite range filters.
+#[derive(Debug, Parser)]
+#[command(name = "grafite-cli", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Build a filter from a file of keys and write it to a file.
+    Build {
+        /// The file of whitespace-separated keys, or `-` to read them from standard input.
+        keys: PathBuf,
+        /// The file to write the serialized filter to.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// The false positive rate of queries of at most the maximum range interval.
+        #[arg(short, long, default_value_t = 0.01)]
+        epsilon: f64,
+        /// The length of the longest range that queries are guaranteed not to miss keys in.
+        #[arg(short, long, default_value_t = 64)]
+        max_interval: u64,
+        /// The seed to derive the hash function from, which is random if omitted.
+        #[arg(short, long)]
+        seed: Option<u64>,
+    },
+    /// Answer point and range queries against a serialized filter.
+    Query {
+        /// The serialized filter.
+        filter: PathBuf,
+        /// The file of queries, one per line, which are read from standard input if omitted.
+        #[arg(short, long)]
+        queries: Option<PathBuf>,
+    },
+    /// Print the parameters and statistics of a serialized filter.
+    Inspect {
+        /// The serialized filter.
+        filter: PathBuf,
+    },
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::Build {
+            keys,
+            output,
+            epsilon,
+            max_interval,
+            seed,
+        } => build(&keys, &output, epsilon, max_interval, seed),
+        Command::Query { filter, queries } => query(&filter, queries.as_deref()),
+        Command::Inspect { filter } => inspect(&filter),
+    }
+}
+
+/// Opens a file for reading, where `-` or `None` stands for standard input.
+fn open(path: Option<&Path>) -> io::Result<Box<dyn BufRead>> {
+    match path {
+        Some(path) if path != Path::new("-") => Ok(Box::new(BufReader::new(File::open(path)?))),
+        _ => Ok(Box::new(io::stdin().lock())),
+    }
+}
+
+/// Parses a single decimal `u64`, naming the offending line on failure.
+fn parse_key(token: &str, line: usize) -> Result<u64, Box<dyn Error>> {
+    token
+        .parse()
+        .map_err(|e| format!("line {line}: invalid key {token:?}: {e}").into())
+}
+
+fn build(
+    keys: &Path,
+    output: &Path,
+    epsilon: f64,
+    max_interval: u64,
+    seed: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let mut values = Vec::new();
+    for (i, line) in open(Some(keys))?.lines().enumerate() {
+        for token in line?.split_whitespace() {
+            values.push(parse_key(token, i + 1)?);
+        }
+    }
+
+    let seed = seed.unwrap_or_else(rand::random);
+    let hasher = OrderPreservingHasher::new_seeded(values.len(), epsilon, max_interval, seed)
+        .map_err(|e| format!("invalid filter parameters: {e:?}"))?;
+    let filter = RangeFilter::try_new(values.iter().copied(), hasher)
+        .map_err(|e| format!("unable to build the range filter: {e:?}"))?;
+
+    let metadata = FilterMetadata::new(FilterMetadata::fingerprint(values.iter().copied()));
+    let mut writer = BufWriter::new(File::create(output)?);
+    filter.write_to_with_metadata(&mut writer, &metadata)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn query(filter: &Path, queries: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let filter = RangeFilter::read_from(BufReader::new(File::open(filter)?))?;
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    for (i, line) in open(queries)?.lines().enumerate() {
+        let line = line?;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let answer = match tokens[..] {
+            [] => continue,
+            [key] => filter.contains(parse_key(key, i + 1)?),
+            [start, end] => filter.query(parse_key(start, i + 1)?..=parse_key(end, i + 1)?),
+            _ => return Err(format!("line {}: expected a key or a range", i + 1).into()),
+        };
+        writeln!(out, "{answer}")?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+fn inspect(path: &Path) -> Result<(), Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    let (filter, metadata) = RangeFilter::read_from_with_metadata(bytes.as_slice())?;
+
+    let hasher = filter.hasher();
+    let num_hashes = filter.backend().len();
+
+    println!("serialized size:     {} bytes", bytes.len());
+    println!("heap size:           {} bytes", filter.heap_size());
+    println!("hash values:         {num_hashes}");
+    println!(
+        "bits per hash value: {:.2}",
+        (8 * bytes.len()) as f64 / num_hashes.max(1) as f64
+    );
+    println!("dense encoding:      {}", filter.backend().is_dense());
+    println!("reduced universe:    {}", hasher.reduced_universe());
+    print_optional("keys", filter.num_elements());
+    print_optional("max interval", filter.max_interval());
+    print_optional("false positive rate", filter.false_positive_rate());
+    print_optional("seed", filter.seed());
+
+    if let Some(metadata) = metadata {
+        println!("id:                  {:032x}", metadata.id);
+        println!("created at:          {} ms", metadata.created_at_millis);
+        println!("source fingerprint:  {:016x}", metadata.source_fingerprint);
+    }
+
+    Ok(())
+}
+
+/// Prints a labelled value that is only known for filters that recorded their build parameters.
+fn print_optional<T: std::fmt::Display>(label: &str, value: Option<T>) {
+    let value = value.map_or_else(|| "unknown".to_string(), |v| v.to_string());
+    println!("{:<21}{value}", format!("{label}:"));
+}
@@ -0,0 +1,178 @@
+//! This module contains the [`BlockFilterBuilder`] and [`BlockFilterReader`] traits, which describe
+//! how an LSM engine builds and probes one filter per block of a sorted table, along with their
+//! reference implementations for [`RangeFilter`].
+
+use crate::{BuildError, OrderPreservingHasher, PersistError, RangeFilter};
+
+/// Builds one serialized filter per block of keys, such as the data blocks of an SSTable.
+///
+/// The engine adds the keys of a block as it writes them, and calls [`Self::finish_block`] once the
+/// block is complete to obtain the bytes of its filter, which it stores alongside the block. Keys
+/// of other types can be mapped onto `u64` with [`ToOrderedU64`](crate::ToOrderedU64) first.
+pub trait BlockFilterBuilder {
+    /// The error returned if a filter could not be built.
+    type Error;
+
+    /// Adds a key of the current block. Keys may be added in any order and may repeat.
+    fn add_key(&mut self, key: u64);
+
+    /// Appends the filter over every key added since the previous call to `out`, and starts a new
+    /// block.
+    fn finish_block(&mut self, out: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// Probes a filter that was serialized by a [`BlockFilterBuilder`], typically to decide whether a
+/// block has to be read at all.
+pub trait BlockFilterReader: Sized {
+    /// The error returned if the bytes could not be read as a filter.
+    type Error;
+
+    /// Reads a filter from the bytes produced by [`BlockFilterBuilder::finish_block`].
+    fn open(bytes: &[u8]) -> Result<Self, Self::Error>;
+
+    /// Checks if there may be any keys in the inclusive range `start..=end` in the block.
+    fn may_contain_range(&self, start: u64, end: u64) -> bool;
+
+    /// Checks if the given key may be in the block.
+    fn may_contain(&self, key: u64) -> bool {
+        self.may_contain_range(key, key)
+    }
+}
+
+/// The reference [`BlockFilterBuilder`], which builds a [`RangeFilter`] per block and serializes
+/// it with [`RangeFilter::write_to`].
+///
+/// A block without any keys is serialized as an empty byte string, which
+/// [`RangeFilterBlockReader`] reads as a filter that matches nothing.
+///
+/// ```
+/// use grafite::{
+///     BlockFilterBuilder, BlockFilterReader, RangeFilterBlockBuilder, RangeFilterBlockReader,
+/// };
+///
+/// let mut builder = RangeFilterBlockBuilder::new(0.01, 64);
+///
+/// let mut blocks = Vec::new();
+/// for block in [[3, 9, 27], [100, 120, 140]] {
+///     for key in block {
+///         builder.add_key(key);
+///     }
+///     let mut bytes = Vec::new();
+///     builder.finish_block(&mut bytes).unwrap();
+///     blocks.push(bytes);
+/// }
+///
+/// let reader = RangeFilterBlockReader::open(&blocks[1]).unwrap();
+/// assert!(reader.may_contain_range(110, 125));
+/// assert!(reader.may_contain(140));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RangeFilterBlockBuilder {
+    /// The keys of the current block.
+    keys: Vec<u64>,
+    /// The false positive rate of every filter.
+    epsilon: f64,
+    /// The maximum range interval of every filter.
+    max_interval: u64,
+    /// The seed that every hash function is derived from, if builds should be reproducible.
+    seed: Option<u64>,
+}
+
+impl RangeFilterBlockBuilder {
+    /// Creates a new builder whose filters have the false positive rate `epsilon` for ranges of up
+    /// to `max_interval` keys.
+    ///
+    /// The parameters are checked when the first block is finished.
+    pub fn new(epsilon: f64, max_interval: u64) -> Self {
+        Self {
+            keys: Vec::new(),
+            epsilon,
+            max_interval,
+            seed: None,
+        }
+    }
+
+    /// Derives the hash function of every filter deterministically from `seed`, so that writing the
+    /// same blocks always produces the same bytes.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the number of keys added to the current block.
+    pub fn num_keys(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+impl BlockFilterBuilder for RangeFilterBlockBuilder {
+    type Error = BuildError;
+
+    fn add_key(&mut self, key: u64) {
+        self.keys.push(key);
+    }
+
+    fn finish_block(&mut self, out: &mut Vec<u8>) -> Result<(), BuildError> {
+        let keys = std::mem::take(&mut self.keys);
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let (n, epsilon, max_interval) = (keys.len(), self.epsilon, self.max_interval);
+        let hasher = match self.seed {
+            Some(seed) => OrderPreservingHasher::new_seeded(n, epsilon, max_interval, seed)?,
+            None => OrderPreservingHasher::new(n, epsilon, max_interval)?,
+        };
+        let filter = RangeFilter::try_new(keys.into_iter(), hasher)?;
+
+        filter
+            .write_to(out)
+            .expect("writing into a `Vec` cannot fail");
+
+        Ok(())
+    }
+}
+
+/// The reference [`BlockFilterReader`] for the filters written by [`RangeFilterBlockBuilder`].
+///
+/// Ranges are checked with [`RangeFilter::query_chunked`], which splits a range that is longer
+/// than the maximum range interval of the block's filter, or that crosses a block boundary of its
+/// reduced universe, into sub-queries that each keep its false positive rate guarantee. A block
+/// that contains a key in the range is therefore never skipped.
+#[derive(Debug, Clone)]
+pub struct RangeFilterBlockReader {
+    /// The filter of the block, or `None` if the block has no keys.
+    filter: Option<RangeFilter>,
+}
+
+impl RangeFilterBlockReader {
+    /// Returns the filter of the block, or `None` if the block has no keys.
+    pub fn filter(&self) -> Option<&RangeFilter> {
+        self.filter.as_ref()
+    }
+}
+
+impl BlockFilterReader for RangeFilterBlockReader {
+    type Error = PersistError;
+
+    fn open(bytes: &[u8]) -> Result<Self, PersistError> {
+        let filter = match bytes {
+            [] => None,
+            bytes => Some(RangeFilter::read_from(bytes)?),
+        };
+
+        Ok(Self { filter })
+    }
+
+    fn may_contain_range(&self, start: u64, end: u64) -> bool {
+        self.filter
+            .as_ref()
+            .is_some_and(|filter| filter.query_chunked(start..=end))
+    }
+
+    fn may_contain(&self, key: u64) -> bool {
+        self.filter
+            .as_ref()
+            .is_some_and(|filter| filter.contains(key))
+    }
+}
@@ -0,0 +1,122 @@
+//! This module contains the [`BucketRangeFilter`] type, the heuristic bucketing range filter
+//! described alongside Grafite in the original paper.
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, EliasFano, ParamError};
+
+/// A heuristic range filter that partitions the key universe into buckets of equal size and stores
+/// which buckets are non-empty.
+///
+/// A query checks whether any bucket that overlaps the queried range is non-empty, so there are no
+/// false negatives. Unlike [`RangeFilter`](crate::RangeFilter), there is no guarantee on the false
+/// positive rate: a query is only a false positive if it shares a bucket with a key, which makes
+/// this filter very effective on clustered keys and on queries far from any key, but an adversary
+/// can make every query a false positive. The non-empty bucket IDs are stored in an Elias-Fano
+/// encoding, just like the hashes of a [`RangeFilter`](crate::RangeFilter).
+///
+/// ```
+/// use grafite::BucketRangeFilter;
+///
+/// let rf = BucketRangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20], 4).unwrap();
+///
+/// assert!(rf.query(3..5));
+/// assert!(!rf.query(16..20));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BucketRangeFilter {
+    /// The number of consecutive keys in every bucket.
+    bucket_size: u64,
+    /// A succinct encoding of the sorted IDs of the non-empty buckets.
+    ef: EliasFano,
+}
+
+impl BucketRangeFilter {
+    /// Creates a new `BucketRangeFilter` over the given keys with buckets of `bucket_size`
+    /// consecutive keys.
+    ///
+    /// If there are no input keys, this function will return [`BuildError::EmptyInput`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bucket_size` is `0`.
+    pub fn new<I>(keys: I, bucket_size: u64) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        assert!(bucket_size > 0, "bucket_size must be positive");
+
+        let mut buckets: Vec<u64> = keys.into_iter().map(|x| x / bucket_size).collect();
+        if buckets.is_empty() {
+            return Err(BuildError::EmptyInput);
+        }
+
+        // Sort and then remove all duplicates.
+        buckets.sort_unstable();
+        buckets.dedup();
+
+        Ok(Self {
+            bucket_size,
+            ef: EliasFano::from_slice(&buckets),
+        })
+    }
+
+    /// Creates a new `BucketRangeFilter` over the given keys given a budget of `bits_per_key` bits
+    /// per key.
+    ///
+    /// The Elias-Fano encoding of `n` bucket IDs out of `u` buckets takes about `2 + log2(u / n)`
+    /// bits per ID, so the keys from `0` to the largest key are split into `n * 2^(B - 2)` buckets.
+    ///
+    /// If `bits_per_key` is not in the range (2, 64], this function will return a
+    /// [`ParamError::Overflow`]. If there are no input keys, this function will return
+    /// [`BuildError::EmptyInput`].
+    pub fn with_budget<I>(keys: I, bits_per_key: u8) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        if bits_per_key <= 2 || bits_per_key > 64 {
+            return Err(ParamError::Overflow.into());
+        }
+
+        let keys: Vec<u64> = keys.into_iter().collect();
+        let Some(&max_key) = keys.iter().max() else {
+            return Err(BuildError::EmptyInput);
+        };
+
+        let universe = max_key as u128 + 1;
+        let num_buckets = (keys.len() as u128) << (bits_per_key - 2);
+        let bucket_size = universe.div_ceil(num_buckets).max(1) as u64;
+
+        Self::new(keys, bucket_size)
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        let start_bucket = start / self.bucket_size;
+        let end_bucket = end / self.bucket_size;
+
+        self.ef
+            .predecessor(end_bucket)
+            .is_some_and(|bucket| bucket >= start_bucket)
+    }
+
+    /// Returns the number of consecutive keys in every bucket.
+    pub fn bucket_size(&self) -> u64 {
+        self.bucket_size
+    }
+
+    /// Returns the amount of space required to store this `BucketRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.ef.heap_size()
+    }
+}
@@ -0,0 +1,323 @@
+//! This module contains the [`RangeFilterBuilder`] type, a fluent interface for constructing a
+//! [`RangeFilter`] without building the [`OrderPreservingHasher`] separately, and the
+//! [`InsertBuilder`] type, which collects keys incrementally before building a [`RangeFilter`].
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::io;
+
+#[cfg(feature = "verify")]
+use crate::VerifiedRangeFilter;
+use crate::{
+    BuildError, IoBuildError, OrderPreservingHasher, ParamError, PrimeStrategy, RangeFilter,
+    MAX_UNIVERSE_SIZE,
+};
+
+/// A builder for a [`RangeFilter`].
+///
+/// The maximum query interval must always be provided, along with exactly one of the false positive
+/// rate `epsilon` or a budget of `bits_per_key`. If a `seed` is provided, the hash function
+/// parameters are generated deterministically from it, so building the same keys with the same
+/// parameters always produces the same filter.
+///
+/// ```
+/// use grafite::RangeFilterBuilder;
+///
+/// let rf = RangeFilterBuilder::new()
+///     .epsilon(0.01)
+///     .max_interval(20)
+///     .seed(42)
+///     .build([1, 2, 3, 7, 8, 9, 15, 20])
+///     .expect("The input parameters should be valid");
+///
+/// assert!(rf.query(3..5));
+/// assert!(!rf.query(10..15));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RangeFilterBuilder {
+    epsilon: Option<f64>,
+    max_interval: Option<u64>,
+    bits_per_key: Option<u8>,
+    seed: Option<u64>,
+    prime_strategy: PrimeStrategy,
+    power_of_two: bool,
+    universe_size: Option<u64>,
+}
+
+impl RangeFilterBuilder {
+    /// Creates a new builder with no parameters set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the false positive rate `epsilon`.
+    ///
+    /// This conflicts with [`Self::bits_per_key`].
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = Some(epsilon);
+        self
+    }
+
+    /// Sets the maximum range interval that will be queried.
+    pub fn max_interval(mut self, max_interval: u64) -> Self {
+        self.max_interval = Some(max_interval);
+        self
+    }
+
+    /// Sets a budget of bits per key, which determines the false positive rate.
+    ///
+    /// This conflicts with [`Self::epsilon`]. See
+    /// [`OrderPreservingHasher::epsilon_with_budget`] for more information.
+    pub fn bits_per_key(mut self, bits_per_key: u8) -> Self {
+        self.bits_per_key = Some(bits_per_key);
+        self
+    }
+
+    /// Sets a seed used to deterministically generate the hash function parameters.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the strategy used to choose the prime of the hash function. Defaults to
+    /// [`PrimeStrategy::Random`].
+    pub fn prime_strategy(mut self, prime_strategy: PrimeStrategy) -> Self {
+        self.prime_strategy = prime_strategy;
+        self
+    }
+
+    /// Sets whether the reduced universe size is rounded up to the next power of two. Defaults to
+    /// `false`.
+    ///
+    /// See [`OrderPreservingHasher::new_power_of_two`] for more information.
+    pub fn power_of_two(mut self, power_of_two: bool) -> Self {
+        self.power_of_two = power_of_two;
+        self
+    }
+
+    /// Sets the size of the universe that every key is known to be less than. Defaults to
+    /// [`MAX_UNIVERSE_SIZE`].
+    ///
+    /// See [`OrderPreservingHasher::new_with_universe`] for more information.
+    pub fn universe_size(mut self, universe_size: u64) -> Self {
+        self.universe_size = Some(universe_size);
+        self
+    }
+
+    /// Validates the parameters and builds only the [`OrderPreservingHasher`] for a set of
+    /// `num_elements` keys.
+    pub fn build_hasher(&self, num_elements: usize) -> Result<OrderPreservingHasher, ParamError> {
+        let max_interval = self
+            .max_interval
+            .ok_or(ParamError::MissingParameter("max_interval"))?;
+
+        let epsilon = match (self.epsilon, self.bits_per_key) {
+            (Some(_), Some(_)) => return Err(ParamError::ConflictingParameters),
+            (Some(epsilon), None) => epsilon,
+            (None, Some(bits_per_key)) => {
+                OrderPreservingHasher::epsilon_with_budget(bits_per_key, max_interval)?
+            }
+            (None, None) => return Err(ParamError::MissingParameter("epsilon")),
+        };
+
+        let universe_size = u128::from(self.universe_size.unwrap_or(MAX_UNIVERSE_SIZE));
+        let strategy = self.prime_strategy;
+        match self.seed {
+            Some(seed) => {
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+                let hasher = OrderPreservingHasher::new_in_universe(
+                    universe_size,
+                    num_elements,
+                    epsilon,
+                    max_interval,
+                    strategy,
+                    self.power_of_two,
+                    &mut rng,
+                )?;
+                Ok(hasher.with_seed(seed))
+            }
+            None => OrderPreservingHasher::new_in_universe(
+                universe_size,
+                num_elements,
+                epsilon,
+                max_interval,
+                strategy,
+                self.power_of_two,
+                &mut rand::thread_rng(),
+            ),
+        }
+    }
+
+    /// Validates the parameters and builds a [`RangeFilter`] over the given keys.
+    ///
+    /// The hasher is sized for the number of distinct keys, so repeated keys do not inflate the
+    /// reduced universe or the space of the filter.
+    ///
+    /// If the parameters are invalid or the filter could not be built for any reason, this
+    /// function will return a [`BuildError`].
+    pub fn build<I>(&self, keys: I) -> Result<RangeFilter, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let keys = distinct_keys(keys)?;
+
+        let hasher = self.build_hasher(keys.len())?;
+
+        RangeFilter::try_new(keys.into_iter(), hasher)
+    }
+
+    /// Validates the parameters and builds a [`RangeFilter`] by streaming over fallible keys twice,
+    /// without ever buffering the keys in memory.
+    ///
+    /// The `open` closure is called once to count the keys, which determines the hasher
+    /// parameters, and once more to hash them. Both calls must produce the same sequence of keys,
+    /// for example by reopening the same file. Since the keys are never buffered, repeated keys
+    /// are counted once for every occurrence, which makes the filter larger than needed for inputs
+    /// with many repeats.
+    ///
+    /// The first I/O error from either pass aborts the build and is returned as an
+    /// [`IoBuildError::Io`]. If the parameters are invalid or the filter could not be built for any
+    /// other reason, this function will return an [`IoBuildError::Build`].
+    pub fn build_two_pass<F, I>(&self, mut open: F) -> Result<RangeFilter, IoBuildError>
+    where
+        F: FnMut() -> io::Result<I>,
+        I: Iterator<Item = io::Result<u64>>,
+    {
+        let mut num_elements = 0;
+        for key in open()? {
+            key?;
+            num_elements += 1;
+        }
+
+        if num_elements == 0 {
+            return Err(BuildError::EmptyInput.into());
+        }
+
+        let hasher = self.build_hasher(num_elements).map_err(BuildError::from)?;
+
+        RangeFilter::try_from_fallible(open()?, hasher)
+    }
+
+    /// Validates the parameters and builds a [`VerifiedRangeFilter`] over the given keys, which
+    /// keeps a sorted copy of them.
+    ///
+    /// If the parameters are invalid or the filter could not be built for any reason, this
+    /// function will return a [`BuildError`].
+    #[cfg(feature = "verify")]
+    pub fn build_verified<I>(&self, keys: I) -> Result<VerifiedRangeFilter, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let keys = distinct_keys(keys)?;
+
+        let hasher = self.build_hasher(keys.len())?;
+
+        VerifiedRangeFilter::try_new(keys, hasher)
+    }
+}
+
+/// Collects the given keys, sorted and without duplicates.
+///
+/// If there are no keys, this function will return [`BuildError::EmptyInput`].
+fn distinct_keys<I>(keys: I) -> Result<Vec<u64>, BuildError>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let mut keys: Vec<u64> = keys.into_iter().collect();
+    if keys.is_empty() {
+        return Err(BuildError::EmptyInput);
+    }
+
+    keys.sort_unstable();
+    keys.dedup();
+
+    Ok(keys)
+}
+
+/// A staged builder that collects keys incrementally before building a [`RangeFilter`].
+///
+/// Keys can be added over time with [`Self::push`] and [`Self::extend`] (or collected from an
+/// iterator), and the filter is built once every key is available with [`Self::finish`]. Since
+/// the hasher parameters depend on the number of keys, [`Self::len`] can be used to create the
+/// hasher right before finishing.
+///
+/// ```
+/// use grafite::{InsertBuilder, OrderPreservingHasher};
+///
+/// let mut builder: InsertBuilder = [1, 2, 3].into_iter().collect();
+/// builder.push(7);
+/// builder.extend([8, 9, 15, 20]);
+///
+/// let hasher = OrderPreservingHasher::new(builder.len(), 0.01, 20).unwrap();
+/// let rf = builder.finish(hasher).unwrap();
+///
+/// assert!(rf.query(3..5));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InsertBuilder {
+    keys: Vec<u64>,
+}
+
+impl InsertBuilder {
+    /// Creates a new, empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty builder with space for at least `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            keys: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a key to the builder.
+    pub fn push(&mut self, key: u64) {
+        self.keys.push(key);
+    }
+
+    /// Returns the number of keys added so far, including duplicates.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if no keys have been added.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Builds a [`RangeFilter`] over every key that was added, using the given hasher.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn finish(self, hasher: OrderPreservingHasher) -> Result<RangeFilter, BuildError> {
+        RangeFilter::try_new(self.keys.into_iter(), hasher)
+    }
+
+    /// Builds a [`VerifiedRangeFilter`] over every key that was added, using the given hasher,
+    /// which keeps a sorted copy of the keys.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    #[cfg(feature = "verify")]
+    pub fn finish_verified(
+        self,
+        hasher: OrderPreservingHasher,
+    ) -> Result<VerifiedRangeFilter, BuildError> {
+        VerifiedRangeFilter::try_new(self.keys, hasher)
+    }
+}
+
+impl Extend<u64> for InsertBuilder {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        self.keys.extend(iter);
+    }
+}
+
+impl FromIterator<u64> for InsertBuilder {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        Self {
+            keys: iter.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! This module contains the [`CompositeEncoder`] type, which packs several fixed-width columns into
+//! a single order-preserving key.
+
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
+/// An error type representing why columns could not be packed by a [`CompositeEncoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositeError {
+    /// If the encoder was created without any columns.
+    NoColumns,
+    /// If a column was given a width of zero bits.
+    ZeroWidth {
+        /// The index of the offending column.
+        column: usize,
+    },
+    /// If the columns do not fit in the integer type that they are packed into.
+    TooWide {
+        /// The total width of the columns in bits.
+        bits: u32,
+        /// The width of the integer type in bits.
+        max: u32,
+    },
+    /// If the wrong number of column values was given.
+    ColumnCount {
+        /// The number of column values that was expected.
+        expected: usize,
+        /// The number of column values that was given.
+        found: usize,
+    },
+    /// If a column value does not fit in the width of its column.
+    ValueOutOfRange {
+        /// The index of the offending column.
+        column: usize,
+        /// The offending value.
+        value: u128,
+    },
+}
+
+/// Packs several fixed-width unsigned columns, such as a tenant ID and a timestamp, into a single
+/// `u64` or `u128` key.
+///
+/// The first column occupies the most significant bits and the last column the least significant
+/// bits, so the packed keys are ordered lexicographically by their columns. This means that all
+/// keys that share their leading columns form one contiguous range, which
+/// [`Self::prefix_range`] computes for queries that fix some leading columns and ask for a range
+/// on the next one.
+///
+/// Packed `u64` keys can be stored in a [`RangeFilter`](crate::RangeFilter), and packed `u128`
+/// keys in a [`RangeFilter128`](crate::RangeFilter128).
+///
+/// ```
+/// use grafite::{CompositeEncoder, OrderPreservingHasher, RangeFilter};
+///
+/// // A 16-bit tenant ID followed by a 48-bit millisecond timestamp.
+/// let encoder = CompositeEncoder::new(&[16, 48]).unwrap();
+///
+/// let rows = [(7, 1_700_000_000_000), (7, 1_700_000_000_500), (9, 1_700_000_000_200)];
+/// let keys = rows.map(|(tenant, ts)| encoder.encode_u64(&[tenant, ts]).unwrap());
+///
+/// let hasher = OrderPreservingHasher::new(keys.len(), 0.01, 1000).unwrap();
+/// let rf = RangeFilter::new(keys.into_iter(), hasher);
+///
+/// // Does tenant 9 have any rows in this second?
+/// let range = encoder
+///     .prefix_range_u64(&[9], 1_700_000_000_000..1_700_000_001_000)
+///     .unwrap();
+/// assert!(rf.query(range));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CompositeEncoder {
+    /// The width in bits of every column, starting with the most significant column.
+    widths: Vec<u32>,
+    /// The total width of the columns in bits.
+    bits: u32,
+}
+
+impl CompositeEncoder {
+    /// Creates a new `CompositeEncoder` given the width in bits of every column, starting with the
+    /// most significant column.
+    ///
+    /// Returns an error if there are no columns, if a column has a width of zero, or if the
+    /// columns add up to more than 128 bits.
+    pub fn new(widths: &[u32]) -> Result<Self, CompositeError> {
+        if widths.is_empty() {
+            return Err(CompositeError::NoColumns);
+        }
+
+        if let Some(column) = widths.iter().position(|&w| w == 0) {
+            return Err(CompositeError::ZeroWidth { column });
+        }
+
+        let bits = widths
+            .iter()
+            .try_fold(0u32, |acc, &w| acc.checked_add(w))
+            .unwrap_or(u32::MAX);
+        if bits > u128::BITS {
+            return Err(CompositeError::TooWide {
+                bits,
+                max: u128::BITS,
+            });
+        }
+
+        Ok(Self {
+            widths: widths.to_vec(),
+            bits,
+        })
+    }
+
+    /// Returns the width in bits of every column, starting with the most significant column.
+    pub fn widths(&self) -> &[u32] {
+        &self.widths
+    }
+
+    /// Returns the total width of the columns in bits.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns `true` if the packed keys fit in a `u64`.
+    pub fn fits_in_u64(&self) -> bool {
+        self.bits <= u64::BITS
+    }
+
+    /// Packs one value per column into a `u128` key.
+    ///
+    /// Returns an error if the number of values does not match the number of columns, or if a
+    /// value does not fit in the width of its column.
+    pub fn encode(&self, columns: &[u128]) -> Result<u128, CompositeError> {
+        if columns.len() != self.widths.len() {
+            return Err(CompositeError::ColumnCount {
+                expected: self.widths.len(),
+                found: columns.len(),
+            });
+        }
+
+        self.pack(columns, &[])
+    }
+
+    /// Packs one value per column into a `u64` key. See [`Self::encode`].
+    ///
+    /// Returns an error if the columns add up to more than 64 bits.
+    pub fn encode_u64(&self, columns: &[u64]) -> Result<u64, CompositeError> {
+        self.check_u64()?;
+
+        let columns: Vec<u128> = columns.iter().map(|&c| c as u128).collect();
+        self.encode(&columns).map(|key| key as u64)
+    }
+
+    /// Unpacks a `u128` key into one value per column.
+    ///
+    /// Bits above the total width of the columns are ignored.
+    pub fn decode(&self, key: u128) -> Vec<u128> {
+        let mut shift = self.bits;
+
+        self.widths
+            .iter()
+            .map(|&width| {
+                shift -= width;
+                (key >> shift) & column_mask(width)
+            })
+            .collect()
+    }
+
+    /// Returns the range of `u128` keys whose leading columns equal `prefix` and whose next column
+    /// lies within `range`, while every later column may take any value.
+    ///
+    /// The range is clamped to the values that fit in its column. If no value does, the returned
+    /// range is empty, and queries for it always return `false`.
+    ///
+    /// Returns an error if `prefix` does not leave at least one column for the range, or if a
+    /// prefix value does not fit in the width of its column.
+    pub fn prefix_range<R>(
+        &self,
+        prefix: &[u128],
+        range: R,
+    ) -> Result<RangeInclusive<u128>, CompositeError>
+    where
+        R: RangeBounds<u128>,
+    {
+        if prefix.len() >= self.widths.len() {
+            return Err(CompositeError::ColumnCount {
+                expected: self.widths.len() - 1,
+                found: prefix.len(),
+            });
+        }
+
+        let max = column_mask(self.widths[prefix.len()]);
+
+        let start = match range.start_bound() {
+            Bound::Included(&s) => Some(s),
+            Bound::Excluded(&s) => s.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&e) => Some(e.min(max)),
+            Bound::Excluded(&e) => e.checked_sub(1).map(|e| e.min(max)),
+            Bound::Unbounded => Some(max),
+        };
+
+        let (Some(start), Some(end)) = (start, end) else {
+            return Ok(empty_range());
+        };
+        if start > end {
+            return Ok(empty_range());
+        }
+
+        let suffix = self.widths.len() - prefix.len() - 1;
+        let mut columns = prefix.to_vec();
+
+        columns.push(start);
+        let low = self.pack(&columns, &vec![0; suffix])?;
+
+        columns.pop();
+        columns.push(end);
+        let high_suffix: Vec<u128> = self.widths[prefix.len() + 1..]
+            .iter()
+            .map(|&w| column_mask(w))
+            .collect();
+        let high = self.pack(&columns, &high_suffix)?;
+
+        Ok(low..=high)
+    }
+
+    /// Returns the range of `u64` keys whose leading columns equal `prefix` and whose next column
+    /// lies within `range`. See [`Self::prefix_range`].
+    ///
+    /// Returns an error if the columns add up to more than 64 bits.
+    pub fn prefix_range_u64<R>(
+        &self,
+        prefix: &[u64],
+        range: R,
+    ) -> Result<RangeInclusive<u64>, CompositeError>
+    where
+        R: RangeBounds<u64>,
+    {
+        self.check_u64()?;
+
+        let prefix: Vec<u128> = prefix.iter().map(|&c| c as u128).collect();
+        let range = (
+            range.start_bound().map(|&s| s as u128),
+            range.end_bound().map(|&e| e as u128),
+        );
+        let range = self.prefix_range(&prefix, range)?;
+
+        Ok(*range.start() as u64..=*range.end() as u64)
+    }
+
+    /// Packs the leading column values followed by the trailing column values, which together
+    /// must cover every column.
+    fn pack(&self, leading: &[u128], trailing: &[u128]) -> Result<u128, CompositeError> {
+        debug_assert_eq!(leading.len() + trailing.len(), self.widths.len());
+
+        let mut key = 0u128;
+        for (column, (&width, &value)) in self
+            .widths
+            .iter()
+            .zip(leading.iter().chain(trailing))
+            .enumerate()
+        {
+            if value > column_mask(width) {
+                return Err(CompositeError::ValueOutOfRange { column, value });
+            }
+            key = key.checked_shl(width).unwrap_or(0) | value;
+        }
+
+        Ok(key)
+    }
+
+    /// Returns an error if the packed keys do not fit in a `u64`.
+    fn check_u64(&self) -> Result<(), CompositeError> {
+        if self.fits_in_u64() {
+            Ok(())
+        } else {
+            Err(CompositeError::TooWide {
+                bits: self.bits,
+                max: u64::BITS,
+            })
+        }
+    }
+}
+
+/// Returns the largest value that fits in a column of the given width.
+fn column_mask(width: u32) -> u128 {
+    u128::MAX >> (u128::BITS - width)
+}
+
+/// Returns a range that contains no keys.
+fn empty_range() -> RangeInclusive<u128> {
+    RangeInclusive::new(1, 0)
+}
+
+/// A key type with a fixed-width, order-preserving encoding, which can be packed into composite
+/// keys.
+///
+/// This is the compile-time counterpart of [`CompositeEncoder`]: with the `derive` feature,
+/// `#[derive(RangeKey)]` implements this trait for a struct by packing the encodings of its fields,
+/// and for an enum without fields by encoding the index of its variant. The derive also implements
+/// [`ToOrderedU64`](crate::ToOrderedU64), so the type can be used directly as the key of a
+/// [`KeyedRangeFilter`](crate::KeyedRangeFilter).
+///
+/// This is implemented for every primitive integer type, `bool`, and `char`.
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use grafite::{KeyedRangeFilter, OrderPreservingHasher, RangeKey};
+///
+/// #[derive(RangeKey, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// enum Region {
+///     Europe,
+///     Americas,
+///     Asia,
+/// }
+///
+/// #[derive(RangeKey, Clone, Copy)]
+/// struct Event {
+///     region: Region,
+///     tenant: u16,
+///     timestamp: u32,
+/// }
+///
+/// assert_eq!(<Event as RangeKey>::BITS, 2 + 16 + 32);
+///
+/// let events = [
+///     Event { region: Region::Asia, tenant: 7, timestamp: 1_000 },
+///     Event { region: Region::Europe, tenant: 3, timestamp: 2_000 },
+/// ];
+/// let hasher = OrderPreservingHasher::new(events.len(), 0.01, 100).unwrap();
+/// let rf = KeyedRangeFilter::new(events.into_iter(), hasher);
+///
+/// let start = Event { region: Region::Asia, tenant: 7, timestamp: 950 };
+/// let end = Event { region: Region::Asia, tenant: 7, timestamp: 1_010 };
+/// assert!(rf.query(start..end));
+/// # }
+/// ```
+pub trait RangeKey {
+    /// The number of bits that the encoding takes up, which is at most `128`.
+    const BITS: u32;
+
+    /// Returns the encoding of the key in the lowest [`Self::BITS`] bits of a `u128`.
+    ///
+    /// The encoding must be monotone: if `a <= b`, then `a.to_range_key() <= b.to_range_key()`.
+    fn to_range_key(&self) -> u128;
+
+    /// Returns the encoding of the key truncated to its `64` most significant bits, which is still
+    /// monotone, but cannot tell apart keys that only differ in the truncated bits.
+    fn to_range_key_u64(&self) -> u64 {
+        (self.to_range_key() >> Self::BITS.saturating_sub(u64::BITS)) as u64
+    }
+}
+
+macro_rules! impl_unsigned_range_key {
+    ($($t:ty),*) => {
+        $(
+            impl RangeKey for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn to_range_key(&self) -> u128 {
+                    *self as u128
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed_range_key {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            /// Flips the sign bit, so negative keys are ordered before non-negative keys.
+            impl RangeKey for $t {
+                const BITS: u32 = <$t>::BITS;
+
+                fn to_range_key(&self) -> u128 {
+                    (*self as $u ^ (1 << (<$u>::BITS - 1))) as u128
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned_range_key!(u8, u16, u32, u64, u128, usize);
+impl_signed_range_key!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+impl RangeKey for bool {
+    const BITS: u32 = 1;
+
+    fn to_range_key(&self) -> u128 {
+        *self as u128
+    }
+}
+
+/// Encodes the scalar value of the character, which is less than `2^21`.
+impl RangeKey for char {
+    const BITS: u32 = 21;
+
+    fn to_range_key(&self) -> u128 {
+        *self as u128
+    }
+}
@@ -0,0 +1,130 @@
+//! This module contains the [`CountingRangeFilter`] type, a [`RangeFilter`] that also stores how
+//! many keys were hashed to each stored hash value.
+
+use std::ops::{Range, RangeBounds};
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, EliasFano, OrderPreservingHasher, RangeFilter};
+
+/// A range filter that stores the multiplicity of every hash value, so that it can approximately
+/// count the keys within a range instead of only answering whether there are any.
+///
+/// The multiplicities are stored as an Elias-Fano encoding of their prefix sums, so counting the
+/// keys in a range costs the same rank operations as [`RangeFilter::rank_range`] plus two accesses
+/// per range of stored hashes. Every input key is counted, including duplicate keys.
+///
+/// The count of a range is never less than the number of keys within it, and overestimates it
+/// by the keys outside of the range whose hash falls into the hashed windows of the range, for the
+/// same reason as false positives of [`RangeFilter::query`]. A range that crosses a block boundary
+/// counts the keys of both of its windows, and a range of at least `r` keys, where `r` is the
+/// reduced universe size, counts every key. This makes the counts suitable as upper bounds for
+/// aggregation pushdown.
+///
+/// ```
+/// use grafite::{CountingRangeFilter, OrderPreservingHasher};
+///
+/// let values = [1, 2, 2, 3, 7, 8, 9, 15, 20];
+/// let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+/// let rf = CountingRangeFilter::new(values.into_iter(), hasher).unwrap();
+///
+/// assert!(rf.count_range(1..=3) >= 4);
+/// assert_eq!(rf.count_range(..0), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CountingRangeFilter {
+    /// The range filter over the distinct hash values.
+    filter: RangeFilter,
+    /// The prefix sums of the multiplicities, where entry `i` is the number of keys whose hash is
+    /// one of the first `i` stored hash values.
+    prefix_counts: EliasFano,
+}
+
+impl CountingRangeFilter {
+    /// Creates a new `CountingRangeFilter` given an iterator of values.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn new<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+    {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher);
+        }
+
+        let mut hashes: Vec<u64> = values.map(|x| hasher.hash(x)).collect();
+        hashes.sort_unstable();
+
+        // Collapse every run of equal hashes into a single hash and the running count.
+        let mut distinct = Vec::new();
+        let mut prefix_counts = vec![0];
+        for hash in hashes {
+            let total = prefix_counts
+                .last_mut()
+                .expect("there is always a prefix sum");
+            if distinct.last() == Some(&hash) {
+                *total += 1;
+            } else {
+                distinct.push(hash);
+                let total = *total + 1;
+                prefix_counts.push(total);
+            }
+        }
+
+        Ok(Self {
+            filter: RangeFilter::from_deduped_hashes(&distinct, hasher)?,
+            prefix_counts: EliasFano::from_slice(&prefix_counts),
+        })
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// See [`RangeFilter::query`] for more information.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        self.filter.query(range)
+    }
+
+    /// Returns an upper bound on the number of input keys, including duplicates, that lie within
+    /// the given range.
+    ///
+    /// Empty and reversed ranges always return `0`.
+    pub fn count_range<R>(&self, range: R) -> u64
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return 0;
+        };
+
+        self.filter
+            .window_indices(start, end)
+            .into_iter()
+            .map(|indices| self.count_indices(indices))
+            .sum()
+    }
+
+    /// Returns the total number of keys whose hashes are stored at the given indices.
+    fn count_indices(&self, indices: Range<usize>) -> u64 {
+        self.prefix_counts.get_unchecked(indices.end)
+            - self.prefix_counts.get_unchecked(indices.start)
+    }
+
+    /// Returns the total number of input keys, including duplicates.
+    pub fn total_count(&self) -> u64 {
+        self.prefix_counts
+            .get_unchecked(self.prefix_counts.len() - 1)
+    }
+
+    /// Returns the underlying range filter over the distinct hash values.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+
+    /// Returns the amount of space required to store this `CountingRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size() + self.prefix_counts.heap_size()
+    }
+}
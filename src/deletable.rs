@@ -0,0 +1,222 @@
+//! This module contains the [`DeletableRangeFilter`] type, a [`RangeFilter`] that supports
+//! removing keys by counting how many keys were hashed to each stored hash value.
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, OrderPreservingHasher, RangeFilter};
+
+/// A range filter that supports removing keys, for example when an LSM compaction drops keys.
+///
+/// Every stored hash value has a counter of how many inserted keys hashed to it. Removing a key
+/// decrements the counter of its hash, and hashes with a counter of zero no longer match any
+/// query. The counters take 4 bytes per distinct hash value in addition to the range filter.
+///
+/// The hashes with a non-zero counter are also marked in a bitmap, which is ranked through a
+/// Fenwick tree over the number of marked hashes in each of its words. A query counts the marked
+/// hashes in the hashed window of the range with two rank operations, like
+/// [`CountingRangeFilter::count_range`](crate::CountingRangeFilter::count_range), and removing the
+/// last key of a hash unmarks it in `O(log n)` time. The bitmap and the tree take about 2 bits per
+/// distinct hash value.
+///
+/// # False positive rate
+///
+/// Removing a key never makes the false positive rate worse than that of the filter with every key
+/// present, since the remaining hashes are a subset of the original ones.
+///
+/// However, **only keys that were inserted may be removed**. A key that was never inserted can
+/// share its hash with a key that was, and removing it decrements the counter of the other key,
+/// which causes false negatives once it reaches zero. The same happens if an inserted key is
+/// removed more times than it was inserted. Callers that cannot guarantee this should check the
+/// key against the source of truth first, or rebuild the filter instead.
+///
+/// ```
+/// use grafite::{DeletableRangeFilter, OrderPreservingHasher};
+///
+/// let values = [1, 2, 3, 7, 8, 9, 15, 20];
+/// let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+/// let mut rf = DeletableRangeFilter::new(values.into_iter(), hasher).unwrap();
+///
+/// assert!(rf.query(15..17));
+/// assert!(rf.remove(15));
+/// assert!(!rf.query(15..17));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeletableRangeFilter {
+    /// The range filter over every hash value that was ever inserted.
+    filter: RangeFilter,
+    /// The number of present keys with the hash value at the same index, saturated at
+    /// [`u32::MAX`].
+    counts: Vec<u32>,
+    /// The stored hash values whose counter is non-zero.
+    present: PresentHashes,
+}
+
+impl DeletableRangeFilter {
+    /// Creates a new `DeletableRangeFilter` given an iterator of values.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn new<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+    {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher);
+        }
+
+        let mut hashes: Vec<u64> = values.map(|x| hasher.hash(x)).collect();
+        hashes.sort_unstable();
+
+        // Collapse every run of equal hashes into a single hash and its count.
+        let mut distinct = Vec::new();
+        let mut counts: Vec<u32> = Vec::new();
+        for hash in hashes {
+            if distinct.last() == Some(&hash) {
+                let count = counts.last_mut().expect("every hash has a count");
+                *count = count.saturating_add(1);
+            } else {
+                distinct.push(hash);
+                counts.push(1);
+            }
+        }
+
+        Ok(Self {
+            filter: RangeFilter::from_deduped_hashes(&distinct, hasher)?,
+            present: PresentHashes::new(counts.len()),
+            counts,
+        })
+    }
+
+    /// Removes one occurrence of a key that was inserted into the filter.
+    ///
+    /// Returns `false` if no present key has the same hash, in which case the key was definitely
+    /// not inserted. See the [type-level documentation](Self) for why only inserted keys may be
+    /// removed.
+    pub fn remove(&mut self, x: u64) -> bool {
+        let hash = self.filter.hasher.hash(x);
+        if !self.filter.contains_hash(hash) {
+            return false;
+        }
+
+        let index = self.filter.ef.rank(hash);
+        let count = &mut self.counts[index];
+        match *count {
+            0 => false,
+            // A saturated counter has lost track of its multiplicity, so it is never decremented.
+            u32::MAX => true,
+            _ => {
+                *count -= 1;
+                if *count == 0 {
+                    self.present.remove(index);
+                }
+                true
+            }
+        }
+    }
+
+    /// Checks if there are any present elements within the given range.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        self.filter
+            .window_indices(start, end)
+            .into_iter()
+            .any(|indices| {
+                !indices.is_empty()
+                    && self.present.rank(indices.end) > self.present.rank(indices.start)
+            })
+    }
+
+    /// Checks if the given key may be among the present keys.
+    pub fn contains(&self, x: u64) -> bool {
+        self.query(x..=x)
+    }
+
+    /// Returns the underlying range filter over every hash value that was ever inserted, which
+    /// ignores removals.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+
+    /// Returns the amount of space required to store this `DeletableRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+            + self.counts.len() * std::mem::size_of::<u32>()
+            + self.present.heap_size()
+    }
+}
+
+/// A bitmap of the stored hash values with a non-zero counter, with a Fenwick tree over the number
+/// of set bits in each of its words to rank it while bits are being cleared.
+#[derive(Debug, Clone)]
+struct PresentHashes {
+    /// The bitmap, where bit `i % 64` of word `i / 64` is set if the hash at index `i` is present.
+    words: Vec<u64>,
+    /// The Fenwick tree over the number of set bits in each word, where entry `j - 1` is the number
+    /// of set bits in words `[j - lowbit(j), j)` and `lowbit(j)` is the lowest set bit of `j`.
+    tree: Vec<u64>,
+}
+
+impl PresentHashes {
+    /// Creates a new bitmap in which each of the `len` hashes is present.
+    fn new(len: usize) -> Self {
+        let mut words = vec![u64::MAX; len / 64];
+        if len % 64 != 0 {
+            words.push((1 << (len % 64)) - 1);
+        }
+
+        // Build the tree in linear time by pushing every entry into its parent.
+        let mut tree: Vec<u64> = words.iter().map(|w| w.count_ones() as u64).collect();
+        for j in 1..=tree.len() {
+            let parent = j + (j & j.wrapping_neg());
+            if parent <= tree.len() {
+                tree[parent - 1] += tree[j - 1];
+            }
+        }
+
+        Self { words, tree }
+    }
+
+    /// Returns the number of present hashes among the first `i` hashes.
+    fn rank(&self, i: usize) -> usize {
+        let (word, bit) = (i / 64, i % 64);
+
+        let mut rank = 0;
+        let mut j = word;
+        while j > 0 {
+            rank += self.tree[j - 1];
+            j &= j - 1;
+        }
+
+        if bit != 0 {
+            rank += (self.words[word] & ((1 << bit) - 1)).count_ones() as u64;
+        }
+        rank as usize
+    }
+
+    /// Marks the hash at index `i`, which must be present, as no longer present.
+    fn remove(&mut self, i: usize) {
+        let (word, bit) = (i / 64, i % 64);
+        debug_assert!(self.words[word] & (1 << bit) != 0);
+        self.words[word] &= !(1 << bit);
+
+        let mut j = word + 1;
+        while j <= self.tree.len() {
+            self.tree[j - 1] -= 1;
+            j += j & j.wrapping_neg();
+        }
+    }
+
+    /// Returns the amount of space required to store this bitmap on the heap.
+    fn heap_size(&self) -> usize {
+        (self.words.len() + self.tree.len()) * std::mem::size_of::<u64>()
+    }
+}
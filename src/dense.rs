@@ -0,0 +1,322 @@
+//! This module contains the [`DenseBitVector`] type, a plain bit vector encoding of a set of
+//! integers that is smaller than [`EliasFano`](crate::EliasFano) for dense sets.
+
+use crate::utils::select_in_word;
+use crate::MemoryBreakdown;
+
+/// The number of words between two consecutive rank samples.
+const BLOCK_WORDS: usize = 8;
+
+/// A plain bit vector over the integers from `0` up to the largest stored value, where the bit of
+/// every stored value is set, along with a rank directory.
+///
+/// This takes about `1.125` bits per integer in the universe regardless of how many values are
+/// stored, while an Elias-Fano encoding of `n` values out of `u` takes about `2 + log2(u / n)` bits
+/// per value. The bit vector is therefore smaller once more than roughly a quarter of the universe
+/// is stored, and it answers membership and predecessor queries within a word without a select.
+///
+/// ```
+/// use grafite::DenseBitVector;
+///
+/// let bits = DenseBitVector::from_slice(&[1, 2, 3, 5, 8]);
+///
+/// assert!(bits.contains(5));
+/// assert_eq!(bits.predecessor(7), Some(5));
+/// assert_eq!(bits.successor(6), Some(8));
+/// assert_eq!(bits.rank(5), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DenseBitVector {
+    /// The bits of the integers from `0` up to and including the largest stored value.
+    bits: Vec<u64>,
+    /// The number of bits, which is the largest stored value plus one.
+    universe: u64,
+    /// The number of stored values.
+    len: usize,
+    /// The number of set bits before every block of `BLOCK_WORDS` words, followed by the total
+    /// number of set bits.
+    ranks: Vec<u64>,
+}
+
+impl DenseBitVector {
+    /// Creates a new `DenseBitVector` from the given strictly increasing values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not strictly increasing, or if the bit vector does not fit in
+    /// memory.
+    pub fn from_slice(values: &[u64]) -> Self {
+        let mut builder = DenseBitVectorBuilder::new(values.len(), values.last().copied());
+        for &x in values {
+            builder.push(x);
+        }
+        builder.finish()
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no stored values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bits, which is the largest stored value plus one.
+    pub fn universe(&self) -> u64 {
+        self.universe
+    }
+
+    /// Checks if the given value is stored.
+    pub fn contains(&self, value: u64) -> bool {
+        value < self.universe && self.bits[(value / 64) as usize] & (1 << (value % 64)) != 0
+    }
+
+    /// Returns the value at position `index`, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        (index < self.len).then(|| self.get_unchecked(index))
+    }
+
+    /// Returns the value at position `index`.
+    ///
+    /// The `index` must be less than [`Self::len`], otherwise this may panic or return an
+    /// arbitrary value.
+    pub fn get_unchecked(&self, index: usize) -> u64 {
+        let rank = index as u64;
+
+        // The last block that starts with at most `rank` set bits contains the answer.
+        let block = self.ranks.partition_point(|&r| r <= rank) - 1;
+        let mut remaining = (rank - self.ranks[block]) as usize;
+
+        let start = block * BLOCK_WORDS;
+        for (i, &word) in self.bits[start..].iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                return ((start + i) * 64 + select_in_word(word, remaining)) as u64;
+            }
+            remaining -= ones;
+        }
+
+        panic!("index {index} is out of bounds")
+    }
+
+    /// Returns the number of stored values that are less than `value`.
+    pub fn rank(&self, value: u64) -> usize {
+        if value >= self.universe {
+            return self.len;
+        }
+
+        let word = (value / 64) as usize;
+        let block = word / BLOCK_WORDS;
+
+        let before: u64 = self.bits[block * BLOCK_WORDS..word]
+            .iter()
+            .map(|w| w.count_ones() as u64)
+            .sum();
+        let partial = self.bits[word] & ((1 << (value % 64)) - 1);
+
+        (self.ranks[block] + before) as usize + partial.count_ones() as usize
+    }
+
+    /// Returns the largest stored value that is less than or equal to `value`, or `None` if every
+    /// stored value is greater than `value`.
+    pub fn predecessor(&self, value: u64) -> Option<u64> {
+        if self.len == 0 {
+            return None;
+        }
+        if value >= self.universe - 1 {
+            return Some(self.universe - 1);
+        }
+
+        // Look for a set bit at or below `value` in its own word first.
+        let word = (value / 64) as usize;
+        let below = self.bits[word] & (u64::MAX >> (63 - value % 64));
+        if below != 0 {
+            return Some(word as u64 * 64 + 63 - below.leading_zeros() as u64);
+        }
+
+        match self.rank(value) {
+            0 => None,
+            rank => Some(self.get_unchecked(rank - 1)),
+        }
+    }
+
+    /// Returns the smallest stored value that is greater than or equal to `value`, or `None` if
+    /// every stored value is less than `value`.
+    pub fn successor(&self, value: u64) -> Option<u64> {
+        if value >= self.universe {
+            return None;
+        }
+
+        // Look for a set bit at or above `value` in its own word first.
+        let word = (value / 64) as usize;
+        let above = self.bits[word] & (u64::MAX << (value % 64));
+        if above != 0 {
+            return Some(word as u64 * 64 + above.trailing_zeros() as u64);
+        }
+
+        // Since `value` is less than the largest stored value, there is always a successor.
+        Some(self.get_unchecked(self.rank(value)))
+    }
+
+    /// Returns an iterator over the stored values in increasing order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = u64> + '_ {
+        Iter {
+            bits: &self.bits,
+            remaining: self.len,
+            word_index: 0,
+            word: self.bits.first().copied().unwrap_or(0),
+        }
+    }
+
+    /// Returns the amount of space required to store this `DenseBitVector` on the heap.
+    pub fn heap_size(&self) -> usize {
+        std::mem::size_of_val(self.bits.as_slice()) + std::mem::size_of_val(self.ranks.as_slice())
+    }
+
+    /// Returns the space used by the bits and rank samples of this `DenseBitVector`, which adds up
+    /// to [`Self::heap_size`].
+    pub fn memory_breakdown(&self) -> MemoryBreakdown {
+        MemoryBreakdown {
+            bit_vector: std::mem::size_of_val(self.bits.as_slice()),
+            index: std::mem::size_of_val(self.ranks.as_slice()),
+            ..MemoryBreakdown::default()
+        }
+    }
+}
+
+/// Builds a [`DenseBitVector`] from values that are pushed one at a time in strictly increasing
+/// order, so that the values never have to be collected first.
+///
+/// The number of values and the largest value must be known up front, since they determine the
+/// size of the bit vector.
+pub(crate) struct DenseBitVectorBuilder {
+    /// The bits of the integers from `0` up to and including the largest value.
+    bits: Vec<u64>,
+    /// The number of bits, which is the largest value plus one.
+    universe: u64,
+    /// The number of values that will be pushed.
+    len: usize,
+    /// The number of values pushed so far.
+    pushed: usize,
+    /// The last value pushed so far.
+    last: u64,
+}
+
+impl DenseBitVectorBuilder {
+    /// Allocates the bit vector for `len` values whose largest value is `max`, which is `None` if
+    /// there are no values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bit vector does not fit in memory.
+    pub(crate) fn new(len: usize, max: Option<u64>) -> Self {
+        let universe = max.map_or(0, |max| {
+            max.checked_add(1).expect("the universe is too large")
+        });
+        let num_words = usize::try_from(universe.div_ceil(64)).expect("the universe is too large");
+
+        Self {
+            bits: vec![0u64; num_words],
+            universe,
+            len,
+            pushed: 0,
+            last: 0,
+        }
+    }
+
+    /// Appends the next value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not strictly increasing, or if more values, or larger values, are
+    /// pushed than the bit vector was allocated for.
+    pub(crate) fn push(&mut self, x: u64) {
+        assert!(
+            self.pushed == 0 || self.last < x,
+            "values must be strictly increasing"
+        );
+        assert!(
+            self.pushed < self.len && x < self.universe,
+            "more or larger values were pushed than the bit vector was allocated for"
+        );
+
+        self.bits[(x / 64) as usize] |= 1 << (x % 64);
+
+        self.pushed += 1;
+        self.last = x;
+    }
+
+    /// Builds the rank directory and returns the finished bit vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer values were pushed than the bit vector was allocated for.
+    pub(crate) fn finish(self) -> DenseBitVector {
+        assert_eq!(
+            self.pushed, self.len,
+            "fewer values were pushed than the bit vector was allocated for"
+        );
+
+        let bits = self.bits;
+        let mut ranks = Vec::with_capacity(bits.len().div_ceil(BLOCK_WORDS) + 1);
+        let mut total = 0;
+        for block in bits.chunks(BLOCK_WORDS) {
+            ranks.push(total);
+            total += block.iter().map(|w| w.count_ones() as u64).sum::<u64>();
+        }
+        ranks.push(total);
+
+        DenseBitVector {
+            bits,
+            universe: self.universe,
+            len: self.len,
+            ranks,
+        }
+    }
+}
+
+/// Returns the number of words required to store a `DenseBitVector` with `universe` bits.
+pub(crate) fn layout_words(universe: u128) -> u128 {
+    let num_words = universe.div_ceil(64);
+    num_words + num_words.div_ceil(BLOCK_WORDS as u128) + 1
+}
+
+/// An iterator over the values of a [`DenseBitVector`].
+struct Iter<'a> {
+    bits: &'a [u64],
+    /// The number of values that have not been returned yet.
+    remaining: usize,
+    /// The index of the current word.
+    word_index: usize,
+    /// The bits of the current word that have not been visited yet.
+    word: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        while self.word == 0 {
+            self.word_index += 1;
+            self.word = self.bits[self.word_index];
+        }
+
+        let value = self.word_index as u64 * 64 + self.word.trailing_zeros() as u64;
+        self.word &= self.word - 1;
+        self.remaining -= 1;
+
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
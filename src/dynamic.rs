@@ -0,0 +1,262 @@
+//! This module contains the [`DynamicRangeFilter`] type, a [`RangeFilter`] that accepts inserts
+//! through a small sorted delta buffer.
+
+use std::ops::RangeBounds;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::filter::range_endpoints;
+use crate::hash::HashedRange;
+use crate::RangeFilter;
+
+/// The default number of buffered hashes that triggers a merge.
+const DEFAULT_MERGE_THRESHOLD: usize = 1 << 12;
+
+/// An updatable range filter that absorbs inserts into a sorted delta buffer of hashes and
+/// periodically merges them into its Elias-Fano encoding.
+///
+/// Queries are answered against both the encoded filter and the delta buffer. Once the buffer
+/// holds `merge_threshold` hashes, it is frozen and merged into a rebuilt encoding on a background
+/// thread, and later inserts go into a new buffer, so the cost of rebuilding is amortized over many
+/// inserts instead of being paid on every batch. Until the rebuilt encoding is swapped in, which
+/// happens on the first insert after the merge has finished, queries also check the frozen hashes.
+/// If the buffer fills up again before the previous merge has finished, that insert waits for it.
+/// A merge can also be run to completion at any time with [`Self::merge`].
+///
+/// Inserted keys are hashed with the hasher of the filter, whose parameters were chosen for a
+/// fixed number of elements. Since the false positive rate grows linearly with the number of
+/// distinct keys, inserting many more keys than the hasher was created for degrades it
+/// accordingly, and the filter should then be rebuilt with a new hasher.
+///
+/// ```
+/// use grafite::{DynamicRangeFilter, OrderPreservingHasher, RangeFilter};
+///
+/// let hasher = OrderPreservingHasher::new(16, 0.01, 20).unwrap();
+/// let rf = RangeFilter::new([1, 2, 3, 7].into_iter(), hasher);
+///
+/// let mut rf = DynamicRangeFilter::new(rf);
+/// assert!(!rf.query(10..15));
+///
+/// rf.insert(12);
+/// assert!(rf.query(10..15));
+///
+/// rf.merge();
+/// assert!(rf.query(10..15));
+/// ```
+#[derive(Debug)]
+pub struct DynamicRangeFilter {
+    /// The encoded filter that every merged hash is stored in, which is shared with the thread of
+    /// a running merge.
+    filter: Arc<RangeFilter>,
+    /// The merge that is running in the background, if any.
+    pending: Option<PendingMerge>,
+    /// The sorted and distinct hashes that have not been merged yet, and are not being merged.
+    delta: Vec<u64>,
+    /// The number of buffered hashes that triggers a merge.
+    merge_threshold: usize,
+}
+
+impl DynamicRangeFilter {
+    /// Creates a new `DynamicRangeFilter` on top of an existing filter, with the default merge
+    /// threshold.
+    pub fn new(filter: RangeFilter) -> Self {
+        Self::with_merge_threshold(filter, DEFAULT_MERGE_THRESHOLD)
+    }
+
+    /// Creates a new `DynamicRangeFilter` on top of an existing filter, which merges its delta
+    /// buffer once it holds `merge_threshold` hashes.
+    ///
+    /// A larger threshold makes inserts cheaper on average, at the cost of more memory and slower
+    /// inserts into the delta buffer, which are linear in its size.
+    pub fn with_merge_threshold(filter: RangeFilter, merge_threshold: usize) -> Self {
+        Self {
+            filter: Arc::new(filter),
+            pending: None,
+            delta: Vec::new(),
+            merge_threshold,
+        }
+    }
+
+    /// Inserts a key into the filter, starting a background merge of the delta buffer once it is
+    /// full.
+    pub fn insert(&mut self, x: u64) {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|p| p.handle.is_finished())
+        {
+            self.finish_merge();
+        }
+
+        let hash = self.filter.hasher.hash(x);
+        let merging = self.pending.as_ref().map_or(&[][..], |p| &p.hashes[..]);
+        if self.filter.contains_hash(hash) || merging.binary_search(&hash).is_ok() {
+            return;
+        }
+
+        if let Err(i) = self.delta.binary_search(&hash) {
+            self.delta.insert(i, hash);
+            if self.delta.len() >= self.merge_threshold {
+                self.start_merge();
+            }
+        }
+    }
+
+    /// Merges every buffered hash into a rebuilt Elias-Fano encoding, waiting for a merge that is
+    /// running in the background to finish first.
+    pub fn merge(&mut self) {
+        self.finish_merge();
+
+        if !self.delta.is_empty() {
+            let delta = std::mem::take(&mut self.delta);
+            self.filter = Arc::new(merge_hashes(&self.filter, &delta));
+        }
+    }
+
+    /// Returns `true` if a merge is running in the background.
+    pub fn is_merging(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Freezes the delta buffer and merges it on a new thread, after waiting for the previous
+    /// merge to finish.
+    fn start_merge(&mut self) {
+        self.finish_merge();
+
+        let hashes = Arc::new(std::mem::take(&mut self.delta));
+        let (filter, delta) = (Arc::clone(&self.filter), Arc::clone(&hashes));
+        let handle = std::thread::spawn(move || merge_hashes(&filter, &delta));
+
+        self.pending = Some(PendingMerge { hashes, handle });
+    }
+
+    /// Waits for the merge that is running in the background, if any, and swaps in its filter.
+    fn finish_merge(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            let filter = pending
+                .handle
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e));
+            self.filter = Arc::new(filter);
+        }
+    }
+
+    /// Checks if there are any elements within the given range among the merged and buffered
+    /// keys.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        match self.filter.hasher.hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => self.query_window(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                self.query_window(start1, end1) || self.query_window(start2, end2)
+            }
+            HashedRange::All => true,
+        }
+    }
+
+    /// Checks if there are any merged, merging or buffered hashes within a hashed window.
+    fn query_window(&self, start_hash: u64, end_hash: u64) -> bool {
+        self.filter.query_prehashed(start_hash, end_hash)
+            || self
+                .pending
+                .as_ref()
+                .is_some_and(|p| query_sorted(&p.hashes, start_hash, end_hash))
+            || query_sorted(&self.delta, start_hash, end_hash)
+    }
+
+    /// Returns the number of buffered hashes that have not been merged yet, and are not being
+    /// merged in the background.
+    pub fn delta_len(&self) -> usize {
+        self.delta.len()
+    }
+
+    /// Returns the encoded filter, which does not include the buffered hashes, nor those of a merge
+    /// that is still running.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+
+    /// Merges the delta buffer and returns the resulting filter.
+    pub fn into_filter(mut self) -> RangeFilter {
+        self.merge();
+        Arc::try_unwrap(self.filter).unwrap_or_else(|filter| (*filter).clone())
+    }
+
+    /// Returns the amount of space required to store this `DynamicRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        let merging = self.pending.as_ref().map_or(0, |p| p.hashes.capacity());
+        self.filter.heap_size() + (merging + self.delta.capacity()) * std::mem::size_of::<u64>()
+    }
+}
+
+impl Clone for DynamicRangeFilter {
+    /// Clones the filter, moving the hashes of a merge that is still running into the delta buffer
+    /// of the clone instead of waiting for it.
+    fn clone(&self) -> Self {
+        let mut delta = self.delta.clone();
+        if let Some(pending) = &self.pending {
+            delta.extend_from_slice(&pending.hashes);
+            delta.sort_unstable();
+        }
+
+        Self {
+            filter: Arc::clone(&self.filter),
+            pending: None,
+            delta,
+            merge_threshold: self.merge_threshold,
+        }
+    }
+}
+
+/// A merge of a frozen delta buffer that runs on a background thread.
+#[derive(Debug)]
+struct PendingMerge {
+    /// The sorted and distinct hashes that are being merged.
+    hashes: Arc<Vec<u64>>,
+    /// The thread that builds the merged filter.
+    handle: JoinHandle<RangeFilter>,
+}
+
+/// Builds a filter over the hashes of `filter` and the sorted hashes of `delta`, which must not
+/// be stored in `filter`.
+fn merge_hashes(filter: &RangeFilter, delta: &[u64]) -> RangeFilter {
+    let mut hashes = Vec::with_capacity(filter.ef.len() + delta.len());
+
+    // Merge the two sorted and disjoint sequences of hashes.
+    let mut delta = delta.iter().copied().peekable();
+    for hash in filter.iter_hashes() {
+        while let Some(next) = delta.next_if(|&next| next < hash) {
+            hashes.push(next);
+        }
+        hashes.push(hash);
+    }
+    hashes.extend(delta);
+
+    RangeFilter::from_sorted_unchecked(&hashes, filter.hasher)
+}
+
+/// Checks if there are any of the sorted hashes within a hashed window.
+fn query_sorted(hashes: &[u64], start_hash: u64, end_hash: u64) -> bool {
+    let (Some(&min_hash), Some(&max_hash)) = (hashes.first(), hashes.last()) else {
+        return false;
+    };
+
+    // See `RangeFilter::query_prehashed` for an explanation of the wrapped case.
+    if start_hash > end_hash {
+        return min_hash <= end_hash || max_hash >= start_hash;
+    }
+
+    // The number of hashes that are less than or equal to the end hash.
+    match hashes.partition_point(|&h| h <= end_hash) {
+        0 => false,
+        i => hashes[i - 1] >= start_hash,
+    }
+}
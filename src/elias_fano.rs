@@ -0,0 +1,529 @@
+//! This module contains the [`EliasFano`] type, the succinct encoding of non-decreasing integer
+//! sequences that a [`RangeFilter`](crate::RangeFilter) stores its hash values in by default.
+//!
+//! Every value `x` is split into its `l` low bits, which are packed into an array of `l`-bit
+//! integers, and its high bits `x >> l`, which are stored in unary: the `i`-th value sets the bit
+//! at position `(x >> l) + i` of a bit vector. Choosing `l = floor(log2(u / n))` for `n` values
+//! less than `u` takes at most `2 + log2(u / n)` bits per value. Accessing a value or answering a
+//! predecessor query selects the `i`-th one or the `h`-th zero of the high bits, which is sped up
+//! by sampling the position of every 256-th one and zero.
+//!
+//! The entire encoding is stored in a single array of 64-bit words with the following layout, so
+//! that it can be written out and queried directly from borrowed or memory-mapped words with
+//! [`EliasFano::from_words`]:
+//!
+//! | Field           | Words                     | Description                                |
+//! |-----------------|---------------------------|--------------------------------------------|
+//! | Version         | 1                         | The layout version, currently `1`.         |
+//! | Length          | 1                         | The number of values `n`.                  |
+//! | Low length      | 1                         | The number of low bits `l` per value.      |
+//! | High length     | 1                         | The number of bits `m` in the high bits.   |
+//! | Low bits        | `ceil(n * l / 64)`        | The packed low bits of every value.        |
+//! | High bits       | `ceil(m / 64)`            | The unary encoded high bits.               |
+//! | Select samples  | `ceil(n / 256)`           | The position of every 256-th one.          |
+//! | Select0 samples | `ceil((m - n) / 256)`     | The position of every 256-th zero.         |
+
+use crate::utils::select_in_word;
+use crate::MemoryBreakdown;
+
+/// The version of the word layout written by this module.
+const LAYOUT_VERSION: u64 = 1;
+
+/// The number of header words at the start of the layout.
+const HEADER_WORDS: usize = 4;
+
+/// The number of ones (or zeros) of the high bits between two consecutive select samples.
+const SAMPLE_RATE: usize = 256;
+
+/// A succinct encoding of a non-decreasing sequence of 64-bit integers that supports access,
+/// rank, predecessor, and successor queries.
+///
+/// The encoding is stored in a single array of words `S`, which is a `Vec<u64>` by default. Any
+/// other storage that implements `AsRef<[u64]>`, such as a borrowed slice of a memory-mapped file
+/// or a [`Cow`](std::borrow::Cow), can be queried directly with [`Self::from_words`]. See the
+/// [module-level documentation](crate::elias_fano) for a description of the layout.
+///
+/// ```
+/// use grafite::EliasFano;
+///
+/// let ef = EliasFano::from_slice(&[1, 3, 3, 7, 100]);
+///
+/// assert_eq!(ef.len(), 5);
+/// assert_eq!(ef.get(2), Some(3));
+/// assert_eq!(ef.predecessor(6), Some(3));
+/// assert_eq!(ef.successor(8), Some(100));
+/// assert_eq!(ef.rank(7), 3);
+///
+/// // The same encoding can be queried from borrowed words.
+/// let borrowed = EliasFano::from_words(ef.as_words()).unwrap();
+/// assert_eq!(borrowed.predecessor(99), Some(7));
+/// ```
+#[derive(Debug, Clone)]
+pub struct EliasFano<S = Vec<u64>> {
+    /// The words of the layout, including the header.
+    words: S,
+    /// The number of values.
+    len: usize,
+    /// The number of low bits per value.
+    low_len: u32,
+    /// The number of bits in the high bits.
+    high_len: usize,
+    /// The index of the first word of the high bits.
+    high_start: usize,
+    /// The index of the first select sample.
+    select1_start: usize,
+    /// The index of the first select0 sample.
+    select0_start: usize,
+}
+
+impl EliasFano {
+    /// Creates a new `EliasFano` encoding of the given non-decreasing values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not sorted in non-decreasing order.
+    pub fn from_slice(values: &[u64]) -> Self {
+        let max = values.last().copied().unwrap_or(0);
+
+        let mut builder = EliasFanoBuilder::new(values.len(), max);
+        for &x in values {
+            builder.push(x);
+        }
+        builder.finish()
+    }
+}
+
+/// Builds an [`EliasFano`] encoding from values that are pushed one at a time in non-decreasing
+/// order, so that the values never have to be collected first.
+///
+/// The number of values and the largest value must be known up front, since they determine the
+/// layout.
+pub(crate) struct EliasFanoBuilder {
+    /// The words of the layout, up to the end of the high bits.
+    words: Vec<u64>,
+    /// The number of values that will be pushed.
+    len: usize,
+    /// The largest value that will be pushed.
+    max: u64,
+    /// The number of low bits per value.
+    low_len: u32,
+    /// The number of bits in the high bits.
+    high_len: usize,
+    /// The number of words of the low bits.
+    low_words: usize,
+    /// The number of values pushed so far.
+    pushed: usize,
+    /// The last value pushed so far.
+    last: u64,
+}
+
+impl EliasFanoBuilder {
+    /// Allocates the layout for `len` values whose largest value is `max`.
+    pub(crate) fn new(len: usize, max: u64) -> Self {
+        let (low_len, high_len) = parameters(len, max);
+        let low_words = (len * low_len as usize).div_ceil(64);
+        let high_words = high_len.div_ceil(64);
+
+        let mut words = vec![0; HEADER_WORDS + low_words + high_words];
+        words[..HEADER_WORDS].copy_from_slice(&[
+            LAYOUT_VERSION,
+            len as u64,
+            low_len as u64,
+            high_len as u64,
+        ]);
+
+        Self {
+            words,
+            len,
+            max,
+            low_len,
+            high_len,
+            low_words,
+            pushed: 0,
+            last: 0,
+        }
+    }
+
+    /// Appends the next value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values are not sorted in non-decreasing order, or if more values, or larger
+    /// values, are pushed than the layout was allocated for.
+    pub(crate) fn push(&mut self, x: u64) {
+        assert!(
+            self.pushed == 0 || self.last <= x,
+            "values must be sorted in non-decreasing order"
+        );
+        assert!(
+            self.pushed < self.len && x <= self.max,
+            "more or larger values were pushed than the layout was allocated for"
+        );
+
+        let i = self.pushed;
+        let (low, high) = self.words[HEADER_WORDS..].split_at_mut(self.low_words);
+        if self.low_len > 0 {
+            let low_mask = (1u64 << self.low_len) - 1;
+            write_bits(low, i * self.low_len as usize, self.low_len, x & low_mask);
+        }
+
+        let pos = (x >> self.low_len) as usize + i;
+        high[pos / 64] |= 1 << (pos % 64);
+
+        self.pushed += 1;
+        self.last = x;
+    }
+
+    /// Samples the high bits and returns the finished encoding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer values were pushed than the layout was allocated for.
+    pub(crate) fn finish(self) -> EliasFano {
+        assert_eq!(
+            self.pushed, self.len,
+            "fewer values were pushed than the layout was allocated for"
+        );
+
+        let Self {
+            mut words,
+            len,
+            high_len,
+            low_words,
+            ..
+        } = self;
+
+        // Sample the position of every `SAMPLE_RATE`-th one and zero of the high bits.
+        let mut select1 = Vec::with_capacity(len.div_ceil(SAMPLE_RATE));
+        let mut select0 = Vec::with_capacity((high_len - len).div_ceil(SAMPLE_RATE));
+        let (mut ones, mut zeros) = (0, 0);
+        for (index, &word) in words[HEADER_WORDS + low_words..].iter().enumerate() {
+            // The bits past the end of the high bits are not zeros.
+            let valid = match high_len - index * 64 {
+                bits if bits >= 64 => u64::MAX,
+                bits => (1 << bits) - 1,
+            };
+
+            sample(&mut select1, &mut ones, index, word);
+            sample(&mut select0, &mut zeros, index, !word & valid);
+        }
+
+        words.extend_from_slice(&select1);
+        words.extend_from_slice(&select0);
+
+        EliasFano::from_words(words).expect("the layout was just written")
+    }
+}
+
+impl<S: AsRef<[u64]>> EliasFano<S> {
+    /// Creates an `EliasFano` encoding from the words of its layout, as returned by
+    /// [`Self::as_words`], without copying them.
+    ///
+    /// Returns `None` if the header is invalid or does not match the number of words. The
+    /// contents of the words are not validated, so words that were not written by this module may
+    /// produce wrong answers or panics, but never undefined behavior.
+    pub fn from_words(words: S) -> Option<Self> {
+        let header = words.as_ref().get(..HEADER_WORDS)?;
+        let [version, len, low_len, high_len] = header.try_into().ok()?;
+        if version != LAYOUT_VERSION || low_len > 63 || high_len <= len {
+            return None;
+        }
+
+        let len = usize::try_from(len).ok()?;
+        let low_len = low_len as u32;
+        let high_len = usize::try_from(high_len).ok()?;
+
+        let high_start = HEADER_WORDS + len.checked_mul(low_len as usize)?.div_ceil(64);
+        let select1_start = high_start + high_len.div_ceil(64);
+        let select0_start = select1_start + len.div_ceil(SAMPLE_RATE);
+        let end = select0_start + (high_len - len).div_ceil(SAMPLE_RATE);
+        if end != words.as_ref().len() {
+            return None;
+        }
+
+        Some(Self {
+            words,
+            len,
+            low_len,
+            high_len,
+            high_start,
+            select1_start,
+            select0_start,
+        })
+    }
+
+    /// Returns the words of the layout, which can be stored and passed to [`Self::from_words`].
+    pub fn as_words(&self) -> &[u64] {
+        self.words.as_ref()
+    }
+
+    /// Returns the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the value at position `index`, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        (index < self.len).then(|| self.get_unchecked(index))
+    }
+
+    /// Returns the value at position `index`.
+    ///
+    /// The `index` must be less than [`Self::len`], otherwise this may panic or return an
+    /// arbitrary value.
+    pub fn get_unchecked(&self, index: usize) -> u64 {
+        let high = (self.select1(index) - index) as u64;
+        (high << self.low_len) | self.low(index)
+    }
+
+    /// Returns the number of values that are less than `value`.
+    pub fn rank(&self, value: u64) -> usize {
+        let Some(max) = self.max() else {
+            return 0;
+        };
+        if value > max {
+            return self.len;
+        }
+
+        // The values with the same high part as `value` are at the indices `start..end`, which is
+        // usually a very short range, and they are sorted by their low bits.
+        let high = (value >> self.low_len) as usize;
+        let start = match high {
+            0 => 0,
+            _ => self.select0(high - 1) - (high - 1),
+        };
+        let end = self.select0(high) - high;
+
+        let low = value & self.low_mask();
+        let (mut lo, mut hi) = (start, end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.low(mid) < low {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Returns the largest value that is less than or equal to `value`, or `None` if every value
+    /// is greater than `value`.
+    pub fn predecessor(&self, value: u64) -> Option<u64> {
+        let max = self.max()?;
+        if value >= max {
+            return Some(max);
+        }
+
+        // Since `value < max`, this cannot overflow.
+        match self.rank(value + 1) {
+            0 => None,
+            rank => Some(self.get_unchecked(rank - 1)),
+        }
+    }
+
+    /// Returns the smallest value that is greater than or equal to `value`, or `None` if every
+    /// value is less than `value`.
+    pub fn successor(&self, value: u64) -> Option<u64> {
+        let rank = self.rank(value);
+        (rank < self.len).then(|| self.get_unchecked(rank))
+    }
+
+    /// Returns an iterator over the values in non-decreasing order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = u64> + '_ {
+        Iter {
+            ef: self,
+            index: 0,
+            word_index: 0,
+            word: self.high_words().first().copied().unwrap_or(0),
+        }
+    }
+
+    /// Returns the amount of space required to store the words of this `EliasFano` encoding.
+    pub fn heap_size(&self) -> usize {
+        std::mem::size_of_val(self.as_words())
+    }
+
+    /// Returns the space used by the low bits, high bits, select samples, and header of this
+    /// `EliasFano` encoding, which adds up to [`Self::heap_size`].
+    pub fn memory_breakdown(&self) -> MemoryBreakdown {
+        let bytes = |words: usize| words * std::mem::size_of::<u64>();
+
+        MemoryBreakdown {
+            low_bits: bytes(self.high_start - HEADER_WORDS),
+            high_bits: bytes(self.select1_start - self.high_start),
+            index: bytes(self.as_words().len() - self.select1_start),
+            other: bytes(HEADER_WORDS),
+            ..MemoryBreakdown::default()
+        }
+    }
+
+    /// Returns the largest value, or `None` if there are no values.
+    fn max(&self) -> Option<u64> {
+        self.len.checked_sub(1).map(|i| self.get_unchecked(i))
+    }
+
+    fn low_mask(&self) -> u64 {
+        (1 << self.low_len) - 1
+    }
+
+    /// Returns the low bits of the value at position `index`.
+    fn low(&self, index: usize) -> u64 {
+        if self.low_len == 0 {
+            return 0;
+        }
+
+        let low = &self.as_words()[HEADER_WORDS..self.high_start];
+        read_bits(low, index * self.low_len as usize, self.low_len)
+    }
+
+    fn high_words(&self) -> &[u64] {
+        &self.as_words()[self.high_start..self.select1_start]
+    }
+
+    /// Returns the position of the `rank`-th one (counting from zero) in the high bits.
+    fn select1(&self, rank: usize) -> usize {
+        let samples = &self.as_words()[self.select1_start..self.select0_start];
+        let sample = samples[rank / SAMPLE_RATE] as usize;
+
+        select(self.high_words(), sample, rank % SAMPLE_RATE, |word| word)
+    }
+
+    /// Returns the position of the `rank`-th zero (counting from zero) in the high bits.
+    fn select0(&self, rank: usize) -> usize {
+        let samples = &self.as_words()[self.select0_start..];
+        let sample = samples[rank / SAMPLE_RATE] as usize;
+
+        let pos = select(self.high_words(), sample, rank % SAMPLE_RATE, |word| !word);
+        debug_assert!(pos < self.high_len, "there are not enough zeros");
+        pos
+    }
+}
+
+/// Returns the number of low bits per value and the number of bits in the high bits of the
+/// encoding of `len` values whose maximum is `max`.
+fn parameters(len: usize, max: u64) -> (u32, usize) {
+    // The number of low bits is `floor(log2(u / n))` with `u = max + 1`, capped so that the high
+    // bits of every value are shifted by less than 64 bits.
+    let ratio = (max as u128 + 1) / len.max(1) as u128;
+    let low_len = ratio.checked_ilog2().unwrap_or(0).min(63);
+
+    // One bit per value, plus one zero for every possible high part up to that of the maximum.
+    let high_len = len + (max >> low_len) as usize + 1;
+
+    (low_len, high_len)
+}
+
+/// Returns the number of words in the layout of the encoding of `len` values whose maximum is
+/// `max`.
+pub(crate) fn layout_words(len: usize, max: u64) -> usize {
+    let (low_len, high_len) = parameters(len, max);
+
+    HEADER_WORDS
+        + (len * low_len as usize).div_ceil(64)
+        + high_len.div_ceil(64)
+        + len.div_ceil(SAMPLE_RATE)
+        + (high_len - len).div_ceil(SAMPLE_RATE)
+}
+
+/// An iterator over the values of an [`EliasFano`] encoding, which scans the high bits once.
+struct Iter<'a, S> {
+    ef: &'a EliasFano<S>,
+    /// The index of the next value.
+    index: usize,
+    /// The index of the current word of the high bits.
+    word_index: usize,
+    /// The bits of the current word that have not been visited yet.
+    word: u64,
+}
+
+impl<S: AsRef<[u64]>> Iterator for Iter<'_, S> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index >= self.ef.len {
+            return None;
+        }
+
+        while self.word == 0 {
+            self.word_index += 1;
+            self.word = self.ef.high_words()[self.word_index];
+        }
+
+        let pos = self.word_index * 64 + self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+
+        let high = (pos - self.index) as u64;
+        let value = (high << self.ef.low_len) | self.ef.low(self.index);
+        self.index += 1;
+
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.ef.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<S: AsRef<[u64]>> ExactSizeIterator for Iter<'_, S> {}
+
+/// Pushes the position of every set bit of the word at `index` whose rank is a multiple of
+/// `SAMPLE_RATE` to `samples`, where `count` is the number of set bits before the word.
+fn sample(samples: &mut Vec<u64>, count: &mut usize, index: usize, word: u64) {
+    let ones = word.count_ones() as usize;
+
+    let mut next = count.next_multiple_of(SAMPLE_RATE);
+    while next < *count + ones {
+        samples.push((index * 64 + select_in_word(word, next - *count)) as u64);
+        next += SAMPLE_RATE;
+    }
+
+    *count += ones;
+}
+
+/// Returns the position of the `rank`-th set bit (counting from zero) of `map(word)` over the
+/// given words, starting from the bit at position `start`.
+fn select(words: &[u64], start: usize, mut rank: usize, map: impl Fn(u64) -> u64) -> usize {
+    let mut index = start / 64;
+    // Clear the bits before the starting position.
+    let mut word = map(words[index]) & (u64::MAX << (start % 64));
+
+    loop {
+        let ones = word.count_ones() as usize;
+        if rank < ones {
+            return index * 64 + select_in_word(word, rank);
+        }
+
+        rank -= ones;
+        index += 1;
+        word = map(words[index]);
+    }
+}
+
+/// Reads the `len`-bit integer at bit position `pos` of the given words, where `0 < len < 64`.
+fn read_bits(words: &[u64], pos: usize, len: u32) -> u64 {
+    let (index, shift) = (pos / 64, (pos % 64) as u32);
+
+    let mut bits = words[index] >> shift;
+    if shift + len > 64 {
+        bits |= words[index + 1] << (64 - shift);
+    }
+    bits & ((1 << len) - 1)
+}
+
+/// Writes the `len`-bit integer `bits` at bit position `pos` of the given zeroed words, where
+/// `0 < len < 64`.
+fn write_bits(words: &mut [u64], pos: usize, len: u32, bits: u64) {
+    let (index, shift) = (pos / 64, (pos % 64) as u32);
+
+    words[index] |= bits << shift;
+    if shift + len > 64 {
+        words[index + 1] |= bits >> (64 - shift);
+    }
+}
@@ -0,0 +1,119 @@
+//! This module contains the [`FprEstimate`] type, the result of measuring the false positive rate
+//! of a [`RangeFilter`] with randomized probes.
+
+use rand::Rng;
+
+use crate::{RangeFilter, RangeFilterBackend};
+
+/// The standard normal quantile of a two-sided 95% confidence interval.
+const Z_95: f64 = 1.959_963_984_540_054;
+
+/// A false positive rate measured with randomized probes, along with a 95% confidence interval.
+///
+/// See [`RangeFilter::estimate_false_positive_rate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FprEstimate {
+    /// The number of probes that did not contain any of the known keys, which are the only probes
+    /// that can be false positives.
+    pub empty_probes: usize,
+    /// The number of empty probes that the filter answered with `true`.
+    pub false_positives: usize,
+    /// The measured false positive rate, or `0` if there were no empty probes.
+    pub rate: f64,
+    /// The lower bound of the 95% Wilson score interval of the false positive rate.
+    pub lower: f64,
+    /// The upper bound of the 95% Wilson score interval of the false positive rate.
+    pub upper: f64,
+}
+
+impl FprEstimate {
+    /// Creates an estimate from the number of false positives among the empty probes.
+    fn new(empty_probes: usize, false_positives: usize) -> Self {
+        if empty_probes == 0 {
+            return Self {
+                empty_probes,
+                false_positives,
+                rate: 0.0,
+                lower: 0.0,
+                upper: 1.0,
+            };
+        }
+
+        // The Wilson score interval stays within `[0, 1]` and behaves well for rates close to `0`,
+        // unlike the normal approximation.
+        let n = empty_probes as f64;
+        let rate = false_positives as f64 / n;
+        let z2 = Z_95 * Z_95;
+
+        let center = (rate + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let margin = Z_95 / (1.0 + z2 / n) * (rate * (1.0 - rate) / n + z2 / (4.0 * n * n)).sqrt();
+
+        Self {
+            empty_probes,
+            false_positives,
+            rate,
+            lower: (center - margin).max(0.0),
+            upper: (center + margin).min(1.0),
+        }
+    }
+
+    /// Checks if the given false positive rate is within the confidence interval.
+    pub fn contains(&self, rate: f64) -> bool {
+        self.lower <= rate && rate <= self.upper
+    }
+}
+
+impl<B: RangeFilterBackend> RangeFilter<B> {
+    /// Measures the false positive rate of the filter by querying `num_probes` ranges of
+    /// `interval_len` keys that start uniformly at random.
+    ///
+    /// `known_keys` must be the sorted keys that the filter was built over. Probes that contain any
+    /// of them are true positives, so they are excluded from the measurement, and the number of
+    /// remaining probes is reported in [`FprEstimate::empty_probes`]. Probes that would extend past
+    /// `u64::MAX` are truncated.
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    /// use rand::{Rng, SeedableRng};
+    /// use rand_chacha::ChaCha8Rng;
+    ///
+    /// let mut rng = ChaCha8Rng::seed_from_u64(42);
+    /// let mut keys: Vec<u64> = (0..1000).map(|_| rng.gen()).collect();
+    /// keys.sort_unstable();
+    ///
+    /// let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.05, 32, &mut rng).unwrap();
+    /// let rf = RangeFilter::new(keys.iter().copied(), hasher);
+    ///
+    /// let estimate = rf.estimate_false_positive_rate(&mut rng, 100_000, 32, &keys);
+    /// assert!(estimate.upper < 0.1);
+    /// ```
+    pub fn estimate_false_positive_rate<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        num_probes: usize,
+        interval_len: u64,
+        known_keys: &[u64],
+    ) -> FprEstimate {
+        debug_assert!(known_keys.windows(2).all(|w| w[0] <= w[1]));
+
+        let mut empty_probes = 0;
+        let mut false_positives = 0;
+
+        for _ in 0..num_probes {
+            let start: u64 = rng.gen();
+            let end = start.saturating_add(interval_len.max(1) - 1);
+
+            let i = known_keys.partition_point(|&key| key < start);
+            if known_keys.get(i).is_some_and(|&key| key <= end) {
+                continue;
+            }
+
+            empty_probes += 1;
+            if self.query(start..=end) {
+                false_positives += 1;
+            }
+        }
+
+        FprEstimate::new(empty_probes, false_positives)
+    }
+}
@@ -0,0 +1,154 @@
+//! This module contains the [`QueryExplanation`] type, which describes how a [`RangeFilter`]
+//! arrived at the answer to a query.
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::hash::HashedRange;
+use crate::{RangeFilter, RangeFilterBackend};
+
+/// A stored hash value, along with its index among the sorted stored hash values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StoredHash {
+    /// The index of the hash value among the sorted stored hash values.
+    pub index: usize,
+    /// The stored hash value.
+    pub hash: u64,
+}
+
+/// How a [`RangeFilter`] answered a query. See [`RangeFilter::query_explain`].
+///
+/// A range `start..=end` is answered by searching the stored hashes for one in the hashed window
+/// `start_hash..=end_hash`. If `start_hash > end_hash`, the window wrapped around the reduced
+/// universe `r` and covers `start_hash..r` and `0..=end_hash`.
+///
+/// A range that crosses from one block of `r` consecutive keys into the next hashes onto one
+/// window per block, and the explanation describes the window that contains the witness of a
+/// positive, or the window of the second block for a negative. A range that contains an entire
+/// block hashes onto every hash value, so it is explained with the window `0..=r - 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryExplanation {
+    /// The range was empty, so no hashes were searched.
+    Empty,
+    /// A stored hash was found inside the hashed window, so the query returned `true`.
+    Positive {
+        /// The hash of the start of the range.
+        start_hash: u64,
+        /// The hash of the end of the range.
+        end_hash: u64,
+        /// The stored hash inside the window that caused the hit.
+        witness: StoredHash,
+    },
+    /// No stored hash was found inside the hashed window, so the query returned `false`.
+    Negative {
+        /// The hash of the start of the range.
+        start_hash: u64,
+        /// The hash of the end of the range.
+        end_hash: u64,
+        /// The nearest stored hash that precedes the window, wrapping around the reduced universe
+        /// if necessary, or `None` if the filter stores no hashes.
+        predecessor: Option<StoredHash>,
+    },
+}
+
+impl QueryExplanation {
+    /// Returns the answer to the query, which is the same as that of [`RangeFilter::query`].
+    pub fn is_positive(&self) -> bool {
+        matches!(self, Self::Positive { .. })
+    }
+}
+
+impl<B: RangeFilterBackend> RangeFilter<B> {
+    /// Answers a query like [`Self::query`], and explains the answer.
+    ///
+    /// For positives, this returns the stored hash that caused the hit, which is the largest
+    /// stored hash inside the hashed window, or the smallest one if the window wrapped around and
+    /// its lower part contains any. For negatives, this returns the nearest stored hash below the
+    /// window. Comparing these to the hashes of the keys that the filter was built over helps to
+    /// tell a false positive apart from a true one, and to debug suspected false negatives.
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, QueryExplanation, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+    /// let rf = RangeFilter::new([1, 2, 3, 7].into_iter(), hasher);
+    ///
+    /// match rf.query_explain(7..=7) {
+    ///     QueryExplanation::Positive { witness, .. } => assert_eq!(witness.hash, hasher.hash(7)),
+    ///     _ => unreachable!(),
+    /// }
+    /// assert_eq!(rf.query_explain(5..5), QueryExplanation::Empty);
+    /// ```
+    pub fn query_explain<R>(&self, range: R) -> QueryExplanation
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return QueryExplanation::Empty;
+        };
+
+        match self.hasher.hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => self.explain_window(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                match self.explain_window(start1, end1) {
+                    positive @ QueryExplanation::Positive { .. } => positive,
+                    _ => self.explain_window(start2, end2),
+                }
+            }
+            HashedRange::All => self.explain_window(0, self.hasher.reduced_universe() - 1),
+        }
+    }
+
+    /// Searches the hashed window `start_hash..=end_hash` for a stored hash, and explains the
+    /// result.
+    fn explain_window(&self, start_hash: u64, end_hash: u64) -> QueryExplanation {
+        let len = self.ef.len();
+
+        let stored = |index: usize| StoredHash {
+            index,
+            hash: self.ef.get(index),
+        };
+
+        if len == 0 {
+            return QueryExplanation::Negative {
+                start_hash,
+                end_hash,
+                predecessor: None,
+            };
+        }
+
+        // This mirrors `Self::query_prehashed`. Since `end_hash < r`, `end_hash + 1` cannot
+        // overflow.
+        let below_end = self.ef.rank(end_hash + 1);
+        let witness = if start_hash > end_hash {
+            // In the wrapped case, any stored hash is either inside the window or precedes it, so
+            // the nearest predecessor is the largest stored hash.
+            let last = stored(len - 1);
+            if below_end > 0 {
+                Ok(stored(0))
+            } else if last.hash >= start_hash {
+                Ok(last)
+            } else {
+                Err(Some(last))
+            }
+        } else {
+            match below_end.checked_sub(1).map(stored) {
+                Some(predecessor) if predecessor.hash >= start_hash => Ok(predecessor),
+                predecessor => Err(predecessor),
+            }
+        };
+
+        match witness {
+            Ok(witness) => QueryExplanation::Positive {
+                start_hash,
+                end_hash,
+                witness,
+            },
+            Err(predecessor) => QueryExplanation::Negative {
+                start_hash,
+                end_hash,
+                predecessor,
+            },
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Out-of-core construction of a [`RangeFilter`] for key sets that do not fit in memory.
+//!
+//! The input keys are hashed in runs of a fixed length, and each run is sorted, deduplicated, and
+//! spilled to a temporary file. The runs are then merged with a k-way merge twice: the first merge
+//! counts the distinct hashes, which determines the layout of the encoding, and the second merge
+//! streams them straight into the encoder. Only one hash per run and the encoding itself are ever
+//! held in memory, so neither the raw keys nor the merged sequence of hashes need to fit in memory.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backend::EncodingBuilder;
+use crate::instrument;
+use crate::{BuildError, BuildPhase, IoBuildError, OrderPreservingHasher, RangeFilter};
+
+/// A counter used to give every spilled run file in this process a unique name.
+static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+
+impl RangeFilter {
+    /// Creates a new `RangeFilter` given an iterator of values, using at most `run_len` hashes of
+    /// intermediate memory and spilling sorted runs to files in `temp_dir`.
+    ///
+    /// Each run is sorted, deduplicated, and spilled to disk, and the runs are then merged and
+    /// streamed into the encoding, without ever collecting the merged hashes. The runs are read
+    /// twice, once to count the distinct hashes and once to encode them. If every value fits into a
+    /// single run, nothing is written to disk. The spilled files are removed once the filter is
+    /// built, even if an error occurs.
+    ///
+    /// If an I/O error occurs or the filter could not be built for any reason, this function will
+    /// return an [`IoBuildError`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `run_len` is `0`.
+    pub fn build_external<I, P>(
+        values: I,
+        hasher: OrderPreservingHasher,
+        temp_dir: P,
+        run_len: usize,
+    ) -> Result<Self, IoBuildError>
+    where
+        I: Iterator<Item = u64>,
+        P: AsRef<Path>,
+    {
+        assert!(run_len > 0, "run_len must be positive");
+
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher.into());
+        }
+
+        let mut values = values.map(|x| hasher.hash(x)).peekable();
+        let mut runs = Runs::default();
+        let mut run = Vec::with_capacity(run_len);
+
+        loop {
+            run.clear();
+            run.extend(values.by_ref().take(run_len));
+
+            run.sort_unstable();
+            run.dedup();
+
+            // If everything fits in the first run, there is no need to spill anything.
+            if runs.paths.is_empty() && values.peek().is_none() {
+                return Ok(Self::from_deduped_hashes(&run, hasher)?);
+            }
+
+            runs.spill(temp_dir.as_ref(), &run)?;
+
+            if values.peek().is_none() {
+                break;
+            }
+        }
+
+        // The layout of the encoding depends on the number of distinct hashes and the largest one.
+        let (mut len, mut max) = (0, 0);
+        runs.merge(|hash| {
+            len += 1;
+            max = hash;
+        })?;
+
+        let mut encoding = EncodingBuilder::new(len, max);
+        runs.merge(|hash| encoding.push(hash))?;
+        instrument::phase_finished(BuildPhase::Encoding, len);
+
+        Ok(Self::from_parts(hasher, encoding.finish())?)
+    }
+}
+
+/// A set of sorted runs of hashes spilled to disk, which are removed when dropped.
+#[derive(Default)]
+struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Runs {
+    /// Writes a sorted run of hashes to a new file in `dir`.
+    fn spill(&mut self, dir: &Path, run: &[u64]) -> io::Result<()> {
+        let id = RUN_ID.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("grafite-run-{}-{}.tmp", std::process::id(), id));
+
+        let file = File::create(&path)?;
+        self.paths.push(path);
+
+        let mut writer = BufWriter::new(file);
+        for &hash in run {
+            writer.write_all(&hash.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Merges every run into a single sorted and deduplicated sequence of hashes, passing each hash
+    /// to `f` in increasing order.
+    fn merge<F: FnMut(u64)>(&self, mut f: F) -> io::Result<()> {
+        let mut readers = self
+            .paths
+            .iter()
+            .map(|path| File::open(path).map(BufReader::new))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        // A min-heap of the next hash from every run, along with the index of that run.
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(hash) = read_hash(reader)? {
+                heap.push(Reverse((hash, i)));
+            }
+        }
+
+        let mut last = None;
+        while let Some(Reverse((hash, i))) = heap.pop() {
+            if last != Some(hash) {
+                f(hash);
+                last = Some(hash);
+            }
+
+            if let Some(next) = read_hash(&mut readers[i])? {
+                heap.push(Reverse((next, i)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Runs {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Reads the next hash from a run, or returns `None` if the run is exhausted.
+///
+/// A run that ends in the middle of a hash was truncated, which returns an
+/// [`io::ErrorKind::UnexpectedEof`] error instead of silently dropping the rest of the run.
+fn read_hash<R: Read>(reader: &mut R) -> io::Result<Option<u64>> {
+    let mut bytes = [0; 8];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match reader.read(&mut bytes[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "a spilled run ends in the middle of a hash",
+                ))
+            }
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(Some(u64::from_le_bytes(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_hash() {
+        let mut run: &[u8] = &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(read_hash(&mut run).unwrap(), Some(1));
+        assert_eq!(read_hash(&mut run).unwrap(), Some(2));
+        assert_eq!(read_hash(&mut run).unwrap(), None);
+
+        let mut truncated: &[u8] = &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0];
+        assert_eq!(read_hash(&mut truncated).unwrap(), Some(1));
+        let e = read_hash(&mut truncated).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
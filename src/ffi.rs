@@ -0,0 +1,237 @@
+//! A C ABI for building, serializing, and querying filters, for embedding this crate in C and C++
+//! storage engines.
+//!
+//! Filters are exposed as opaque [`GrafiteFilter`] handles over `u64` keys. Every function reports
+//! failures through a [`GrafiteStatus`] code instead of panicking across the FFI boundary, and
+//! returns its results through out-pointers. A handle returned by this module must eventually be
+//! released with [`grafite_filter_free`].
+//!
+//! A C header can be generated from this module with `cbindgen` and the `cbindgen.toml` at the root
+//! of the repository, and the library can be built for linking with, for example,
+//! `cargo rustc --release --features ffi --crate-type staticlib`.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::{BuildError, OrderPreservingHasher, RangeFilter};
+
+/// The status code returned by every function of the C ABI.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrafiteStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// The parameters used to create the hash function were invalid.
+    InvalidParameters = 2,
+    /// There were no keys to build the filter over.
+    EmptyInput = 3,
+    /// The filter could not be built for another reason.
+    BuildFailed = 4,
+    /// The bytes could not be read as a serialized filter.
+    InvalidData = 5,
+    /// The output buffer is too small, and the required size was written to the length pointer.
+    BufferTooSmall = 6,
+    /// The call panicked, which indicates a bug in this crate.
+    Panic = 7,
+}
+
+/// An opaque handle to a filter over `u64` keys.
+#[derive(Debug)]
+pub struct GrafiteFilter {
+    /// The wrapped filter.
+    filter: RangeFilter,
+}
+
+/// Runs `f`, converting a panic into [`GrafiteStatus::Panic`].
+fn guard<F>(f: F) -> GrafiteStatus
+where
+    F: FnOnce() -> GrafiteStatus,
+{
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(GrafiteStatus::Panic)
+}
+
+/// Builds a filter over `len` keys for the given false positive rate and maximum range interval,
+/// deriving the hash function deterministically from `seed`, and writes its handle to `out`.
+///
+/// # Safety
+///
+/// `keys` must point to `len` readable keys, and `out` must be valid for writes. If `len` is zero,
+/// `keys` may be null.
+#[no_mangle]
+pub unsafe extern "C" fn grafite_filter_build(
+    keys: *const u64,
+    len: usize,
+    epsilon: f64,
+    max_interval: u64,
+    seed: u64,
+    out: *mut *mut GrafiteFilter,
+) -> GrafiteStatus {
+    if out.is_null() || (keys.is_null() && len > 0) {
+        return GrafiteStatus::NullPointer;
+    }
+
+    guard(|| {
+        let keys = if len == 0 {
+            &[]
+        } else {
+            // SAFETY: The caller guarantees that `keys` points to `len` readable keys.
+            unsafe { slice::from_raw_parts(keys, len) }
+        };
+
+        let hasher = match OrderPreservingHasher::new_seeded(len, epsilon, max_interval, seed) {
+            Ok(hasher) => hasher,
+            Err(_) if len == 0 => return GrafiteStatus::EmptyInput,
+            Err(_) => return GrafiteStatus::InvalidParameters,
+        };
+
+        let filter = match RangeFilter::try_new(keys.iter().copied(), hasher) {
+            Ok(filter) => filter,
+            Err(BuildError::EmptyInput) => return GrafiteStatus::EmptyInput,
+            Err(BuildError::Param(_)) => return GrafiteStatus::InvalidParameters,
+            Err(_) => return GrafiteStatus::BuildFailed,
+        };
+
+        // SAFETY: The caller guarantees that `out` is valid for writes.
+        unsafe { out.write(Box::into_raw(Box::new(GrafiteFilter { filter }))) };
+        GrafiteStatus::Ok
+    })
+}
+
+/// Reads a filter that was serialized with [`grafite_filter_serialize`] or
+/// [`RangeFilter::write_to`] from `len` bytes, and writes its handle to `out`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn grafite_filter_deserialize(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut GrafiteFilter,
+) -> GrafiteStatus {
+    if bytes.is_null() || out.is_null() {
+        return GrafiteStatus::NullPointer;
+    }
+
+    guard(|| {
+        // SAFETY: The caller guarantees that `bytes` points to `len` readable bytes.
+        let bytes = unsafe { slice::from_raw_parts(bytes, len) };
+
+        let Ok(filter) = RangeFilter::read_from(bytes) else {
+            return GrafiteStatus::InvalidData;
+        };
+
+        // SAFETY: The caller guarantees that `out` is valid for writes.
+        unsafe { out.write(Box::into_raw(Box::new(GrafiteFilter { filter }))) };
+        GrafiteStatus::Ok
+    })
+}
+
+/// Serializes the filter into the buffer `buf` of `*len` bytes in the format of
+/// [`RangeFilter::write_to`], and writes the number of bytes written to `len`.
+///
+/// If the buffer is too small, nothing is written to it, the required size is written to `len`,
+/// and [`GrafiteStatus::BufferTooSmall`] is returned. Passing a null buffer with a length of zero
+/// therefore queries the required size.
+///
+/// # Safety
+///
+/// `filter` must be a live handle, `len` must be valid for reads and writes, and `buf` must be
+/// valid for writes of `*len` bytes unless `*len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn grafite_filter_serialize(
+    filter: *const GrafiteFilter,
+    buf: *mut u8,
+    len: *mut usize,
+) -> GrafiteStatus {
+    if filter.is_null() || len.is_null() {
+        return GrafiteStatus::NullPointer;
+    }
+
+    guard(|| {
+        // SAFETY: The caller guarantees that `filter` is a live handle.
+        let filter = unsafe { &(*filter).filter };
+
+        let mut bytes = Vec::new();
+        if filter.write_to(&mut bytes).is_err() {
+            return GrafiteStatus::Panic;
+        }
+
+        // SAFETY: The caller guarantees that `len` is valid for reads and writes.
+        let capacity = unsafe { len.replace(bytes.len()) };
+        if capacity < bytes.len() {
+            return GrafiteStatus::BufferTooSmall;
+        }
+        if buf.is_null() {
+            return GrafiteStatus::NullPointer;
+        }
+
+        // SAFETY: The caller guarantees that `buf` is valid for writes of `capacity` bytes, which
+        // is at least the number of bytes copied.
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len()) };
+        GrafiteStatus::Ok
+    })
+}
+
+/// Checks if there may be any keys in the inclusive range `start..=end`, and writes the answer to
+/// `out`.
+///
+/// # Safety
+///
+/// `filter` must be a live handle, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn grafite_filter_query(
+    filter: *const GrafiteFilter,
+    start: u64,
+    end: u64,
+    out: *mut bool,
+) -> GrafiteStatus {
+    if filter.is_null() || out.is_null() {
+        return GrafiteStatus::NullPointer;
+    }
+
+    guard(|| {
+        // SAFETY: The caller guarantees that `filter` is a live handle and `out` is writable.
+        unsafe { out.write((*filter).filter.query(start..=end)) };
+        GrafiteStatus::Ok
+    })
+}
+
+/// Checks if the given key may be among the original input set, and writes the answer to `out`.
+///
+/// # Safety
+///
+/// `filter` must be a live handle, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn grafite_filter_contains(
+    filter: *const GrafiteFilter,
+    key: u64,
+    out: *mut bool,
+) -> GrafiteStatus {
+    if filter.is_null() || out.is_null() {
+        return GrafiteStatus::NullPointer;
+    }
+
+    guard(|| {
+        // SAFETY: The caller guarantees that `filter` is a live handle and `out` is writable.
+        unsafe { out.write((*filter).filter.contains(key)) };
+        GrafiteStatus::Ok
+    })
+}
+
+/// Releases a filter handle. Passing a null pointer does nothing.
+///
+/// # Safety
+///
+/// `filter` must be null or a live handle, which must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn grafite_filter_free(filter: *mut GrafiteFilter) {
+    if !filter.is_null() {
+        // SAFETY: The caller guarantees that `filter` is a live handle returned by this module,
+        // which was created with `Box::into_raw`.
+        drop(unsafe { Box::from_raw(filter) });
+    }
+}
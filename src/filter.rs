@@ -1,59 +1,638 @@
-use std::ops::RangeBounds;
-use vers_vecs::EliasFanoVec;
+use crate::hash::HashedRange;
+use crate::progress::Unobserved;
+use crate::{
+    elias_fano, instrument, BuildPhase, Encoding, OrderPreservingHasher, ParamError,
+    RangeFilterBackend,
+};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::{Range, RangeBounds};
 
-use crate::OrderPreservingHasher;
+/// An error type representing why a [`RangeFilter`] could not be built.
+#[derive(Debug, Clone, Copy)]
+pub enum BuildError {
+    /// If there are no input values to build the filter over.
+    EmptyInput,
+    /// If the hasher's reduced universe is empty, so no value can be hashed into it.
+    InvalidHasher,
+    /// If a hash value is not less than the size of the reduced universe.
+    HashOutOfRange {
+        /// The offending hash value.
+        hash: u64,
+        /// The size of the reduced universe of the hasher.
+        reduced_universe: u64,
+    },
+    /// If the parameters used to create the hasher were invalid.
+    Param(ParamError),
+    /// If the build was cancelled by a [`BuildObserver`](crate::BuildObserver).
+    Cancelled,
+    /// If re-encoding a filter with [`RangeFilter::reencode`] would take more space than an
+    /// Elias-Fano encoding of the same hash values.
+    LargerEncoding {
+        /// The estimated size of the requested backend, in bytes.
+        size: usize,
+        /// The estimated size of an Elias-Fano encoding, in bytes.
+        elias_fano: usize,
+    },
+}
+
+impl From<ParamError> for BuildError {
+    fn from(e: ParamError) -> Self {
+        Self::Param(e)
+    }
+}
+
+/// An error type representing why a [`RangeFilter`] query could not be answered reliably.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryError {
+    /// If the queried range is longer than the maximum range interval, in which case the false
+    /// positive rate guarantee does not hold.
+    IntervalTooLong {
+        /// The number of keys in the queried range, saturated at [`u64::MAX`].
+        length: u64,
+        /// The maximum range interval that the filter was built for.
+        max_interval: u64,
+    },
+    /// If the maximum range interval of the filter is not known, because its hasher was created
+    /// with a reduced universe size chosen directly.
+    UnknownMaxInterval,
+}
+
+/// An error type representing why [`RangeFilter`]s could not be merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    /// If the filters were built with different hash functions, so their hashes are not
+    /// comparable.
+    HasherMismatch,
+    /// If there are no filters to merge.
+    NoFilters,
+}
+
+/// An error type representing why a [`RangeFilter`] could not be built from a construction path
+/// that performs I/O.
+#[derive(Debug)]
+pub enum IoBuildError {
+    /// An I/O error occurred while building the filter.
+    Io(std::io::Error),
+    /// The filter could not be built from the input values.
+    Build(BuildError),
+}
+
+impl std::fmt::Display for IoBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error while building the range filter: {e}"),
+            Self::Build(e) => write!(f, "unable to build the range filter: {e:?}"),
+        }
+    }
+}
+
+impl std::error::Error for IoBuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Build(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for IoBuildError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<BuildError> for IoBuildError {
+    fn from(e: BuildError) -> Self {
+        Self::Build(e)
+    }
+}
 
 /// The Grafite Range Filter.
+///
+/// The hash values are stored in a [`RangeFilterBackend`], which defaults to [`Encoding`], which
+/// picks between an Elias-Fano encoding and a dense bit vector based on the density of the hash
+/// values. Filters with another backend are built with [`Self::try_with_backend`].
+///
+/// The hash function and the backend can only be read through [`Self::hasher`] and
+/// [`Self::backend`], since replacing either one on its own would make the stored hash values
+/// meaningless and cause false negatives. A filter can be assembled from both with
+/// [`Self::from_parts`], which checks that they fit together.
 #[derive(Debug, Clone)]
-pub struct RangeFilter {
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct RangeFilter<B = Encoding> {
     /// The hash function used to encode the hash values.
-    pub hasher: OrderPreservingHasher,
+    pub(crate) hasher: OrderPreservingHasher,
     /// A succinct encoding of a non-decreasing sequence of integer hash values.
-    pub ef: EliasFanoVec,
+    #[cfg_attr(feature = "rkyv", rkyv(with = crate::archive::AsHashes))]
+    pub(crate) ef: B,
 }
 
 /// The `RangeFilter` must be built on items that are able to be turned into a 64-bit integer.
 impl RangeFilter {
     /// Creates a new `RangeFilter` given a slice of values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter could not be built, for example if there are no input values. See
+    /// [`Self::try_new`] for a fallible version of this function.
     pub fn new<I>(values: I, hasher: OrderPreservingHasher) -> Self
     where
         I: Iterator<Item = u64>,
     {
-        // Hash all items in the input set.
-        let mut hashes: Vec<u64> = values.map(|x| hasher.hash(x)).collect();
+        match Self::try_new(values, hasher) {
+            Ok(filter) => filter,
+            Err(e) => panic!("Unable to build the range filter: {e:?}"),
+        }
+    }
 
+    /// Creates a new `RangeFilter` given a slice of values.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+    {
+        Self::try_with_backend(values, hasher)
+    }
+
+    /// Creates a new `RangeFilter` given an iterator of fallible values, such as keys that are read
+    /// directly from a file.
+    ///
+    /// The first error produced by the iterator aborts the build and is returned as an
+    /// [`IoBuildError::Io`]. If the filter could not be built for any other reason, this function
+    /// will return an [`IoBuildError::Build`].
+    pub fn try_from_fallible<I>(
+        values: I,
+        hasher: OrderPreservingHasher,
+    ) -> Result<Self, IoBuildError>
+    where
+        I: Iterator<Item = std::io::Result<u64>>,
+    {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher.into());
+        }
+
+        // Hash all items in the input set, stopping at the first error.
+        let hashes = values
+            .map(|x| x.map(|x| hasher.hash(x)))
+            .collect::<std::io::Result<Vec<u64>>>()?;
+
+        Ok(Self::from_hashes(hashes, hasher)?)
+    }
+
+    /// Creates a new `RangeFilter` from values that have already been hashed by `hasher`.
+    ///
+    /// The hashes do not need to be sorted or distinct, but they must all be less than the reduced
+    /// universe size of `hasher`. This is useful for callers that cache hashed keys, since it
+    /// avoids hashing every key again.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn from_hashes(
+        mut hashes: Vec<u64>,
+        hasher: OrderPreservingHasher,
+    ) -> Result<Self, BuildError> {
         // Sort and then remove all duplicates.
         hashes.sort_unstable();
         hashes.dedup();
 
-        assert!(hashes[hashes.len() - 1] < hasher.reduced_universe());
+        Self::from_deduped_hashes(&hashes, hasher)
+    }
+
+    /// Creates a new `RangeFilter` directly from a slice of already hashed values, skipping the
+    /// sorting and deduplication done by [`Self::new`].
+    ///
+    /// The `hashes` must be non-empty, strictly increasing, and less than the reduced universe size
+    /// of `hasher`, and they must have been produced by `hasher`. This is only checked with debug
+    /// assertions: if any of these conditions do not hold, queries on the resulting filter may
+    /// return false negatives.
+    pub fn from_sorted_unchecked(hashes: &[u64], hasher: OrderPreservingHasher) -> Self {
+        Self::from_sorted_hashes_unchecked(hashes, hasher)
+    }
+}
+
+impl<B: RangeFilterBackend> RangeFilter<B> {
+    /// Creates a new `RangeFilter` that stores its hash values in the backend `B`, given an
+    /// iterator of values.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_with_backend<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+    {
+        Self::try_build_observed(values, hasher, &mut Unobserved)
+    }
+
+    /// Creates a new `RangeFilter` from hashes that are already sorted and deduplicated, checking
+    /// that they are all less than the reduced universe size of `hasher`.
+    pub(crate) fn from_deduped_hashes(
+        hashes: &[u64],
+        hasher: OrderPreservingHasher,
+    ) -> Result<Self, BuildError> {
+        check_deduped_hashes(hashes, &hasher)?;
+
+        let ef = B::from_sorted(hashes);
+        instrument::phase_finished(BuildPhase::Encoding, hashes.len());
+
+        Ok(Self { hasher, ef })
+    }
+
+    /// Creates a new `RangeFilter` from hashes that are strictly increasing, which is only checked
+    /// with debug assertions. See [`RangeFilter::from_sorted_unchecked`].
+    pub(crate) fn from_sorted_hashes_unchecked(
+        hashes: &[u64],
+        hasher: OrderPreservingHasher,
+    ) -> Self {
+        debug_assert!(!hashes.is_empty(), "hashes must not be empty");
+        debug_assert!(
+            hashes.windows(2).all(|w| w[0] < w[1]),
+            "hashes must be strictly increasing"
+        );
+        debug_assert!(
+            hashes.last().is_none_or(|&h| h < hasher.reduced_universe()),
+            "hashes must be less than the reduced universe size"
+        );
 
         Self {
             hasher,
-            ef: EliasFanoVec::from_slice(&hashes),
+            ef: B::from_sorted(hashes),
         }
     }
 
+    /// Creates a new `RangeFilter` from a hash function and a backend that stores the hash values
+    /// produced by it.
+    ///
+    /// The backend must be non-empty, and every stored hash value must be less than the reduced
+    /// universe size of `hasher`, otherwise this function will return a [`BuildError`]. The hash
+    /// values must also have been produced by `hasher`, which cannot be checked: if they were not,
+    /// queries on the resulting filter may return false negatives.
+    pub fn from_parts(hasher: OrderPreservingHasher, backend: B) -> Result<Self, BuildError> {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher);
+        }
+
+        let Some(last) = backend.len().checked_sub(1) else {
+            return Err(BuildError::EmptyInput);
+        };
+        let max_hash = backend.get(last);
+        if max_hash >= hasher.reduced_universe() {
+            return Err(BuildError::HashOutOfRange {
+                hash: max_hash,
+                reduced_universe: hasher.reduced_universe(),
+            });
+        }
+
+        Ok(Self {
+            hasher,
+            ef: backend,
+        })
+    }
+
+    /// Consumes this `RangeFilter`, returning its hash function and backend.
+    pub fn into_parts(self) -> (OrderPreservingHasher, B) {
+        (self.hasher, self.ef)
+    }
+
+    /// Re-encodes the stored hash values with the backend `C`, keeping the same hash function.
+    ///
+    /// Only the stored hash values are read, so the keys that the filter was built over are not
+    /// needed, and the new filter answers every query exactly like this one.
+    ///
+    /// The hash values are first copied into a temporary buffer of 8 bytes per value, so the peak
+    /// memory use is that buffer plus both backends. A [`DenseBitVector`](crate::DenseBitVector)
+    /// takes a bit for every value of the reduced universe `r`, however few hash values `n` are
+    /// stored, so it is much larger than an [`EliasFano`](crate::EliasFano) encoding unless `r / n`
+    /// is small.
+    ///
+    /// If the backend `C` would take more space than an Elias-Fano encoding of the same hash
+    /// values, according to [`RangeFilterBackend::estimated_size_in_bytes`], this function will
+    /// return [`BuildError::LargerEncoding`] before allocating anything.
+    ///
+    /// ```
+    /// use grafite::{BuildError, DenseBitVector, EliasFano, OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new_with_reduced(1 << 10);
+    /// let rf: RangeFilter<EliasFano> =
+    ///     RangeFilter::try_with_backend((0..1000).step_by(3), hasher).unwrap();
+    ///
+    /// let dense: RangeFilter<DenseBitVector> = rf.reencode().unwrap();
+    /// assert!(dense.iter_hashes().eq(rf.iter_hashes()));
+    /// assert!(dense.query(3..5));
+    ///
+    /// // A sparse filter is far smaller with Elias-Fano.
+    /// let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    /// let rf: RangeFilter<EliasFano> =
+    ///     RangeFilter::try_with_backend([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), hasher).unwrap();
+    /// assert!(matches!(
+    ///     rf.reencode::<DenseBitVector>(),
+    ///     Err(BuildError::LargerEncoding { .. })
+    /// ));
+    /// ```
+    pub fn reencode<C: RangeFilterBackend>(&self) -> Result<RangeFilter<C>, BuildError> {
+        let len = self.ef.len();
+        if let Some(max) = len.checked_sub(1).map(|last| self.ef.get(last)) {
+            let elias_fano = 8 * elias_fano::layout_words(len, max);
+            if let Some(size) = C::estimated_size_in_bytes(len, max).filter(|&s| s > elias_fano) {
+                return Err(BuildError::LargerEncoding { size, elias_fano });
+            }
+        }
+
+        let hashes: Vec<u64> = self.ef.iter().collect();
+
+        Ok(RangeFilter::from_sorted_hashes_unchecked(
+            &hashes,
+            self.hasher,
+        ))
+    }
+
     /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// Empty and reversed ranges, such as `5..5` or `7..=3`, contain no elements and always return
+    /// `false`.
+    ///
+    /// There are no false negatives. The false positive rate is guaranteed for ranges that are no
+    /// longer than the maximum range interval `L` the filter was built for, and grows with the
+    /// length of longer ranges: the keys of a range of `m` keys hash onto `m` hash values, so its
+    /// false positive rate is about `m / L` times that of the filter. Use [`Self::query_checked`]
+    /// to reject such ranges instead.
+    ///
+    /// A range that crosses from one block of `r` consecutive keys into the next, where `r` is the
+    /// reduced universe size, hashes onto a separate window of hash values in each block, so both
+    /// windows are checked. A range that contains an entire block always returns `true`.
     pub fn query<R>(&self, range: R) -> bool
     where
         R: RangeBounds<u64>,
     {
-        let start = match range.start_bound() {
-            std::ops::Bound::Included(&i) => i,
-            std::ops::Bound::Excluded(_) => unreachable!("Somehow had an exclusive start bound"),
-            std::ops::Bound::Unbounded => 0,
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        self.query_inclusive(start, end)
+    }
+
+    /// Checks if there are any elements within the given range among the original input set, or
+    /// returns [`QueryError::IntervalTooLong`] if the range is longer than the maximum range
+    /// interval that the filter was built for.
+    ///
+    /// If the maximum range interval is not known, this function will return
+    /// [`QueryError::UnknownMaxInterval`]. Otherwise, this is the same as [`Self::query`], and an
+    /// empty range returns `Ok(false)`.
+    pub fn query_checked<R>(&self, range: R) -> Result<bool, QueryError>
+    where
+        R: RangeBounds<u64>,
+    {
+        let max_interval = self.max_interval().ok_or(QueryError::UnknownMaxInterval)?;
+
+        let Some((start, end)) = range_endpoints(&range) else {
+            return Ok(false);
+        };
+
+        // The range has `end - start + 1` keys, which may overflow.
+        if end - start >= max_interval {
+            return Err(QueryError::IntervalTooLong {
+                length: (end - start).saturating_add(1),
+                max_interval,
+            });
+        }
+
+        Ok(self.query_inclusive(start, end))
+    }
+
+    /// Checks if there are any elements within the given range among the original input set,
+    /// splitting ranges that are longer than the maximum range interval into sub-queries.
+    ///
+    /// Each sub-query covers at most `L` keys, where `L` is the maximum range interval the filter
+    /// was built for, and lies within a single block of `r` consecutive keys, where `r` is the
+    /// reduced universe size, so each one hashes onto a single window and keeps the false positive
+    /// rate guarantee of the filter. Ranges that contain an entire block are answered with `true`
+    /// immediately, since such a block hashes onto every possible hash value, so every other range
+    /// crosses at most two block boundaries. A range of `m` keys therefore costs at most
+    /// `ceil(m / L) + 2` queries, and by the union bound its false positive rate is at most that
+    /// many times the false positive rate of the filter.
+    ///
+    /// If the maximum range interval is not known, this is the same as [`Self::query`].
+    pub fn query_chunked<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
         };
 
-        let end = match range.end_bound() {
-            std::ops::Bound::Included(&i) => i,
-            std::ops::Bound::Excluded(&e) => e - 1,
-            std::ops::Bound::Unbounded => u64::MAX,
+        let Some(max_interval) = self.max_interval().filter(|&l| l > 0) else {
+            return self.query_inclusive(start, end);
         };
 
-        let start_hash = self.hasher.hash(start);
-        let end_hash = self.hasher.hash(end);
+        // Any `2r - 1` consecutive keys contain an entire block.
+        let r = self.hasher.reduced_universe();
+        if (end - start) as u128 + 1 >= 2 * r as u128 - 1 {
+            return true;
+        }
+
+        let mut chunk_start = start;
+        loop {
+            // The last key of the block of `chunk_start`, which saturates in a partial last block.
+            let block_end = (chunk_start - chunk_start % r).saturating_add(r - 1);
+            let chunk_end = chunk_start
+                .saturating_add(max_interval - 1)
+                .min(block_end)
+                .min(end);
+            if self.query_inclusive(chunk_start, chunk_end) {
+                return true;
+            }
+            if chunk_end == end {
+                return false;
+            }
+            chunk_start = chunk_end + 1;
+        }
+    }
+
+    /// Checks if the given key may be among the original input set.
+    ///
+    /// This is equivalent to `self.query(x..=x)`, but only hashes the key once and probes for its
+    /// exact hash.
+    pub fn contains(&self, x: u64) -> bool {
+        self.contains_hash(self.hasher.hash(x))
+    }
+
+    /// Checks if the exact hash value `hash` is stored in the filter.
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.ef.predecessor(hash) == Some(hash)
+    }
+
+    /// Checks many ranges at once, returning whether each range may contain an element.
+    ///
+    /// Each range is given as its inclusive `(start, end)` endpoints, so `(start, end)` is the same
+    /// as querying `start..=end` with [`Self::query`]. See [`Self::query_many_into`] for a version
+    /// that writes into a caller-provided buffer instead of allocating.
+    pub fn query_many(&self, ranges: &[(u64, u64)]) -> Vec<bool> {
+        let mut out = vec![false; ranges.len()];
+        self.query_many_into(ranges, &mut out);
+
+        out
+    }
+
+    /// Checks many ranges at once, writing whether `ranges[i]` may contain an element into
+    /// `out[i]`.
+    ///
+    /// See [`Self::query_many`] for more information.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ranges` and `out` have different lengths.
+    pub fn query_many_into(&self, ranges: &[(u64, u64)], out: &mut [bool]) {
+        assert_eq!(
+            ranges.len(),
+            out.len(),
+            "ranges and out must have the same length"
+        );
+
+        for (result, &(start, end)) in out.iter_mut().zip(ranges) {
+            *result = self.query_inclusive(start, end);
+        }
+    }
+
+    /// Checks a batch of ranges that are sorted by their start key in a single forward pass over
+    /// the stored hashes, returning whether each range may contain an element.
+    ///
+    /// Each range is given as its inclusive `(start, end)` endpoints, as in [`Self::query_many`],
+    /// and the results are the same as those of [`Self::query_many`].
+    ///
+    /// Instead of an independent predecessor search per range, the hashed windows of the ranges, of
+    /// which a range that crosses a block boundary has two, are ordered by their start hash and
+    /// answered while decoding the hashes once from smallest to largest. Since the hash function
+    /// preserves order within each block of the reduced universe, ranges that are sorted by start
+    /// key are already sorted in long runs, which makes ordering them cheap. Ranges that are not
+    /// sorted are still answered correctly, only more slowly.
+    ///
+    /// Since every stored hash may be decoded, this is only faster than [`Self::query_many`] when
+    /// the batch is large relative to the number of elements in the filter.
+    pub fn query_sorted_batch(&self, ranges: &[(u64, u64)]) -> Vec<bool> {
+        let mut out = vec![false; ranges.len()];
+
+        // The start hash, end hash, and output index of every range that has not wrapped around.
+        let mut probes = Vec::with_capacity(ranges.len());
+        for (i, &(start, end)) in ranges.iter().enumerate() {
+            if start > end {
+                continue;
+            }
+
+            let (first, second) = match self.hasher.hash_range(start, end) {
+                HashedRange::One(start_hash, end_hash) => ((start_hash, end_hash), None),
+                HashedRange::Two(first, second) => (first, Some(second)),
+                HashedRange::All => {
+                    out[i] = true;
+                    continue;
+                }
+            };
+
+            for (start_hash, end_hash) in std::iter::once(first).chain(second) {
+                // See `Self::query_prehashed` for an explanation of the wrapped case.
+                if start_hash > end_hash {
+                    out[i] |= self.min_hash() <= end_hash || self.max_hash() >= start_hash;
+                } else {
+                    probes.push((start_hash, end_hash, i));
+                }
+            }
+        }
+
+        // A stable sort detects the existing sorted runs.
+        probes.sort_by_key(|&(start_hash, _, _)| start_hash);
+
+        let mut hashes = self.iter_hashes().peekable();
+        for (start_hash, end_hash, i) in probes {
+            // Skip to the smallest hash that is at least the start hash.
+            while hashes.next_if(|&hash| hash < start_hash).is_some() {}
+
+            out[i] |= hashes.peek().is_some_and(|&hash| hash <= end_hash);
+        }
+
+        out
+    }
+
+    /// Checks if any of the given filters may contain elements within the given range, for example
+    /// the filters of every level of an LSM tree.
+    ///
+    /// This stops at the first filter that returns `true`. See [`Self::query_matching`] to find out
+    /// which filters matched.
+    pub fn query_any<R>(filters: &[Self], range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        Self::query_matching(filters, range).next().is_some()
+    }
+
+    /// Returns the indices of the filters that may contain elements within the given range, in
+    /// increasing order.
+    ///
+    /// The filters are queried lazily, so taking only the first index short-circuits on the first
+    /// filter that returns `true`.
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+    /// let levels = [
+    ///     RangeFilter::new([1, 2, 3, 7].into_iter(), hasher),
+    ///     RangeFilter::new([100, 200].into_iter(), hasher),
+    /// ];
+    ///
+    /// assert!(RangeFilter::query_any(&levels, 150..=200));
+    /// assert_eq!(RangeFilter::query_matching(&levels, 150..=200).next(), Some(1));
+    /// ```
+    pub fn query_matching<R>(filters: &[Self], range: R) -> impl Iterator<Item = usize> + '_
+    where
+        R: RangeBounds<u64>,
+    {
+        // Empty and reversed ranges become `[1, 0]`, which never matches.
+        let (start, end) = range_endpoints(&range).unwrap_or((1, 0));
+
+        filters
+            .iter()
+            .enumerate()
+            .filter(move |(_, filter)| filter.query_inclusive(start, end))
+            .map(|(i, _)| i)
+    }
+
+    /// Checks if there are any elements within the inclusive range `[start, end]`, which is empty
+    /// if `start > end`.
+    pub(crate) fn query_inclusive(&self, start: u64, end: u64) -> bool {
+        if start > end {
+            return false;
+        }
+
+        let result = match self.hasher.hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => self.query_prehashed(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                self.query_prehashed(start1, end1) || self.query_prehashed(start2, end2)
+            }
+            HashedRange::All => true,
+        };
+        instrument::query(start, end, result);
+        result
+    }
 
+    /// Checks if there are any elements within a range whose endpoints have already been hashed.
+    ///
+    /// For an inclusive range `start..=end`, this is equivalent to [`Self::query`] when
+    /// `start_hash` and `end_hash` are `hasher.hash(start)` and `hasher.hash(end)`. This allows the
+    /// endpoints to be hashed once and then reused across many filters that were built with the
+    /// same hasher, for example one filter per block of a table.
+    ///
+    /// The range must lie within a single block of `r` consecutive keys, where `r` is the reduced
+    /// universe size, so that `start / r == end / r`. Keys of different blocks hash onto unrelated
+    /// windows, so a range that crosses a block boundary must be split at the boundary, and each
+    /// part queried on its own.
+    ///
+    /// The hashes must have been produced by a hasher with the same parameters as the hasher of
+    /// this filter, otherwise the result is meaningless and may be a false negative.
+    pub fn query_prehashed(&self, start_hash: u64, end_hash: u64) -> bool {
         // If the start hash is greater than the end hash, then the range has wrapped around due to
         // the reduced universe. Thus we can just check the min and max hashes to see if there is an
         // element between the endpoints.
@@ -71,29 +650,375 @@ impl RangeFilter {
         }
     }
 
+    /// Returns the number of stored hashes that fall inside the hashed windows of the given range.
+    ///
+    /// Every distinct key of the original input set that lies within the range has its hash in one
+    /// of these windows, so this is an upper bound on the number of distinct keys that might be in
+    /// the range. It can overestimate because of hash collisions and keys outside of the range that
+    /// hash into the windows, for example to give query planners a cheap bound. This costs two rank
+    /// operations for a range within a single block, and up to four times as many otherwise.
+    ///
+    /// A range of at least `r` keys, where `r` is the reduced universe size, has windows that are
+    /// at least as wide as the reduced universe in total, so every stored hash is counted.
+    pub fn rank_range<R>(&self, range: R) -> usize
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return 0;
+        };
+
+        self.window_indices(start, end)
+            .into_iter()
+            .map(|indices| indices.len())
+            .sum()
+    }
+
+    /// Returns the indices of the stored hashes that fall inside the hashed windows of the
+    /// non-empty inclusive range `[start, end]`, as up to four disjoint ranges of indices.
+    ///
+    /// See [`Self::rank_range`] for which hashes are included.
+    pub(crate) fn window_indices(&self, start: u64, end: u64) -> [Range<usize>; 4] {
+        let mut indices: [Range<usize>; 4] = Default::default();
+
+        let r = self.hasher.reduced_universe();
+        if end - start >= r - 1 {
+            indices[0] = 0..self.ef.len();
+            return indices;
+        }
+
+        // A single window is given twice, which is merged below.
+        let windows = match self.hasher.hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => [(start_hash, end_hash); 2],
+            HashedRange::Two(first, second) => [first, second],
+            HashedRange::All => [(0, r - 1); 2],
+        };
+
+        // Split every window into half-open intervals of hashes that do not wrap around. See
+        // `Self::query_prehashed` for an explanation of the wrapped case, where the window is
+        // `[start_hash, r) ∪ [0, end_hash]`. Since `end_hash < r`, `end_hash + 1` cannot overflow.
+        let mut intervals = [(0, 0); 4];
+        for (i, (start_hash, end_hash)) in windows.into_iter().enumerate() {
+            if start_hash > end_hash {
+                intervals[2 * i] = (start_hash, r);
+                intervals[2 * i + 1] = (0, end_hash + 1);
+            } else {
+                intervals[2 * i] = (start_hash, end_hash + 1);
+            }
+        }
+
+        // The windows of two blocks can overlap, so overlapping intervals are merged to count
+        // every stored hash only once.
+        intervals.sort_unstable();
+        let mut merged: [(u64, u64); 4] = [(0, 0); 4];
+        let mut len = 0;
+        for (start_hash, end_hash) in intervals {
+            if start_hash == end_hash {
+                continue;
+            }
+            match merged[..len].last_mut() {
+                Some(last) if start_hash <= last.1 => last.1 = last.1.max(end_hash),
+                _ => {
+                    merged[len] = (start_hash, end_hash);
+                    len += 1;
+                }
+            }
+        }
+
+        for (indices, &(start_hash, end_hash)) in indices.iter_mut().zip(&merged[..len]) {
+            *indices = self.ef.rank(start_hash)..self.ef.rank(end_hash);
+        }
+        indices
+    }
+
+    /// Returns an estimate of the fraction of the stored keys that lie within the given range, for
+    /// example for cardinality estimation in cost-based query planners.
+    ///
+    /// The estimate starts from [`Self::rank_range`] and subtracts the number of stored hashes
+    /// expected to fall into the hashed windows by chance. Windows of `w` hashes in total receive
+    /// each of the stored hashes of keys outside of the range with probability `w / r`, which is
+    /// the same quantity that determines the false positive rate, so if `k` of the `n` stored
+    /// hashes are counted, about `(k - n * w / r) / (1 - w / r)` of them belong to keys in the
+    /// range. The result is clamped to `[0.0, 1.0]`.
+    ///
+    /// A range of at least `r` keys counts every stored hash, so its estimate is `1.0`.
+    ///
+    /// Since distinct keys can share a hash, this estimates the fraction of distinct stored hashes
+    /// rather than of the original keys.
+    pub fn selectivity<R>(&self, range: R) -> f64
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return 0.0;
+        };
+
+        let n = self.ef.len() as f64;
+        let width = (end - start) as f64 + 1.0;
+        let r = self.hasher.reduced_universe() as f64;
+
+        let rank = self.rank_range(range) as f64;
+        let chance = width / r;
+        if chance >= 1.0 {
+            return (rank / n).clamp(0.0, 1.0);
+        }
+        let hits = (rank - n * chance) / (1.0 - chance);
+
+        (hits / n).clamp(0.0, 1.0)
+    }
+
+    /// Returns an iterator over the stored hashes in increasing order.
+    ///
+    /// Since duplicate hashes are removed when the filter is built, every hash appears once. This
+    /// allows exporting, re-encoding, or merging the contents of a filter, for example with
+    /// [`RangeFilter::from_sorted_unchecked`].
+    pub fn iter_hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.ef.iter()
+    }
+
+    /// Returns a new `RangeFilter` over the union of the keys of both filters, merging their stored
+    /// hashes without access to the original keys.
+    ///
+    /// The resulting filter uses the hasher of `self`, including its recorded build parameters. The
+    /// false positive rate of the union is that of a filter built over the combined number of
+    /// distinct keys, so the hasher should have been created for at least that many keys.
+    ///
+    /// If the filters were built with different hash functions, this function will return
+    /// [`MergeError::HasherMismatch`].
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    /// let a = RangeFilter::new([1, 2, 3, 7].into_iter(), hasher);
+    /// let b = RangeFilter::new([8, 9, 15, 20].into_iter(), hasher);
+    ///
+    /// let union = a.union(&b).unwrap();
+    /// assert!(union.query(1..2));
+    /// assert!(union.query(15..16));
+    /// ```
+    pub fn union(&self, other: &Self) -> Result<Self, MergeError> {
+        if self.hasher.raw_parts() != other.hasher.raw_parts() {
+            return Err(MergeError::HasherMismatch);
+        }
+
+        let mut hashes = Vec::with_capacity(self.ef.len() + other.ef.len());
+
+        // Merge the two sorted sequences, keeping a single copy of the hashes they share.
+        let mut left = self.iter_hashes().peekable();
+        let mut right = other.iter_hashes().peekable();
+        while let (Some(&l), Some(&r)) = (left.peek(), right.peek()) {
+            if l <= r {
+                left.next();
+            }
+            if r <= l {
+                right.next();
+            }
+            hashes.push(l.min(r));
+        }
+        hashes.extend(left);
+        hashes.extend(right);
+
+        Ok(Self::from_sorted_hashes_unchecked(&hashes, self.hasher))
+    }
+
+    /// Returns a new `RangeFilter` over the union of the keys of every filter, merging their stored
+    /// hashes without access to the original keys.
+    ///
+    /// This is intended for compactions, where the filter of the output is built directly from the
+    /// filters of the inputs instead of hashing every key again. The hashes are merged with a
+    /// single `k`-way pass over the sorted hashes of the `k` filters.
+    ///
+    /// The resulting filter uses the hasher of the first filter. See [`Self::union`] for how this
+    /// affects the false positive rate.
+    ///
+    /// If `filters` is empty, this function will return [`MergeError::NoFilters`]. If the filters
+    /// were built with different hash functions, this function will return
+    /// [`MergeError::HasherMismatch`].
+    pub fn merge(filters: &[Self]) -> Result<Self, MergeError> {
+        let Some(first) = filters.first() else {
+            return Err(MergeError::NoFilters);
+        };
+        if filters
+            .iter()
+            .any(|f| f.hasher.raw_parts() != first.hasher.raw_parts())
+        {
+            return Err(MergeError::HasherMismatch);
+        }
+
+        let capacity = filters.iter().map(|f| f.ef.len()).max().unwrap_or(0);
+        let mut hashes: Vec<u64> = Vec::with_capacity(capacity);
+
+        // A min-heap of the next hash of every filter, along with the index of the filter.
+        let mut iters: Vec<_> = filters.iter().map(Self::iter_hashes).collect();
+        let mut heap: BinaryHeap<Reverse<(u64, usize)>> = iters
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, iter)| Some(Reverse((iter.next()?, i))))
+            .collect();
+
+        while let Some(Reverse((hash, i))) = heap.pop() {
+            if hashes.last() != Some(&hash) {
+                hashes.push(hash);
+            }
+            if let Some(next) = iters[i].next() {
+                heap.push(Reverse((next, i)));
+            }
+        }
+
+        Ok(Self::from_sorted_hashes_unchecked(&hashes, first.hasher))
+    }
+
+    /// Returns the largest stored hash that is less than or equal to `hash`, or `None` if every
+    /// stored hash is greater than `hash`.
+    pub fn predecessor_hash(&self, hash: u64) -> Option<u64> {
+        self.ef.predecessor(hash)
+    }
+
+    /// Returns the smallest stored hash that is greater than or equal to `hash`, or `None` if every
+    /// stored hash is less than `hash`.
+    pub fn successor_hash(&self, hash: u64) -> Option<u64> {
+        self.ef.successor(hash)
+    }
+
+    /// Returns the seed that the hash function parameters of this filter were generated from, or
+    /// `None` if they were generated from a non-deterministic source of randomness.
+    ///
+    /// See [`OrderPreservingHasher::new_seeded`] for more information.
+    pub fn seed(&self) -> Option<u64> {
+        self.hasher.seed()
+    }
+
+    /// Returns the hash function used to encode the hash values.
+    pub fn hasher(&self) -> &OrderPreservingHasher {
+        &self.hasher
+    }
+
+    /// Returns the backend that stores the hash values.
+    pub fn backend(&self) -> &B {
+        &self.ef
+    }
+
     /// Gets the minimum hash value in the sorted hash codes.
     fn min_hash(&self) -> u64 {
-        self.ef.get_unchecked(0)
+        self.ef.get(0)
     }
 
     /// Gets the maximum hash value in the sorted hash codes.
     fn max_hash(&self) -> u64 {
-        self.ef.get_unchecked(self.ef.len() - 1)
+        self.ef.get(self.ef.len() - 1)
+    }
+    /// Returns the number of elements that the filter was built for, or `None` if it is not known.
+    ///
+    /// See [`OrderPreservingHasher::num_elements`] for more information.
+    pub fn num_elements(&self) -> Option<usize> {
+        self.hasher.num_elements()
+    }
+
+    /// Returns the maximum range interval that the filter was built for, or `None` if it is not
+    /// known.
+    ///
+    /// See [`OrderPreservingHasher::max_interval`] for more information.
+    pub fn max_interval(&self) -> Option<u64> {
+        self.hasher.max_interval()
     }
-    /// Returns the false positive rate, epsilon.
+
+    /// Returns the false positive rate, epsilon, or `None` if the number of elements or maximum
+    /// range interval that the filter was built for is not known.
     ///
     /// The false positive rate is determined by the hash function used, the maximum range of values
-    /// queried, and the total number of distinct values inside the range filter.
-    pub fn false_positive_rate(&self, num_elements: usize, max_interval: u64) -> f64 {
+    /// queried, and the total number of distinct values inside the range filter, which are
+    /// recorded when the hasher is created. See [`Self::false_positive_rate_for`] to calculate the
+    /// false positive rate for other parameters.
+    pub fn false_positive_rate(&self) -> Option<f64> {
+        Some(self.false_positive_rate_for(self.num_elements()?, self.max_interval()?))
+    }
+
+    /// Returns the false positive rate, epsilon, for the given number of distinct values inside the
+    /// range filter and maximum range of values queried.
+    pub fn false_positive_rate_for(&self, num_elements: usize, max_interval: u64) -> f64 {
         // The false positive rate is equal to nL / r.
         (num_elements as u64 * max_interval) as f64 / self.hasher.reduced_universe() as f64
     }
 
     /// Returns the amount of space required to store this `RangeFilter` on the heap.
     ///
-    /// Internally, this function simply calls [`heap_size`](EliasFanoVec::heap_size) on the inner
-    /// [`EliasFanoVec`] structure.
+    /// Internally, this function simply calls
+    /// [`size_in_bytes`](RangeFilterBackend::size_in_bytes) on the inner backend.
     pub fn heap_size(&self) -> usize {
-        self.ef.heap_size()
+        self.ef.size_in_bytes()
+    }
+
+    /// Returns the number of bits used to encode each distinct hash value stored in the filter, or
+    /// `0.0` if the filter stores no hash values.
+    ///
+    /// Keys that collide into the same hash value are only stored once, so this is the realized
+    /// space usage after deduplication and encoding overheads, which can be compared with the
+    /// budget passed to [`OrderPreservingHasher::new_with_budget`].
+    pub fn bits_per_key(&self) -> f64 {
+        match self.ef.len() {
+            0 => 0.0,
+            len => (8 * self.heap_size()) as f64 / len as f64,
+        }
     }
 }
+
+/// Checks that sorted and deduplicated hashes are non-empty and less than the reduced universe size
+/// of `hasher`.
+pub(crate) fn check_deduped_hashes(
+    hashes: &[u64],
+    hasher: &OrderPreservingHasher,
+) -> Result<(), BuildError> {
+    let Some(&max_hash) = hashes.last() else {
+        return Err(BuildError::EmptyInput);
+    };
+    if max_hash >= hasher.reduced_universe() {
+        return Err(BuildError::HashOutOfRange {
+            hash: max_hash,
+            reduced_universe: hasher.reduced_universe(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Converts a range into its inclusive `(start, end)` endpoints, or `None` if the range is empty or
+/// reversed.
+///
+/// This is the exact conversion used by [`RangeFilter::query`], exposed so that adapters built on
+/// top of the filter can interpret ranges the same way. An excluded end bound of `0`, as in `..0`,
+/// or an excluded start bound of [`u64::MAX`] describes an empty range and returns `None` instead
+/// of overflowing.
+///
+/// ```
+/// use grafite::range_endpoints;
+/// use std::ops::Bound;
+///
+/// assert_eq!(range_endpoints(&(3..7)), Some((3, 6)));
+/// assert_eq!(range_endpoints(&(3..=7)), Some((3, 7)));
+/// assert_eq!(range_endpoints(&(..)), Some((0, u64::MAX)));
+/// assert_eq!(range_endpoints(&(..0)), None);
+/// assert_eq!(range_endpoints(&(5..5)), None);
+///
+/// let exclusive = (Bound::Excluded(3), Bound::Excluded(7));
+/// assert_eq!(range_endpoints(&exclusive), Some((4, 6)));
+/// assert_eq!(range_endpoints(&(Bound::Excluded(u64::MAX), Bound::Unbounded)), None);
+/// ```
+pub fn range_endpoints<R>(range: &R) -> Option<(u64, u64)>
+where
+    R: RangeBounds<u64>,
+{
+    let start = match range.start_bound() {
+        std::ops::Bound::Included(&i) => i,
+        std::ops::Bound::Excluded(&s) => s.checked_add(1)?,
+        std::ops::Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        std::ops::Bound::Included(&i) => i,
+        std::ops::Bound::Excluded(&e) => e.checked_sub(1)?,
+        std::ops::Bound::Unbounded => u64::MAX,
+    };
+
+    (start <= end).then_some((start, end))
+}
@@ -3,11 +3,53 @@
 //!
 //! See the documentation for [`OrderPreservingHasher`] for more information.
 
+use crate::instrument;
+#[cfg(target_arch = "x86_64")]
+use crate::simd;
 use crate::utils::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::{ChaCha20Rng, ChaCha8Rng};
+use std::fmt;
+use std::num::NonZeroU64;
 
 /// The default universe size for 64-bit unsigned integers, which is equivalent to [`u64::MAX`].
 pub const MAX_UNIVERSE_SIZE: u64 = u64::MAX;
 
+/// The universe size for 128-bit unsigned integers, which is equivalent to [`u128::MAX`].
+pub const MAX_UNIVERSE_SIZE_128: u128 = u128::MAX;
+
+/// The Mersenne prime `2^61 - 1`, used by [`PrimeStrategy::Mersenne61`].
+pub const MERSENNE_61: u64 = (1 << 61) - 1;
+
+/// The windows of hash values that the keys of a range hash onto, as returned by
+/// [`OrderPreservingHasher::hash_range`].
+///
+/// Each window is given as its inclusive `(start_hash, end_hash)` endpoints, and wraps around the
+/// end of the reduced universe if `start_hash > end_hash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HashedRange {
+    /// The range lies within a single block, and hashes onto a single window.
+    One(u64, u64),
+    /// The range crosses from one block into the next, and hashes onto one window per block.
+    Two((u64, u64), (u64, u64)),
+    /// The range contains an entire block, and hashes onto every hash value.
+    All,
+}
+
+/// The strategy used to choose the large prime `p` of an [`OrderPreservingHasher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PrimeStrategy {
+    /// Samples a random prime greater than the reduced universe size. This is the default.
+    #[default]
+    Random,
+    /// Uses the fixed Mersenne prime [`MERSENNE_61`], which requires the reduced universe size to
+    /// be smaller than it.
+    ///
+    /// Reducing modulo a Mersenne prime only needs shifts, masks, and additions, which makes
+    /// hashing faster and removes the random prime search from construction entirely.
+    Mersenne61,
+}
+
 /// An error type representing if the parameters of an [`OrderPreservingHasher`] are invalid for any
 /// reason.
 #[derive(Debug, Clone, Copy)]
@@ -21,6 +63,20 @@ pub enum ParamError {
     /// If overflow occurs in the calculation of the reduced universe size, or if the bits used per
     /// key is invalid.
     Overflow,
+    /// If the number of elements is zero.
+    NoElements,
+    /// If a required parameter was not provided to a
+    /// [`RangeFilterBuilder`](crate::RangeFilterBuilder). Stores the name of the missing parameter.
+    MissingParameter(&'static str),
+    /// If both `epsilon` and `bits_per_key` were provided to a
+    /// [`RangeFilterBuilder`](crate::RangeFilterBuilder), since each one determines the other.
+    ConflictingParameters,
+    /// If the parameters passed to [`OrderPreservingHasher::from_parts`] do not describe a valid
+    /// hash function.
+    InvalidParts,
+    /// If [`PrimeStrategy::Mersenne61`] was requested but the reduced universe size is not smaller
+    /// than [`MERSENNE_61`]. Stores the reduced universe size.
+    ReducedUniverseTooLarge(u64),
 }
 
 /// A struct to help manage the order-preserving hash function used for the Grafite range filter.
@@ -32,7 +88,21 @@ pub enum ParamError {
 /// group them into a struct and use a [`Self::hash`] method to hash all of the input values.
 ///
 /// See the [`Self::new`] and [`Self::hash`] methods for more information.
-#[derive(Debug, Clone, Copy)]
+///
+/// # Keyed hash functions
+///
+/// Anyone who knows the constants `c1`, `c2`, and `p` can compute the hash of any key, and so can
+/// construct query ranges whose hashed windows contain a stored hash value but no stored key. An
+/// attacker who can issue queries could use such ranges to force a false positive on every query,
+/// and with it a lookup in the slower storage behind the filter. A hash function created with
+/// [`Self::new_keyed`] derives its constants from a secret key instead, and keeps them out of its
+/// [`Debug`] output and out of the serialized formats of [`RangeFilter`](crate::RangeFilter), so
+/// that they cannot leak through logs or stored files.
+#[derive(Clone, Copy)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct OrderPreservingHasher {
     /// The first arbitrary constant.
     c1: u64,
@@ -42,6 +112,22 @@ pub struct OrderPreservingHasher {
     p: u64,
     /// The size of the reduced universe.
     r: u64,
+    /// The seed used to generate the parameters, if they were generated deterministically.
+    seed: Option<u64>,
+    /// The number of elements the parameters were generated for, if known.
+    num_elements: Option<u64>,
+    /// The maximum range interval the parameters were generated for, if known.
+    max_interval: Option<u64>,
+    /// A value derived from the secret key that the parameters were generated from, if they were
+    /// keyed, which allows checking that the same key is supplied again without revealing it.
+    key_tag: Option<u64>,
+    /// Precomputed constants for reducing modulo `p` without a division.
+    p_barrett: Barrett128,
+    /// Precomputed constants for dividing by `r` without a division.
+    r_barrett: Barrett64,
+    /// The base 2 logarithm of `r` if it is a power of two, in which case dividing by `r` is a
+    /// shift and a mask.
+    r_shift: Option<u32>,
 }
 
 impl OrderPreservingHasher {
@@ -54,11 +140,422 @@ impl OrderPreservingHasher {
     /// See Section 3 of the original paper for more information on how the hash function works and
     /// behaves.
     pub fn new(num_elements: usize, epsilon: f64, max_interval: u64) -> Result<Self, ParamError> {
+        Self::new_with_rng(num_elements, epsilon, max_interval, &mut rand::thread_rng())
+    }
+
+    /// Creates a new hash function helper struct whose random parameters are generated
+    /// deterministically from `seed`.
+    ///
+    /// Calling this function with the same arguments always produces the same hash function, which
+    /// makes builds reproducible. The seed is recorded and can be retrieved with [`Self::seed`].
+    ///
+    /// See the [`Self::new`] method for more information.
+    pub fn new_seeded(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        seed: u64,
+    ) -> Result<Self, ParamError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let hasher = Self::new_with_rng(num_elements, epsilon, max_interval, &mut rng)?;
+
+        Ok(hasher.with_seed(seed))
+    }
+
+    /// Creates a new hash function helper struct, generating the random parameters with `rng`.
+    ///
+    /// This allows callers to supply a seeded or hardware source of randomness instead of
+    /// [`rand::thread_rng`]. Note that the resulting hash function does not record a
+    /// [`seed`](Self::seed), since the state of `rng` is not known.
+    ///
+    /// See the [`Self::new`] method for more information.
+    pub fn new_with_rng<R: Rng + ?Sized>(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        Self::new_with_strategy(
+            num_elements,
+            epsilon,
+            max_interval,
+            PrimeStrategy::Random,
+            rng,
+        )
+    }
+
+    /// Creates a new keyed hash function helper struct, whose random parameters are derived from
+    /// the secret `key`.
+    ///
+    /// The same `key` and arguments always produce the same hash function, so a filter that was
+    /// persisted without its constants can be loaded again by supplying the key, for example with
+    /// [`RangeFilter::read_from_keyed`](crate::RangeFilter::read_from_keyed). The key should come
+    /// from a cryptographically secure source and be kept secret. See the
+    /// [type-level documentation](Self#keyed-hash-functions) for more information.
+    ///
+    /// The constants are only kept out of the formats that store them separately from the hash
+    /// values. The `rkyv` archive is queried in place without a key, so it still stores them.
+    ///
+    /// See the [`Self::new`] method for more information.
+    pub fn new_keyed(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        key: &[u8; 32],
+    ) -> Result<Self, ParamError> {
+        let mut rng = ChaCha20Rng::from_seed(*key);
+        let hasher = Self::derive_in_universe(
+            MAX_UNIVERSE_SIZE as u128,
+            num_elements,
+            epsilon,
+            max_interval,
+            PrimeStrategy::Random,
+            false,
+            &mut rng,
+        )?
+        .with_key_tag(rng.gen());
+        instrument::hasher_derived(&hasher, epsilon);
+
+        Ok(hasher)
+    }
+
+    /// Creates a new keyed hash function helper struct with a custom reduced universe size, whose
+    /// random parameters are derived from the secret `key`.
+    ///
+    /// See the [`Self::new_with_reduced`] and [`Self::new_keyed`] methods for more information.
+    pub fn new_with_reduced_keyed(r: u64, key: &[u8; 32]) -> Self {
+        let mut rng = ChaCha20Rng::from_seed(*key);
+        let hasher = Self::new_with_reduced_rng(r, &mut rng);
+
+        hasher.with_key_tag(rng.gen())
+    }
+
+    /// Creates a new hash function helper struct for keys that are known to be less than
+    /// `universe_size`.
+    ///
+    /// The maximum range interval that a false positive rate can be guaranteed for grows with the
+    /// size of the universe of keys, and [`Self::new`] assumes the universe of every 64-bit key,
+    /// [`MAX_UNIVERSE_SIZE`]. Passing the actual universe size checks `max_interval` against the
+    /// tight bound instead, and reports it in [`ParamError::InvalidMaxInterval`] if it is exceeded.
+    /// Keys outside of the universe can still be hashed, but the false positive rate is only
+    /// guaranteed within it.
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, ParamError};
+    ///
+    /// // Keys in `[0, 2^40)`, for which a maximum interval of `2^40` is far too large.
+    /// let result = OrderPreservingHasher::new_with_universe(1 << 40, 1000, 0.01, 1 << 40);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(ParamError::InvalidMaxInterval(max)) if max == (1 << 40) / 100_000
+    /// ));
+    /// ```
+    ///
+    /// See the [`Self::new`] method for more information.
+    pub fn new_with_universe(
+        universe_size: u64,
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+    ) -> Result<Self, ParamError> {
+        Self::new_with_universe_rng(
+            universe_size,
+            num_elements,
+            epsilon,
+            max_interval,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Creates a new hash function helper struct for keys that are known to be less than
+    /// `universe_size`, whose random parameters are generated deterministically from `seed`.
+    ///
+    /// See the [`Self::new_with_universe`] and [`Self::new_seeded`] methods for more information.
+    pub fn new_with_universe_seeded(
+        universe_size: u64,
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        seed: u64,
+    ) -> Result<Self, ParamError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let hasher = Self::new_with_universe_rng(
+            universe_size,
+            num_elements,
+            epsilon,
+            max_interval,
+            &mut rng,
+        )?;
+
+        Ok(hasher.with_seed(seed))
+    }
+
+    /// Creates a new hash function helper struct for keys that are known to be less than
+    /// `universe_size`, generating the random parameters with `rng`.
+    ///
+    /// See the [`Self::new_with_universe`] and [`Self::new_with_rng`] methods for more information.
+    pub fn new_with_universe_rng<R: Rng + ?Sized>(
+        universe_size: u64,
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        Self::new_in_universe(
+            universe_size as u128,
+            num_elements,
+            epsilon,
+            max_interval,
+            PrimeStrategy::Random,
+            false,
+            rng,
+        )
+    }
+
+    /// Creates a new hash function helper struct for 128-bit keys, which are hashed with
+    /// [`Self::hash_u128`].
+    ///
+    /// The parameters have the same meaning as in [`Self::new`], but the maximum range interval is
+    /// checked against the 128-bit universe of keys. The reduced universe size still has to fit in
+    /// 64 bits, otherwise this function will return [`ParamError::Overflow`].
+    pub fn new_u128(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+    ) -> Result<Self, ParamError> {
+        Self::new_u128_with_rng(num_elements, epsilon, max_interval, &mut rand::thread_rng())
+    }
+
+    /// Creates a new hash function helper struct for 128-bit keys whose random parameters are
+    /// generated deterministically from `seed`.
+    ///
+    /// See the [`Self::new_u128`] and [`Self::new_seeded`] methods for more information.
+    pub fn new_u128_seeded(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        seed: u64,
+    ) -> Result<Self, ParamError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let hasher = Self::new_u128_with_rng(num_elements, epsilon, max_interval, &mut rng)?;
+
+        Ok(hasher.with_seed(seed))
+    }
+
+    /// Creates a new hash function helper struct for 128-bit keys, generating the random
+    /// parameters with `rng`.
+    ///
+    /// See the [`Self::new_u128`] and [`Self::new_with_rng`] methods for more information.
+    pub fn new_u128_with_rng<R: Rng + ?Sized>(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        Self::new_in_universe(
+            MAX_UNIVERSE_SIZE_128,
+            num_elements,
+            epsilon,
+            max_interval,
+            PrimeStrategy::Random,
+            false,
+            rng,
+        )
+    }
+
+    /// Creates a new hash function helper struct that uses the fixed Mersenne prime
+    /// [`MERSENNE_61`] instead of a random prime.
+    ///
+    /// This avoids the random prime search entirely and allows the hash function to use a fast
+    /// modular reduction. If the reduced universe size is not smaller than [`MERSENNE_61`], this
+    /// function will return [`ParamError::ReducedUniverseTooLarge`].
+    ///
+    /// See the [`Self::new`] and [`PrimeStrategy`] docs for more information.
+    pub fn new_mersenne(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+    ) -> Result<Self, ParamError> {
+        Self::new_with_strategy(
+            num_elements,
+            epsilon,
+            max_interval,
+            PrimeStrategy::Mersenne61,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Creates a new hash function helper struct whose reduced universe size is rounded up to the
+    /// next power of two.
+    ///
+    /// With a power of two reduced universe, the final reductions of the hash function become a
+    /// shift and a mask, which speeds up both building and querying. Since the reduced universe
+    /// can grow by up to a factor of two, the false positive rate can only decrease, at the cost of
+    /// up to one extra bit per key.
+    ///
+    /// If the rounded reduced universe size overflows, this function will return
+    /// [`ParamError::Overflow`]. See the [`Self::new`] method for more information.
+    pub fn new_power_of_two(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+    ) -> Result<Self, ParamError> {
+        Self::new_with_rounding(
+            num_elements,
+            epsilon,
+            max_interval,
+            PrimeStrategy::Random,
+            true,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Creates a new hash function helper struct, choosing the prime with `strategy` and generating
+    /// the random parameters with `rng`.
+    ///
+    /// See the [`Self::new`] method for more information.
+    pub fn new_with_strategy<R: Rng + ?Sized>(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        strategy: PrimeStrategy,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        Self::new_with_rounding(num_elements, epsilon, max_interval, strategy, false, rng)
+    }
+
+    /// Creates a new hash function helper struct, choosing the prime with `strategy`, optionally
+    /// rounding the reduced universe size up to a power of two, and generating the random
+    /// parameters with `rng`.
+    ///
+    /// See the [`Self::new_with_strategy`] and [`Self::new_power_of_two`] methods for more
+    /// information.
+    fn new_with_rounding<R: Rng + ?Sized>(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        strategy: PrimeStrategy,
+        power_of_two: bool,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        Self::new_in_universe(
+            MAX_UNIVERSE_SIZE as u128,
+            num_elements,
+            epsilon,
+            max_interval,
+            strategy,
+            power_of_two,
+            rng,
+        )
+    }
+
+    /// Creates a new hash function helper struct for keys from a universe of size `universe_size`.
+    ///
+    /// See the [`Self::new_with_rounding`] method for more information.
+    pub(crate) fn new_in_universe<R: Rng + ?Sized>(
+        universe_size: u128,
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        strategy: PrimeStrategy,
+        power_of_two: bool,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        let hasher = Self::derive_in_universe(
+            universe_size,
+            num_elements,
+            epsilon,
+            max_interval,
+            strategy,
+            power_of_two,
+            rng,
+        )?;
+        instrument::hasher_derived(&hasher, epsilon);
+
+        Ok(hasher)
+    }
+
+    /// Derives the parameters of a hash function for keys from a universe of size
+    /// `universe_size`, without recording them.
+    ///
+    /// See the [`Self::new_with_rounding`] method for more information.
+    fn derive_in_universe<R: Rng + ?Sized>(
+        universe_size: u128,
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        strategy: PrimeStrategy,
+        power_of_two: bool,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
         if epsilon <= 0.0 || 1.0 <= epsilon {
             return Err(ParamError::InvalidEpsilon(epsilon));
         }
+        if num_elements == 0 {
+            return Err(ParamError::NoElements);
+        }
+
+        let max_range_interval = Self::max_range_interval(universe_size, num_elements, epsilon);
+        let inverse_epsilon = (1.0 / epsilon).floor() as u64;
 
-        let max_range_interval = Self::max_range_interval(MAX_UNIVERSE_SIZE, num_elements, epsilon);
+        Self::derive_from_bounds(
+            num_elements,
+            inverse_epsilon,
+            max_range_interval,
+            max_interval,
+            strategy,
+            power_of_two,
+            rng,
+        )
+    }
+
+    /// Derives the parameters of a hash function for keys from a universe of size
+    /// `universe_size`, given the false positive rate as `1 / inverse_epsilon`, without recording
+    /// them.
+    ///
+    /// Unlike [`Self::derive_in_universe`], every step is computed with integers.
+    fn derive_with_inverse_epsilon<R: Rng + ?Sized>(
+        universe_size: u128,
+        num_elements: usize,
+        inverse_epsilon: NonZeroU64,
+        max_interval: u64,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        let inverse_epsilon = inverse_epsilon.get();
+        if inverse_epsilon == 1 {
+            return Err(ParamError::InvalidEpsilon(1.0));
+        }
+        if num_elements == 0 {
+            return Err(ParamError::NoElements);
+        }
+
+        // The maximum range interval `(u * e) / n` is `u / (n * k)` for `e = 1 / k`.
+        let max_range_interval = (universe_size / (num_elements as u128 * inverse_epsilon as u128))
+            .min(u64::MAX as u128) as u64;
+
+        Self::derive_from_bounds(
+            num_elements,
+            inverse_epsilon,
+            max_range_interval,
+            max_interval,
+            PrimeStrategy::Random,
+            false,
+            rng,
+        )
+    }
+
+    /// Derives the parameters of a hash function with the reduced universe size
+    /// `num_elements * max_interval * inverse_epsilon`, checking that `max_interval` is at most
+    /// `max_range_interval`.
+    fn derive_from_bounds<R: Rng + ?Sized>(
+        num_elements: usize,
+        inverse_epsilon: u64,
+        max_range_interval: u64,
+        max_interval: u64,
+        strategy: PrimeStrategy,
+        power_of_two: bool,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
         if max_interval > max_range_interval {
             return Err(ParamError::InvalidMaxInterval(max_range_interval));
         }
@@ -66,23 +563,38 @@ impl OrderPreservingHasher {
         let upper = (num_elements as u64)
             .checked_mul(max_interval)
             .ok_or(ParamError::Overflow)?;
-        let lower = (1.0 / epsilon).floor() as u64;
+        let mut reduced_universe_size = upper
+            .checked_mul(inverse_epsilon)
+            .ok_or(ParamError::Overflow)?;
+        if power_of_two {
+            reduced_universe_size = reduced_universe_size
+                .checked_next_power_of_two()
+                .ok_or(ParamError::Overflow)?;
+        }
 
-        let reduced_universe_size = upper.checked_mul(lower).ok_or(ParamError::Overflow)?;
+        Ok(Self::from_reduced(reduced_universe_size, strategy, rng)?
+            .with_build_params(num_elements as u64, max_interval))
+    }
 
+    /// Generates the prime and the two constants of a hash function for the reduced universe size
+    /// `r`, choosing the prime with the given `strategy`.
+    fn from_reduced<R: Rng + ?Sized>(
+        r: u64,
+        strategy: PrimeStrategy,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
         // Generate `p > r`.
-        let p = gen_prime(1 + reduced_universe_size..MAX_UNIVERSE_SIZE);
+        let p = match strategy {
+            PrimeStrategy::Random => gen_prime(rng, 1 + r..MAX_UNIVERSE_SIZE),
+            PrimeStrategy::Mersenne61 if r < MERSENNE_61 => MERSENNE_61,
+            PrimeStrategy::Mersenne61 => return Err(ParamError::ReducedUniverseTooLarge(r)),
+        };
 
         // Generate two numbers `c1, c2 < p` with `c1 != 0`.
-        let c1 = gen_random(1..p);
-        let c2 = gen_random(0..p);
+        let c1 = gen_random(rng, 1..p);
+        let c2 = gen_random(rng, 0..p);
 
-        Ok(Self {
-            c1,
-            c2,
-            p,
-            r: reduced_universe_size,
-        })
+        Ok(Self::from_raw_parts(c1, c2, p, r, None))
     }
 
     /// Calculates the false positive rate of the [`RangeFilter`](crate::RangeFilter) given a
@@ -94,10 +606,42 @@ impl OrderPreservingHasher {
             Err(ParamError::Overflow)
         } else {
             // We calculate the false positive rate with `L / 2^(B-2)`.
-            Ok(max_interval as f64 / (1 << (bits_per_key - 2)) as f64)
+            Ok(max_interval as f64 / (1u64 << (bits_per_key - 2)) as f64)
         }
     }
 
+    /// Calculates the smallest budget of bits per key for which the false positive rate of the
+    /// [`RangeFilter`](crate::RangeFilter) is at most `epsilon` for ranges of up to `max_interval`
+    /// keys, along with the resulting false positive rate.
+    ///
+    /// This is the inverse of [`Self::epsilon_with_budget`], which the returned false positive rate
+    /// is computed with, so it may be smaller than the target `epsilon`.
+    ///
+    /// ```
+    /// use grafite::OrderPreservingHasher;
+    ///
+    /// let (bits_per_key, epsilon) = OrderPreservingHasher::budget_for_epsilon(0.01, 20).unwrap();
+    /// assert_eq!((bits_per_key, epsilon), (13, 20.0 / 2048.0));
+    /// assert_eq!(OrderPreservingHasher::epsilon_with_budget(bits_per_key, 20).unwrap(), epsilon);
+    /// ```
+    ///
+    /// If `epsilon` is not strictly in between `0.0` and `1.0`, or no budget of at most 64 bits per
+    /// key is enough, this function will return a [`ParamError`].
+    pub fn budget_for_epsilon(epsilon: f64, max_interval: u64) -> Result<(u8, f64), ParamError> {
+        if !(epsilon > 0.0 && epsilon < 1.0) {
+            return Err(ParamError::InvalidEpsilon(epsilon));
+        }
+
+        for bits_per_key in 3..=64 {
+            let budget_epsilon = Self::epsilon_with_budget(bits_per_key, max_interval)?;
+            if budget_epsilon <= epsilon {
+                return Ok((bits_per_key, budget_epsilon));
+            }
+        }
+
+        Err(ParamError::Overflow)
+    }
+
     /// Creates a hash function given a budget of `bits_per_key` bits per key for every distinct
     /// value that is input into the range filter.
     ///
@@ -113,6 +657,111 @@ impl OrderPreservingHasher {
         Self::new(num_elements, epsilon, max_interval)
     }
 
+    /// Creates a hash function given a budget of `bits_per_key` bits per key, generating the random
+    /// parameters with `rng`.
+    ///
+    /// See the [`Self::new_with_budget`] and [`Self::new_with_rng`] methods for more information.
+    pub fn new_with_budget_rng<R: Rng + ?Sized>(
+        num_elements: usize,
+        bits_per_key: u8,
+        max_interval: u64,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        let epsilon = Self::epsilon_with_budget(bits_per_key, max_interval)?;
+        Self::new_with_rng(num_elements, epsilon, max_interval, rng)
+    }
+
+    /// Creates a hash function given a budget of `bits_per_key` bits per key, whose random
+    /// parameters are generated deterministically from `seed`.
+    ///
+    /// See the [`Self::new_with_budget`] and [`Self::new_seeded`] methods for more information.
+    pub fn new_with_budget_seeded(
+        num_elements: usize,
+        bits_per_key: u8,
+        max_interval: u64,
+        seed: u64,
+    ) -> Result<Self, ParamError> {
+        let epsilon = Self::epsilon_with_budget(bits_per_key, max_interval)?;
+        Self::new_seeded(num_elements, epsilon, max_interval, seed)
+    }
+
+    /// Creates a new hash function helper struct for the false positive rate `1 / inverse_epsilon`.
+    ///
+    /// The reduced universe size and the maximum range interval are derived from `epsilon` with
+    /// floating point arithmetic in [`Self::new`], so a rate such as `0.1`, which has no exact
+    /// binary representation, could be rounded differently across platforms or compiler settings.
+    /// This function only uses integer arithmetic, so the derived parameters are exact and always
+    /// identical. An `inverse_epsilon` of `1` is not a valid false positive rate, so this function
+    /// will return [`ParamError::InvalidEpsilon`] for it.
+    ///
+    /// ```
+    /// use grafite::OrderPreservingHasher;
+    /// use std::num::NonZeroU64;
+    ///
+    /// let k = NonZeroU64::new(100).unwrap();
+    /// let hasher = OrderPreservingHasher::new_with_inverse_epsilon(1000, k, 32).unwrap();
+    /// assert_eq!(hasher.reduced_universe(), 1000 * 32 * 100);
+    /// ```
+    ///
+    /// See the [`Self::new`] method for more information.
+    pub fn new_with_inverse_epsilon(
+        num_elements: usize,
+        inverse_epsilon: NonZeroU64,
+        max_interval: u64,
+    ) -> Result<Self, ParamError> {
+        Self::new_with_inverse_epsilon_rng(
+            num_elements,
+            inverse_epsilon,
+            max_interval,
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Creates a new hash function helper struct for the false positive rate `1 / inverse_epsilon`,
+    /// generating the random parameters with `rng`.
+    ///
+    /// See the [`Self::new_with_inverse_epsilon`] and [`Self::new_with_rng`] methods for more
+    /// information.
+    pub fn new_with_inverse_epsilon_rng<R: Rng + ?Sized>(
+        num_elements: usize,
+        inverse_epsilon: NonZeroU64,
+        max_interval: u64,
+        rng: &mut R,
+    ) -> Result<Self, ParamError> {
+        let hasher = Self::derive_with_inverse_epsilon(
+            MAX_UNIVERSE_SIZE as u128,
+            num_elements,
+            inverse_epsilon,
+            max_interval,
+            rng,
+        )?;
+        instrument::hasher_derived(&hasher, 1.0 / inverse_epsilon.get() as f64);
+
+        Ok(hasher)
+    }
+
+    /// Creates a new hash function helper struct for the false positive rate `1 / inverse_epsilon`,
+    /// whose random parameters are generated deterministically from `seed`.
+    ///
+    /// See the [`Self::new_with_inverse_epsilon`] and [`Self::new_seeded`] methods for more
+    /// information.
+    pub fn new_with_inverse_epsilon_seeded(
+        num_elements: usize,
+        inverse_epsilon: NonZeroU64,
+        max_interval: u64,
+        seed: u64,
+    ) -> Result<Self, ParamError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let hasher = Self::new_with_inverse_epsilon_rng(
+            num_elements,
+            inverse_epsilon,
+            max_interval,
+            &mut rng,
+        )?;
+
+        Ok(hasher.with_seed(seed))
+    }
+
     /// Creates a new hash function helper struct where the caller can pass in a custom reduced
     /// universe size.
     ///
@@ -125,18 +774,195 @@ impl OrderPreservingHasher {
     /// See the [`Self::new`] method for more information on how the hash function works and
     /// behaves.
     pub fn new_with_reduced(r: u64) -> Self {
-        let p = gen_prime(1 + r..MAX_UNIVERSE_SIZE);
+        Self::new_with_reduced_rng(r, &mut rand::thread_rng())
+    }
 
-        // Generate two numbers `c1, c2 < p` with `c1 != 0`.
-        let c1 = gen_random(1..p);
-        let c2 = gen_random(0..p);
+    /// Creates a new hash function helper struct with a custom reduced universe size, whose random
+    /// parameters are generated deterministically from `seed`.
+    ///
+    /// See the [`Self::new_with_reduced`] and [`Self::new_seeded`] methods for more information.
+    pub fn new_with_reduced_seeded(r: u64, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        Self::new_with_reduced_rng(r, &mut rng).with_seed(seed)
+    }
+
+    /// Creates a new hash function helper struct with a custom reduced universe size, generating
+    /// the random parameters with `rng`.
+    ///
+    /// See the [`Self::new_with_reduced`] and [`Self::new_with_rng`] methods for more information.
+    pub fn new_with_reduced_rng<R: Rng + ?Sized>(r: u64, rng: &mut R) -> Self {
+        Self::from_reduced(r, PrimeStrategy::Random, rng)
+            .expect("A random prime can be generated for any reduced universe size")
+    }
+
+    /// Creates a hash function helper struct from previously generated parameters, for example to
+    /// reconstruct an identical hash function on another machine.
+    ///
+    /// The parameters are validated: `r` must be positive, `p` must be a prime greater than `r`,
+    /// and the constants must satisfy `0 < c1 < p` and `c2 < p`. If they are invalid, this function
+    /// will return [`ParamError::InvalidParts`].
+    pub fn from_parts(c1: u64, c2: u64, p: u64, r: u64) -> Result<Self, ParamError> {
+        if r == 0 || p <= r || c1 == 0 || c1 >= p || c2 >= p || !is_probable_prime(p) {
+            return Err(ParamError::InvalidParts);
+        }
+
+        Ok(Self::from_raw_parts(c1, c2, p, r, None))
+    }
+
+    /// Returns the first constant `c1` of the hash function.
+    ///
+    /// If the hash function [is keyed](Self::is_keyed), this is derived from the secret key and
+    /// must be kept as secret as the key itself.
+    pub fn c1(&self) -> u64 {
+        self.c1
+    }
+
+    /// Returns the second constant `c2` of the hash function.
+    ///
+    /// If the hash function [is keyed](Self::is_keyed), this is derived from the secret key and
+    /// must be kept as secret as the key itself.
+    pub fn c2(&self) -> u64 {
+        self.c2
+    }
+
+    /// Returns the large prime `p` of the hash function.
+    ///
+    /// If the hash function [is keyed](Self::is_keyed), this is derived from the secret key and
+    /// must be kept as secret as the key itself.
+    pub fn p(&self) -> u64 {
+        self.p
+    }
+
+    /// Records the seed that the parameters of this hash function were generated from.
+    pub(crate) fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
 
-        Self { c1, c2, p, r }
+    /// Records that the parameters of this hash function were derived from a secret key, along
+    /// with a tag that identifies the key.
+    fn with_key_tag(self, key_tag: u64) -> Self {
+        Self {
+            key_tag: Some(key_tag),
+            ..self
+        }
     }
 
-    // A hash function taken from a pairwise-independent family.
-    fn inner_hash(&self, x: u64) -> u64 {
-        ((self.c1.overflowing_mul(x).0.overflowing_add(self.c2)).0 % self.p) % self.r
+    /// Returns the tag that identifies the secret key that the parameters of this hash function
+    /// were derived from, or `None` if they were not keyed.
+    pub(crate) fn key_tag(&self) -> Option<u64> {
+        self.key_tag
+    }
+
+    /// Records the number of elements and the maximum range interval that the parameters of this
+    /// hash function were generated for.
+    pub(crate) fn with_build_params(self, num_elements: u64, max_interval: u64) -> Self {
+        Self {
+            num_elements: Some(num_elements),
+            max_interval: Some(max_interval),
+            ..self
+        }
+    }
+
+    /// Creates a hash function helper struct directly from its parameters, without any validation.
+    pub(crate) fn from_raw_parts(c1: u64, c2: u64, p: u64, r: u64, seed: Option<u64>) -> Self {
+        Self {
+            c1,
+            c2,
+            p,
+            r,
+            seed,
+            num_elements: None,
+            max_interval: None,
+            key_tag: None,
+            p_barrett: Barrett128::new(p),
+            r_barrett: Barrett64::new(r),
+            r_shift: r.is_power_of_two().then(|| r.trailing_zeros()),
+        }
+    }
+
+    /// Returns the parameters `[c1, c2, p, r]` of the hash function.
+    pub(crate) fn raw_parts(&self) -> [u64; 4] {
+        [self.c1, self.c2, self.p, self.r]
+    }
+
+    // A hash function taken from a pairwise-independent family, `((c1 * x + c2) mod p) mod r`.
+    //
+    // The product is computed with 128-bit arithmetic, since wrapping on overflow would break the
+    // algebra that the pairwise independence (and thus the false positive rate) relies on. Both
+    // reductions use precomputed Barrett constants instead of dividing, unless `p` is
+    // `MERSENNE_61` (`MERSENNE`) or `r` is a power of two (`POW2`).
+    #[inline(always)]
+    fn inner_hash<const MERSENNE: bool, const POW2: bool>(&self, x: u64) -> u64 {
+        // Since `c1, c2 < p < 2^64`, this cannot overflow 128 bits.
+        let y = self.c1 as u128 * x as u128 + self.c2 as u128;
+
+        let y = if MERSENNE {
+            // Reduce with only shifts and additions.
+            mod_mersenne_61(y)
+        } else {
+            self.p_barrett.rem(y)
+        };
+
+        self.div_rem_r::<POW2>(y).1
+    }
+
+    // Returns `(x / r, x % r)`.
+    #[inline(always)]
+    fn div_rem_r<const POW2: bool>(&self, x: u64) -> (u64, u64) {
+        if POW2 {
+            let shift = self.r_shift.unwrap_or_default();
+            (x >> shift, x & (self.r - 1))
+        } else {
+            self.r_barrett.div_rem(x)
+        }
+    }
+
+    // The order-preserving hash function, specialized on the reductions it uses.
+    #[inline(always)]
+    fn hash_specialized<const MERSENNE: bool, const POW2: bool>(&self, x: u64) -> u64 {
+        let (inner, y) = self.div_rem_r::<POW2>(x);
+        let q = self.inner_hash::<MERSENNE, POW2>(inner);
+
+        // Compute `(q + x) mod r` without overflowing, since `q < r`.
+        match q.checked_add(y) {
+            Some(sum) if sum < self.r => sum,
+            _ => q.wrapping_add(y).wrapping_sub(self.r),
+        }
+    }
+
+    // Hashes every key in `keys` into `out` with a loop that is free of any dispatch, using AVX2
+    // if the CPU supports it.
+    fn hash_slice<const MERSENNE: bool, const POW2: bool>(&self, keys: &[u64], out: &mut [u64]) {
+        #[cfg(target_arch = "x86_64")]
+        let (keys, out) = if std::is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 is supported, and `hash_many` checked that the lengths are equal.
+            let done =
+                unsafe { simd::hash_slice::<MERSENNE, POW2>(&self.simd_params(), keys, out) };
+            (&keys[done..], &mut out[done..])
+        } else {
+            (keys, out)
+        };
+
+        for (hash, &x) in out.iter_mut().zip(keys) {
+            *hash = self.hash_specialized::<MERSENNE, POW2>(x);
+        }
+    }
+
+    // Returns the constants of the hash function in the form used by the AVX2 implementation.
+    #[cfg(target_arch = "x86_64")]
+    fn simd_params(&self) -> simd::HashParams {
+        simd::HashParams {
+            c1: self.c1,
+            c2: self.c2,
+            p: self.p_barrett.d,
+            p_m: self.p_barrett.m,
+            r: self.r_barrett.d,
+            r_m: self.r_barrett.m,
+            r_shift: self.r_shift.unwrap_or_default(),
+        }
     }
 
     /// A hash function that preserves locality and ordering modulo the reduced universe of integer
@@ -144,10 +970,66 @@ impl OrderPreservingHasher {
     ///
     /// TODO more docs.
     pub fn hash(&self, x: u64) -> u64 {
-        let inner = x / self.r;
-        let q = self.inner_hash(inner);
+        match (self.p == MERSENNE_61, self.r_shift.is_some()) {
+            (false, false) => self.hash_specialized::<false, false>(x),
+            (false, true) => self.hash_specialized::<false, true>(x),
+            (true, false) => self.hash_specialized::<true, false>(x),
+            (true, true) => self.hash_specialized::<true, true>(x),
+        }
+    }
+
+    /// Hashes a 128-bit key into the reduced universe, preserving the ordering of keys modulo the
+    /// reduced universe in the same way as [`Self::hash`].
+    ///
+    /// Like in [`Self::hash`], the key is split into a block `x / r` and an offset `x mod r`, and
+    /// the offset is shifted by a hash of the block. Since the block can be wider than 64 bits, it
+    /// is first reduced modulo `p`, so blocks that are a multiple of `p` apart are shifted by the
+    /// same amount. Such keys are at least `p * r` apart, which is far more than any range query
+    /// can span. Keys that fit in 64 bits are hashed exactly like [`Self::hash`] hashes them.
+    pub fn hash_u128(&self, x: u128) -> u64 {
+        if let Ok(x) = u64::try_from(x) {
+            return self.hash(x);
+        }
 
-        (q.overflowing_add(x).0) % self.r
+        let r = self.r as u128;
+        let (block, y) = (x / r, (x % r) as u64);
+        let q = self.inner_hash::<false, false>(self.p_barrett.rem(block));
+
+        // Compute `(q + y) mod r` without overflowing, since `q, y < r`.
+        match q.checked_add(y) {
+            Some(sum) if sum < self.r => sum,
+            _ => q.wrapping_add(y).wrapping_sub(self.r),
+        }
+    }
+
+    /// Hashes a slice of keys at once, writing the hash of `keys[i]` into `out[i]`.
+    ///
+    /// This produces the same hashes as calling [`Self::hash`] on every key, but chooses the
+    /// reductions used by the hash function once for the entire batch. The resulting loop has no
+    /// branches between keys, which lets the compiler unroll it and overlap the multiplications of
+    /// neighboring keys.
+    ///
+    /// On x86-64 CPUs that support AVX2, which is detected at runtime, four keys are hashed at once
+    /// with vector instructions. AVX2 has no widening 64-bit multiplication, so the products of the
+    /// hash function and its Barrett reductions are assembled from 32-bit limbs, and the hashes are
+    /// identical to those of the scalar loop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` and `out` have different lengths.
+    pub fn hash_many(&self, keys: &[u64], out: &mut [u64]) {
+        assert_eq!(
+            keys.len(),
+            out.len(),
+            "keys and out must have the same length"
+        );
+
+        match (self.p == MERSENNE_61, self.r_shift.is_some()) {
+            (false, false) => self.hash_slice::<false, false>(keys, out),
+            (false, true) => self.hash_slice::<false, true>(keys, out),
+            (true, false) => self.hash_slice::<true, false>(keys, out),
+            (true, true) => self.hash_slice::<true, true>(keys, out),
+        }
     }
 
     /// Returns the size of the reduced universe that the hash function maps to.
@@ -155,6 +1037,93 @@ impl OrderPreservingHasher {
         self.r
     }
 
+    /// Hashes the non-empty inclusive range `[start, end]` onto the windows of hash values that
+    /// its keys hash onto.
+    ///
+    /// Keys within the same block `x / r` of the reduced universe hash onto consecutive hash
+    /// values, wrapping around at most once, but keys of different blocks are shifted by unrelated
+    /// amounts. A range that crosses from one block into the next therefore hashes onto two
+    /// separate windows, and a range that contains an entire block hashes onto every hash value.
+    pub(crate) fn hash_range(&self, start: u64, end: u64) -> HashedRange {
+        debug_assert!(start <= end);
+
+        let (start_block, end_block) = match self.r_shift {
+            Some(shift) => (start >> shift, end >> shift),
+            None => (
+                self.r_barrett.div_rem(start).0,
+                self.r_barrett.div_rem(end).0,
+            ),
+        };
+
+        match end_block - start_block {
+            0 => HashedRange::One(self.hash(start), self.hash(end)),
+            1 => {
+                // The first key of the second block, which is greater than `start`.
+                let boundary = end_block * self.r;
+                HashedRange::Two(
+                    (self.hash(start), self.hash(boundary - 1)),
+                    (self.hash(boundary), self.hash(end)),
+                )
+            }
+            _ => HashedRange::All,
+        }
+    }
+
+    /// Hashes the non-empty inclusive range `[start, end]` of 128-bit keys onto the windows of hash
+    /// values that its keys hash onto, in the same way as [`Self::hash_range`].
+    pub(crate) fn hash_range_u128(&self, start: u128, end: u128) -> HashedRange {
+        debug_assert!(start <= end);
+
+        let r = self.r as u128;
+        match end / r - start / r {
+            0 => HashedRange::One(self.hash_u128(start), self.hash_u128(end)),
+            1 => {
+                // The first key of the second block, which is greater than `start`.
+                let boundary = end / r * r;
+                HashedRange::Two(
+                    (self.hash_u128(start), self.hash_u128(boundary - 1)),
+                    (self.hash_u128(boundary), self.hash_u128(end)),
+                )
+            }
+            _ => HashedRange::All,
+        }
+    }
+
+    /// Returns `true` if every hash value fits in 32 bits, which is the case when the reduced
+    /// universe size is at most `2^32`.
+    ///
+    /// Filters built with such a hasher hash and sort 32-bit values instead of 64-bit ones, and
+    /// persist their hash values as 32-bit integers, which halves the memory traffic of both.
+    pub fn fits_in_u32(&self) -> bool {
+        self.r <= 1 << 32
+    }
+
+    /// Returns the seed that the hash function parameters were generated from, or `None` if they
+    /// were generated from a non-deterministic source of randomness.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Returns `true` if the hash function parameters were derived from a secret key with
+    /// [`Self::new_keyed`] or [`Self::new_with_reduced_keyed`].
+    pub fn is_keyed(&self) -> bool {
+        self.key_tag.is_some()
+    }
+
+    /// Returns the number of elements that the hash function parameters were generated for, or
+    /// `None` if the reduced universe size was chosen directly, e.g. with
+    /// [`Self::new_with_reduced`] or [`Self::from_parts`].
+    pub fn num_elements(&self) -> Option<usize> {
+        self.num_elements.map(|n| n as usize)
+    }
+
+    /// Returns the maximum range interval that the hash function parameters were generated for, or
+    /// `None` if the reduced universe size was chosen directly, e.g. with
+    /// [`Self::new_with_reduced`] or [`Self::from_parts`].
+    pub fn max_interval(&self) -> Option<u64> {
+        self.max_interval
+    }
+
     /// Returns the maximum range interval given the number of elements in the set and the false
     /// positive rate.
     ///
@@ -163,17 +1132,83 @@ impl OrderPreservingHasher {
     /// -   `e`: The false positive rate `epsilon`
     /// -   `n`: The number of elements in the input set
     ///
-    /// If the universe size is not known, [`MAX_UNIVERSE_SIZE`] should be used.
+    /// If the universe size is not known, [`MAX_UNIVERSE_SIZE`] should be used. Since the maximum
+    /// range interval is a 64-bit integer, it saturates at [`u64::MAX`] for larger universes.
     ///
     /// # Panics
     ///
     /// Panics if `epsilon` is not strictly in between `0.0` and `1.0`.
-    fn max_range_interval(universe_size: u64, num_elements: usize, epsilon: f64) -> u64 {
+    fn max_range_interval(universe_size: u128, num_elements: usize, epsilon: f64) -> u64 {
         assert!(
             0.0 < epsilon && epsilon < 1.0,
             "epsilon must be between 0.0 and 1.0"
         );
 
-        ((universe_size as f64) * epsilon) as u64 / num_elements as u64
+        let max_range_interval = ((universe_size as f64) * epsilon) as u128 / num_elements as u128;
+        max_range_interval.min(u64::MAX as u128) as u64
+    }
+}
+
+/// A placeholder for a secret value in [`Debug`] output.
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl fmt::Debug for OrderPreservingHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("OrderPreservingHasher");
+
+        // The precomputed reduction constants are left out, since they reveal `p`.
+        if self.is_keyed() {
+            debug
+                .field("c1", &Redacted)
+                .field("c2", &Redacted)
+                .field("p", &Redacted);
+        } else {
+            debug
+                .field("c1", &self.c1)
+                .field("c2", &self.c2)
+                .field("p", &self.p);
+        }
+
+        debug
+            .field("r", &self.r)
+            .field("seed", &self.seed)
+            .field("num_elements", &self.num_elements)
+            .field("max_interval", &self.max_interval)
+            .field("keyed", &self.is_keyed())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl ArchivedOrderPreservingHasher {
+    /// Converts the archived hasher back into an [`OrderPreservingHasher`].
+    ///
+    /// This is a cheap copy of the four hash parameters.
+    pub fn to_hasher(&self) -> OrderPreservingHasher {
+        let hasher = OrderPreservingHasher::from_raw_parts(
+            self.c1.to_native(),
+            self.c2.to_native(),
+            self.p.to_native(),
+            self.r.to_native(),
+            self.seed.as_ref().map(|seed| seed.to_native()),
+        );
+
+        let hasher = match self.key_tag.as_ref() {
+            Some(key_tag) => hasher.with_key_tag(key_tag.to_native()),
+            None => hasher,
+        };
+
+        match (self.num_elements.as_ref(), self.max_interval.as_ref()) {
+            (Some(num_elements), Some(max_interval)) => {
+                hasher.with_build_params(num_elements.to_native(), max_interval.to_native())
+            }
+            _ => hasher,
+        }
     }
 }
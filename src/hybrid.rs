@@ -0,0 +1,157 @@
+//! This module contains the [`HybridRangeFilter`] type, which pairs a [`RangeFilter`] with a Bloom
+//! filter for point queries.
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::utils::mix64;
+use crate::{BuildError, OrderPreservingHasher, ParamError, RangeFilter};
+
+/// A combined filter that answers point queries with a Bloom filter and range queries with a
+/// [`RangeFilter`].
+///
+/// A [`RangeFilter`] built for ranges of up to `L` keys has the same false positive rate for a
+/// single key as for a range of `L` keys, which is much worse than what a Bloom filter achieves
+/// with the same space. This type keeps a Bloom filter over the same keys with its own false
+/// positive rate for point queries, and routes every query that covers a single key to it.
+///
+/// ```
+/// use grafite::{HybridRangeFilter, OrderPreservingHasher};
+///
+/// let values = [1, 2, 3, 7, 8, 9, 15, 20];
+/// let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+/// let rf = HybridRangeFilter::new(values, hasher, 0.001).unwrap();
+///
+/// assert!(rf.contains(7));
+/// assert!(rf.query(3..5));
+/// assert!(!rf.query(10..15));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HybridRangeFilter {
+    /// The range filter used for queries that cover more than one key.
+    range: RangeFilter,
+    /// The Bloom filter used for queries that cover a single key.
+    points: BloomFilter,
+}
+
+impl HybridRangeFilter {
+    /// Creates a new hybrid filter over the given keys, whose range filter uses `hasher` and whose
+    /// Bloom filter has a false positive rate of `point_epsilon`.
+    ///
+    /// If `point_epsilon` is not strictly in between `0.0` and `1.0`, this function will return a
+    /// [`ParamError::InvalidEpsilon`]. If the range filter could not be built for any reason, this
+    /// function will return a [`BuildError`].
+    pub fn new<I>(
+        keys: I,
+        hasher: OrderPreservingHasher,
+        point_epsilon: f64,
+    ) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        if point_epsilon <= 0.0 || 1.0 <= point_epsilon {
+            return Err(ParamError::InvalidEpsilon(point_epsilon).into());
+        }
+
+        let keys: Vec<u64> = keys.into_iter().collect();
+        let range = RangeFilter::try_new(keys.iter().copied(), hasher)?;
+
+        let mut points = BloomFilter::new(keys.len(), point_epsilon);
+        for &key in &keys {
+            points.insert(key);
+        }
+
+        Ok(Self { range, points })
+    }
+
+    /// Checks if the given key may be among the original input set, using the Bloom filter.
+    pub fn contains(&self, x: u64) -> bool {
+        self.points.contains(x)
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// Ranges that cover a single key are answered by [`Self::contains`], and every other range is
+    /// answered by [`RangeFilter::query_chunked`] on the range filter, which splits ranges that
+    /// are longer than its maximum range interval into sub-queries that each keep its false
+    /// positive rate guarantee. Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        match range_endpoints(&range) {
+            None => false,
+            Some((start, end)) if start == end => self.contains(start),
+            Some((start, end)) => self.range.query_chunked(start..=end),
+        }
+    }
+
+    /// Returns the range filter used for queries that cover more than one key.
+    pub fn range_filter(&self) -> &RangeFilter {
+        &self.range
+    }
+
+    /// Returns the amount of space required to store this filter on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.range.heap_size() + self.points.heap_size()
+    }
+}
+
+/// A standard Bloom filter over 64-bit keys that uses double hashing to derive its `k` probes.
+#[derive(Debug, Clone)]
+struct BloomFilter {
+    /// The bits of the filter.
+    bits: Vec<u64>,
+    /// The number of bits, which is always a positive multiple of `64`.
+    num_bits: u64,
+    /// The number of probes per key.
+    num_probes: u32,
+    /// A random seed mixed into every key, so that the probed bits are not predictable.
+    seed: u64,
+}
+
+impl BloomFilter {
+    /// Creates an empty Bloom filter sized for `num_elements` keys and a false positive rate of
+    /// `epsilon`, which must be strictly in between `0.0` and `1.0`.
+    fn new(num_elements: usize, epsilon: f64) -> Self {
+        // The optimal size is `-n ln(e) / ln(2)^2` bits with `(m / n) ln(2)` probes.
+        let ln2 = std::f64::consts::LN_2;
+        let bits_per_key = -epsilon.ln() / (ln2 * ln2);
+
+        let num_bits = (num_elements.max(1) as f64 * bits_per_key).ceil() as u64;
+        let num_words = num_bits.div_ceil(64).max(1);
+        let num_probes = (bits_per_key * ln2).round().max(1.0) as u32;
+
+        Self {
+            bits: vec![0; num_words as usize],
+            num_bits: num_words * 64,
+            num_probes,
+            seed: rand::random(),
+        }
+    }
+
+    /// Returns the bit positions probed for the key `x`.
+    fn probes(&self, x: u64) -> impl Iterator<Item = u64> {
+        let num_bits = self.num_bits;
+        let h1 = mix64(x ^ self.seed);
+        // The step is odd so that it is never zero.
+        let h2 = mix64(h1) | 1;
+
+        (0..self.num_probes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    fn insert(&mut self, x: u64) {
+        for bit in self.probes(x) {
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    fn contains(&self, x: u64) -> bool {
+        self.probes(x)
+            .all(|bit| self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+
+    fn heap_size(&self) -> usize {
+        self.bits.len() * std::mem::size_of::<u64>()
+    }
+}
@@ -0,0 +1,88 @@
+//! Hooks that emit `tracing` spans and events when the `tracing` feature is enabled, and compile to
+//! nothing otherwise.
+//!
+//! Events are emitted under the `grafite` target: the derived hash function parameters and the end
+//! of every build phase at the `DEBUG` level, within a `build` span, and one in every
+//! [`QUERY_SAMPLE_INTERVAL`] queries of each thread at the `TRACE` level.
+
+use crate::{BuildPhase, OrderPreservingHasher};
+
+/// The number of queries of a thread between two traced queries.
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+pub(crate) const QUERY_SAMPLE_INTERVAL: u32 = 1 << 10;
+
+/// A guard that keeps the span of a build entered until it is dropped.
+pub(crate) struct BuildSpan {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Enters a span that covers building a filter over at least `num_keys` keys.
+#[inline]
+pub(crate) fn build_span(num_keys: usize) -> BuildSpan {
+    #[cfg(not(feature = "tracing"))]
+    let _ = num_keys;
+
+    BuildSpan {
+        #[cfg(feature = "tracing")]
+        _span: tracing::debug_span!(target: "grafite", "build", num_keys).entered(),
+    }
+}
+
+/// Records the parameters of a hash function derived from the build parameters.
+#[inline]
+pub(crate) fn hasher_derived(hasher: &OrderPreservingHasher, epsilon: f64) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: "grafite",
+        num_elements = hasher.num_elements(),
+        epsilon,
+        max_interval = hasher.max_interval(),
+        reduced_universe = hasher.reduced_universe(),
+        // The prime of a keyed hash function is secret.
+        prime = (!hasher.is_keyed()).then(|| hasher.p()),
+        "derived hash function parameters"
+    );
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (hasher, epsilon);
+}
+
+/// Records the end of a build phase that processed `items` items.
+#[inline]
+pub(crate) fn phase_finished(phase: BuildPhase, items: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(target: "grafite", ?phase, items, "finished build phase");
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (phase, items);
+}
+
+/// Records a query of the inclusive range `start..=end` if it is sampled.
+#[inline]
+pub(crate) fn query(start: u64, end: u64, result: bool) {
+    #[cfg(feature = "tracing")]
+    {
+        use std::cell::Cell;
+
+        thread_local! {
+            static QUERIES: Cell<u32> = const { Cell::new(0) };
+        }
+
+        if !tracing::enabled!(target: "grafite", tracing::Level::TRACE) {
+            return;
+        }
+
+        let sampled = QUERIES.with(|queries| {
+            let count = queries.get().wrapping_add(1);
+            queries.set(count);
+            count % QUERY_SAMPLE_INTERVAL == 0
+        });
+        if sampled {
+            tracing::trace!(target: "grafite", start, end, result, "sampled query");
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    let _ = (start, end, result);
+}
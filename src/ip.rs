@@ -0,0 +1,103 @@
+//! Support for range filtering IPv4 and IPv6 addresses, including CIDR block queries.
+//!
+//! IPv4 addresses are mapped onto `u64` with [`ToOrderedU64`] and stored in a
+//! [`KeyedRangeFilter`], while IPv6 addresses need the 128-bit path of [`RangeFilter128`], whose
+//! keys are built with `u128::from(addr)`.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+
+use crate::{KeyedRangeFilter, RangeFilter128, RangeFilterBackend, ToOrderedU64};
+
+/// Maps the address onto its big-endian integer value, which is its numeric order.
+impl ToOrderedU64 for Ipv4Addr {
+    fn to_ordered_u64(&self) -> u64 {
+        u32::from(*self) as u64
+    }
+}
+
+/// Returns the range of addresses in the IPv4 CIDR block `addr/prefix_len`.
+///
+/// The host bits of `addr` are ignored, so `10.1.2.3/8` is the same block as `10.0.0.0/8`.
+///
+/// # Panics
+///
+/// Panics if `prefix_len` is greater than `32`.
+///
+/// ```
+/// use grafite::ipv4_cidr;
+/// use std::net::Ipv4Addr;
+///
+/// let block = ipv4_cidr(Ipv4Addr::new(10, 1, 2, 3), 8);
+/// assert_eq!(block, Ipv4Addr::new(10, 0, 0, 0)..=Ipv4Addr::new(10, 255, 255, 255));
+/// ```
+pub fn ipv4_cidr(addr: Ipv4Addr, prefix_len: u8) -> RangeInclusive<Ipv4Addr> {
+    assert!(prefix_len <= 32, "an IPv4 prefix has at most 32 bits");
+
+    let host_mask = u32::MAX.checked_shr(prefix_len as u32).unwrap_or(0);
+    let start = u32::from(addr) & !host_mask;
+
+    Ipv4Addr::from(start)..=Ipv4Addr::from(start | host_mask)
+}
+
+/// Returns the range of addresses in the IPv6 CIDR block `addr/prefix_len`, as the integer values
+/// of the addresses.
+///
+/// The host bits of `addr` are ignored.
+///
+/// # Panics
+///
+/// Panics if `prefix_len` is greater than `128`.
+pub fn ipv6_cidr(addr: Ipv6Addr, prefix_len: u8) -> RangeInclusive<u128> {
+    assert!(prefix_len <= 128, "an IPv6 prefix has at most 128 bits");
+
+    let host_mask = u128::MAX.checked_shr(prefix_len as u32).unwrap_or(0);
+    let start = u128::from(addr) & !host_mask;
+
+    start..=start | host_mask
+}
+
+impl<B: RangeFilterBackend> KeyedRangeFilter<Ipv4Addr, B> {
+    /// Checks if there may be any addresses in the CIDR block `addr/prefix_len` among the original
+    /// input set, for example to check if a segment has any flows from `10.0.0.0/8`.
+    ///
+    /// The block covers `2^(32 - prefix_len)` addresses, and the maximum range interval of the
+    /// hasher should be at least that large for the shortest prefix that is queried, otherwise the
+    /// false positive rate of the query is not guaranteed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is greater than `32`.
+    ///
+    /// ```
+    /// use grafite::{KeyedRangeFilter, OrderPreservingHasher};
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let addrs = [Ipv4Addr::new(10, 4, 0, 1), Ipv4Addr::new(192, 168, 1, 20)];
+    /// let hasher = OrderPreservingHasher::new(addrs.len(), 0.01, 1 << 24).unwrap();
+    /// let rf = KeyedRangeFilter::new(addrs.into_iter(), hasher);
+    ///
+    /// assert!(rf.query_cidr(Ipv4Addr::new(10, 0, 0, 0), 8));
+    /// assert!(rf.query_cidr(Ipv4Addr::new(192, 168, 1, 0), 24));
+    /// ```
+    pub fn query_cidr(&self, addr: Ipv4Addr, prefix_len: u8) -> bool {
+        self.query(ipv4_cidr(addr, prefix_len))
+    }
+}
+
+impl RangeFilter128 {
+    /// Checks if there may be any addresses in the IPv6 CIDR block `addr/prefix_len` among the
+    /// original input set, whose keys must have been built with `u128::from(addr)`.
+    ///
+    /// The block covers `2^(128 - prefix_len)` addresses, and the maximum range interval of the
+    /// hasher should be at least that large for the shortest prefix that is queried, otherwise the
+    /// false positive rate of the query is not guaranteed. Since the maximum range interval is a
+    /// 64-bit integer, the prefix should be longer than `64` bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix_len` is greater than `128`.
+    pub fn query_ipv6_cidr(&self, addr: Ipv6Addr, prefix_len: u8) -> bool {
+        self.query(ipv6_cidr(addr, prefix_len))
+    }
+}
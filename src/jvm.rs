@@ -0,0 +1,200 @@
+//! JNI bindings for probing filters from JVM-based storage engines.
+//!
+//! The bindings implement the native methods of a Java class `grafite.RangeFilter`, which is
+//! expected to be declared as:
+//!
+//! ```java
+//! package grafite;
+//!
+//! final class RangeFilter {
+//!     static native long load(java.nio.ByteBuffer buffer);
+//!     static native boolean query(long handle, long start, long end);
+//!     static native boolean contains(long handle, long key);
+//!     static native long heapSize(long handle);
+//!     static native void free(long handle);
+//! }
+//! ```
+//!
+//! A filter is loaded from a direct `ByteBuffer` holding a filter serialized with
+//! [`RangeFilter::write_to`], for example one that was written by a Rust ingestion job and then
+//! memory-mapped by the JVM. Loading returns an opaque handle, which must eventually be released
+//! with `free`. Keys are `u64` values, which are passed as Java `long`s and compared as unsigned
+//! integers, in the same way as `Long.compareUnsigned`.
+//!
+//! Failures are reported by throwing an `IllegalArgumentException` for invalid buffers, an
+//! `IllegalStateException` for null handles, and a `RuntimeException` if a call panics, which
+//! indicates a bug in this crate. The library can be built for loading with `System.loadLibrary`
+//! with, for example, `cargo rustc --release --features jni --crate-type cdylib`.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::slice;
+
+use jni::objects::{JByteBuffer, JClass};
+use jni::sys::{jboolean, jlong, JNI_FALSE};
+use jni::JNIEnv;
+
+use crate::RangeFilter;
+
+/// A Java exception that should be thrown when a native method returns.
+struct Exception {
+    /// The JNI name of the exception class, such as `java/lang/IllegalArgumentException`.
+    class: &'static str,
+    /// The message of the exception.
+    message: String,
+}
+
+impl Exception {
+    /// Creates an `IllegalArgumentException` with the given message.
+    fn illegal_argument(message: impl Into<String>) -> Self {
+        Self {
+            class: "java/lang/IllegalArgumentException",
+            message: message.into(),
+        }
+    }
+}
+
+/// Runs `f`, throwing its exception, or a `RuntimeException` if it panics, and returning `default`
+/// on failure.
+fn guard<T, F>(env: &mut JNIEnv, default: T, f: F) -> T
+where
+    F: FnOnce(&mut JNIEnv) -> Result<T, Exception>,
+{
+    let exception = match catch_unwind(AssertUnwindSafe(|| f(env))) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(exception)) => exception,
+        Err(_) => Exception {
+            class: "java/lang/RuntimeException",
+            message: "grafite panicked in a native method".to_string(),
+        },
+    };
+
+    // An exception that is already pending, for example one thrown by the JVM while accessing the
+    // buffer, takes precedence.
+    if !env.exception_check().unwrap_or(true) {
+        // If throwing fails, there is nothing left to report the failure to.
+        let _ = env.throw_new(exception.class, exception.message);
+    }
+
+    default
+}
+
+/// Returns the filter behind a handle returned by [`Java_grafite_RangeFilter_load`].
+///
+/// # Safety
+///
+/// `handle` must be zero or a live handle.
+unsafe fn filter<'a>(handle: jlong) -> Result<&'a RangeFilter, Exception> {
+    let filter = handle as *const RangeFilter;
+
+    // SAFETY: The caller guarantees that a non-zero `handle` is live.
+    unsafe { filter.as_ref() }.ok_or(Exception {
+        class: "java/lang/IllegalStateException",
+        message: "the filter handle is null".to_string(),
+    })
+}
+
+/// Reads a filter serialized with [`RangeFilter::write_to`] from all bytes of a direct
+/// `ByteBuffer`, and returns its handle.
+///
+/// Throws an `IllegalArgumentException` if the buffer is not direct, or if its contents are not a
+/// valid filter.
+#[no_mangle]
+pub extern "system" fn Java_grafite_RangeFilter_load<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    buffer: JByteBuffer<'local>,
+) -> jlong {
+    guard(&mut env, 0, |env| {
+        let not_direct = |_| Exception::illegal_argument("the buffer must be a direct ByteBuffer");
+        let address = env.get_direct_buffer_address(&buffer).map_err(not_direct)?;
+        let len = env
+            .get_direct_buffer_capacity(&buffer)
+            .map_err(not_direct)?;
+
+        // SAFETY: The JVM guarantees that a direct buffer points to `len` readable bytes, which
+        // stay alive while `buffer` is referenced by this call.
+        let bytes = unsafe { slice::from_raw_parts(address, len) };
+        let filter = RangeFilter::read_from(bytes)
+            .map_err(|e| Exception::illegal_argument(format!("invalid filter: {e}")))?;
+
+        Ok(Box::into_raw(Box::new(filter)) as jlong)
+    })
+}
+
+/// Checks if there may be any keys in the inclusive range `start..=end`, where both endpoints are
+/// unsigned.
+///
+/// # Safety
+///
+/// `handle` must be zero or a live handle returned by [`Java_grafite_RangeFilter_load`].
+#[no_mangle]
+pub unsafe extern "system" fn Java_grafite_RangeFilter_query<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+    start: jlong,
+    end: jlong,
+) -> jboolean {
+    guard(&mut env, JNI_FALSE, |_| {
+        // SAFETY: The caller guarantees that a non-zero `handle` is live.
+        let filter = unsafe { filter(handle) }?;
+        Ok(filter.query(start as u64..=end as u64).into())
+    })
+}
+
+/// Checks if the given unsigned key may be among the original input set.
+///
+/// # Safety
+///
+/// `handle` must be zero or a live handle returned by [`Java_grafite_RangeFilter_load`].
+#[no_mangle]
+pub unsafe extern "system" fn Java_grafite_RangeFilter_contains<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+    key: jlong,
+) -> jboolean {
+    guard(&mut env, JNI_FALSE, |_| {
+        // SAFETY: The caller guarantees that a non-zero `handle` is live.
+        let filter = unsafe { filter(handle) }?;
+        Ok(filter.contains(key as u64).into())
+    })
+}
+
+/// Returns the amount of space required to store the filter on the heap.
+///
+/// # Safety
+///
+/// `handle` must be zero or a live handle returned by [`Java_grafite_RangeFilter_load`].
+#[no_mangle]
+pub unsafe extern "system" fn Java_grafite_RangeFilter_heapSize<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) -> jlong {
+    guard(&mut env, 0, |_| {
+        // SAFETY: The caller guarantees that a non-zero `handle` is live.
+        let filter = unsafe { filter(handle) }?;
+        Ok(filter.heap_size() as jlong)
+    })
+}
+
+/// Releases a filter handle. Passing a zero handle does nothing.
+///
+/// # Safety
+///
+/// `handle` must be zero or a live handle returned by [`Java_grafite_RangeFilter_load`], which
+/// must not be used after this call.
+#[no_mangle]
+pub unsafe extern "system" fn Java_grafite_RangeFilter_free<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) {
+    let filter = handle as *mut RangeFilter;
+    if !filter.is_null() {
+        // SAFETY: The caller guarantees that `handle` is a live handle returned by this module,
+        // which was created with `Box::into_raw`.
+        drop(unsafe { Box::from_raw(filter) });
+    }
+}
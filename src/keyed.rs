@@ -0,0 +1,350 @@
+//! This module contains the [`ToOrderedU64`] trait, which maps key types onto `u64` while
+//! preserving their order, and the [`KeyedRangeFilter`] type, a range filter that is generic over
+//! such key types.
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, Encoding, OrderPreservingHasher, RangeFilter, RangeFilterBackend};
+
+/// A key type that can be mapped onto a `u64` while preserving its order.
+///
+/// The mapping must be monotone: if `a <= b`, then `a.to_ordered_u64() <= b.to_ordered_u64()`.
+/// Distinct keys may be mapped onto the same integer, in which case a filter cannot tell them
+/// apart, but a mapping that is not monotone causes false negatives.
+///
+/// This is implemented for every primitive integer type of at most 64 bits, `bool`, `char`, `f32`,
+/// `f64`, byte strings and strings, references to any implementing type, and [`Reverse`] of any
+/// implementing type.
+///
+/// ```
+/// use grafite::ToOrderedU64;
+///
+/// assert!((-1i64).to_ordered_u64() < 0i64.to_ordered_u64());
+/// assert!((-1.5f64).to_ordered_u64() < 0.25f64.to_ordered_u64());
+/// assert_eq!(7u64.to_ordered_u64(), 7);
+/// ```
+pub trait ToOrderedU64 {
+    /// Maps this key onto a `u64` with the same relative order as every other key of this type.
+    fn to_ordered_u64(&self) -> u64;
+}
+
+macro_rules! impl_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl ToOrderedU64 for $ty {
+                fn to_ordered_u64(&self) -> u64 {
+                    *self as u64
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed {
+    ($($ty:ty),*) => {
+        $(
+            /// Flips the sign bit of the key after widening it, which maps the keys onto the
+            /// unsigned integers in order.
+            impl ToOrderedU64 for $ty {
+                fn to_ordered_u64(&self) -> u64 {
+                    (*self as i64 as u64) ^ (1 << 63)
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u8, u16, u32, u64, usize, bool, char);
+impl_signed!(i8, i16, i32, i64, isize);
+
+/// Applies the standard total-order bit transform to the key: the bits of non-negative numbers get
+/// their sign bit set, and the bits of negative numbers are inverted. This orders every finite
+/// number and both infinities exactly like their numeric values, with the following policy for
+/// special values:
+///
+/// -   `-0.0` is treated as `0.0`, so a query for `0.0..=0.0` matches a key of `-0.0` and vice
+///     versa.
+/// -   Every NaN, regardless of its sign and payload, is treated as a single NaN that is greater
+///     than `f64::INFINITY`. A NaN key is therefore only matched by ranges that end at a NaN.
+///
+/// Note that the maximum range interval of a hasher counts the representable `f64` values in a
+/// range, and not its numeric width. There are `2^52` values between two consecutive powers of
+/// two, so filters over `f64` keys are best suited to point queries and narrow ranges, and the
+/// hasher should be created with the number of representable values that the widest query spans.
+impl ToOrderedU64 for f64 {
+    fn to_ordered_u64(&self) -> u64 {
+        let x = if self.is_nan() {
+            f64::NAN.abs()
+        } else if *self == 0.0 {
+            0.0
+        } else {
+            *self
+        };
+
+        let bits = x.to_bits();
+        if bits >> 63 == 0 {
+            bits | (1 << 63)
+        } else {
+            !bits
+        }
+    }
+}
+
+/// Widens the key to an `f64`, which represents every `f32` exactly, and then maps it like an
+/// `f64` key.
+impl ToOrderedU64 for f32 {
+    fn to_ordered_u64(&self) -> u64 {
+        f64::from(*self).to_ordered_u64()
+    }
+}
+
+/// Maps the first eight bytes of the string onto a big-endian integer, padding shorter strings with
+/// zeros.
+///
+/// This preserves the lexicographic order of byte strings, but strings that share their first
+/// eight bytes are mapped onto the same integer. Filters over long keys with common prefixes can
+/// therefore not tell such keys apart, and are mostly useful for
+/// [prefix queries](KeyedRangeFilter::query_prefix).
+impl ToOrderedU64 for [u8] {
+    fn to_ordered_u64(&self) -> u64 {
+        let mut bytes = [0; 8];
+        let len = self.len().min(8);
+        bytes[..len].copy_from_slice(&self[..len]);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+/// Maps the UTF-8 bytes of the string like a byte string, since their lexicographic order is the
+/// order of the strings.
+impl ToOrderedU64 for str {
+    fn to_ordered_u64(&self) -> u64 {
+        self.as_bytes().to_ordered_u64()
+    }
+}
+
+impl ToOrderedU64 for Vec<u8> {
+    fn to_ordered_u64(&self) -> u64 {
+        self.as_slice().to_ordered_u64()
+    }
+}
+
+impl ToOrderedU64 for String {
+    fn to_ordered_u64(&self) -> u64 {
+        self.as_str().to_ordered_u64()
+    }
+}
+
+impl<K: ToOrderedU64 + ?Sized> ToOrderedU64 for &K {
+    fn to_ordered_u64(&self) -> u64 {
+        (**self).to_ordered_u64()
+    }
+}
+
+/// Inverts the mapped key, which reverses the order of the keys.
+impl<K: ToOrderedU64> ToOrderedU64 for Reverse<K> {
+    fn to_ordered_u64(&self) -> u64 {
+        !self.0.to_ordered_u64()
+    }
+}
+
+/// A range filter over keys of any type that implements [`ToOrderedU64`].
+///
+/// Every key is mapped with [`ToOrderedU64::to_ordered_u64`] and the mapped keys are stored in an
+/// ordinary [`RangeFilter`]. Both construction and queries apply the same mapping, so queries take
+/// ranges of the native key type, and callers never have to transform keys or range endpoints
+/// themselves.
+///
+/// ```
+/// use grafite::{KeyedRangeFilter, OrderPreservingHasher};
+///
+/// let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+/// let rf = KeyedRangeFilter::new([-7i32, -3, 2, 1000].into_iter(), hasher);
+///
+/// assert!(rf.query(-5..0));
+/// assert!(rf.query(-1..=2));
+/// ```
+pub struct KeyedRangeFilter<K, B = Encoding> {
+    /// The filter that the mapped keys are stored in.
+    filter: RangeFilter<B>,
+    /// The type of the keys, which is only used to map them.
+    key: PhantomData<fn() -> K>,
+}
+
+/// A range filter over signed 64-bit keys, which are mapped onto `u64` by flipping their sign bit.
+///
+/// Casting signed keys to `u64` directly places every negative key after every non-negative one,
+/// so ranges that cross zero would be hashed as wrapped ranges.
+///
+/// ```
+/// use grafite::{OrderPreservingHasher, SignedRangeFilter};
+///
+/// let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+/// let rf = SignedRangeFilter::new([-7, -3, 2, 1000].into_iter(), hasher);
+///
+/// assert!(rf.query(-5..0));
+/// assert!(rf.query(-1..=2));
+/// assert!(rf.query(..-6));
+/// ```
+pub type SignedRangeFilter<B = Encoding> = KeyedRangeFilter<i64, B>;
+
+/// A range filter over `f64` keys. See the [`ToOrderedU64`] implementation of `f64` for how the
+/// keys are mapped, including `-0.0` and NaN.
+///
+/// ```
+/// use grafite::{FloatRangeFilter, OrderPreservingHasher};
+///
+/// let hasher = OrderPreservingHasher::new(4, 0.01, 1 << 20).unwrap();
+/// let rf = FloatRangeFilter::new([-2.5, -0.0, 1.0, 3.75].into_iter(), hasher);
+///
+/// assert!(rf.query(0.0..=0.0));
+/// assert!(rf.query(-2.5..=-2.5));
+/// assert!(rf.query(3.75..=3.75));
+/// ```
+pub type FloatRangeFilter<B = Encoding> = KeyedRangeFilter<f64, B>;
+
+impl<K: ToOrderedU64> KeyedRangeFilter<K> {
+    /// Creates a new `KeyedRangeFilter` given an iterator of keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter could not be built, for example if there are no input values. See
+    /// [`Self::try_new`] for a fallible version of this function.
+    pub fn new<I>(values: I, hasher: OrderPreservingHasher) -> Self
+    where
+        I: Iterator<Item = K>,
+    {
+        match Self::try_new(values, hasher) {
+            Ok(filter) => filter,
+            Err(e) => panic!("Unable to build the range filter: {e:?}"),
+        }
+    }
+
+    /// Creates a new `KeyedRangeFilter` given an iterator of keys.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = K>,
+    {
+        Self::try_with_backend(values, hasher)
+    }
+}
+
+impl<K: ToOrderedU64, B: RangeFilterBackend> KeyedRangeFilter<K, B> {
+    /// Creates a new `KeyedRangeFilter` that stores its mapped keys in the backend `B`, given an
+    /// iterator of keys.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_with_backend<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = K>,
+    {
+        let values = values.map(|x| x.to_ordered_u64());
+        let filter = RangeFilter::try_with_backend(values, hasher)?;
+
+        Ok(Self::from_filter(filter))
+    }
+
+    /// Wraps a filter whose keys were mapped with [`ToOrderedU64::to_ordered_u64`], for example
+    /// one that was loaded with [`RangeFilter::read_from`] after being written from
+    /// [`Self::filter`].
+    ///
+    /// If the keys were mapped in any other way, queries may return false negatives.
+    pub fn from_filter(filter: RangeFilter<B>) -> Self {
+        Self {
+            filter,
+            key: PhantomData,
+        }
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<K>,
+    {
+        let start = range.start_bound().map(ToOrderedU64::to_ordered_u64);
+        let end = range.end_bound().map(ToOrderedU64::to_ordered_u64);
+
+        match range_endpoints(&(start, end)) {
+            Some((start, end)) => self.filter.query(start..=end),
+            None => false,
+        }
+    }
+
+    /// Returns the hash function used to hash the mapped keys of this filter.
+    pub fn hasher(&self) -> &OrderPreservingHasher {
+        &self.filter.hasher
+    }
+
+    /// Returns the filter that the mapped keys are stored in.
+    pub fn filter(&self) -> &RangeFilter<B> {
+        &self.filter
+    }
+
+    /// Consumes this filter and returns the filter that the mapped keys are stored in.
+    pub fn into_filter(self) -> RangeFilter<B> {
+        self.filter
+    }
+
+    /// Returns the amount of space required to store this `KeyedRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+    }
+}
+
+impl<K, B> KeyedRangeFilter<K, B>
+where
+    K: AsRef<[u8]> + ToOrderedU64,
+    B: RangeFilterBackend,
+{
+    /// Checks if there may be any keys that start with `prefix` among the original input set.
+    ///
+    /// The prefix is converted into the range of mapped keys from the prefix padded with zeros to
+    /// the prefix padded with `0xff` bytes. Since only the first eight bytes of every key are
+    /// mapped, a prefix of `k < 8` bytes covers `2^(8 * (8 - k))` mapped keys, and the maximum
+    /// range interval of the hasher must be at least that large for the shortest prefix that is
+    /// queried, otherwise the query may return a false negative. Prefixes of at least eight bytes
+    /// are point queries.
+    ///
+    /// ```
+    /// use grafite::{KeyedRangeFilter, OrderPreservingHasher};
+    ///
+    /// let keys = ["user:alice", "user:bob", "order:17"];
+    /// let hasher = OrderPreservingHasher::new(keys.len(), 0.01, 1 << 24).unwrap();
+    /// let rf = KeyedRangeFilter::new(keys.into_iter(), hasher);
+    ///
+    /// assert!(rf.query_prefix(b"user:b"));
+    /// assert!(rf.query_prefix(b"order:1"));
+    /// ```
+    pub fn query_prefix(&self, prefix: &[u8]) -> bool {
+        let start = prefix.to_ordered_u64();
+        let padding = u64::MAX.checked_shr(8 * prefix.len() as u32).unwrap_or(0);
+
+        self.filter.query(start..=start | padding)
+    }
+}
+
+impl<K, B: fmt::Debug> fmt::Debug for KeyedRangeFilter<K, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyedRangeFilter")
+            .field("filter", &self.filter)
+            .finish()
+    }
+}
+
+impl<K, B: Clone> Clone for KeyedRangeFilter<K, B> {
+    fn clone(&self) -> Self {
+        Self {
+            filter: self.filter.clone(),
+            key: PhantomData,
+        }
+    }
+}
@@ -1,8 +1,123 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "arrow")]
+mod arrow;
+mod backend;
+mod block;
+mod bucket;
+mod builder;
+mod composite;
+mod counting;
+mod deletable;
+mod dense;
+mod dynamic;
+pub mod elias_fano;
+mod estimate;
+mod explain;
+mod external;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod filter;
 mod hash;
+mod hybrid;
+mod instrument;
+mod ip;
+#[cfg(feature = "jni")]
+pub mod jvm;
+mod keyed;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod monitored;
+mod multi;
+mod narrowed;
+#[cfg(feature = "node")]
+mod node;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod partitioned;
+pub mod persist;
+mod progress;
+#[cfg(feature = "datafusion")]
+mod pruning;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "object_store")]
+mod remote;
+#[cfg(feature = "parquet")]
+mod row_group;
+mod shared;
+#[cfg(target_arch = "x86_64")]
+mod simd;
+mod stats;
+mod tiered;
+mod timestamp;
+mod tune;
 mod utils;
+#[cfg(feature = "uuid")]
+mod uuid_key;
+#[cfg(feature = "verify")]
+mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wide;
+pub mod workloads;
 
-pub use crate::filter::RangeFilter;
+#[cfg(feature = "rkyv")]
+pub use crate::archive::AsHashes;
+pub use crate::backend::{Encoding, RangeFilterBackend};
+pub use crate::block::{
+    BlockFilterBuilder, BlockFilterReader, RangeFilterBlockBuilder, RangeFilterBlockReader,
+};
+pub use crate::bucket::BucketRangeFilter;
+pub use crate::builder::{InsertBuilder, RangeFilterBuilder};
+pub use crate::composite::{CompositeEncoder, CompositeError, RangeKey};
+pub use crate::counting::CountingRangeFilter;
+pub use crate::deletable::DeletableRangeFilter;
+pub use crate::dense::DenseBitVector;
+pub use crate::dynamic::DynamicRangeFilter;
+pub use crate::elias_fano::EliasFano;
+pub use crate::estimate::FprEstimate;
+pub use crate::explain::{QueryExplanation, StoredHash};
+#[cfg(feature = "rkyv")]
+pub use crate::filter::ArchivedRangeFilter;
+pub use crate::filter::{
+    range_endpoints, BuildError, IoBuildError, MergeError, QueryError, RangeFilter,
+};
 pub use crate::hash::*;
+pub use crate::hybrid::HybridRangeFilter;
+pub use crate::ip::{ipv4_cidr, ipv6_cidr};
+pub use crate::keyed::{FloatRangeFilter, KeyedRangeFilter, SignedRangeFilter, ToOrderedU64};
+#[cfg(feature = "mmap")]
+pub use crate::mapped::MappedRangeFilter;
+pub use crate::monitored::{
+    FilterMetrics, HashedWindow, MonitoredRangeFilter, DEFAULT_FEEDBACK_CAPACITY,
+};
+pub use crate::multi::{FilterId, MultiFilter};
+pub use crate::narrowed::NarrowedRangeFilter;
+#[cfg(feature = "node")]
+pub use crate::node::NodeRangeFilter;
+pub use crate::partitioned::PartitionedRangeFilter;
+pub use crate::persist::{FilterMetadata, PersistError};
+pub use crate::progress::{BuildObserver, BuildPhase};
+#[cfg(feature = "datafusion")]
+pub use crate::pruning::FilterPruningStatistics;
+#[cfg(feature = "python")]
+pub use crate::python::{PyOrderPreservingHasher, PyRangeFilter};
+#[cfg(feature = "object_store")]
+pub use crate::remote::RemoteError;
+#[cfg(feature = "parquet")]
+pub use crate::row_group::{RowGroupError, RowGroupFilters};
+pub use crate::shared::SharedRangeFilter;
+pub use crate::stats::{FilterStats, GapHistogram, MemoryBreakdown};
+pub use crate::tiered::TieredRangeFilter;
+pub use crate::timestamp::{TimeUnit, Timestamp, TimestampRangeFilter};
+pub use crate::tune::{IntervalChoice, Tuning};
+#[cfg(feature = "verify")]
+pub use crate::verify::VerifiedRangeFilter;
+#[cfg(feature = "wasm")]
+pub use crate::wasm::WasmRangeFilter;
+pub use crate::wide::RangeFilter128;
+#[cfg(feature = "derive")]
+pub use grafite_derive::RangeKey;
@@ -0,0 +1,263 @@
+//! This module contains the [`MappedRangeFilter`] type, a filter that is queried directly from a
+//! memory-mapped file in the [`persist`](crate::persist) format.
+
+use std::fs::File;
+use std::ops::RangeBounds;
+
+use memmap2::Mmap;
+
+use crate::filter::range_endpoints;
+use crate::hash::HashedRange;
+use crate::persist::read_layout;
+use crate::{OrderPreservingHasher, PersistError, RangeFilter};
+
+/// A filter that is queried directly from a memory-mapped file written with
+/// [`RangeFilter::write_to`], for filters that are too large to load into memory or to keep in
+/// the page cache.
+///
+/// Opening the filter only reads its header, and each query only touches the pages of the stored
+/// hash values that it needs. Since the hash values are spread roughly uniformly over the reduced
+/// universe, a query first estimates the position of its hashed endpoint by interpolation, and
+/// then searches outwards from the estimate. This keeps the pages that a query touches close
+/// together, which allows [`Self::prefetch`] to ask the operating system to read them ahead of
+/// time with `madvise(MADV_WILLNEED)`, so that a scan can overlap its page faults with other work.
+///
+/// The checksum of the file is not verified when it is opened, since that would read every page.
+/// See [`Self::verify`] for a method that does.
+#[derive(Debug)]
+pub struct MappedRangeFilter {
+    /// The mapped file.
+    mmap: Mmap,
+    /// The hash function of the filter.
+    hasher: OrderPreservingHasher,
+    /// The byte offset of the first hash value.
+    offset: usize,
+    /// The number of hash values.
+    len: usize,
+    /// The number of bytes that every hash value is stored in.
+    width: usize,
+}
+
+impl MappedRangeFilter {
+    /// Maps a file written with [`RangeFilter::write_to`] into memory.
+    ///
+    /// If the header of the file is invalid or the file is too short to hold all of its hash
+    /// values, this function will return a [`PersistError`].
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, since the filter reads the
+    /// mapped memory without any synchronization. See [`Mmap::map`] for more information.
+    pub unsafe fn map(file: &File) -> Result<Self, PersistError> {
+        // SAFETY: The caller guarantees that the file is not modified while it is mapped.
+        let mmap = unsafe { Mmap::map(file)? };
+
+        let layout = read_layout(&mmap)?;
+        let width = layout.width();
+        let end = layout
+            .len
+            .checked_mul(width as u64)
+            .and_then(|hashes| hashes.checked_add(layout.offset as u64 + 4));
+        if end.is_none_or(|end| end > mmap.len() as u64) {
+            return Err(PersistError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+        if layout.len == 0 {
+            return Err(PersistError::Corrupt);
+        }
+
+        Ok(Self {
+            hasher: layout.hasher,
+            offset: layout.offset,
+            len: layout.len as usize,
+            width,
+            mmap,
+        })
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// This has the same semantics as [`RangeFilter::query`].
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        match self.hasher.hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => self.query_window(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                self.query_window(start1, end1) || self.query_window(start2, end2)
+            }
+            HashedRange::All => true,
+        }
+    }
+
+    /// Checks if there are any stored hashes within a hashed window.
+    fn query_window(&self, start_hash: u64, end_hash: u64) -> bool {
+        // See `RangeFilter::query_prehashed` for an explanation of the wrapped case.
+        if start_hash > end_hash {
+            return self.hash_at(0) <= end_hash || self.hash_at(self.len - 1) >= start_hash;
+        }
+
+        match self.rank(end_hash) {
+            0 => false,
+            rank => self.hash_at(rank - 1) >= start_hash,
+        }
+    }
+
+    /// Advises the operating system that the pages a query for the given range will touch are
+    /// needed soon, so that they can be read ahead of the query.
+    ///
+    /// The advice covers the pages around the estimated position of the hashed endpoint, wide
+    /// enough that the query almost never leaves it. This only has an effect on Unix platforms,
+    /// and errors from the operating system are ignored, since the advice is only a hint.
+    pub fn prefetch<R>(&self, range: R)
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return;
+        };
+
+        match self.hasher.hash_range(start, end) {
+            HashedRange::One(start_hash, end_hash) => self.prefetch_window(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                self.prefetch_window(start1, end1);
+                self.prefetch_window(start2, end2);
+            }
+            // Such a query is answered without reading any hash values.
+            HashedRange::All => {}
+        }
+    }
+
+    /// Advises the operating system that the pages a query for a hashed window will touch are
+    /// needed soon.
+    fn prefetch_window(&self, start_hash: u64, end_hash: u64) {
+        if start_hash > end_hash {
+            self.advise(0, 1);
+            self.advise(self.len - 1, self.len);
+            return;
+        }
+
+        // The rank of a hash value deviates from its estimate by about `sqrt(n)` positions.
+        let slack = 4 * (self.len as f64).sqrt() as usize + 1;
+        let guess = self.estimate(end_hash);
+        self.advise(guess.saturating_sub(slack), (guess + slack).min(self.len));
+    }
+
+    /// Reads every page of the file and verifies its checksum and hash values.
+    ///
+    /// If the file is corrupt, this function will return a [`PersistError`].
+    pub fn verify(&self) -> Result<(), PersistError> {
+        self.to_filter().map(|_| ())
+    }
+
+    /// Loads the whole filter into memory, verifying its checksum and hash values.
+    ///
+    /// If the file is corrupt, this function will return a [`PersistError`].
+    pub fn to_filter(&self) -> Result<RangeFilter, PersistError> {
+        RangeFilter::read_from(&self.mmap[..])
+    }
+
+    /// Returns the hash function used to hash the keys of this filter.
+    pub fn hasher(&self) -> &OrderPreservingHasher {
+        &self.hasher
+    }
+
+    /// Returns the number of distinct hash values stored in the filter.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the filter stores no hash values, which is never the case for a valid
+    /// file.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the hash value at the given index.
+    fn hash_at(&self, i: usize) -> u64 {
+        let start = self.offset + i * self.width;
+        let bytes = &self.mmap[start..start + self.width];
+
+        match *bytes {
+            [a, b, c, d] => u32::from_le_bytes([a, b, c, d]) as u64,
+            _ => u64::from_le_bytes(bytes.try_into().expect("hash values are 4 or 8 bytes")),
+        }
+    }
+
+    /// Returns the estimated number of hash values less than or equal to `hash`, assuming that the
+    /// hash values are spread uniformly over the reduced universe.
+    fn estimate(&self, hash: u64) -> usize {
+        let r = self.hasher.reduced_universe() as u128;
+        ((hash as u128 * self.len as u128) / r.max(1)).min(self.len as u128) as usize
+    }
+
+    /// Returns the number of hash values less than or equal to `hash`.
+    ///
+    /// The search starts at the estimate and gallops outwards until it brackets the answer, and
+    /// then binary searches within the bracket, so it only touches pages near the estimate.
+    fn rank(&self, hash: u64) -> usize {
+        let at_most = |i: usize| self.hash_at(i) <= hash;
+        let guess = self.estimate(hash);
+
+        // Find `lo <= rank <= hi`.
+        let (mut lo, mut hi);
+        if guess < self.len && at_most(guess) {
+            let (mut below, mut step) = (guess, 1);
+            loop {
+                let next = below + step;
+                if next >= self.len {
+                    (lo, hi) = (below + 1, self.len);
+                    break;
+                }
+                if !at_most(next) {
+                    (lo, hi) = (below + 1, next);
+                    break;
+                }
+                (below, step) = (next, step * 2);
+            }
+        } else {
+            let (mut above, mut step) = (guess, 1);
+            loop {
+                if above < step {
+                    (lo, hi) = (0, above);
+                    break;
+                }
+                let next = above - step;
+                if at_most(next) {
+                    (lo, hi) = (next + 1, above);
+                    break;
+                }
+                (above, step) = (next, step * 2);
+            }
+        }
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if at_most(mid) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        lo
+    }
+
+    /// Advises the operating system that the hash values with indices in `start..end` are needed.
+    fn advise(&self, start: usize, end: usize) {
+        #[cfg(unix)]
+        {
+            let offset = self.offset + start * self.width;
+            let len = (end - start) * self.width;
+            let _ = self
+                .mmap
+                .advise_range(memmap2::Advice::WillNeed, offset, len);
+        }
+        #[cfg(not(unix))]
+        let _ = (start, end);
+    }
+}
@@ -0,0 +1,262 @@
+//! This module contains the [`MonitoredRangeFilter`] type, a [`RangeFilter`] that counts its
+//! queries and their outcomes for monitoring in production, and records the hashed windows of the
+//! false positives reported by the caller.
+
+use std::collections::VecDeque;
+use std::ops::RangeBounds;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::filter::range_endpoints;
+use crate::hash::HashedRange;
+use crate::{Encoding, RangeFilter, RangeFilterBackend};
+
+/// The default number of false positive windows that a [`MonitoredRangeFilter`] keeps.
+pub const DEFAULT_FEEDBACK_CAPACITY: usize = 1024;
+
+/// The hashed window of a range that was reported as a false positive.
+///
+/// A range `start..=end` is answered by searching the stored hashes for one in
+/// `hash(start)..=hash(end)`, so a false positive means some other key was hashed into this
+/// window. If `start_hash > end_hash`, the window wrapped around the reduced universe and covers
+/// `start_hash..r` and `0..=end_hash`.
+///
+/// A range that crosses from one block of `r` consecutive keys into the next is searched in one
+/// window per block, so it is recorded as one window for the part of the range in each block. A
+/// range that contains an entire block hashes onto every hash value, so it is recorded as a single
+/// window `0..=r - 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HashedWindow {
+    /// The original start of the range.
+    pub start: u64,
+    /// The original inclusive end of the range.
+    pub end: u64,
+    /// The hash of the start of the range.
+    pub start_hash: u64,
+    /// The hash of the end of the range.
+    pub end_hash: u64,
+}
+
+/// A snapshot of the counters of a [`MonitoredRangeFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FilterMetrics {
+    /// The number of queries issued.
+    pub queries: u64,
+    /// The number of queries that returned `true`.
+    pub positives: u64,
+    /// The number of positives that the caller reported as false positives.
+    pub false_positives: u64,
+}
+
+impl FilterMetrics {
+    /// Returns the fraction of queries that returned `true`, or `0` if there were no queries.
+    pub fn positive_rate(&self) -> f64 {
+        ratio(self.positives, self.queries)
+    }
+
+    /// Returns the fraction of positives that were reported as false positives, or `0` if there
+    /// were no positives.
+    ///
+    /// This is the fraction of the work done after a positive answer that was wasted, which is the
+    /// measure of filter effectiveness that matters to callers. Unlike the false positive rate, it
+    /// depends on how many queries actually contain keys.
+    pub fn false_discovery_rate(&self) -> f64 {
+        ratio(self.false_positives, self.positives)
+    }
+}
+
+/// Returns `numerator / denominator`, or `0` if the denominator is `0`.
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// A range filter that counts the queries it answers, how many of them were positive, and how many
+/// of those the caller reported as false positives.
+///
+/// The hashed windows of the most recently reported false positives are kept as well, up to a
+/// fixed capacity, so that the hash values responsible for them can be inspected with
+/// [`Self::false_positive_windows`].
+///
+/// The counters are atomic, so a `MonitoredRangeFilter` can be shared between threads and queried
+/// through a shared reference. They are updated with relaxed ordering, so a snapshot taken with
+/// [`Self::metrics`] while queries are running may be slightly inconsistent, which is fine for
+/// dashboards.
+///
+/// ```
+/// use grafite::{MonitoredRangeFilter, OrderPreservingHasher, RangeFilter};
+///
+/// let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+/// let rf = MonitoredRangeFilter::new(RangeFilter::new([1, 2, 3, 7].into_iter(), hasher));
+///
+/// assert!(rf.query(3..5));
+/// assert!(!rf.query(10..15));
+///
+/// let metrics = rf.metrics();
+/// assert_eq!((metrics.queries, metrics.positives), (2, 1));
+///
+/// // Suppose the engine checked the data for a positive range and found no keys in it.
+/// rf.report_false_positive(4..5);
+/// assert_eq!(rf.metrics().false_positives, 1);
+/// assert_eq!(rf.false_positive_windows()[0].start, 4);
+/// ```
+#[derive(Debug)]
+pub struct MonitoredRangeFilter<B = Encoding> {
+    /// The monitored filter.
+    filter: RangeFilter<B>,
+    /// The number of queries issued.
+    queries: AtomicU64,
+    /// The number of queries that returned `true`.
+    positives: AtomicU64,
+    /// The number of positives that the caller reported as false positives.
+    false_positives: AtomicU64,
+    /// The hashed windows of the most recently reported false positives, oldest first.
+    windows: Mutex<VecDeque<HashedWindow>>,
+    /// The maximum number of windows to keep.
+    feedback_capacity: usize,
+}
+
+impl<B: RangeFilterBackend> MonitoredRangeFilter<B> {
+    /// Wraps a filter, starting every counter at zero and keeping up to
+    /// [`DEFAULT_FEEDBACK_CAPACITY`] false positive windows.
+    pub fn new(filter: RangeFilter<B>) -> Self {
+        Self::with_feedback_capacity(filter, DEFAULT_FEEDBACK_CAPACITY)
+    }
+
+    /// Wraps a filter, starting every counter at zero and keeping up to `capacity` false positive
+    /// windows.
+    ///
+    /// A capacity of `0` only counts false positives without recording their windows.
+    pub fn with_feedback_capacity(filter: RangeFilter<B>, capacity: usize) -> Self {
+        Self {
+            filter,
+            queries: AtomicU64::new(0),
+            positives: AtomicU64::new(0),
+            false_positives: AtomicU64::new(0),
+            windows: Mutex::new(VecDeque::new()),
+            feedback_capacity: capacity,
+        }
+    }
+
+    /// Checks if there are any elements within the given range among the original input set,
+    /// counting the query and its outcome.
+    ///
+    /// See [`RangeFilter::query`] for more information.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        self.record(self.filter.query(range))
+    }
+
+    /// Checks if the given key may be among the original input set, counting the query and its
+    /// outcome.
+    pub fn contains(&self, x: u64) -> bool {
+        self.record(self.filter.contains(x))
+    }
+
+    /// Counts a query with the given outcome, and returns the outcome.
+    fn record(&self, positive: bool) -> bool {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if positive {
+            self.positives.fetch_add(1, Ordering::Relaxed);
+        }
+        positive
+    }
+
+    /// Reports that the given range was a false positive, because the caller found no keys in it
+    /// after checking the underlying data.
+    ///
+    /// This counts the false positive and records its [`HashedWindow`]s, evicting the oldest
+    /// windows if the capacity has been reached. Empty ranges can never be positive, so they are
+    /// ignored.
+    pub fn report_false_positive<R>(&self, range: R)
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return;
+        };
+
+        self.false_positives.fetch_add(1, Ordering::Relaxed);
+        if self.feedback_capacity == 0 {
+            return;
+        }
+
+        let hasher = &self.filter.hasher;
+        let window = |start, end| HashedWindow {
+            start,
+            end,
+            start_hash: hasher.hash(start),
+            end_hash: hasher.hash(end),
+        };
+        let (first, second) = match hasher.hash_range(start, end) {
+            HashedRange::One(..) => (window(start, end), None),
+            HashedRange::Two(..) => {
+                // The first key of the second block.
+                let r = hasher.reduced_universe();
+                let boundary = end / r * r;
+                (window(start, boundary - 1), Some(window(boundary, end)))
+            }
+            HashedRange::All => {
+                let all = HashedWindow {
+                    start,
+                    end,
+                    start_hash: 0,
+                    end_hash: hasher.reduced_universe() - 1,
+                };
+                (all, None)
+            }
+        };
+
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        for window in std::iter::once(first).chain(second) {
+            if windows.len() == self.feedback_capacity {
+                windows.pop_front();
+            }
+            windows.push_back(window);
+        }
+    }
+
+    /// Returns the hashed windows of the most recently reported false positives, oldest first.
+    pub fn false_positive_windows(&self) -> Vec<HashedWindow> {
+        let windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        windows.iter().copied().collect()
+    }
+
+    /// Returns a snapshot of the counters.
+    pub fn metrics(&self) -> FilterMetrics {
+        FilterMetrics {
+            queries: self.queries.load(Ordering::Relaxed),
+            positives: self.positives.load(Ordering::Relaxed),
+            false_positives: self.false_positives.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns a snapshot of the counters, and resets every counter to zero.
+    ///
+    /// This is useful for exporting the counters as deltas over fixed reporting intervals. The
+    /// recorded false positive windows are kept.
+    pub fn take_metrics(&self) -> FilterMetrics {
+        FilterMetrics {
+            queries: self.queries.swap(0, Ordering::Relaxed),
+            positives: self.positives.swap(0, Ordering::Relaxed),
+            false_positives: self.false_positives.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the monitored filter.
+    ///
+    /// Queries made directly on the returned filter are not counted.
+    pub fn filter(&self) -> &RangeFilter<B> {
+        &self.filter
+    }
+
+    /// Consumes this `MonitoredRangeFilter`, returning the monitored filter.
+    pub fn into_filter(self) -> RangeFilter<B> {
+        self.filter
+    }
+}
@@ -0,0 +1,274 @@
+//! This module contains the [`MultiFilter`] type, a container of [`RangeFilter`]s keyed by an id
+//! such as a segment or column family, along with the [`FilterId`] trait for its ids.
+
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, OrderPreservingHasher, PersistError, RangeFilter};
+
+/// The magic bytes at the start of every serialized [`MultiFilter`].
+const MULTI_MAGIC: [u8; 4] = *b"GRFM";
+
+/// The current version of the serialized [`MultiFilter`] format.
+const MULTI_FORMAT_VERSION: u32 = 1;
+
+/// An id that a [`MultiFilter`] can map to a [`RangeFilter`].
+///
+/// This is implemented for [`u64`] and [`String`], and describes how ids are encoded when a
+/// [`MultiFilter`] is serialized.
+pub trait FilterId: Ord + Sized {
+    /// Appends the encoding of this id to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decodes an id from the front of `bytes`, advancing it past the id, or returns `None` if
+    /// `bytes` does not start with a valid encoding.
+    fn decode(bytes: &mut &[u8]) -> Option<Self>;
+}
+
+impl FilterId for u64 {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let (id, rest) = bytes.split_first_chunk::<8>()?;
+        *bytes = rest;
+        Some(u64::from_le_bytes(*id))
+    }
+}
+
+impl FilterId for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let len = usize::try_from(u64::decode(bytes)?).ok()?;
+        if len > bytes.len() {
+            return None;
+        }
+        let (id, rest) = bytes.split_at(len);
+        *bytes = rest;
+        String::from_utf8(id.to_vec()).ok()
+    }
+}
+
+/// A container that maps ids, such as segment or column family ids, to [`RangeFilter`]s.
+///
+/// This handles the bookkeeping that storage engines with many filters need: building a filter for
+/// every group of keys, finding the filters that may contain a range, and persisting every filter
+/// in a single stream with [`Self::write_to`] and [`Self::read_from`].
+///
+/// ```
+/// use grafite::MultiFilter;
+///
+/// let groups = [(1, vec![1, 2, 3]), (2, vec![100, 200, 300])];
+/// let filters: MultiFilter = MultiFilter::build(groups, 0.01, 20).unwrap();
+///
+/// assert_eq!(filters.query(&1, 2..4), Some(true));
+/// assert_eq!(filters.query(&3, 2..4), None);
+/// assert_eq!(filters.candidates(150..=200).collect::<Vec<_>>(), [&2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiFilter<K = u64> {
+    /// The filters, sorted by id.
+    filters: BTreeMap<K, RangeFilter>,
+}
+
+impl<K: FilterId> MultiFilter<K> {
+    /// Creates an empty `MultiFilter`.
+    pub fn new() -> Self {
+        Self {
+            filters: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a filter for every group of keys, each with a false positive rate of `epsilon` for
+    /// ranges of up to `max_interval` keys.
+    ///
+    /// If an id appears in more than one group, only the filter of its last group is kept. If any
+    /// filter could not be built for any reason, this function will return a [`BuildError`].
+    pub fn build<I, J>(groups: I, epsilon: f64, max_interval: u64) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = (K, J)>,
+        J: IntoIterator<Item = u64>,
+    {
+        let mut filters = Self::new();
+        for (id, keys) in groups {
+            let keys: Vec<u64> = keys.into_iter().collect();
+            let hasher = OrderPreservingHasher::new(keys.len(), epsilon, max_interval)?;
+
+            filters.insert(id, RangeFilter::try_new(keys.into_iter(), hasher)?);
+        }
+
+        Ok(filters)
+    }
+
+    /// Inserts a filter with the given id, returning the filter it replaced, if any.
+    pub fn insert(&mut self, id: K, filter: RangeFilter) -> Option<RangeFilter> {
+        self.filters.insert(id, filter)
+    }
+
+    /// Removes the filter with the given id, returning it if it was present.
+    pub fn remove<Q>(&mut self, id: &Q) -> Option<RangeFilter>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.filters.remove(id)
+    }
+
+    /// Returns the filter with the given id, if it is present.
+    pub fn get<Q>(&self, id: &Q) -> Option<&RangeFilter>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.filters.get(id)
+    }
+
+    /// Checks if there are any elements within the given range in the filter with the given id, or
+    /// returns `None` if there is no such filter.
+    pub fn query<Q, R>(&self, id: &Q, range: R) -> Option<bool>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<u64>,
+    {
+        self.get(id).map(|filter| filter.query(range))
+    }
+
+    /// Returns the ids of every filter that may contain elements within the given range, in
+    /// increasing order.
+    ///
+    /// Each filter is checked with [`RangeFilter::query_chunked`], so ranges longer than the
+    /// maximum range interval are split into sub-queries that each keep the false positive rate
+    /// guarantee of the filter. Empty and reversed ranges never match any filter.
+    pub fn candidates<R>(&self, range: R) -> impl Iterator<Item = &K> + '_
+    where
+        R: RangeBounds<u64>,
+    {
+        let endpoints = range_endpoints(&range);
+
+        self.filters
+            .iter()
+            .filter(move |(_, filter)| {
+                endpoints.is_some_and(|(start, end)| filter.query_chunked(start..=end))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// Returns an iterator over the ids and filters, in increasing order of id.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &RangeFilter)> + '_ {
+        self.filters.iter()
+    }
+
+    /// Returns the number of filters.
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns `true` if there are no filters.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Returns the amount of space required to store every filter on the heap, not including the
+    /// ids.
+    pub fn heap_size(&self) -> usize {
+        self.filters.values().map(RangeFilter::heap_size).sum()
+    }
+
+    /// Writes every filter and its id to the given writer.
+    ///
+    /// The stream starts with a header and a block of the encoded ids followed by its CRC32
+    /// checksum, after which every filter is written in the [`persist`](crate::persist) format in
+    /// the same order as the ids.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut ids = Vec::new();
+        (self.filters.len() as u64).encode(&mut ids);
+        for id in self.filters.keys() {
+            id.encode(&mut ids);
+        }
+
+        writer.write_all(&MULTI_MAGIC)?;
+        writer.write_all(&MULTI_FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(ids.len() as u64).to_le_bytes())?;
+        writer.write_all(&ids)?;
+        writer.write_all(&crc32fast::hash(&ids).to_le_bytes())?;
+
+        for filter in self.filters.values() {
+            filter.write_to(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a `MultiFilter` that was written with [`write_to`](Self::write_to).
+    ///
+    /// If the input is invalid or any checksum does not match, this function will return a
+    /// [`PersistError`].
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, PersistError> {
+        let mut header = [0; 16];
+        reader.read_exact(&mut header)?;
+        let (magic, rest) = header.split_at(4);
+        let (version, ids_len) = rest.split_at(4);
+        if magic != MULTI_MAGIC {
+            return Err(PersistError::InvalidMagic);
+        }
+        let version = u32::from_le_bytes(version.try_into().expect("the version has 4 bytes"));
+        if version != MULTI_FORMAT_VERSION {
+            return Err(PersistError::UnsupportedVersion(version));
+        }
+        let ids_len = u64::from_le_bytes(ids_len.try_into().expect("the length has 8 bytes"));
+
+        // Read the ids without trusting their length for the allocation.
+        let mut ids = Vec::new();
+        (&mut reader).take(ids_len).read_to_end(&mut ids)?;
+        if ids.len() as u64 != ids_len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let mut expected = [0; 4];
+        reader.read_exact(&mut expected)?;
+        let expected = u32::from_le_bytes(expected);
+        let actual = crc32fast::hash(&ids);
+        if expected != actual {
+            return Err(PersistError::ChecksumMismatch { expected, actual });
+        }
+
+        let mut bytes = ids.as_slice();
+        let count = u64::decode(&mut bytes).ok_or(PersistError::Corrupt)?;
+
+        let mut filters = Self::new();
+        for _ in 0..count {
+            let id = K::decode(&mut bytes).ok_or(PersistError::Corrupt)?;
+            let filter = RangeFilter::read_from(&mut reader)?;
+
+            // The ids are written in strictly increasing order.
+            if filters
+                .filters
+                .last_key_value()
+                .is_some_and(|(last, _)| *last >= id)
+            {
+                return Err(PersistError::Corrupt);
+            }
+            filters.insert(id, filter);
+        }
+        if !bytes.is_empty() {
+            return Err(PersistError::Corrupt);
+        }
+
+        Ok(filters)
+    }
+}
+
+impl<K: FilterId> Default for MultiFilter<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
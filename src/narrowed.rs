@@ -0,0 +1,147 @@
+//! This module contains the [`NarrowedRangeFilter`] type, a [`RangeFilter`] over keys that are
+//! offset by their minimum, so that its hash function only has to cover the span of the keys.
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, Encoding, RangeFilter, RangeFilterBackend, RangeFilterBuilder};
+
+/// A range filter over keys that are stored as offsets from the smallest key.
+///
+/// When every key lies in a narrow span `[min, max]` of the 64-bit key space, the hash function
+/// only has to cover the `max - min + 1` offsets of the span, as described in
+/// [`OrderPreservingHasher::new_with_universe`], and queries outside of the span cannot contain any
+/// key. This filter records `min` and `max` and hashes `key - min` over the narrowed universe.
+/// Queries are clamped to `[min, max]` first, so ranges outside of the span are always answered
+/// with `false` instead of being false positives at the usual rate.
+///
+/// Filters are built with [`RangeFilterBuilder::build_narrowed`], which finds the span in a first
+/// pass over the keys.
+///
+/// ```
+/// use grafite::RangeFilterBuilder;
+///
+/// let keys = [1 << 40, (1 << 40) + 3, (1 << 40) + 50_000, (1 << 40) + 999_999];
+/// let rf = RangeFilterBuilder::new()
+///     .epsilon(0.01)
+///     .max_interval(20)
+///     .build_narrowed(keys)
+///     .unwrap();
+///
+/// assert_eq!((rf.min_key(), rf.max_key()), (1 << 40, (1 << 40) + 999_999));
+/// assert!(rf.query((1 << 40) + 1..(1 << 40) + 5));
+/// assert!(!rf.query(..1 << 40));
+/// ```
+///
+/// [`OrderPreservingHasher::new_with_universe`]: crate::OrderPreservingHasher::new_with_universe
+#[derive(Debug, Clone)]
+pub struct NarrowedRangeFilter<B = Encoding> {
+    /// The smallest key, which every key is offset by.
+    min: u64,
+    /// The largest key.
+    max: u64,
+    /// The filter over the offsets of the keys.
+    filter: RangeFilter<B>,
+}
+
+impl<B: RangeFilterBackend> NarrowedRangeFilter<B> {
+    /// Wraps a filter over the offsets `key - min` of keys in `[min, max]`, for example one that
+    /// was loaded with [`RangeFilter::read_from`] after being written from [`Self::filter`].
+    ///
+    /// If the keys were offset in any other way, or lie outside of `[min, max]`, queries may return
+    /// false negatives.
+    pub fn from_filter(filter: RangeFilter<B>, min: u64, max: u64) -> Self {
+        debug_assert!(min <= max, "min must not be greater than max");
+
+        Self { min, max, filter }
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// Empty and reversed ranges, and ranges outside of `[min, max]`, always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        let (start, end) = (start.max(self.min), end.min(self.max));
+        start <= end && self.filter.query(start - self.min..=end - self.min)
+    }
+
+    /// Checks if the given key may be among the original input set.
+    pub fn contains(&self, x: u64) -> bool {
+        self.query(x..=x)
+    }
+
+    /// Returns the smallest key, which every key is offset by.
+    pub fn min_key(&self) -> u64 {
+        self.min
+    }
+
+    /// Returns the largest key.
+    pub fn max_key(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the filter that the offsets of the keys are stored in.
+    pub fn filter(&self) -> &RangeFilter<B> {
+        &self.filter
+    }
+
+    /// Consumes this filter and returns the filter that the offsets of the keys are stored in.
+    pub fn into_filter(self) -> RangeFilter<B> {
+        self.filter
+    }
+
+    /// Returns the amount of space required to store this `NarrowedRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+    }
+}
+
+impl RangeFilterBuilder {
+    /// Validates the parameters and builds a [`NarrowedRangeFilter`] over the given keys, in two
+    /// passes over them.
+    ///
+    /// The first pass finds the smallest key `min`, the largest key `max`, and the number of keys,
+    /// and the hash function is derived for the universe `[0, max - min]`, overriding any
+    /// [`universe_size`](Self::universe_size) that was set. The second pass hashes the offsets
+    /// `key - min`. The keys are never buffered, so cloning the iterator should be cheap, as it is
+    /// for slice iterators.
+    ///
+    /// The maximum range interval that the false positive rate can be guaranteed for is
+    /// `(max - min + 1) * epsilon / n`, so keys that are dense within their span may be rejected
+    /// with [`ParamError::InvalidMaxInterval`](crate::ParamError::InvalidMaxInterval), in which
+    /// case [`Self::build`] should be used instead.
+    ///
+    /// If the parameters are invalid or the filter could not be built for any reason, this
+    /// function will return a [`BuildError`].
+    pub fn build_narrowed<I>(&self, keys: I) -> Result<NarrowedRangeFilter, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+        I::IntoIter: Clone,
+    {
+        let keys = keys.into_iter();
+
+        let mut span = None;
+        let mut num_keys = 0;
+        for key in keys.clone() {
+            let (min, max) = span.get_or_insert((key, key));
+            *min = key.min(*min);
+            *max = key.max(*max);
+            num_keys += 1;
+        }
+        let Some((min, max)) = span else {
+            return Err(BuildError::EmptyInput);
+        };
+
+        let universe_size = (max - min).saturating_add(1);
+        let hasher = self.universe_size(universe_size).build_hasher(num_keys)?;
+        let filter = RangeFilter::try_new(keys.map(|key| key - min), hasher)?;
+
+        Ok(NarrowedRangeFilter::from_filter(filter, min, max))
+    }
+}
@@ -0,0 +1,125 @@
+//! Node.js bindings for building, serializing, and querying filters with `napi-rs`.
+//!
+//! The bindings are compiled into a native Node.js addon that exports a `RangeFilter` class over
+//! `u64` keys, which are passed as `BigInt`s, or as a `BigUint64Array` when building a filter.
+//! Filters are serialized into `Buffer`s in the format of [`RangeFilter::write_to`], so services
+//! written in JavaScript can probe filters built by Rust ingestion jobs and vice versa.
+//!
+//! The addon is built by compiling this crate as a `cdylib` with the `node` feature, for example
+//! with `napi build --features node`.
+
+use napi::bindgen_prelude::{BigInt, BigUint64Array, Buffer};
+use napi::{Error, Result, Status};
+use napi_derive::napi;
+
+use crate::{OrderPreservingHasher, RangeFilter};
+
+/// A [`RangeFilter`] over `u64` keys that can be used from Node.js, where it is exported as
+/// `RangeFilter`.
+#[napi(js_name = "RangeFilter")]
+#[derive(Debug, Clone)]
+pub struct NodeRangeFilter {
+    /// The wrapped filter.
+    filter: RangeFilter,
+}
+
+#[napi]
+impl NodeRangeFilter {
+    /// Builds a filter over `keys` for the given false positive rate and maximum range interval.
+    ///
+    /// The hash function is derived deterministically from `seed`, so building the same keys with
+    /// the same parameters always produces the same filter.
+    #[napi(factory)]
+    pub fn build(
+        keys: BigUint64Array,
+        epsilon: f64,
+        max_interval: BigInt,
+        seed: BigInt,
+    ) -> Result<Self> {
+        let (max_interval, seed) = (to_u64(&max_interval)?, to_u64(&seed)?);
+
+        let hasher = OrderPreservingHasher::new_seeded(keys.len(), epsilon, max_interval, seed)
+            .map_err(|e| invalid_arg(format!("invalid filter parameters: {e:?}")))?;
+        let filter = RangeFilter::try_new(keys.iter().copied(), hasher)
+            .map_err(|e| invalid_arg(format!("unable to build the range filter: {e:?}")))?;
+
+        Ok(Self { filter })
+    }
+
+    /// Reads a filter that was written with [`Self::serialize`] or [`RangeFilter::write_to`].
+    #[napi(factory)]
+    pub fn deserialize(bytes: Buffer) -> Result<Self> {
+        let filter =
+            RangeFilter::read_from(bytes.as_ref()).map_err(|e| invalid_arg(e.to_string()))?;
+
+        Ok(Self { filter })
+    }
+
+    /// Writes the filter into a `Buffer` in the format of [`RangeFilter::write_to`].
+    #[napi]
+    pub fn serialize(&self) -> Buffer {
+        let mut bytes = Vec::new();
+        self.filter
+            .write_to(&mut bytes)
+            .expect("writing into a `Vec` cannot fail");
+
+        bytes.into()
+    }
+
+    /// Checks if there may be any keys in the inclusive range `start..=end`.
+    #[napi]
+    pub fn query(&self, start: BigInt, end: BigInt) -> Result<bool> {
+        Ok(self.filter.query(to_u64(&start)?..=to_u64(&end)?))
+    }
+
+    /// Checks if the given key may be among the original input set.
+    #[napi]
+    pub fn contains(&self, key: BigInt) -> Result<bool> {
+        Ok(self.filter.contains(to_u64(&key)?))
+    }
+
+    /// Returns the number of distinct hash values stored in the filter.
+    #[napi(getter)]
+    pub fn len(&self) -> u32 {
+        self.filter.ef.len() as u32
+    }
+
+    /// Returns `true` if the filter stores no hash values.
+    #[napi(js_name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.filter.ef.is_empty()
+    }
+
+    /// Returns the amount of space required to store the filter on the heap.
+    #[napi(js_name = "heapSize")]
+    pub fn heap_size(&self) -> f64 {
+        self.filter.heap_size() as f64
+    }
+}
+
+impl NodeRangeFilter {
+    /// Wraps a filter so it can be handed to Node.js.
+    pub fn from_filter(filter: RangeFilter) -> Self {
+        Self { filter }
+    }
+
+    /// Returns the wrapped filter.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
+
+/// Converts a `BigInt` into a `u64`, failing if it is negative or does not fit.
+fn to_u64(x: &BigInt) -> Result<u64> {
+    match x.get_u64() {
+        (false, value, true) => Ok(value),
+        _ => Err(invalid_arg(format!(
+            "{x:?} is not a 64-bit unsigned integer"
+        ))),
+    }
+}
+
+/// Returns an error for an invalid argument.
+fn invalid_arg(message: String) -> Error {
+    Error::new(Status::InvalidArg, message)
+}
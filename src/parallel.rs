@@ -0,0 +1,73 @@
+//! Parallel construction and querying of a [`RangeFilter`] with [`rayon`].
+
+use rayon::prelude::*;
+
+use crate::{BuildError, OrderPreservingHasher, RangeFilter};
+
+impl RangeFilter {
+    /// Creates a new `RangeFilter` given a parallel iterator of values.
+    ///
+    /// This is the same as [`Self::new`], except that the input values are hashed in parallel and
+    /// the hashes are sorted in parallel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter could not be built, for example if there are no input values. See
+    /// [`Self::try_par_new`] for a fallible version of this function.
+    pub fn par_new<I>(values: I, hasher: OrderPreservingHasher) -> Self
+    where
+        I: IntoParallelIterator<Item = u64>,
+    {
+        match Self::try_par_new(values, hasher) {
+            Ok(filter) => filter,
+            Err(e) => panic!("Unable to build the range filter: {e:?}"),
+        }
+    }
+
+    /// Creates a new `RangeFilter` given a parallel iterator of values.
+    ///
+    /// This is the same as [`Self::try_new`], except that the input values are hashed in parallel
+    /// and the hashes are sorted in parallel.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_par_new<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: IntoParallelIterator<Item = u64>,
+    {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher);
+        }
+
+        // Hash all items in the input set, and then sort and remove all duplicates.
+        let hashes = if hasher.fits_in_u32() {
+            // Sorting 32-bit hashes moves half as much memory as sorting 64-bit ones.
+            let mut hashes: Vec<u32> = values
+                .into_par_iter()
+                .map(|x| hasher.hash(x) as u32)
+                .collect();
+            hashes.par_sort_unstable();
+            hashes.dedup();
+            hashes.into_par_iter().map(u64::from).collect()
+        } else {
+            let mut hashes: Vec<u64> = values.into_par_iter().map(|x| hasher.hash(x)).collect();
+            hashes.par_sort_unstable();
+            hashes.dedup();
+            hashes
+        };
+
+        Self::from_deduped_hashes(&hashes, hasher)
+    }
+
+    /// Checks many ranges at once in parallel, returning whether each range may contain an
+    /// element.
+    ///
+    /// This is the same as [`Self::query_many`], except that the ranges are partitioned across the
+    /// threads of the current [`rayon`] thread pool.
+    pub fn par_query_many(&self, ranges: &[(u64, u64)]) -> Vec<bool> {
+        ranges
+            .par_iter()
+            .map(|&(start, end)| self.query_inclusive(start, end))
+            .collect()
+    }
+}
@@ -0,0 +1,135 @@
+//! This module contains the [`PartitionedRangeFilter`] type, which splits the key space into
+//! partitions that each have their own small [`RangeFilter`].
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, OrderPreservingHasher, RangeFilter};
+
+/// A range filter that splits the sorted keys into partitions of consecutive keys, each with its
+/// own small [`RangeFilter`], along with a top-level index of the first key of every partition.
+///
+/// A single filter over a very large set of keys spreads its hashes over a large Elias-Fano
+/// encoding, so every query touches memory that is unlikely to be cached. A partitioned filter
+/// first routes a query through the small top-level index, and then only queries the partitions
+/// whose keys overlap the range, each of which is small enough to be cached or loaded on demand.
+///
+/// Every partition is built with the same false positive rate `epsilon` and maximum range interval
+/// `L`. A range that spans `k` partitions is answered by `k` sub-queries, so its false positive
+/// rate is at most `k * epsilon`. Since the partitions hold many keys, ranges of at most `L` keys
+/// rarely span more than two partitions.
+///
+/// ```
+/// use grafite::PartitionedRangeFilter;
+///
+/// let rf = PartitionedRangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20], 0.01, 20, 3)
+///     .expect("The input parameters should be valid");
+///
+/// assert_eq!(rf.partitions().len(), 3);
+/// assert!(rf.query(3..5));
+/// assert!(!rf.query(10..15));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PartitionedRangeFilter {
+    /// The first key of every partition, in strictly increasing order.
+    index: Vec<u64>,
+    /// The filters of the partitions, where partition `i` covers the keys from `index[i]` up to
+    /// but excluding `index[i + 1]`.
+    partitions: Vec<RangeFilter>,
+}
+
+impl PartitionedRangeFilter {
+    /// Creates a new partitioned filter over the given keys, with partitions of `partition_size`
+    /// distinct keys that each have a false positive rate of `epsilon` for ranges of up to
+    /// `max_interval` keys.
+    ///
+    /// If there are no input keys, this function will return [`BuildError::EmptyInput`]. If any
+    /// partition could not be built for any reason, this function will return a [`BuildError`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partition_size` is `0`.
+    pub fn new<I>(
+        keys: I,
+        epsilon: f64,
+        max_interval: u64,
+        partition_size: usize,
+    ) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        assert!(partition_size > 0, "partition_size must be positive");
+
+        let mut keys: Vec<u64> = keys.into_iter().collect();
+        if keys.is_empty() {
+            return Err(BuildError::EmptyInput);
+        }
+
+        // Sort and then remove all duplicates, so that no key is split across partitions.
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut index = Vec::with_capacity(keys.len().div_ceil(partition_size));
+        let mut partitions = Vec::with_capacity(index.capacity());
+        for chunk in keys.chunks(partition_size) {
+            let hasher = OrderPreservingHasher::new(chunk.len(), epsilon, max_interval)?;
+
+            index.push(chunk[0]);
+            partitions.push(RangeFilter::try_new(chunk.iter().copied(), hasher)?);
+        }
+
+        Ok(Self { index, partitions })
+    }
+
+    /// Checks if there are any elements within the given range among the original input set, by
+    /// querying every partition that overlaps the range.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        // The partitions that start at or before `end`, the last of which is the only candidate if
+        // the range starts in it.
+        let last = self.index.partition_point(|&first| first <= end);
+        if last == 0 {
+            return false;
+        }
+        let first = self.index.partition_point(|&first| first <= start).max(1) - 1;
+
+        (first..last).any(|i| {
+            // Clamp the range to the keys covered by the partition.
+            let sub_start = start.max(self.index[i]);
+            let sub_end = match self.index.get(i + 1) {
+                Some(&next) => end.min(next - 1),
+                None => end,
+            };
+
+            self.partitions[i].query(sub_start..=sub_end)
+        })
+    }
+
+    /// Returns the first key of every partition, in increasing order.
+    pub fn index(&self) -> &[u64] {
+        &self.index
+    }
+
+    /// Returns the filters of the partitions, in the same order as [`Self::index`].
+    pub fn partitions(&self) -> &[RangeFilter] {
+        &self.partitions
+    }
+
+    /// Returns the amount of space required to store the index and every partition on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.index.len() * std::mem::size_of::<u64>()
+            + self
+                .partitions
+                .iter()
+                .map(RangeFilter::heap_size)
+                .sum::<usize>()
+    }
+}
@@ -0,0 +1,774 @@
+//! This module contains a simple binary format for persisting a [`RangeFilter`] to disk (or any
+//! other [`Write`] sink) and loading it back.
+//!
+//! The format is little-endian and laid out as follows:
+//!
+//! | Field        | Size             | Description                                        |
+//! |--------------|------------------|----------------------------------------------------|
+//! | Magic        | 4 bytes          | The bytes `GRAF`.                                  |
+//! | Version      | 4 bytes          | The format version, currently [`FORMAT_VERSION`].  |
+//! | Flags        | 4 bytes          | Bit `0` is set if a metadata block is present, bit |
+//! |              |                  | `1` is set if a seed is present, bit `2` is set if |
+//! |              |                  | the build parameters are present, bit `3` is set   |
+//! |              |                  | if the hashes are stored in a dense bit vector     |
+//! |              |                  | instead of an Elias-Fano encoding, bit `4` is set  |
+//! |              |                  | if the hashes are stored as 32-bit integers, and   |
+//! |              |                  | bit `5` is set if the hash function is keyed.      |
+//! | Metadata     | 0 or 32 bytes    | An optional [`FilterMetadata`] block.              |
+//! | Seed         | 0 or 8 bytes     | The optional seed of the hash parameters.          |
+//! | Build params | 0 or 16 bytes    | The optional number of elements and maximum range  |
+//! |              |                  | interval the hash parameters were generated for.   |
+//! | Hasher       | 32 bytes         | The hash parameters `c1`, `c2`, `p`, and `r`. If   |
+//! |              |                  | the hash function is keyed, `c1` is replaced by a  |
+//! |              |                  | tag that identifies the key, and `c2` and `p` are  |
+//! |              |                  | `0`.                                               |
+//! | Length       | 8 bytes          | The number of distinct hash values `n`.            |
+//! | Hashes       | `8 * n` or       | The sorted hash values of the filter, as 32-bit    |
+//! |              | `4 * n` bytes    | integers if the reduced universe size `r` is at    |
+//! |              |                  | most `2^32`, and as 64-bit integers otherwise.     |
+//! | Checksum     | 4 bytes          | A CRC32 checksum over every field after `Version`. |
+//!
+//! The checksum is verified when the filter is loaded, so a corrupted file produces a
+//! [`PersistError`] instead of a filter that silently returns wrong answers.
+//!
+//! The constants of a [keyed](OrderPreservingHasher::new_keyed) hash function are never written,
+//! so a filter with one can only be read back by supplying the same secret key, with
+//! [`RangeFilter::read_from_keyed`].
+//!
+//! With the `zstd` feature enabled, the same format can also be written through a zstd stream with
+//! [`RangeFilter::write_to_compressed`] and read back with [`RangeFilter::read_from_compressed`].
+//!
+//! With the `postcard` feature enabled, [`RangeFilter::to_postcard`] produces a much more compact
+//! representation that stores the gaps between consecutive hash values as variable-length integers.
+//! It carries no checksum, since postcard payloads are typically embedded in another framed
+//! message. The constants of a keyed hash function are left out in the same way, and such a filter
+//! is read back with [`RangeFilter::from_postcard_keyed`].
+//!
+//! On `no_std` targets, filters in the format above can be queried without allocating with the
+//! `grafite-query` crate, which reads the hash values in place from a byte slice.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utils::mix64;
+use crate::{DenseBitVector, EliasFano, Encoding, OrderPreservingHasher, RangeFilter};
+
+/// The magic bytes at the start of every serialized [`RangeFilter`].
+pub const MAGIC: [u8; 4] = *b"GRAF";
+
+/// The current version of the serialized format.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The flag bit set if a serialized filter contains a [`FilterMetadata`] block.
+const FLAG_METADATA: u32 = 1;
+
+/// The flag bit set if a serialized filter contains the seed of its hash parameters.
+const FLAG_SEED: u32 = 2;
+
+/// The flag bit set if a serialized filter contains the number of elements and the maximum range
+/// interval that its hash parameters were generated for.
+const FLAG_BUILD_PARAMS: u32 = 4;
+
+/// The flag bit set if the hash values of a serialized filter are stored in a [`DenseBitVector`]
+/// instead of an [`EliasFano`] encoding.
+const FLAG_DENSE: u32 = 8;
+
+/// The flag bit set if the hash values of a serialized filter are stored as 32-bit integers.
+const FLAG_COMPACT: u32 = 16;
+
+/// The flag bit set if the hash function of a serialized filter is keyed, in which case its
+/// constants are not stored.
+const FLAG_KEYED: u32 = 32;
+
+/// An optional block of metadata that can be persisted alongside a [`RangeFilter`].
+///
+/// This allows catalog code to identify a filter and detect when it is stale or does not match the
+/// key set it is supposed to describe, without decoding the filter itself. See
+/// [`RangeFilter::write_to_with_metadata`] and [`read_metadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilterMetadata {
+    /// A unique identifier for the filter, typically a UUID.
+    pub id: u128,
+    /// The time the filter was built, in milliseconds since the Unix epoch.
+    pub created_at_millis: u64,
+    /// A fingerprint of the source key set, e.g. computed with [`FilterMetadata::fingerprint`].
+    pub source_fingerprint: u64,
+}
+
+impl FilterMetadata {
+    /// Creates a new metadata block with a random (version 4) UUID and the current time.
+    pub fn new(source_fingerprint: u64) -> Self {
+        let id = rand::random::<u128>();
+        // Set the version (4) and variant (RFC 4122) bits of the UUID.
+        let id = (id & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+
+        let created_at_millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+
+        Self {
+            id,
+            created_at_millis,
+            source_fingerprint,
+        }
+    }
+
+    /// Computes an order-independent fingerprint of a set of keys.
+    ///
+    /// Every key is mixed with a 64-bit finalizer and the results are summed, so the same multiset
+    /// of keys always produces the same fingerprint regardless of the order they are given in.
+    pub fn fingerprint<I>(keys: I) -> u64
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        keys.into_iter()
+            .fold(0, |acc: u64, key| acc.wrapping_add(mix64(key)))
+    }
+}
+
+/// Reads only the [`FilterMetadata`] block of a filter that was written with
+/// [`RangeFilter::write_to_with_metadata`], without decoding the rest of the filter.
+///
+/// Returns `None` if the filter was written without metadata. Note that the checksum is stored at
+/// the end of the filter, so it is _not_ verified by this function.
+pub fn read_metadata<R: Read>(reader: R) -> Result<Option<FilterMetadata>, PersistError> {
+    let mut reader = ChecksumReader::new(reader);
+    read_header(&mut reader).map(|header| header.metadata)
+}
+
+/// The number of leading bytes of a serialized filter that [`serialized_len`] needs to determine
+/// the length of the whole filter, unless the filter is shorter than that.
+pub const LEN_PREFIX_BYTES: usize = 108;
+
+/// Returns the total length in bytes of a serialized filter, given a prefix of it.
+///
+/// The prefix must contain the header, the hash parameters, and the number of hash values, which
+/// always fit in the first [`LEN_PREFIX_BYTES`] bytes. This allows callers that fetch filters in
+/// pieces, such as ranged reads from remote storage, to read the prefix first and then fetch
+/// exactly the remaining bytes. The checksum is not verified.
+///
+/// If the prefix is too short or is not the start of a valid filter, this function will return a
+/// [`PersistError`].
+pub fn serialized_len(prefix: &[u8]) -> Result<u64, PersistError> {
+    let (layout, _) = read_prefix(prefix)?;
+
+    layout
+        .len
+        .checked_mul(layout.width() as u64)
+        .and_then(|hashes| hashes.checked_add(layout.offset as u64 + 4))
+        .ok_or(PersistError::Corrupt)
+}
+
+/// The location of the hash values within a serialized filter, along with its hash function, or
+/// the undecoded parameters of it.
+pub(crate) struct Layout<H = OrderPreservingHasher> {
+    /// The hash function of the filter.
+    #[cfg_attr(not(feature = "mmap"), allow(dead_code))]
+    pub(crate) hasher: H,
+    /// The byte offset of the first hash value.
+    pub(crate) offset: usize,
+    /// The number of hash values.
+    pub(crate) len: u64,
+    /// Whether the hash values are stored as 32-bit integers.
+    pub(crate) compact: bool,
+}
+
+impl<H> Layout<H> {
+    /// Returns the number of bytes that every hash value is stored in.
+    pub(crate) fn width(&self) -> usize {
+        if self.compact {
+            4
+        } else {
+            8
+        }
+    }
+}
+
+/// Reads everything up to the hash values of a serialized filter, given a prefix of it, and
+/// validates its hash parameters.
+///
+/// If the hash function is keyed, this function will return [`PersistError::KeyRequired`].
+#[cfg(feature = "mmap")]
+pub(crate) fn read_layout(prefix: &[u8]) -> Result<Layout, PersistError> {
+    let (layout, header) = read_prefix(prefix)?;
+
+    Ok(Layout {
+        hasher: decode_hasher(layout.hasher, &header, None)?,
+        offset: layout.offset,
+        len: layout.len,
+        compact: layout.compact,
+    })
+}
+
+/// Reads everything up to the hash values of a serialized filter, given a prefix of it, returning
+/// the undecoded hash parameters along with the header.
+fn read_prefix(prefix: &[u8]) -> Result<(Layout<[u64; 4]>, Header), PersistError> {
+    let mut reader = ChecksumReader::new(prefix);
+    let header = read_header(&mut reader)?;
+
+    let params = [
+        reader.read_u64()?,
+        reader.read_u64()?,
+        reader.read_u64()?,
+        reader.read_u64()?,
+    ];
+    let len = reader.read_u64()?;
+
+    let layout = Layout {
+        hasher: params,
+        offset: prefix.len() - reader.inner.len(),
+        len,
+        compact: header.compact,
+    };
+    Ok((layout, header))
+}
+
+/// Validates decoded hash parameters and restores the hash function they describe.
+///
+/// The constants of a keyed hash function are not stored, so they are derived again from `key`,
+/// whose tag must match the one stored in place of `c1`.
+fn decode_hasher(
+    params: [u64; 4],
+    header: &Header,
+    key: Option<&[u8; 32]>,
+) -> Result<OrderPreservingHasher, PersistError> {
+    let [c1, c2, p, r] = params;
+
+    let hasher = if header.keyed {
+        let key = key.ok_or(PersistError::KeyRequired)?;
+        // A prime greater than `r` must exist below `u64::MAX` for the constants to be derived.
+        if r == 0 || r >= u64::MAX - 1 {
+            return Err(PersistError::Corrupt);
+        }
+
+        let hasher = OrderPreservingHasher::new_with_reduced_keyed(r, key);
+        if hasher.key_tag() != Some(c1) {
+            return Err(PersistError::KeyMismatch);
+        }
+        hasher
+    } else {
+        // Validate the hash parameters, and then restore the seed they were generated from.
+        OrderPreservingHasher::from_parts(c1, c2, p, r).map_err(|_| PersistError::Corrupt)?;
+        OrderPreservingHasher::from_raw_parts(c1, c2, p, r, header.seed)
+    };
+
+    Ok(match header.build_params {
+        Some((num_elements, max_interval)) => hasher.with_build_params(num_elements, max_interval),
+        None => hasher,
+    })
+}
+
+/// The optional fields that precede the hash parameters of a serialized filter.
+struct Header {
+    metadata: Option<FilterMetadata>,
+    seed: Option<u64>,
+    build_params: Option<(u64, u64)>,
+    dense: bool,
+    compact: bool,
+    keyed: bool,
+}
+
+/// Reads and validates the magic bytes, version, and optional fields of a filter.
+fn read_header<R: Read>(reader: &mut ChecksumReader<R>) -> Result<Header, PersistError> {
+    let mut magic = [0; 4];
+    reader.inner.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(PersistError::InvalidMagic);
+    }
+
+    let mut version = [0; 4];
+    reader.inner.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != FORMAT_VERSION {
+        return Err(PersistError::UnsupportedVersion(version));
+    }
+
+    let known_flags =
+        FLAG_METADATA | FLAG_SEED | FLAG_BUILD_PARAMS | FLAG_DENSE | FLAG_COMPACT | FLAG_KEYED;
+    let flags = reader.read_u32()?;
+    if flags & !known_flags != 0 {
+        return Err(PersistError::Corrupt);
+    }
+
+    let metadata = if flags & FLAG_METADATA != 0 {
+        let id_low = reader.read_u64()?;
+        let id_high = reader.read_u64()?;
+        Some(FilterMetadata {
+            id: ((id_high as u128) << 64) | id_low as u128,
+            created_at_millis: reader.read_u64()?,
+            source_fingerprint: reader.read_u64()?,
+        })
+    } else {
+        None
+    };
+
+    let seed = if flags & FLAG_SEED != 0 {
+        Some(reader.read_u64()?)
+    } else {
+        None
+    };
+
+    let build_params = if flags & FLAG_BUILD_PARAMS != 0 {
+        Some((reader.read_u64()?, reader.read_u64()?))
+    } else {
+        None
+    };
+
+    Ok(Header {
+        metadata,
+        seed,
+        build_params,
+        dense: flags & FLAG_DENSE != 0,
+        compact: flags & FLAG_COMPACT != 0,
+        keyed: flags & FLAG_KEYED != 0,
+    })
+}
+
+/// An error type representing why a serialized [`RangeFilter`] could not be loaded.
+#[derive(Debug)]
+pub enum PersistError {
+    /// An I/O error from the underlying reader.
+    Io(io::Error),
+    /// If the input does not start with the expected magic bytes, such as [`MAGIC`].
+    InvalidMagic,
+    /// If the format version is not supported. Stores the version that was read.
+    UnsupportedVersion(u32),
+    /// If the stored checksum does not match the checksum of the data that was read.
+    ChecksumMismatch {
+        /// The checksum stored in the input.
+        expected: u32,
+        /// The checksum computed over the data that was read.
+        actual: u32,
+    },
+    /// If the filter has a keyed hash function, whose constants are not stored, but no key was
+    /// supplied to derive them. Such filters must be read with [`RangeFilter::read_from_keyed`] or
+    /// [`RangeFilter::from_postcard_keyed`].
+    KeyRequired,
+    /// If the supplied key is not the key that the hash function of the filter was derived from.
+    KeyMismatch,
+    /// If the input could not be decoded by [`postcard`].
+    #[cfg(feature = "postcard")]
+    Postcard(postcard::Error),
+    /// If the checksum is valid but the decoded filter is not, e.g. if the hash values are not
+    /// strictly increasing or are not less than the reduced universe size.
+    Corrupt,
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::InvalidMagic => write!(f, "input is not a serialized range filter"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch: expected {expected:#010x}, computed {actual:#010x}"
+            ),
+            Self::KeyRequired => write!(f, "a key is required to read a keyed range filter"),
+            Self::KeyMismatch => write!(f, "the key does not match the keyed range filter"),
+            #[cfg(feature = "postcard")]
+            Self::Postcard(e) => write!(f, "postcard error: {e}"),
+            Self::Corrupt => write!(f, "serialized range filter is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "postcard")]
+            Self::Postcard(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl RangeFilter {
+    /// Writes this `RangeFilter` to the given writer.
+    ///
+    /// See the [module-level documentation](crate::persist) for a description of the format.
+    pub fn write_to<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_with(writer, None)
+    }
+
+    /// Writes this `RangeFilter` to the given writer along with a [`FilterMetadata`] block.
+    ///
+    /// The metadata can be read back with
+    /// [`read_from_with_metadata`](Self::read_from_with_metadata) or, without decoding the filter,
+    /// with [`read_metadata`].
+    pub fn write_to_with_metadata<W: Write>(
+        &self,
+        writer: W,
+        metadata: &FilterMetadata,
+    ) -> io::Result<()> {
+        self.write_with(writer, Some(metadata))
+    }
+
+    fn write_with<W: Write>(&self, writer: W, metadata: Option<&FilterMetadata>) -> io::Result<()> {
+        let mut writer = ChecksumWriter::new(writer);
+
+        writer.inner.write_all(&MAGIC)?;
+        writer.inner.write_all(&FORMAT_VERSION.to_le_bytes())?;
+
+        let seed = self.hasher.seed();
+        let build_params = self.build_params();
+
+        let mut flags = 0;
+        if metadata.is_some() {
+            flags |= FLAG_METADATA;
+        }
+        if seed.is_some() {
+            flags |= FLAG_SEED;
+        }
+        if build_params.is_some() {
+            flags |= FLAG_BUILD_PARAMS;
+        }
+        if self.ef.is_dense() {
+            flags |= FLAG_DENSE;
+        }
+        let compact = self.hasher.fits_in_u32();
+        if compact {
+            flags |= FLAG_COMPACT;
+        }
+        if self.hasher.is_keyed() {
+            flags |= FLAG_KEYED;
+        }
+        writer.write_u32(flags)?;
+
+        if let Some(metadata) = metadata {
+            writer.write_u64(metadata.id as u64)?;
+            writer.write_u64((metadata.id >> 64) as u64)?;
+            writer.write_u64(metadata.created_at_millis)?;
+            writer.write_u64(metadata.source_fingerprint)?;
+        }
+        if let Some(seed) = seed {
+            writer.write_u64(seed)?;
+        }
+        if let Some((num_elements, max_interval)) = build_params {
+            writer.write_u64(num_elements)?;
+            writer.write_u64(max_interval)?;
+        }
+
+        for param in self.persisted_params() {
+            writer.write_u64(param)?;
+        }
+
+        writer.write_u64(self.ef.len() as u64)?;
+        for hash in self.ef.iter() {
+            if compact {
+                writer.write_u32(hash as u32)?;
+            } else {
+                writer.write_u64(hash)?;
+            }
+        }
+
+        let checksum = writer.crc.finalize();
+        writer.inner.write_all(&checksum.to_le_bytes())
+    }
+
+    /// Reads a `RangeFilter` that was written with [`write_to`](Self::write_to) or
+    /// [`write_to_with_metadata`](Self::write_to_with_metadata).
+    ///
+    /// If the input is invalid or its checksum does not match, this function will return a
+    /// [`PersistError`].
+    pub fn read_from<R: Read>(reader: R) -> Result<Self, PersistError> {
+        Self::read_from_with_metadata(reader).map(|(filter, _)| filter)
+    }
+
+    /// Reads a `RangeFilter` and its optional [`FilterMetadata`] block.
+    ///
+    /// If the input is invalid or its checksum does not match, this function will return a
+    /// [`PersistError`].
+    pub fn read_from_with_metadata<R: Read>(
+        reader: R,
+    ) -> Result<(Self, Option<FilterMetadata>), PersistError> {
+        Self::read_with(reader, None)
+    }
+
+    /// Reads a `RangeFilter` whose hash function was derived from the secret `key` with
+    /// [`OrderPreservingHasher::new_keyed`].
+    ///
+    /// Filters whose hash function is not keyed are read as with [`read_from`](Self::read_from),
+    /// ignoring the key.
+    ///
+    /// If the key does not match, this function will return [`PersistError::KeyMismatch`]. If the
+    /// input is invalid or its checksum does not match, this function will return a
+    /// [`PersistError`].
+    pub fn read_from_keyed<R: Read>(reader: R, key: &[u8; 32]) -> Result<Self, PersistError> {
+        Self::read_with(reader, Some(key)).map(|(filter, _)| filter)
+    }
+
+    fn read_with<R: Read>(
+        reader: R,
+        key: Option<&[u8; 32]>,
+    ) -> Result<(Self, Option<FilterMetadata>), PersistError> {
+        let mut reader = ChecksumReader::new(reader);
+
+        let header = read_header(&mut reader)?;
+
+        let params = [
+            reader.read_u64()?,
+            reader.read_u64()?,
+            reader.read_u64()?,
+            reader.read_u64()?,
+        ];
+
+        let len = reader.read_u64()?;
+        let mut hashes = Vec::with_capacity(len.min(1 << 20) as usize);
+        for _ in 0..len {
+            let hash = if header.compact {
+                u64::from(reader.read_u32()?)
+            } else {
+                reader.read_u64()?
+            };
+            hashes.push(hash);
+        }
+
+        let actual = reader.crc.clone().finalize();
+        let mut expected = [0; 4];
+        reader.inner.read_exact(&mut expected)?;
+        let expected = u32::from_le_bytes(expected);
+        if expected != actual {
+            return Err(PersistError::ChecksumMismatch { expected, actual });
+        }
+
+        let hasher = decode_hasher(params, &header, key)?;
+        let filter = Self::from_decoded_parts(hasher, Some(header.dense), &hashes)?;
+        Ok((filter, header.metadata))
+    }
+
+    /// Returns the number of elements and the maximum range interval that the hash parameters were
+    /// generated for, if they are both known.
+    fn build_params(&self) -> Option<(u64, u64)> {
+        Some((self.num_elements()? as u64, self.max_interval()?))
+    }
+
+    /// Returns the hash parameters to persist, which replace the constants of a keyed hash
+    /// function with the tag of its key.
+    fn persisted_params(&self) -> [u64; 4] {
+        match self.hasher.key_tag() {
+            Some(key_tag) => [key_tag, 0, 0, self.hasher.reduced_universe()],
+            None => self.hasher.raw_parts(),
+        }
+    }
+
+    /// Validates decoded hash values and builds a `RangeFilter` from them and a decoded hash
+    /// function.
+    ///
+    /// If `dense` is `None`, the encoding of the hash values is chosen automatically.
+    fn from_decoded_parts(
+        hasher: OrderPreservingHasher,
+        dense: Option<bool>,
+        hashes: &[u64],
+    ) -> Result<Self, PersistError> {
+        if hashes.is_empty()
+            || hashes.windows(2).any(|w| w[0] >= w[1])
+            || hashes[hashes.len() - 1] >= hasher.reduced_universe()
+        {
+            return Err(PersistError::Corrupt);
+        }
+
+        let ef = match dense {
+            Some(true) => Encoding::Dense(DenseBitVector::from_slice(hashes)),
+            Some(false) => Encoding::EliasFano(EliasFano::from_slice(hashes)),
+            None => Encoding::from_slice(hashes),
+        };
+
+        Ok(Self { hasher, ef })
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl RangeFilter {
+    /// Writes this `RangeFilter` to the given writer, compressed with zstd at the given `level`.
+    ///
+    /// A `level` of `0` uses zstd's default compression level. The uncompressed stream is exactly
+    /// the format written by [`write_to`](Self::write_to).
+    pub fn write_to_compressed<W: Write>(&self, writer: W, level: i32) -> io::Result<()> {
+        let mut encoder = zstd::Encoder::new(writer, level)?;
+        self.write_to(&mut encoder)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Reads a `RangeFilter` that was written with
+    /// [`write_to_compressed`](Self::write_to_compressed).
+    ///
+    /// If the input is invalid or its checksum does not match, this function will return a
+    /// [`PersistError`].
+    pub fn read_from_compressed<R: Read>(reader: R) -> Result<Self, PersistError> {
+        let decoder = zstd::Decoder::new(reader)?;
+        Self::read_from(decoder)
+    }
+}
+
+/// The compact wire representation of a [`RangeFilter`] used by the `postcard` feature.
+///
+/// The hash values are stored as gaps between consecutive values, which postcard encodes as
+/// variable-length integers. This is much smaller than the fixed-width [`write_to`] format whenever
+/// the stored hash values are dense in the reduced universe.
+///
+/// [`write_to`]: RangeFilter::write_to
+#[cfg(feature = "postcard")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CompactFilter<G> {
+    params: [u64; 4],
+    seed: Option<u64>,
+    build_params: Option<(u64, u64)>,
+    gaps: G,
+}
+
+/// Serializes the gaps between consecutive hash values without collecting them first.
+#[cfg(feature = "postcard")]
+struct Gaps<'a>(&'a Encoding);
+
+#[cfg(feature = "postcard")]
+impl serde::Serialize for Gaps<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        let mut prev = 0;
+        for hash in self.0.iter() {
+            seq.serialize_element(&(hash - prev))?;
+            prev = hash;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl RangeFilter {
+    /// Serializes this `RangeFilter` into a compact [`postcard`] representation.
+    pub fn to_postcard(&self) -> Result<Vec<u8>, postcard::Error> {
+        postcard::to_allocvec(&self.compact())
+    }
+
+    /// Serializes this `RangeFilter` into a compact [`postcard`] representation inside of the
+    /// given buffer, returning the used part of the buffer.
+    ///
+    /// This does not allocate, which makes it suitable for writing into a fixed-size buffer.
+    pub fn to_postcard_slice<'a>(
+        &self,
+        buf: &'a mut [u8],
+    ) -> Result<&'a mut [u8], postcard::Error> {
+        postcard::to_slice(&self.compact(), buf)
+    }
+
+    /// Deserializes a `RangeFilter` that was serialized with [`to_postcard`](Self::to_postcard) or
+    /// [`to_postcard_slice`](Self::to_postcard_slice).
+    ///
+    /// If the input is invalid, this function will return a [`PersistError`].
+    pub fn from_postcard(bytes: &[u8]) -> Result<Self, PersistError> {
+        Self::from_postcard_with(bytes, None)
+    }
+
+    /// Deserializes a `RangeFilter` whose hash function was derived from the secret `key` with
+    /// [`OrderPreservingHasher::new_keyed`].
+    ///
+    /// Filters whose hash function is not keyed are deserialized as with
+    /// [`from_postcard`](Self::from_postcard), ignoring the key. If the key does not match, this
+    /// function will return [`PersistError::KeyMismatch`].
+    pub fn from_postcard_keyed(bytes: &[u8], key: &[u8; 32]) -> Result<Self, PersistError> {
+        Self::from_postcard_with(bytes, Some(key))
+    }
+
+    fn from_postcard_with(bytes: &[u8], key: Option<&[u8; 32]>) -> Result<Self, PersistError> {
+        let compact: CompactFilter<Vec<u64>> =
+            postcard::from_bytes(bytes).map_err(PersistError::Postcard)?;
+
+        let mut hashes = compact.gaps;
+        let mut prev: u64 = 0;
+        for hash in hashes.iter_mut() {
+            prev = prev.checked_add(*hash).ok_or(PersistError::Corrupt)?;
+            *hash = prev;
+        }
+
+        // The postcard format has no flags, so a keyed hash function is marked by `p = 0`, which is
+        // never valid otherwise.
+        let header = Header {
+            metadata: None,
+            seed: compact.seed,
+            build_params: compact.build_params,
+            dense: false,
+            compact: false,
+            keyed: compact.params[2] == 0,
+        };
+        let hasher = decode_hasher(compact.params, &header, key)?;
+
+        Self::from_decoded_parts(hasher, None, &hashes)
+    }
+
+    fn compact(&self) -> CompactFilter<Gaps<'_>> {
+        CompactFilter {
+            params: self.persisted_params(),
+            seed: self.hasher.seed(),
+            build_params: self.build_params(),
+            gaps: Gaps(&self.ef),
+        }
+    }
+}
+
+/// A writer wrapper that computes a CRC32 checksum over the integers written through it.
+struct ChecksumWriter<W> {
+    inner: W,
+    crc: crc32fast::Hasher,
+}
+
+impl<W: Write> ChecksumWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            crc: crc32fast::Hasher::new(),
+        }
+    }
+
+    fn write_u32(&mut self, x: u32) -> io::Result<()> {
+        let bytes = x.to_le_bytes();
+        self.crc.update(&bytes);
+        self.inner.write_all(&bytes)
+    }
+
+    fn write_u64(&mut self, x: u64) -> io::Result<()> {
+        let bytes = x.to_le_bytes();
+        self.crc.update(&bytes);
+        self.inner.write_all(&bytes)
+    }
+}
+
+/// A reader wrapper that computes a CRC32 checksum over the integers read through it.
+struct ChecksumReader<R> {
+    inner: R,
+    crc: crc32fast::Hasher,
+}
+
+impl<R: Read> ChecksumReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            crc: crc32fast::Hasher::new(),
+        }
+    }
+
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        self.inner.read_exact(&mut bytes)?;
+        self.crc.update(&bytes);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        self.inner.read_exact(&mut bytes)?;
+        self.crc.update(&bytes);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
@@ -0,0 +1,219 @@
+//! This module contains the [`BuildObserver`] trait, a hook for reporting the progress of building
+//! a [`RangeFilter`] and for cooperatively cancelling the build.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::filter::check_deduped_hashes;
+use crate::instrument;
+use crate::{BuildError, OrderPreservingHasher, RangeFilter, RangeFilterBackend};
+
+/// The number of items processed between every progress report while hashing and encoding.
+const REPORT_INTERVAL: usize = 1 << 20;
+
+/// A phase of building a [`RangeFilter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuildPhase {
+    /// Hashing every input value.
+    Hashing,
+    /// Sorting and deduplicating the hash values.
+    Sorting,
+    /// Encoding the distinct hash values with the backend of the filter.
+    Encoding,
+}
+
+/// An observer that is notified of the progress of building a [`RangeFilter`].
+///
+/// This is implemented for every closure of the form `FnMut(BuildPhase, usize, usize)`.
+pub trait BuildObserver {
+    /// Called periodically during every [`BuildPhase`] with the number of items processed so far
+    /// and the total number of items in that phase.
+    ///
+    /// During [`BuildPhase::Hashing`], the total is taken from the input iterator's
+    /// [`size_hint`](Iterator::size_hint), so it is only a lower bound if the iterator does not
+    /// know its exact length. The sorting phase is reported once before it starts and once after it
+    /// finishes. The encoding phase is reported in the same way, and also periodically while it
+    /// runs if the backend of the filter supports it through
+    /// [`RangeFilterBackend::from_sorted_interruptible`], which every backend of this crate does.
+    fn on_progress(&mut self, phase: BuildPhase, items_done: usize, items_total: usize);
+
+    /// Returns `true` if the build should be aborted with [`BuildError::Cancelled`].
+    ///
+    /// This is checked every time progress is reported, so the build is aborted at the next
+    /// reporting point. Note that sorting cannot be interrupted once it starts, since it runs to
+    /// completion in a single call to the standard library's sort, and neither can encoding with
+    /// a backend that does not report its progress.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// An observer that does not report progress, and cancels the build once a flag is set.
+struct CancelFlag<'a>(&'a AtomicBool);
+
+impl BuildObserver for CancelFlag<'_> {
+    fn on_progress(&mut self, _phase: BuildPhase, _items_done: usize, _items_total: usize) {}
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// An observer that neither reports progress nor cancels the build.
+pub(crate) struct Unobserved;
+
+impl BuildObserver for Unobserved {
+    fn on_progress(&mut self, _phase: BuildPhase, _items_done: usize, _items_total: usize) {}
+}
+
+impl<F> BuildObserver for F
+where
+    F: FnMut(BuildPhase, usize, usize),
+{
+    fn on_progress(&mut self, phase: BuildPhase, items_done: usize, items_total: usize) {
+        self(phase, items_done, items_total)
+    }
+}
+
+impl RangeFilter {
+    /// Creates a new `RangeFilter` given an iterator of values, which can be cancelled by setting
+    /// `cancel` to `true` from another thread.
+    ///
+    /// If the build is cancelled, this function will return [`BuildError::Cancelled`]. If the
+    /// filter could not be built for any other reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new_cancellable<I>(
+        values: I,
+        hasher: OrderPreservingHasher,
+        cancel: &AtomicBool,
+    ) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+    {
+        Self::try_new_with_observer(values, hasher, &mut CancelFlag(cancel))
+    }
+
+    /// Creates a new `RangeFilter` given an iterator of values, reporting the progress of every
+    /// phase of the build to `observer`.
+    ///
+    /// If the observer cancels the build, this function will return [`BuildError::Cancelled`].
+    /// If the filter could not be built for any other reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new_with_observer<I, O>(
+        values: I,
+        hasher: OrderPreservingHasher,
+        observer: &mut O,
+    ) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+        O: BuildObserver + ?Sized,
+    {
+        Self::try_build_observed(values, hasher, observer)
+    }
+}
+
+impl<B: RangeFilterBackend> RangeFilter<B> {
+    /// Builds a `RangeFilter` given an iterator of values, reporting the progress of every phase
+    /// of the build to `observer`. Every other way of building a filter from an iterator goes
+    /// through this function.
+    pub(crate) fn try_build_observed<I, O>(
+        values: I,
+        hasher: OrderPreservingHasher,
+        observer: &mut O,
+    ) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u64>,
+        O: BuildObserver + ?Sized,
+    {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher);
+        }
+
+        let _span = instrument::build_span(values.size_hint().0);
+
+        // Hash all items in the input set, and then sort and remove all duplicates.
+        let hashes = if hasher.fits_in_u32() {
+            // Sorting 32-bit hashes moves half as much memory as sorting 64-bit ones.
+            let hashes = hash_all(values, observer, |x| hasher.hash(x) as u32)?;
+            let hashes = sort_and_dedup(hashes, observer)?;
+            hashes.into_iter().map(u64::from).collect()
+        } else {
+            let hashes = hash_all(values, observer, |x| hasher.hash(x))?;
+            sort_and_dedup(hashes, observer)?
+        };
+
+        let total = hashes.len();
+        report(observer, BuildPhase::Encoding, 0, total)?;
+        check_deduped_hashes(&hashes, &hasher)?;
+
+        let mut checkpoint = |done| report(observer, BuildPhase::Encoding, done, total).is_ok();
+        let ef = B::from_sorted_interruptible(&hashes, REPORT_INTERVAL, &mut checkpoint)
+            .ok_or(BuildError::Cancelled)?;
+        instrument::phase_finished(BuildPhase::Encoding, total);
+        report(observer, BuildPhase::Encoding, total, total)?;
+
+        Ok(Self { hasher, ef })
+    }
+}
+
+/// Hashes every value with `hash`, reporting the progress to the observer.
+fn hash_all<I, O, T>(
+    values: I,
+    observer: &mut O,
+    hash: impl Fn(u64) -> T,
+) -> Result<Vec<T>, BuildError>
+where
+    I: Iterator<Item = u64>,
+    O: BuildObserver + ?Sized,
+{
+    let (lower, upper) = values.size_hint();
+    let total = upper.unwrap_or(lower);
+
+    let mut hashes = Vec::with_capacity(lower);
+    report(observer, BuildPhase::Hashing, 0, total)?;
+    for x in values {
+        hashes.push(hash(x));
+        if hashes.len() % REPORT_INTERVAL == 0 {
+            let done = hashes.len();
+            report(observer, BuildPhase::Hashing, done, total.max(done))?;
+        }
+    }
+    report(observer, BuildPhase::Hashing, hashes.len(), hashes.len())?;
+    instrument::phase_finished(BuildPhase::Hashing, hashes.len());
+
+    Ok(hashes)
+}
+
+/// Sorts the hashes and then removes all duplicates, reporting the progress to the observer.
+fn sort_and_dedup<O, T>(mut hashes: Vec<T>, observer: &mut O) -> Result<Vec<T>, BuildError>
+where
+    O: BuildObserver + ?Sized,
+    T: Ord,
+{
+    let total = hashes.len();
+    report(observer, BuildPhase::Sorting, 0, total)?;
+    hashes.sort_unstable();
+    hashes.dedup();
+    report(observer, BuildPhase::Sorting, total, total)?;
+    instrument::phase_finished(BuildPhase::Sorting, hashes.len());
+
+    Ok(hashes)
+}
+
+/// Reports progress to the observer, and then checks if the build has been cancelled.
+fn report<O>(
+    observer: &mut O,
+    phase: BuildPhase,
+    items_done: usize,
+    items_total: usize,
+) -> Result<(), BuildError>
+where
+    O: BuildObserver + ?Sized,
+{
+    observer.on_progress(phase, items_done, items_total);
+
+    if observer.is_cancelled() {
+        Err(BuildError::Cancelled)
+    } else {
+        Ok(())
+    }
+}
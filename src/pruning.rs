@@ -0,0 +1,261 @@
+//! This module contains the [`FilterPruningStatistics`] type, which lets DataFusion's
+//! `PruningPredicate` consult the filters of a [`RowGroupFilters`] when it decides which row
+//! groups of a Parquet file to scan.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, BooleanArray};
+use datafusion::common::{Column, Result, ScalarValue};
+use datafusion::logical_expr::Operator;
+use datafusion::physical_expr::expressions::{self, BinaryExpr, Literal};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+
+use crate::{RowGroupFilters, ToOrderedU64};
+
+/// The [`PruningStatistics`] of one column of a Parquet file, answered with the range filter of
+/// each of its row groups.
+///
+/// DataFusion only asks pruning statistics about ranges through their min/max values, which the
+/// filters do not store, and asks about sets of values for equality and `IN` predicates, which the
+/// filters answer with one point query per value. The min/max values, null counts and row counts
+/// are taken from other statistics of the same row groups if they are given with
+/// [`Self::with_statistics`]. To also consult the filters for range predicates, prune with
+/// [`Self::prune`] instead of [`PruningPredicate::prune`].
+///
+/// Literals are mapped onto keys like the values of the column in [`RowGroupFilters`]: signed
+/// integers like `i64` keys and unsigned integers like `u64` keys. Other literals, such as those of
+/// a column that is cast to another type in the predicate, are never checked against the filters.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use datafusion::arrow::datatypes::{DataType, Field, Schema};
+/// use datafusion::common::ScalarValue;
+/// use datafusion::logical_expr::Operator;
+/// use datafusion::physical_expr::expressions::{BinaryExpr, Column, Literal};
+/// use datafusion::physical_optimizer::pruning::PruningPredicate;
+/// use grafite::{OrderPreservingHasher, RangeFilter, RowGroupFilters, ToOrderedU64};
+///
+/// let filter = |keys: &[i64]| {
+///     let hasher = OrderPreservingHasher::new(keys.len(), 0.01, 16).unwrap();
+///     Some(RangeFilter::new(keys.iter().map(|k| k.to_ordered_u64()), hasher))
+/// };
+/// let filters = RowGroupFilters::from_filters(vec![filter(&[1, 5, 9]), filter(&[1000, 1005])]);
+///
+/// // ts >= 1000 AND ts <= 1010
+/// let schema = Arc::new(Schema::new(vec![Field::new("ts", DataType::Int64, false)]));
+/// let bound = |op, value| {
+///     let column = Arc::new(Column::new("ts", 0));
+///     let value = Arc::new(Literal::new(ScalarValue::Int64(Some(value))));
+///     Arc::new(BinaryExpr::new(column, op, value))
+/// };
+/// let expr = Arc::new(BinaryExpr::new(
+///     bound(Operator::GtEq, 1000),
+///     Operator::And,
+///     bound(Operator::LtEq, 1010),
+/// ));
+/// let predicate = PruningPredicate::try_new(expr, schema).unwrap();
+///
+/// let statistics = filters.pruning_statistics("ts");
+/// assert_eq!(statistics.prune(&predicate).unwrap(), vec![false, true]);
+/// ```
+#[derive(Clone, Copy)]
+pub struct FilterPruningStatistics<'a> {
+    /// The name of the column that the filters were built over.
+    column: &'a str,
+    /// The filter of every row group.
+    filters: &'a RowGroupFilters,
+    /// The statistics that answer everything except for sets of values of the filtered column.
+    statistics: Option<&'a dyn PruningStatistics>,
+}
+
+impl RowGroupFilters {
+    /// Returns the [`PruningStatistics`] of the column with the given name, answered with these
+    /// filters.
+    pub fn pruning_statistics<'a>(&'a self, column: &'a str) -> FilterPruningStatistics<'a> {
+        FilterPruningStatistics {
+            column,
+            filters: self,
+            statistics: None,
+        }
+    }
+}
+
+impl<'a> FilterPruningStatistics<'a> {
+    /// Takes the min/max values, null counts and row counts of every column, and the sets of
+    /// values of other columns, from the given statistics of the same row groups, such as those
+    /// read from the metadata of the Parquet file.
+    pub fn with_statistics(self, statistics: &'a dyn PruningStatistics) -> Self {
+        Self {
+            statistics: Some(statistics),
+            ..self
+        }
+    }
+
+    /// Returns which row groups may contain rows that satisfy the predicate, in the same way as
+    /// [`PruningPredicate::prune`], but also skips the row groups whose filter has no value in the
+    /// range that the predicate allows for the filtered column.
+    ///
+    /// The range is the intersection of every comparison between the column and a literal in the
+    /// top-level conjunction of the predicate, such as `ts >= 10 AND ts < 20 AND name = 'a'`, and
+    /// it is checked with [`RangeFilter::query_chunked`](crate::RangeFilter::query_chunked), so it
+    /// may be longer than the maximum range interval of the filters. Comparisons that are nested
+    /// within other expressions are only used by the predicate itself.
+    ///
+    /// If the predicate could not be evaluated, this function will return its error.
+    pub fn prune(&self, predicate: &PruningPredicate) -> Result<Vec<bool>> {
+        let mut keep = predicate.prune(self)?;
+
+        let mut range = KeyRange::default();
+        self.narrow(predicate.orig_expr(), &mut range);
+        if range.constrained {
+            for (i, keep) in keep.iter_mut().enumerate() {
+                *keep &= range.start <= range.end
+                    && self
+                        .filters
+                        .filter(i)
+                        .is_some_and(|f| f.query_chunked(range.start..=range.end));
+            }
+        }
+
+        Ok(keep)
+    }
+
+    /// Narrows `range` with every comparison between the filtered column and a literal in the
+    /// conjunction `expr`.
+    fn narrow(&self, expr: &Arc<dyn PhysicalExpr>, range: &mut KeyRange) {
+        let Some(binary) = expr.as_any().downcast_ref::<BinaryExpr>() else {
+            return;
+        };
+
+        if *binary.op() == Operator::And {
+            self.narrow(binary.left(), range);
+            self.narrow(binary.right(), range);
+            return;
+        }
+
+        // Comparisons are normalized to `column op literal`.
+        let (op, literal) = if self.is_column(binary.left()) {
+            (*binary.op(), binary.right())
+        } else if self.is_column(binary.right()) {
+            let Some(op) = binary.op().swap() else {
+                return;
+            };
+            (op, binary.left())
+        } else {
+            return;
+        };
+        let Some(key) = literal
+            .as_any()
+            .downcast_ref::<Literal>()
+            .and_then(|literal| to_key(literal.value()))
+        else {
+            return;
+        };
+
+        match op {
+            Operator::Eq => {
+                range.start = range.start.max(key);
+                range.end = range.end.min(key);
+            }
+            Operator::GtEq => range.start = range.start.max(key),
+            Operator::LtEq => range.end = range.end.min(key),
+            // The range is empty if the bound excludes every key.
+            Operator::Gt => match key.checked_add(1) {
+                Some(start) => range.start = range.start.max(start),
+                None => (range.start, range.end) = (1, 0),
+            },
+            Operator::Lt => match key.checked_sub(1) {
+                Some(end) => range.end = range.end.min(end),
+                None => (range.start, range.end) = (1, 0),
+            },
+            _ => return,
+        }
+        range.constrained = true;
+    }
+
+    /// Returns `true` if the expression is the filtered column.
+    fn is_column(&self, expr: &Arc<dyn PhysicalExpr>) -> bool {
+        expr.as_any()
+            .downcast_ref::<expressions::Column>()
+            .is_some_and(|column| column.name() == self.column)
+    }
+}
+
+impl PruningStatistics for FilterPruningStatistics<'_> {
+    fn min_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.statistics?.min_values(column)
+    }
+
+    fn max_values(&self, column: &Column) -> Option<ArrayRef> {
+        self.statistics?.max_values(column)
+    }
+
+    fn num_containers(&self) -> usize {
+        self.filters.num_row_groups()
+    }
+
+    fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        self.statistics?.null_counts(column)
+    }
+
+    fn row_counts(&self, column: &Column) -> Option<ArrayRef> {
+        self.statistics?.row_counts(column)
+    }
+
+    /// Returns `false` for the row groups whose filter has none of the values, and null for the
+    /// others, since a filter can never tell that a row group only has the given values.
+    fn contained(&self, column: &Column, values: &HashSet<ScalarValue>) -> Option<BooleanArray> {
+        if column.name != self.column {
+            return self.statistics?.contained(column, values);
+        }
+
+        let keys: Vec<u64> = values.iter().map(to_key).collect::<Option<_>>()?;
+        let contained = (0..self.filters.num_row_groups())
+            .map(|i| {
+                let may_contain = self
+                    .filters
+                    .filter(i)
+                    .is_some_and(|f| keys.iter().any(|&key| f.query(key..=key)));
+                (!may_contain).then_some(false)
+            })
+            .collect::<Vec<_>>();
+
+        Some(BooleanArray::from(contained))
+    }
+}
+
+/// An inclusive range of keys, which is empty if `start > end`.
+struct KeyRange {
+    start: u64,
+    end: u64,
+    /// Whether any comparison narrowed the range.
+    constrained: bool,
+}
+
+impl Default for KeyRange {
+    fn default() -> Self {
+        Self {
+            start: 0,
+            end: u64::MAX,
+            constrained: false,
+        }
+    }
+}
+
+/// Maps a non-null integer literal onto the key of the same value in [`RowGroupFilters`].
+fn to_key(value: &ScalarValue) -> Option<u64> {
+    match *value {
+        ScalarValue::Int8(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::Int16(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::Int32(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::Int64(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::UInt8(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::UInt16(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::UInt32(Some(v)) => Some(v.to_ordered_u64()),
+        ScalarValue::UInt64(Some(v)) => Some(v),
+        _ => None,
+    }
+}
@@ -0,0 +1,187 @@
+//! Python bindings for building, serializing, and querying filters with PyO3.
+//!
+//! The bindings are compiled into a Python extension module named `grafite`, which exposes
+//! [`OrderPreservingHasher`] and [`RangeFilter`] over `u64` keys. Filters can be built directly
+//! from NumPy arrays and serialized into the format of [`RangeFilter::write_to`], so filters built
+//! by Python pipelines can be read by Rust services and vice versa.
+//!
+//! The extension module is built by compiling this crate as a `cdylib` with the `python` feature,
+//! for example with `maturin build --features python`.
+
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::{OrderPreservingHasher, RangeFilter};
+
+/// An [`OrderPreservingHasher`] that can be used from Python, where it is exported as
+/// `OrderPreservingHasher`.
+#[pyclass(name = "OrderPreservingHasher", module = "grafite", frozen)]
+#[derive(Debug, Clone, Copy)]
+pub struct PyOrderPreservingHasher {
+    /// The wrapped hash function.
+    hasher: OrderPreservingHasher,
+}
+
+#[pymethods]
+impl PyOrderPreservingHasher {
+    /// Creates a new hash function for the given number of keys, false positive rate, and maximum
+    /// range interval.
+    ///
+    /// If a seed is given, the hash function is derived deterministically from it.
+    #[new]
+    #[pyo3(signature = (num_elements, epsilon, max_interval, seed = None))]
+    fn new(
+        num_elements: usize,
+        epsilon: f64,
+        max_interval: u64,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let hasher = match seed {
+            Some(seed) => {
+                OrderPreservingHasher::new_seeded(num_elements, epsilon, max_interval, seed)
+            }
+            None => OrderPreservingHasher::new(num_elements, epsilon, max_interval),
+        }
+        .map_err(|e| PyValueError::new_err(format!("invalid filter parameters: {e:?}")))?;
+
+        Ok(Self { hasher })
+    }
+
+    /// Hashes a single key.
+    fn hash(&self, x: u64) -> u64 {
+        self.hasher.hash(x)
+    }
+
+    /// Returns the size of the reduced universe that keys are hashed into.
+    #[getter]
+    fn reduced_universe(&self) -> u64 {
+        self.hasher.reduced_universe()
+    }
+
+    /// Returns the seed that the hash function was derived from, if any.
+    #[getter]
+    fn seed(&self) -> Option<u64> {
+        self.hasher.seed()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.hasher)
+    }
+}
+
+/// A [`RangeFilter`] over `u64` keys that can be used from Python, where it is exported as
+/// `RangeFilter`.
+#[pyclass(name = "RangeFilter", module = "grafite", frozen)]
+#[derive(Debug, Clone)]
+pub struct PyRangeFilter {
+    /// The wrapped filter.
+    filter: RangeFilter,
+}
+
+#[pymethods]
+impl PyRangeFilter {
+    /// Builds a filter over a one-dimensional NumPy array of `uint64` keys.
+    #[new]
+    fn new(keys: PyReadonlyArray1<'_, u64>, hasher: &PyOrderPreservingHasher) -> PyResult<Self> {
+        let keys = keys.as_array();
+        let filter = RangeFilter::try_new(keys.iter().copied(), hasher.hasher).map_err(|e| {
+            PyValueError::new_err(format!("unable to build the range filter: {e:?}"))
+        })?;
+
+        Ok(Self { filter })
+    }
+
+    /// Reads a filter that was written with `to_bytes` or [`RangeFilter::write_to`].
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        let filter =
+            RangeFilter::read_from(bytes).map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+        Ok(Self { filter })
+    }
+
+    /// Writes the filter into `bytes` in the format of [`RangeFilter::write_to`].
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let mut bytes = Vec::new();
+        self.filter
+            .write_to(&mut bytes)
+            .expect("writing into a `Vec` cannot fail");
+
+        PyBytes::new(py, &bytes)
+    }
+
+    /// Checks if there may be any keys in the inclusive range `start..=end`.
+    fn query(&self, start: u64, end: u64) -> bool {
+        self.filter.query(start..=end)
+    }
+
+    /// Checks many inclusive ranges at once, given as NumPy arrays of their endpoints, and returns
+    /// a NumPy array of booleans.
+    fn query_many<'py>(
+        &self,
+        py: Python<'py>,
+        starts: PyReadonlyArray1<'py, u64>,
+        ends: PyReadonlyArray1<'py, u64>,
+    ) -> PyResult<Bound<'py, PyArray1<bool>>> {
+        let (starts, ends) = (starts.as_array(), ends.as_array());
+        if starts.len() != ends.len() {
+            return Err(PyValueError::new_err(
+                "`starts` and `ends` must have the same length",
+            ));
+        }
+
+        let results: Vec<bool> = starts
+            .iter()
+            .zip(ends.iter())
+            .map(|(&start, &end)| self.filter.query(start..=end))
+            .collect();
+
+        Ok(PyArray1::from_vec(py, results))
+    }
+
+    /// Checks if the given key may be among the original input set.
+    fn __contains__(&self, key: u64) -> bool {
+        self.filter.contains(key)
+    }
+
+    /// Returns the number of distinct hash values stored in the filter.
+    fn __len__(&self) -> usize {
+        self.filter.ef.len()
+    }
+
+    /// Returns the hash function of the filter.
+    #[getter]
+    fn hasher(&self) -> PyOrderPreservingHasher {
+        PyOrderPreservingHasher {
+            hasher: self.filter.hasher,
+        }
+    }
+
+    /// Returns the amount of space required to store the filter on the heap.
+    fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+    }
+}
+
+impl PyRangeFilter {
+    /// Wraps a filter so it can be handed to Python.
+    pub fn from_filter(filter: RangeFilter) -> Self {
+        Self { filter }
+    }
+
+    /// Returns the wrapped filter.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
+
+/// The `grafite` Python extension module.
+#[pymodule]
+fn grafite(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyOrderPreservingHasher>()?;
+    m.add_class::<PyRangeFilter>()?;
+
+    Ok(())
+}
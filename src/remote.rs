@@ -0,0 +1,85 @@
+//! Asynchronous loading of serialized filters from object stores such as S3, GCS, and Azure Blob
+//! Storage through the [`object_store`] crate.
+
+use std::fmt;
+
+use object_store::path::Path;
+use object_store::ObjectStore;
+
+use crate::persist::{serialized_len, LEN_PREFIX_BYTES};
+use crate::{PersistError, RangeFilter};
+
+/// An error type representing why a [`RangeFilter`] could not be loaded from an object store.
+#[derive(Debug)]
+pub enum RemoteError {
+    /// An error occurred while fetching the object.
+    ObjectStore(object_store::Error),
+    /// The fetched bytes are not a valid serialized filter.
+    Persist(PersistError),
+}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ObjectStore(e) => write!(f, "object store error while loading the filter: {e}"),
+            Self::Persist(e) => write!(f, "unable to load the range filter: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RemoteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ObjectStore(e) => Some(e),
+            Self::Persist(e) => Some(e),
+        }
+    }
+}
+
+impl From<object_store::Error> for RemoteError {
+    fn from(e: object_store::Error) -> Self {
+        Self::ObjectStore(e)
+    }
+}
+
+impl From<PersistError> for RemoteError {
+    fn from(e: PersistError) -> Self {
+        Self::Persist(e)
+    }
+}
+
+impl RangeFilter {
+    /// Loads a filter that was written with [`RangeFilter::write_to`] from an object store.
+    ///
+    /// The object is fetched with ranged reads: the first request fetches the header, which
+    /// determines the length of the filter, and the second request fetches exactly the rest of the
+    /// filter. Any bytes stored after the filter in the same object are never fetched, so filters
+    /// can be embedded at the start of larger objects. The checksum is verified once the filter has
+    /// been fetched.
+    ///
+    /// If the object could not be fetched or is not a valid filter, this function will return a
+    /// [`RemoteError`].
+    pub async fn read_from_object_store(
+        store: &dyn ObjectStore,
+        path: &Path,
+    ) -> Result<Self, RemoteError> {
+        let size = store.head(path).await?.size;
+
+        let prefix = store
+            .get_range(path, 0..size.min(LEN_PREFIX_BYTES as u64))
+            .await?;
+        let len = serialized_len(&prefix)?;
+        if len > size {
+            return Err(PersistError::Io(std::io::ErrorKind::UnexpectedEof.into()).into());
+        }
+
+        let mut bytes = Vec::with_capacity(len as usize);
+        bytes.extend_from_slice(&prefix[..(prefix.len() as u64).min(len) as usize]);
+        if len > prefix.len() as u64 {
+            let rest = store.get_range(path, prefix.len() as u64..len).await?;
+            bytes.extend_from_slice(&rest);
+        }
+
+        Ok(Self::read_from(bytes.as_slice())?)
+    }
+}
@@ -0,0 +1,245 @@
+//! This module contains the [`RowGroupFilters`] type, which holds one filter per row group of a
+//! Parquet column, so that readers can skip row groups beyond what min/max statistics allow.
+
+use std::fmt;
+use std::ops::RangeBounds;
+
+use parquet::basic::{ConvertedType, LogicalType, Type as PhysicalType};
+use parquet::column::reader::{ColumnReader, ColumnReaderImpl};
+use parquet::data_type::DataType;
+use parquet::errors::ParquetError;
+use parquet::file::reader::FileReader;
+use parquet::schema::types::ColumnDescriptor;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, OrderPreservingHasher, RangeFilter, ToOrderedU64};
+
+/// The number of records decoded from a column chunk at a time.
+const BATCH_SIZE: usize = 1 << 13;
+
+/// An error type representing why the filters of a Parquet column could not be built.
+#[derive(Debug)]
+pub enum RowGroupError {
+    /// An error occurred while reading the Parquet file.
+    Parquet(ParquetError),
+    /// A filter could not be built from the values of a column chunk.
+    Build(BuildError),
+    /// If the column does not have the `INT32` or `INT64` physical type. Stores the physical type
+    /// of the column.
+    UnsupportedColumn(PhysicalType),
+}
+
+impl fmt::Display for RowGroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parquet(e) => write!(f, "Parquet error while building the range filters: {e}"),
+            Self::Build(e) => write!(f, "unable to build the range filter: {e:?}"),
+            Self::UnsupportedColumn(t) => write!(f, "unsupported physical column type {t}"),
+        }
+    }
+}
+
+impl std::error::Error for RowGroupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parquet(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParquetError> for RowGroupError {
+    fn from(e: ParquetError) -> Self {
+        Self::Parquet(e)
+    }
+}
+
+impl From<BuildError> for RowGroupError {
+    fn from(e: BuildError) -> Self {
+        Self::Build(e)
+    }
+}
+
+/// One [`RangeFilter`] per row group of an integer Parquet column.
+///
+/// Columns with the `INT32` or `INT64` physical type are supported. Values of signed columns are
+/// mapped onto `u64` like `i64` keys with [`ToOrderedU64`], and values of unsigned columns (with
+/// an unsigned integer logical or converted type) are mapped like `u64` keys, so queries should use
+/// ranges of `i64` or `u64` keys respectively. Null values are skipped, and a row group without any
+/// non-null values has no filter and never matches.
+///
+/// Each filter is built with the same false positive rate and maximum range interval, and sized
+/// for the number of distinct values in its row group.
+#[derive(Debug, Clone)]
+pub struct RowGroupFilters {
+    /// The filter of every row group, or `None` if the row group has no non-null values.
+    filters: Vec<Option<RangeFilter>>,
+}
+
+impl RowGroupFilters {
+    /// Builds one filter per row group of the column with index `column` of a Parquet file.
+    ///
+    /// If a filter could not be built for any reason, this function will return a
+    /// [`RowGroupError`].
+    pub fn build<R>(
+        reader: &R,
+        column: usize,
+        epsilon: f64,
+        max_interval: u64,
+    ) -> Result<Self, RowGroupError>
+    where
+        R: FileReader + ?Sized,
+    {
+        let metadata = reader.metadata();
+        let descr = metadata.file_metadata().schema_descr().column(column);
+
+        let filters = (0..metadata.num_row_groups())
+            .map(|i| {
+                let chunk = reader.get_row_group(i)?.get_column_reader(column)?;
+                Self::build_column_chunk(chunk, &descr, epsilon, max_interval)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { filters })
+    }
+
+    /// Builds the filter of a single column chunk from its reader and the descriptor of its
+    /// column, returning `None` if the chunk has no non-null values.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`RowGroupError`].
+    pub fn build_column_chunk(
+        chunk: ColumnReader,
+        descr: &ColumnDescriptor,
+        epsilon: f64,
+        max_interval: u64,
+    ) -> Result<Option<RangeFilter>, RowGroupError> {
+        let unsigned = is_unsigned(descr);
+
+        let mut keys: Vec<u64> = match chunk {
+            ColumnReader::Int32ColumnReader(reader) if unsigned => read_values(reader)?
+                .into_iter()
+                .map(|v| v as u32 as u64)
+                .collect(),
+            ColumnReader::Int32ColumnReader(reader) => read_values(reader)?
+                .iter()
+                .map(ToOrderedU64::to_ordered_u64)
+                .collect(),
+            ColumnReader::Int64ColumnReader(reader) if unsigned => {
+                read_values(reader)?.into_iter().map(|v| v as u64).collect()
+            }
+            ColumnReader::Int64ColumnReader(reader) => read_values(reader)?
+                .iter()
+                .map(ToOrderedU64::to_ordered_u64)
+                .collect(),
+            _ => return Err(RowGroupError::UnsupportedColumn(descr.physical_type())),
+        };
+
+        if keys.is_empty() {
+            return Ok(None);
+        }
+
+        // Repeated values share a hash, so the hasher is sized for the distinct values only.
+        keys.sort_unstable();
+        keys.dedup();
+
+        let hasher = OrderPreservingHasher::new(keys.len(), epsilon, max_interval)
+            .map_err(BuildError::from)?;
+        let filter = RangeFilter::try_new(keys.into_iter(), hasher)?;
+
+        Ok(Some(filter))
+    }
+
+    /// Creates a new `RowGroupFilters` from the filter of every row group, for example after
+    /// loading them with [`RangeFilter::read_from`].
+    pub fn from_filters(filters: Vec<Option<RangeFilter>>) -> Self {
+        Self { filters }
+    }
+
+    /// Returns the indices of the row groups that may contain values within the given range, in
+    /// increasing order.
+    ///
+    /// Each filter is checked with [`RangeFilter::query_chunked`], which splits a range that is
+    /// longer than the maximum range interval, or that crosses a block boundary of the reduced
+    /// universe, into sub-queries that each keep the false positive rate guarantee of the filter.
+    /// A row group that has a value in the range is therefore never skipped. Empty and reversed
+    /// ranges never match any row group.
+    pub fn matching_row_groups<K, R>(&self, range: R) -> Vec<usize>
+    where
+        K: ToOrderedU64,
+        R: RangeBounds<K>,
+    {
+        let start = range.start_bound().map(ToOrderedU64::to_ordered_u64);
+        let end = range.end_bound().map(ToOrderedU64::to_ordered_u64);
+        let Some((start, end)) = range_endpoints(&(start, end)) else {
+            return Vec::new();
+        };
+
+        self.filters
+            .iter()
+            .enumerate()
+            .filter(|(_, filter)| {
+                filter
+                    .as_ref()
+                    .is_some_and(|f| f.query_chunked(start..=end))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the filter of the given row group, or `None` if the row group does not exist or has
+    /// no non-null values.
+    pub fn filter(&self, row_group: usize) -> Option<&RangeFilter> {
+        self.filters.get(row_group)?.as_ref()
+    }
+
+    /// Returns the number of row groups.
+    pub fn num_row_groups(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns the amount of space required to store the filters on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filters.capacity() * std::mem::size_of::<Option<RangeFilter>>()
+            + self
+                .filters
+                .iter()
+                .flatten()
+                .map(RangeFilter::heap_size)
+                .sum::<usize>()
+    }
+}
+
+/// Returns `true` if the integer column has an unsigned logical or converted type.
+fn is_unsigned(descr: &ColumnDescriptor) -> bool {
+    match descr.logical_type() {
+        Some(LogicalType::Integer { is_signed, .. }) => !is_signed,
+        _ => matches!(
+            descr.converted_type(),
+            ConvertedType::UINT_8
+                | ConvertedType::UINT_16
+                | ConvertedType::UINT_32
+                | ConvertedType::UINT_64
+        ),
+    }
+}
+
+/// Reads every non-null value of a column chunk.
+fn read_values<T: DataType>(mut reader: ColumnReaderImpl<T>) -> Result<Vec<T::T>, ParquetError> {
+    let (mut values, mut def_levels, mut rep_levels) = (Vec::new(), Vec::new(), Vec::new());
+
+    loop {
+        let (records, _, _) = reader.read_records(
+            BATCH_SIZE,
+            Some(&mut def_levels),
+            Some(&mut rep_levels),
+            &mut values,
+        )?;
+        if records == 0 {
+            return Ok(values);
+        }
+
+        def_levels.clear();
+        rep_levels.clear();
+    }
+}
@@ -0,0 +1,85 @@
+//! This module contains the [`SharedRangeFilter`] type, a [`RangeFilter`] that can be cloned
+//! without copying its hashes.
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::RangeFilter;
+
+/// A reference-counted [`RangeFilter`] that is cheap to clone.
+///
+/// Cloning a [`RangeFilter`] copies its entire Elias-Fano encoding, which can take hundreds of
+/// megabytes for large key sets. Cloning a `SharedRangeFilter` only increments a reference count,
+/// so a single filter can be handed to many query tasks or threads. Every method of
+/// [`RangeFilter`] that takes `&self` is available through [`Deref`].
+///
+/// ```
+/// use grafite::{OrderPreservingHasher, RangeFilter, SharedRangeFilter};
+///
+/// let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+/// let rf = SharedRangeFilter::new(RangeFilter::new([1, 2, 3, 7].into_iter(), hasher));
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let rf = rf.clone();
+///         std::thread::spawn(move || rf.query(3..5))
+///     })
+///     .collect();
+///
+/// assert!(handles.into_iter().all(|handle| handle.join().unwrap()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedRangeFilter {
+    /// The shared filter.
+    filter: Arc<RangeFilter>,
+}
+
+impl SharedRangeFilter {
+    /// Creates a new `SharedRangeFilter` that owns the given filter.
+    pub fn new(filter: RangeFilter) -> Self {
+        Self {
+            filter: Arc::new(filter),
+        }
+    }
+
+    /// Returns the underlying filter if this is its only handle, or `self` otherwise.
+    pub fn try_unwrap(self) -> Result<RangeFilter, Self> {
+        Arc::try_unwrap(self.filter).map_err(|filter| Self { filter })
+    }
+
+    /// Returns the underlying filter, cloning it if there are other handles to it.
+    pub fn into_inner(self) -> RangeFilter {
+        Arc::unwrap_or_clone(self.filter)
+    }
+
+    /// Returns `true` if both handles share the same filter.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.filter, &other.filter)
+    }
+}
+
+impl Deref for SharedRangeFilter {
+    type Target = RangeFilter;
+
+    fn deref(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
+
+impl AsRef<RangeFilter> for SharedRangeFilter {
+    fn as_ref(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
+
+impl From<RangeFilter> for SharedRangeFilter {
+    fn from(filter: RangeFilter) -> Self {
+        Self::new(filter)
+    }
+}
+
+impl From<Arc<RangeFilter>> for SharedRangeFilter {
+    fn from(filter: Arc<RangeFilter>) -> Self {
+        Self { filter }
+    }
+}
@@ -0,0 +1,287 @@
+//! An AVX2 implementation of the order-preserving hash function, which hashes four keys at once.
+//!
+//! AVX2 has no widening 64-bit multiplication, so every 64-bit product is assembled from the
+//! 32-bit limb products of `_mm256_mul_epu32`, and every unsigned comparison flips the sign bits
+//! before using the signed `_mm256_cmpgt_epi64`. Each step mirrors a step of the scalar hash
+//! function, including the Barrett reductions, so the hashes are identical to the scalar ones.
+
+use std::arch::x86_64::*;
+
+/// The constants of a hash function, in the form used by the vectorized reductions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HashParams {
+    /// The first arbitrary constant.
+    pub(crate) c1: u64,
+    /// The second arbitrary constant.
+    pub(crate) c2: u64,
+    /// The prime `p`.
+    pub(crate) p: u64,
+    /// The Barrett reciprocal of `p`.
+    pub(crate) p_m: u128,
+    /// The size of the reduced universe.
+    pub(crate) r: u64,
+    /// The Barrett reciprocal of `r`.
+    pub(crate) r_m: u64,
+    /// The base 2 logarithm of `r`, if it is a power of two.
+    pub(crate) r_shift: u32,
+}
+
+/// Hashes the keys in `keys` into `out` four at a time, and returns the number of keys hashed.
+///
+/// Fewer than four keys are left at the end, for the scalar hash function to hash.
+///
+/// # Safety
+///
+/// The CPU must support AVX2, and `keys` and `out` must have the same length.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn hash_slice<const MERSENNE: bool, const POW2: bool>(
+    params: &HashParams,
+    keys: &[u64],
+    out: &mut [u64],
+) -> usize {
+    debug_assert_eq!(keys.len(), out.len());
+
+    let consts = Consts::new(params);
+    let chunks = keys.len() / 4;
+    for i in 0..chunks {
+        // SAFETY: `4 * i + 3 < keys.len() == out.len()`, and the loads and stores are unaligned.
+        unsafe {
+            let x = _mm256_loadu_si256(keys.as_ptr().add(4 * i).cast());
+            let h = hash::<MERSENNE, POW2>(&consts, x);
+            _mm256_storeu_si256(out.as_mut_ptr().add(4 * i).cast(), h);
+        }
+    }
+
+    // The remaining keys are left to the scalar hash function.
+    4 * chunks
+}
+
+/// The constants of a hash function, broadcast to every lane.
+struct Consts {
+    c1: __m256i,
+    c2: __m256i,
+    p: __m256i,
+    p_m_lo: __m256i,
+    p_m_hi: __m256i,
+    r: __m256i,
+    r_m: __m256i,
+    r_mask: __m256i,
+    r_shift: __m128i,
+}
+
+impl Consts {
+    #[target_feature(enable = "avx2")]
+    fn new(params: &HashParams) -> Self {
+        Self {
+            c1: splat(params.c1),
+            c2: splat(params.c2),
+            p: splat(params.p),
+            p_m_lo: splat(params.p_m as u64),
+            p_m_hi: splat((params.p_m >> 64) as u64),
+            r: splat(params.r),
+            r_m: splat(params.r_m),
+            r_mask: splat(params.r.wrapping_sub(1)),
+            r_shift: _mm_set_epi64x(0, params.r_shift as i64),
+        }
+    }
+}
+
+/// The order-preserving hash function, see `OrderPreservingHasher::hash_specialized`.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn hash<const MERSENNE: bool, const POW2: bool>(c: &Consts, x: __m256i) -> __m256i {
+    let (inner, y) = div_rem_r::<POW2>(c, x);
+    let q = inner_hash::<MERSENNE, POW2>(c, inner);
+
+    // Compute `(q + y) mod r` without overflowing, since `q < r`.
+    let sum = _mm256_add_epi64(q, y);
+    let wrap = _mm256_or_si256(lt(sum, q), _mm256_xor_si256(lt(sum, c.r), ones()));
+    _mm256_sub_epi64(sum, _mm256_and_si256(wrap, c.r))
+}
+
+/// The inner hash function `((c1 * x + c2) mod p) mod r`, see `OrderPreservingHasher::inner_hash`.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn inner_hash<const MERSENNE: bool, const POW2: bool>(c: &Consts, x: __m256i) -> __m256i {
+    let (lo, hi) = mul_wide(c.c1, x);
+    let (lo, hi) = add_wide(lo, hi, c.c2, _mm256_setzero_si256());
+
+    let y = if MERSENNE {
+        mod_mersenne_61(lo, hi)
+    } else {
+        rem_p(c, lo, hi)
+    };
+
+    div_rem_r::<POW2>(c, y).1
+}
+
+/// Returns `(x / r, x % r)`, see `OrderPreservingHasher::div_rem_r` and `Barrett64::div_rem`.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn div_rem_r<const POW2: bool>(c: &Consts, x: __m256i) -> (__m256i, __m256i) {
+    if POW2 {
+        return (
+            _mm256_srl_epi64(x, c.r_shift),
+            _mm256_and_si256(x, c.r_mask),
+        );
+    }
+
+    // The estimate is at most 2 less than the true quotient.
+    let mut q = mul_wide(x, c.r_m).1;
+    let mut rem = _mm256_sub_epi64(x, mul_lo(q, c.r));
+
+    for _ in 0..2 {
+        let fix = ge(rem, c.r);
+        q = _mm256_sub_epi64(q, fix);
+        rem = _mm256_sub_epi64(rem, _mm256_and_si256(fix, c.r));
+    }
+
+    (q, rem)
+}
+
+/// Returns `y % p` for the 128-bit `y = hi * 2^64 + lo`, see `Barrett128::rem`.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn rem_p(c: &Consts, lo: __m256i, hi: __m256i) -> __m256i {
+    let zero = _mm256_setzero_si256();
+
+    // The high 128 bits of the 256-bit product `y * m`.
+    let (_, ll_hi) = mul_wide(lo, c.p_m_lo);
+    let (lh_lo, lh_hi) = mul_wide(lo, c.p_m_hi);
+    let (hl_lo, hl_hi) = mul_wide(hi, c.p_m_lo);
+    let (hh_lo, hh_hi) = mul_wide(hi, c.p_m_hi);
+
+    let (cross_lo, cross_hi) = add_wide(ll_hi, zero, lh_lo, zero);
+    let (_, cross_hi) = add_wide(cross_lo, cross_hi, hl_lo, zero);
+
+    let (q_lo, q_hi) = add_wide(hh_lo, hh_hi, lh_hi, zero);
+    let (q_lo, q_hi) = add_wide(q_lo, q_hi, hl_hi, zero);
+    let (q_lo, q_hi) = add_wide(q_lo, q_hi, cross_hi, zero);
+
+    // The remainder `y - q * p`, computed with wrapping 128-bit arithmetic.
+    let (qp_lo, qp_hi) = mul_wide(q_lo, c.p);
+    let qp_hi = _mm256_add_epi64(qp_hi, mul_lo(q_hi, c.p));
+    let (mut rem_lo, mut rem_hi) = sub_wide(lo, hi, qp_lo, qp_hi);
+
+    for _ in 0..2 {
+        let high = _mm256_xor_si256(_mm256_cmpeq_epi64(rem_hi, zero), ones());
+        let fix = _mm256_or_si256(high, ge(rem_lo, c.p));
+        (rem_lo, rem_hi) = sub_wide(rem_lo, rem_hi, _mm256_and_si256(fix, c.p), zero);
+    }
+
+    rem_lo
+}
+
+/// Returns `y mod (2^61 - 1)` for the 128-bit `y = hi * 2^64 + lo < 2^125`, see
+/// `utils::mod_mersenne_61`.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn mod_mersenne_61(lo: __m256i, hi: __m256i) -> __m256i {
+    let p = _mm256_set1_epi64x(((1u64 << 61) - 1) as i64);
+
+    // Since `y < 2^125`, `y >> 61` fits in 64 bits, but adding it to `y & p` can carry.
+    let shifted = _mm256_or_si256(_mm256_srli_epi64::<61>(lo), _mm256_slli_epi64::<3>(hi));
+    let low = _mm256_and_si256(lo, p);
+    let sum = _mm256_add_epi64(low, shifted);
+    let carry = _mm256_and_si256(lt(sum, low), _mm256_set1_epi64x(1 << 3));
+
+    // The second fold is less than `2^61 + 2^4`, so it fits in 64 bits.
+    let shifted = _mm256_or_si256(_mm256_srli_epi64::<61>(sum), carry);
+    let y = _mm256_add_epi64(_mm256_and_si256(sum, p), shifted);
+
+    _mm256_sub_epi64(y, _mm256_and_si256(ge(y, p), p))
+}
+
+/// Returns `x` in every lane.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn splat(x: u64) -> __m256i {
+    _mm256_set1_epi64x(x as i64)
+}
+
+/// Returns all bits set in every lane.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn ones() -> __m256i {
+    _mm256_set1_epi64x(-1)
+}
+
+/// Returns a mask of the lanes where `a < b` as unsigned integers.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn lt(a: __m256i, b: __m256i) -> __m256i {
+    let sign = _mm256_set1_epi64x(i64::MIN);
+    _mm256_cmpgt_epi64(_mm256_xor_si256(b, sign), _mm256_xor_si256(a, sign))
+}
+
+/// Returns a mask of the lanes where `a >= b` as unsigned integers.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn ge(a: __m256i, b: __m256i) -> __m256i {
+    _mm256_xor_si256(lt(a, b), ones())
+}
+
+/// Returns the low 64 bits of the product `a * b`.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn mul_lo(a: __m256i, b: __m256i) -> __m256i {
+    let lo_lo = _mm256_mul_epu32(a, b);
+    let lo_hi = _mm256_mul_epu32(a, _mm256_srli_epi64::<32>(b));
+    let hi_lo = _mm256_mul_epu32(_mm256_srli_epi64::<32>(a), b);
+
+    let cross = _mm256_add_epi64(lo_hi, hi_lo);
+    _mm256_add_epi64(lo_lo, _mm256_slli_epi64::<32>(cross))
+}
+
+/// Returns the full 128-bit product `a * b` as its `(low, high)` 64-bit halves.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn mul_wide(a: __m256i, b: __m256i) -> (__m256i, __m256i) {
+    let mask = _mm256_set1_epi64x(u32::MAX as i64);
+    let (a_hi, b_hi) = (_mm256_srli_epi64::<32>(a), _mm256_srli_epi64::<32>(b));
+
+    let lo_lo = _mm256_mul_epu32(a, b);
+    let lo_hi = _mm256_mul_epu32(a, b_hi);
+    let hi_lo = _mm256_mul_epu32(a_hi, b);
+    let hi_hi = _mm256_mul_epu32(a_hi, b_hi);
+
+    // The sum of the middle 32-bit words, which cannot overflow 64 bits.
+    let cross = _mm256_add_epi64(
+        _mm256_srli_epi64::<32>(lo_lo),
+        _mm256_add_epi64(_mm256_and_si256(lo_hi, mask), _mm256_and_si256(hi_lo, mask)),
+    );
+
+    let lo = _mm256_or_si256(
+        _mm256_and_si256(lo_lo, mask),
+        _mm256_slli_epi64::<32>(cross),
+    );
+    let hi = _mm256_add_epi64(
+        _mm256_add_epi64(hi_hi, _mm256_srli_epi64::<32>(cross)),
+        _mm256_add_epi64(
+            _mm256_srli_epi64::<32>(lo_hi),
+            _mm256_srli_epi64::<32>(hi_lo),
+        ),
+    );
+
+    (lo, hi)
+}
+
+/// Returns the wrapping 128-bit sum `a + b` of two numbers given as `(low, high)` halves.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn add_wide(a_lo: __m256i, a_hi: __m256i, b_lo: __m256i, b_hi: __m256i) -> (__m256i, __m256i) {
+    let lo = _mm256_add_epi64(a_lo, b_lo);
+    // The carry mask is `-1` in the lanes that carried.
+    let hi = _mm256_sub_epi64(_mm256_add_epi64(a_hi, b_hi), lt(lo, a_lo));
+    (lo, hi)
+}
+
+/// Returns the wrapping 128-bit difference `a - b` of two numbers given as `(low, high)` halves.
+#[inline]
+#[target_feature(enable = "avx2")]
+fn sub_wide(a_lo: __m256i, a_hi: __m256i, b_lo: __m256i, b_hi: __m256i) -> (__m256i, __m256i) {
+    let lo = _mm256_sub_epi64(a_lo, b_lo);
+    // The borrow mask is `-1` in the lanes that borrowed.
+    let hi = _mm256_add_epi64(_mm256_sub_epi64(a_hi, b_hi), lt(a_lo, b_lo));
+    (lo, hi)
+}
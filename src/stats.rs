@@ -0,0 +1,233 @@
+//! This module contains the [`FilterStats`] type, a snapshot of the internals of a [`RangeFilter`],
+//! the [`MemoryBreakdown`] type, which itemizes the space that a filter uses, and the
+//! [`GapHistogram`] type, which describes how the stored hash values are spread out.
+
+use crate::{OrderPreservingHasher, RangeFilter, RangeFilterBackend};
+
+/// Statistics describing the hash function and encoded hashes of a [`RangeFilter`].
+///
+/// The constants of a keyed hash function are secret, so they are left out of the statistics, which
+/// are often logged. See [`RangeFilter::stats`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FilterStats {
+    /// The number of distinct hash values stored in the filter.
+    pub num_hashes: usize,
+    /// The reduced universe size `r` that keys are hashed into.
+    pub reduced_universe: u64,
+    /// The large prime `p` of the hash function, or `None` if the hash function is keyed.
+    pub prime: Option<u64>,
+    /// The first constant `c1` of the hash function, or `None` if the hash function is keyed.
+    pub c1: Option<u64>,
+    /// The second constant `c2` of the hash function, or `None` if the hash function is keyed.
+    pub c2: Option<u64>,
+    /// The number of bits used to encode the hash values.
+    pub encoded_bits: usize,
+    /// The number of encoded bits per distinct hash value. See [`RangeFilter::bits_per_key`].
+    pub bits_per_key: f64,
+    /// The smallest stored hash value, or `None` if there are none.
+    pub min_hash: Option<u64>,
+    /// The largest stored hash value, or `None` if there are none.
+    pub max_hash: Option<u64>,
+}
+
+/// The space used by each part of a [`RangeFilter`], in bytes.
+///
+/// Parts that a backend does not use, such as the low bits of a [`DenseBitVector`], are `0`.
+/// Backends that do not itemize their space, such as those of other crates, report all of it as
+/// [`Self::other`]. See [`RangeFilter::memory_breakdown`].
+///
+/// [`DenseBitVector`]: crate::DenseBitVector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryBreakdown {
+    /// The packed low bits of an Elias-Fano encoding.
+    pub low_bits: usize,
+    /// The unary encoded high bits of an Elias-Fano encoding.
+    pub high_bits: usize,
+    /// The bits of a dense bit vector.
+    pub bit_vector: usize,
+    /// The select samples of an Elias-Fano encoding, or the rank samples of a dense bit vector.
+    pub index: usize,
+    /// Any other space, such as layout headers.
+    pub other: usize,
+    /// The hash function, which is stored inline rather than on the heap.
+    pub hasher: usize,
+}
+
+impl MemoryBreakdown {
+    /// Returns the total space of every part.
+    pub fn total(&self) -> usize {
+        self.low_bits + self.high_bits + self.bit_vector + self.index + self.other + self.hasher
+    }
+}
+
+/// The distribution of the gaps between consecutive stored hash values of a [`RangeFilter`].
+///
+/// A well-behaved hash function spreads the hash values of random keys out evenly, so the gaps
+/// follow a geometric distribution whose standard deviation is close to its mean. Keys that are
+/// clustered within the maximum range interval keep their clusters after hashing, which shows up as
+/// many small gaps next to a few very large ones, and a [`Self::coefficient_of_variation`] well
+/// above `1`. Ranges that fall into a cluster of hash values are more likely to be false positives.
+///
+/// See [`RangeFilter::gap_histogram`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapHistogram {
+    /// The number of gaps in each power-of-two bucket, where bucket `i` counts the gaps `g` with
+    /// `2^i <= g < 2^(i + 1)`.
+    pub buckets: [usize; 64],
+    /// The number of gaps, which is one less than the number of stored hash values.
+    pub num_gaps: usize,
+    /// The smallest gap, or `None` if there are no gaps.
+    pub min_gap: Option<u64>,
+    /// The largest gap, or `None` if there are no gaps.
+    pub max_gap: Option<u64>,
+    /// The mean gap, or `0` if there are no gaps.
+    pub mean: f64,
+    /// The standard deviation of the gaps, or `0` if there are no gaps.
+    pub std_dev: f64,
+}
+
+impl GapHistogram {
+    /// Computes the histogram of the gaps between the given strictly increasing hash values.
+    fn from_hashes(hashes: impl Iterator<Item = u64>) -> Self {
+        let mut buckets = [0; 64];
+        let (mut min_gap, mut max_gap) = (None::<u64>, None::<u64>);
+        let (mut sum, mut sum_squares) = (0.0, 0.0);
+        let mut num_gaps = 0;
+
+        let mut prev = None;
+        for hash in hashes {
+            if let Some(prev) = prev {
+                let gap: u64 = hash - prev;
+                buckets[gap.ilog2() as usize] += 1;
+                min_gap = Some(min_gap.map_or(gap, |min| min.min(gap)));
+                max_gap = Some(max_gap.map_or(gap, |max| max.max(gap)));
+
+                let gap = gap as f64;
+                sum += gap;
+                sum_squares += gap * gap;
+                num_gaps += 1;
+            }
+            prev = Some(hash);
+        }
+
+        let (mean, std_dev) = if num_gaps == 0 {
+            (0.0, 0.0)
+        } else {
+            let mean = sum / num_gaps as f64;
+            let variance = (sum_squares / num_gaps as f64 - mean * mean).max(0.0);
+            (mean, variance.sqrt())
+        };
+
+        Self {
+            buckets,
+            num_gaps,
+            min_gap,
+            max_gap,
+            mean,
+            std_dev,
+        }
+    }
+
+    /// Returns the ratio of the standard deviation to the mean of the gaps, or `0` if there are no
+    /// gaps.
+    ///
+    /// This is close to `1` for evenly spread hash values, and grows as they become clustered.
+    pub fn coefficient_of_variation(&self) -> f64 {
+        if self.mean == 0.0 {
+            0.0
+        } else {
+            self.std_dev / self.mean
+        }
+    }
+
+    /// Returns the fraction of gaps that are less than `gap`, or `0` if there are no gaps.
+    ///
+    /// Only whole buckets are counted, so `gap` is rounded down to a power of two.
+    pub fn fraction_below(&self, gap: u64) -> f64 {
+        if self.num_gaps == 0 || gap == 0 {
+            return 0.0;
+        }
+
+        let below: usize = self.buckets[..gap.ilog2() as usize].iter().sum();
+        below as f64 / self.num_gaps as f64
+    }
+}
+
+impl<B: RangeFilterBackend> RangeFilter<B> {
+    /// Returns statistics describing the hash function and encoded hashes of this filter.
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new_with_reduced(1 << 10);
+    /// let rf = RangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), hasher);
+    ///
+    /// let stats = rf.stats();
+    /// assert_eq!(stats.num_hashes, rf.iter_hashes().count());
+    /// assert_eq!(stats.reduced_universe, 1 << 10);
+    /// assert!(stats.min_hash <= stats.max_hash);
+    /// ```
+    pub fn stats(&self) -> FilterStats {
+        let num_hashes = self.ef.len();
+        let encoded_bits = 8 * self.heap_size();
+
+        // The constants of a keyed hash function are secret.
+        let public = |constant| (!self.hasher.is_keyed()).then_some(constant);
+
+        let (min_hash, max_hash) = if num_hashes == 0 {
+            (None, None)
+        } else {
+            (Some(self.ef.get(0)), Some(self.ef.get(num_hashes - 1)))
+        };
+
+        FilterStats {
+            num_hashes,
+            reduced_universe: self.hasher.reduced_universe(),
+            prime: public(self.hasher.p()),
+            c1: public(self.hasher.c1()),
+            c2: public(self.hasher.c2()),
+            encoded_bits,
+            bits_per_key: self.bits_per_key(),
+            min_hash,
+            max_hash,
+        }
+    }
+
+    /// Returns the space used by each part of this filter.
+    ///
+    /// Apart from [`MemoryBreakdown::hasher`], the parts add up to [`Self::heap_size`].
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    /// let rf = RangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), hasher);
+    ///
+    /// let breakdown = rf.memory_breakdown();
+    /// assert_eq!(breakdown.total() - breakdown.hasher, rf.heap_size());
+    /// ```
+    pub fn memory_breakdown(&self) -> MemoryBreakdown {
+        MemoryBreakdown {
+            hasher: std::mem::size_of::<OrderPreservingHasher>(),
+            ..self.ef.memory_breakdown()
+        }
+    }
+
+    /// Returns the distribution of the gaps between consecutive stored hash values.
+    ///
+    /// This iterates over every stored hash value, so it takes linear time.
+    ///
+    /// ```
+    /// use grafite::{OrderPreservingHasher, RangeFilter};
+    ///
+    /// let hasher = OrderPreservingHasher::new_with_reduced(1 << 20);
+    /// let rf = RangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), hasher);
+    ///
+    /// let histogram = rf.gap_histogram();
+    /// assert_eq!(histogram.num_gaps, rf.iter_hashes().count() - 1);
+    /// assert_eq!(histogram.buckets.iter().sum::<usize>(), histogram.num_gaps);
+    /// ```
+    pub fn gap_histogram(&self) -> GapHistogram {
+        GapHistogram::from_hashes(self.iter_hashes())
+    }
+}
@@ -0,0 +1,105 @@
+//! This module contains the [`TieredRangeFilter`] type, a composite filter that maintains several
+//! [`RangeFilter`]s tuned for different maximum query intervals.
+
+use std::ops::RangeBounds;
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, OrderPreservingHasher, ParamError, RangeFilter};
+
+/// A composite range filter made of several [`RangeFilter`] tiers over the same keys, each built
+/// for a different maximum range interval `L`.
+///
+/// The space of a [`RangeFilter`] grows with `log(L)`, so a single filter that supports long
+/// ranges pays for them on every key, while a filter built for short ranges loses its false
+/// positive rate guarantee on long ones. A tiered filter routes each query to the tier with the
+/// smallest `L` that covers the length of the queried range, so short and long range scans both
+/// get the guaranteed false positive rate. Ranges longer than the largest tier are split into
+/// sub-queries with [`RangeFilter::query_chunked`].
+///
+/// ```
+/// use grafite::TieredRangeFilter;
+///
+/// let rf = TieredRangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20], 0.01, &[1 << 5, 1 << 16])
+///     .expect("The input parameters should be valid");
+///
+/// assert!(rf.query(3..5));
+/// assert!(!rf.query(10..15));
+/// assert!(rf.query(10..1_000));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TieredRangeFilter {
+    /// The tiers, sorted by strictly increasing maximum range interval.
+    tiers: Vec<RangeFilter>,
+}
+
+impl TieredRangeFilter {
+    /// Creates a new tiered filter over the given keys, with one tier with false positive rate
+    /// `epsilon` for every maximum range interval in `max_intervals`.
+    ///
+    /// If `max_intervals` is empty, this function will return a
+    /// [`ParamError::MissingParameter`]. If any tier could not be built for any reason, this
+    /// function will return a [`BuildError`].
+    pub fn new<I>(keys: I, epsilon: f64, max_intervals: &[u64]) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let keys: Vec<u64> = keys.into_iter().collect();
+        if keys.is_empty() {
+            return Err(BuildError::EmptyInput);
+        }
+
+        let mut max_intervals = max_intervals.to_vec();
+        max_intervals.sort_unstable();
+        max_intervals.dedup();
+        if max_intervals.is_empty() {
+            return Err(ParamError::MissingParameter("max_intervals").into());
+        }
+
+        let tiers = max_intervals
+            .into_iter()
+            .map(|max_interval| {
+                let hasher = OrderPreservingHasher::new(keys.len(), epsilon, max_interval)?;
+                RangeFilter::try_new(keys.iter().copied(), hasher)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { tiers })
+    }
+
+    /// Checks if there are any elements within the given range among the original input set,
+    /// using the tier with the smallest maximum range interval that covers the range.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        self.tier_for(end - start).query_chunked(start..=end)
+    }
+
+    /// Returns the tier that answers ranges spanning `end - start = span`.
+    fn tier_for(&self, span: u64) -> &RangeFilter {
+        // Every tier records its maximum range interval, since it was built with
+        // `OrderPreservingHasher::new`.
+        let covers = |tier: &RangeFilter| tier.max_interval().is_some_and(|l| span < l);
+
+        self.tiers
+            .iter()
+            .find(|tier| covers(tier))
+            .unwrap_or_else(|| &self.tiers[self.tiers.len() - 1])
+    }
+
+    /// Returns the tiers of this filter, sorted by increasing maximum range interval.
+    pub fn tiers(&self) -> &[RangeFilter] {
+        &self.tiers
+    }
+
+    /// Returns the amount of space required to store every tier on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.tiers.iter().map(RangeFilter::heap_size).sum()
+    }
+}
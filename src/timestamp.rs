@@ -0,0 +1,284 @@
+//! This module contains the [`TimestampRangeFilter`] type, a range filter over timestamps, along
+//! with the [`Timestamp`] trait and the [`TimeUnit`] that timestamps are scaled to.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{BuildError, Encoding, OrderPreservingHasher, RangeFilter, RangeFilterBackend};
+
+/// A point in time that can be expressed as a signed number of nanoseconds since the Unix epoch.
+///
+/// This is implemented for [`SystemTime`], for [`Duration`] as the time elapsed since the Unix
+/// epoch, for `chrono::DateTime` behind the `chrono` feature, and for `time::OffsetDateTime`
+/// behind the `time` feature.
+pub trait Timestamp {
+    /// Returns the number of nanoseconds since the Unix epoch, which is negative for earlier
+    /// points in time.
+    fn nanos_since_epoch(&self) -> i128;
+}
+
+impl Timestamp for SystemTime {
+    fn nanos_since_epoch(&self) -> i128 {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_nanos() as i128,
+            Err(e) => -(e.duration().as_nanos() as i128),
+        }
+    }
+}
+
+impl Timestamp for Duration {
+    fn nanos_since_epoch(&self) -> i128 {
+        self.as_nanos() as i128
+    }
+}
+
+impl<T: Timestamp + ?Sized> Timestamp for &T {
+    fn nanos_since_epoch(&self) -> i128 {
+        (**self).nanos_since_epoch()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Timestamp for chrono::DateTime<Tz> {
+    fn nanos_since_epoch(&self) -> i128 {
+        self.timestamp() as i128 * 1_000_000_000 + self.timestamp_subsec_nanos() as i128
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn nanos_since_epoch(&self) -> i128 {
+        self.unix_timestamp_nanos()
+    }
+}
+
+/// The resolution that the keys of a [`TimestampRangeFilter`] are stored at.
+///
+/// Timestamps are rounded down to a whole number of units since the Unix epoch, so a filter cannot
+/// tell apart timestamps within the same unit. Coarser units make the same time range span fewer
+/// keys, which allows a smaller maximum range interval for the hasher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    /// Whole seconds.
+    Seconds,
+    /// Whole milliseconds.
+    Millis,
+    /// Whole microseconds.
+    Micros,
+    /// Whole nanoseconds.
+    Nanos,
+}
+
+impl TimeUnit {
+    /// Returns the number of nanoseconds in one unit.
+    pub fn nanos(self) -> u64 {
+        match self {
+            Self::Seconds => 1_000_000_000,
+            Self::Millis => 1_000_000,
+            Self::Micros => 1_000,
+            Self::Nanos => 1,
+        }
+    }
+
+    /// Maps a timestamp onto a `u64` while preserving its order.
+    ///
+    /// The timestamp is rounded down to a whole number of units since the Unix epoch, clamped to
+    /// the range of an `i64`, and then mapped like a signed key by flipping its sign bit, so
+    /// timestamps before the epoch are ordered before every later timestamp. In nanoseconds, this
+    /// range covers the years 1677 to 2262.
+    ///
+    /// ```
+    /// use grafite::TimeUnit;
+    /// use std::time::Duration;
+    ///
+    /// let a = TimeUnit::Millis.encode(&Duration::from_micros(1_500));
+    /// let b = TimeUnit::Millis.encode(&Duration::from_micros(1_999));
+    /// assert_eq!(a, b);
+    /// assert!(a < TimeUnit::Millis.encode(&Duration::from_millis(2)));
+    /// ```
+    pub fn encode<T: Timestamp + ?Sized>(self, timestamp: &T) -> u64 {
+        self.encode_nanos(timestamp.nanos_since_epoch())
+    }
+
+    /// Maps a number of nanoseconds since the Unix epoch onto a `u64`. See [`Self::encode`].
+    fn encode_nanos(self, nanos: i128) -> u64 {
+        let units = nanos.div_euclid(self.nanos() as i128);
+        let units = units.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+
+        (units as u64) ^ (1 << 63)
+    }
+}
+
+/// A range filter over timestamps of any type that implements [`Timestamp`].
+///
+/// Every timestamp is scaled to the [`TimeUnit`] of the filter and stored in an ordinary
+/// [`RangeFilter`]. Queries take ranges of the native timestamp type and apply the same scaling,
+/// so callers never have to convert units themselves. The maximum range interval of the hasher is
+/// counted in units, so a filter that stores milliseconds and answers queries over ranges of up to
+/// a minute needs a maximum range interval of `60_000`.
+///
+/// ```
+/// use grafite::{OrderPreservingHasher, TimeUnit, TimestampRangeFilter};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let events = [1_700_000_000_123, 1_700_000_042_000, 1_700_000_900_500]
+///     .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+///
+/// let hasher = OrderPreservingHasher::new(events.len(), 0.01, 60_000).unwrap();
+/// let rf = TimestampRangeFilter::new(events.into_iter(), TimeUnit::Millis, hasher);
+///
+/// let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// assert!(rf.query(start..start + Duration::from_secs(60)));
+/// ```
+pub struct TimestampRangeFilter<T = SystemTime, B = Encoding> {
+    /// The filter that the scaled timestamps are stored in.
+    filter: RangeFilter<B>,
+    /// The resolution that the timestamps are scaled to.
+    unit: TimeUnit,
+    /// The type of the timestamps, which is only used to scale them.
+    timestamp: PhantomData<fn() -> T>,
+}
+
+impl<T: Timestamp> TimestampRangeFilter<T> {
+    /// Creates a new `TimestampRangeFilter` given an iterator of timestamps and the unit that
+    /// they are scaled to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter could not be built, for example if there are no input values. See
+    /// [`Self::try_new`] for a fallible version of this function.
+    pub fn new<I>(values: I, unit: TimeUnit, hasher: OrderPreservingHasher) -> Self
+    where
+        I: Iterator<Item = T>,
+    {
+        match Self::try_new(values, unit, hasher) {
+            Ok(filter) => filter,
+            Err(e) => panic!("Unable to build the range filter: {e:?}"),
+        }
+    }
+
+    /// Creates a new `TimestampRangeFilter` given an iterator of timestamps and the unit that
+    /// they are scaled to.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new<I>(
+        values: I,
+        unit: TimeUnit,
+        hasher: OrderPreservingHasher,
+    ) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = T>,
+    {
+        Self::try_with_backend(values, unit, hasher)
+    }
+}
+
+impl<T: Timestamp, B: RangeFilterBackend> TimestampRangeFilter<T, B> {
+    /// Creates a new `TimestampRangeFilter` that stores its scaled timestamps in the backend `B`,
+    /// given an iterator of timestamps and the unit that they are scaled to.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_with_backend<I>(
+        values: I,
+        unit: TimeUnit,
+        hasher: OrderPreservingHasher,
+    ) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = T>,
+    {
+        let values = values.map(|x| unit.encode(&x));
+        let filter = RangeFilter::try_with_backend(values, hasher)?;
+
+        Ok(Self::from_filter(filter, unit))
+    }
+
+    /// Wraps a filter whose keys were scaled with [`TimeUnit::encode`], for example one that was
+    /// loaded with [`RangeFilter::read_from`] after being written from [`Self::filter`].
+    ///
+    /// The unit is not stored in the filter itself, so it must be the same unit that the keys were
+    /// scaled to, otherwise queries may return false negatives.
+    pub fn from_filter(filter: RangeFilter<B>, unit: TimeUnit) -> Self {
+        Self {
+            filter,
+            unit,
+            timestamp: PhantomData,
+        }
+    }
+
+    /// Checks if there are any timestamps within the given range among the original input set.
+    ///
+    /// The range is widened to whole units, so a timestamp in the same unit as an endpoint of the
+    /// range is matched even if it lies just outside of the range. Empty and reversed ranges
+    /// always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<T>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(s) => s.nanos_since_epoch(),
+            Bound::Excluded(s) => s.nanos_since_epoch() + 1,
+            Bound::Unbounded => i128::MIN,
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(e) => e.nanos_since_epoch(),
+            Bound::Excluded(e) => e.nanos_since_epoch() - 1,
+            Bound::Unbounded => i128::MAX,
+        };
+
+        if start > end {
+            return false;
+        }
+
+        let (start, end) = (self.unit.encode_nanos(start), self.unit.encode_nanos(end));
+        self.filter.query(start..=end)
+    }
+
+    /// Returns the resolution that the timestamps of this filter are scaled to.
+    pub fn unit(&self) -> TimeUnit {
+        self.unit
+    }
+
+    /// Returns the hash function used to hash the scaled timestamps of this filter.
+    pub fn hasher(&self) -> &OrderPreservingHasher {
+        &self.filter.hasher
+    }
+
+    /// Returns the filter that the scaled timestamps are stored in.
+    pub fn filter(&self) -> &RangeFilter<B> {
+        &self.filter
+    }
+
+    /// Consumes this filter and returns the filter that the scaled timestamps are stored in.
+    pub fn into_filter(self) -> RangeFilter<B> {
+        self.filter
+    }
+
+    /// Returns the amount of space required to store this `TimestampRangeFilter` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+    }
+}
+
+impl<T, B: fmt::Debug> fmt::Debug for TimestampRangeFilter<T, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimestampRangeFilter")
+            .field("filter", &self.filter)
+            .field("unit", &self.unit)
+            .finish()
+    }
+}
+
+impl<T, B: Clone> Clone for TimestampRangeFilter<T, B> {
+    fn clone(&self) -> Self {
+        Self {
+            filter: self.filter.clone(),
+            unit: self.unit,
+            timestamp: PhantomData,
+        }
+    }
+}
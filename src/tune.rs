@@ -0,0 +1,224 @@
+//! This module contains the [`Tuning`] type, a recommendation for the parameters of a
+//! [`RangeFilter`](crate::RangeFilter) given a memory budget and a sample of the query workload,
+//! and the [`IntervalChoice`] type, a maximum range interval chosen from recorded queries.
+
+use crate::elias_fano;
+use crate::{OrderPreservingHasher, ParamError, RangeFilterBuilder};
+
+/// Recommended parameters for a [`RangeFilter`](crate::RangeFilter), derived from a memory budget,
+/// an estimated number of keys, and a sample of the lengths of the ranges that will be queried.
+///
+/// With a budget of `B` bits per key, the reduced universe holds `2^(B-2)` hash values per key, so
+/// a query over `l` keys is a false positive with probability around `l / 2^(B-2)`. The maximum
+/// interval `L` determines the guarantee: the false positive rate `epsilon = L / 2^(B-2)` holds for
+/// every query of at most `L` keys, and longer queries only keep it for each of their sub-queries
+/// when they are split with [`RangeFilter::query_chunked`](crate::RangeFilter::query_chunked). The
+/// recommendation therefore spends the whole budget, and picks the smallest `L` that covers every
+/// sampled length for which `epsilon < 1` is still possible.
+///
+/// ```
+/// use grafite::Tuning;
+///
+/// // 10 KiB for 4096 keys, queried with ranges of up to 16 keys.
+/// let tuning = Tuning::recommend(10 * 1024, 4096, &[1, 4, 4, 8, 16]).unwrap();
+/// assert_eq!(tuning.max_interval, 16);
+/// assert!(tuning.epsilon < 0.001);
+///
+/// let rf = tuning.builder().build(0..4096).unwrap();
+/// assert!(rf.heap_size() <= 10 * 1024);
+/// assert!(rf.query(100..=100));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    /// The false positive rate that is guaranteed for queries of at most [`Self::max_interval`]
+    /// keys.
+    pub epsilon: f64,
+    /// The recommended maximum range interval.
+    pub max_interval: u64,
+    /// The number of bits per key that the budget allows, before the encoding overheads.
+    pub bits_per_key: u8,
+    /// The expected false positive rate over the sampled queries, including those longer than
+    /// [`Self::max_interval`] when they are answered with
+    /// [`RangeFilter::query_chunked`](crate::RangeFilter::query_chunked).
+    pub expected_false_positive_rate: f64,
+}
+
+impl Tuning {
+    /// Recommends parameters for a filter over about `num_keys` keys that fits in `budget_bytes`
+    /// bytes, for queries whose lengths in keys are distributed like `query_lengths`.
+    ///
+    /// The budget covers the encoded hash values, which make up almost all of the space of a
+    /// filter, and is checked against their Elias-Fano layout, which is never smaller than the
+    /// default [`Encoding`](crate::Encoding). Bits beyond what the 64-bit hash values can make use
+    /// of are not spent, and empty queries of length `0` are ignored.
+    ///
+    /// If `num_keys` is zero, the budget leaves at most 2 bits per key, `query_lengths` contains no
+    /// non-empty queries, or every sampled length is too long for a false positive rate below `1`,
+    /// this function will return a [`ParamError`].
+    pub fn recommend(
+        budget_bytes: usize,
+        num_keys: usize,
+        query_lengths: &[u64],
+    ) -> Result<Self, ParamError> {
+        if num_keys == 0 {
+            return Err(ParamError::NoElements);
+        }
+
+        // The reduced universe `num_keys * 2^(B-2)` must fit in the 64-bit universe, and its
+        // encoding, including the select samples and header, must fit in the budget.
+        let fits = |bits_per_key: u8| {
+            let r = (num_keys as u128) << (bits_per_key - 2);
+            r <= u64::MAX as u128
+                && elias_fano::layout_words(num_keys, r as u64 - 1) * 8 <= budget_bytes
+        };
+        let mut bits_per_key = (budget_bytes as u128 * 8 / num_keys as u128).min(64) as u8;
+        while bits_per_key > 2 && !fits(bits_per_key) {
+            bits_per_key -= 1;
+        }
+        if bits_per_key <= 2 {
+            return Err(ParamError::Overflow);
+        }
+        let per_key = (1u64 << (bits_per_key - 2)) as f64;
+
+        let lengths = || query_lengths.iter().copied().filter(|&len| len > 0);
+        if lengths().next().is_none() {
+            return Err(ParamError::MissingParameter("query_lengths"));
+        }
+
+        let max_interval = lengths()
+            .filter(|&len| (len as f64) < per_key)
+            .max()
+            .ok_or(ParamError::InvalidMaxInterval(per_key as u64 - 1))?;
+        let epsilon = OrderPreservingHasher::epsilon_with_budget(bits_per_key, max_interval)?;
+
+        let (count, total) = lengths().fold((0usize, 0.0), |(count, total), len| {
+            (count + 1, total + (len as f64 / per_key).min(1.0))
+        });
+
+        Ok(Self {
+            epsilon,
+            max_interval,
+            bits_per_key,
+            expected_false_positive_rate: total / count as f64,
+        })
+    }
+
+    /// Returns a [`RangeFilterBuilder`] with the recommended `bits_per_key` and `max_interval`.
+    pub fn builder(&self) -> RangeFilterBuilder {
+        RangeFilterBuilder::new()
+            .bits_per_key(self.bits_per_key)
+            .max_interval(self.max_interval)
+    }
+}
+
+/// A maximum range interval chosen to cover a quantile of the lengths of recorded queries, along
+/// with how the remaining, longer queries would be answered.
+///
+/// Queries longer than the maximum interval `L` never return false negatives from
+/// [`RangeFilter::query`], but their false positive rate is not guaranteed and grows with their
+/// length. [`RangeFilter::query_chunked`] instead splits a query of `m` keys into about
+/// `ceil(m / L)` sub-queries that each keep the guarantees of the filter, plus at most two more
+/// where it crosses a block boundary of the reduced universe, so its false positive rate is at
+/// most that many times that of the filter. [`Self::max_chunks`] and [`Self::mean_chunks`]
+/// describe that cost over the recorded queries.
+///
+/// ```
+/// use grafite::IntervalChoice;
+///
+/// // 98 point lookups and two scans.
+/// let mut queries = vec![(7, 7); 98];
+/// queries.extend([(0, 99), (1000, 1999)]);
+///
+/// let choice = IntervalChoice::from_queries(queries, 0.98).unwrap();
+/// assert_eq!(choice.max_interval, 1);
+/// assert_eq!((choice.num_queries, choice.num_longer), (100, 2));
+/// assert_eq!(choice.max_chunks, 1000);
+///
+/// let choice = IntervalChoice::from_queries([(7, 7), (0, 99), (1000, 1999)], 0.99).unwrap();
+/// assert_eq!(choice.max_interval, 1000);
+/// assert_eq!(choice.num_longer, 0);
+/// ```
+///
+/// [`RangeFilter::query`]: crate::RangeFilter::query
+/// [`RangeFilter::query_chunked`]: crate::RangeFilter::query_chunked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntervalChoice {
+    /// The recommended maximum range interval, which is the smallest length that at least the
+    /// requested quantile of the recorded queries do not exceed.
+    pub max_interval: u64,
+    /// The number of non-empty recorded queries.
+    pub num_queries: usize,
+    /// The number of recorded queries longer than [`Self::max_interval`], which only keep the false
+    /// positive rate guarantee if they are split into sub-queries.
+    pub num_longer: usize,
+    /// The length of the longest recorded query, saturated at [`u64::MAX`].
+    pub longest: u64,
+    /// The number of sub-queries of at most [`Self::max_interval`] keys that the longest recorded
+    /// query is split into, not counting the up to two extra sub-queries that
+    /// [`RangeFilter::query_chunked`] cuts at block boundaries.
+    ///
+    /// [`RangeFilter::query_chunked`]: crate::RangeFilter::query_chunked
+    pub max_chunks: u64,
+    /// The average number of sub-queries of at most [`Self::max_interval`] keys that each recorded
+    /// query is split into, which is `1.0` if no query is longer than [`Self::max_interval`]. Like
+    /// [`Self::max_chunks`], this does not count the sub-queries that
+    /// [`RangeFilter::query_chunked`] cuts at block boundaries.
+    ///
+    /// [`RangeFilter::query_chunked`]: crate::RangeFilter::query_chunked
+    pub mean_chunks: f64,
+}
+
+impl IntervalChoice {
+    /// Chooses a maximum range interval that covers the `quantile` of the lengths of the given
+    /// queries, such as `0.99` for the 99th percentile.
+    ///
+    /// Each query is given as its inclusive `(start, end)` endpoints, like in
+    /// [`RangeFilter::query_many`](crate::RangeFilter::query_many). Empty and reversed ranges are
+    /// ignored, and if no queries remain, this function returns `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quantile` is not in `(0, 1]`.
+    pub fn from_queries<I>(queries: I, quantile: f64) -> Option<Self>
+    where
+        I: IntoIterator<Item = (u64, u64)>,
+    {
+        assert!(
+            quantile > 0.0 && quantile <= 1.0,
+            "quantile must be in (0, 1], but was {quantile}"
+        );
+
+        let mut lengths: Vec<u64> = queries
+            .into_iter()
+            .filter(|&(start, end)| start <= end)
+            .map(|(start, end)| (end - start).saturating_add(1))
+            .collect();
+        lengths.sort_unstable();
+
+        let num_queries = lengths.len();
+        let longest = *lengths.last()?;
+
+        // The nearest-rank quantile, which is always one of the recorded lengths.
+        let rank = ((quantile * num_queries as f64).ceil() as usize).clamp(1, num_queries);
+        let max_interval = lengths[rank - 1];
+        let num_longer = num_queries - lengths.partition_point(|&len| len <= max_interval);
+
+        let chunks = |len: u64| len.div_ceil(max_interval);
+        let total_chunks: f64 = lengths.iter().map(|&len| chunks(len) as f64).sum();
+
+        Some(Self {
+            max_interval,
+            num_queries,
+            num_longer,
+            longest,
+            max_chunks: chunks(longest),
+            mean_chunks: total_chunks / num_queries as f64,
+        })
+    }
+
+    /// Returns the fraction of the recorded queries that are no longer than
+    /// [`Self::max_interval`].
+    pub fn covered_fraction(&self) -> f64 {
+        (self.num_queries - self.num_longer) as f64 / self.num_queries as f64
+    }
+}
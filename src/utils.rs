@@ -1,4 +1,4 @@
-//! Utility and helper functions for hashing and prime number generation.
+//! Utility and helper functions for hashing, prime number generation, and bit manipulation.
 
 use rand::prelude::*;
 use std::ops::Range;
@@ -11,8 +11,143 @@ const ITERATIONS: usize = 128;
 /// # Panics
 ///
 /// Panics if the range is empty.
-pub fn gen_random(range: Range<u64>) -> u64 {
-    rand::thread_rng().gen_range(range)
+pub fn gen_random<R: Rng + ?Sized>(rng: &mut R, range: Range<u64>) -> u64 {
+    rng.gen_range(range)
+}
+
+/// Reduces a number modulo the Mersenne prime `2^61 - 1` without a division.
+///
+/// The input must be less than `2^125`, which always holds for `c1 * x + c2` when `c1, c2 < 2^61`
+/// and `x < 2^64`.
+pub fn mod_mersenne_61(y: u128) -> u64 {
+    const P: u128 = (1 << 61) - 1;
+
+    // Since `2^61 = 1 (mod P)`, the high bits can be folded onto the low bits.
+    let y = (y & P) + (y >> 61);
+    let y = (y & P) + (y >> 61);
+    let y = y as u64;
+
+    if y >= P as u64 {
+        y - P as u64
+    } else {
+        y
+    }
+}
+
+/// The SplitMix64 finalizer, used to spread the bits of a key before fingerprinting or probing.
+pub fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Precomputed constants for dividing 64-bit numbers by a fixed divisor with Barrett reduction.
+///
+/// Dividing by a runtime value is one of the slowest integer instructions, so this replaces it with
+/// a multiplication by a precomputed reciprocal and at most two correcting subtractions.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct Barrett64 {
+    /// The divisor.
+    pub(crate) d: u64,
+    /// The reciprocal `floor((2^64 - 1) / d)`.
+    pub(crate) m: u64,
+}
+
+impl Barrett64 {
+    /// Precomputes the constants for dividing by `d`.
+    ///
+    /// If `d` is zero, the constants are meaningless and [`Self::div_rem`] returns an unspecified
+    /// result instead of panicking.
+    pub fn new(d: u64) -> Self {
+        Self {
+            d,
+            m: u64::MAX.checked_div(d).unwrap_or(0),
+        }
+    }
+
+    /// Returns the quotient and remainder `(x / d, x % d)`.
+    pub fn div_rem(&self, x: u64) -> (u64, u64) {
+        // The estimate is at most 2 less than the true quotient.
+        let mut q = ((x as u128 * self.m as u128) >> 64) as u64;
+        let mut rem = x - q * self.d;
+
+        for _ in 0..2 {
+            if rem >= self.d {
+                q += 1;
+                rem -= self.d;
+            }
+        }
+
+        (q, rem)
+    }
+}
+
+/// Precomputed constants for reducing 128-bit numbers modulo a fixed 64-bit divisor with Barrett
+/// reduction.
+///
+/// See [`Barrett64`] for more information.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
+pub struct Barrett128 {
+    /// The divisor.
+    pub(crate) d: u64,
+    /// The reciprocal `floor((2^128 - 1) / d)`.
+    pub(crate) m: u128,
+}
+
+impl Barrett128 {
+    /// Precomputes the constants for reducing modulo `d`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d` is zero.
+    pub fn new(d: u64) -> Self {
+        Self {
+            d,
+            m: u128::MAX / d as u128,
+        }
+    }
+
+    /// Returns the remainder `y % d`.
+    pub fn rem(&self, y: u128) -> u64 {
+        let d = self.d as u128;
+
+        // The estimate is at most 2 less than the true quotient, so the remainder computed from it
+        // is less than `3 * d` and the wrapping arithmetic is exact.
+        let q = mul_hi_128(y, self.m);
+        let mut rem = y.wrapping_sub(q.wrapping_mul(d));
+
+        for _ in 0..2 {
+            if rem >= d {
+                rem -= d;
+            }
+        }
+
+        rem as u64
+    }
+}
+
+/// Returns the high 128 bits of the full 256-bit product `a * b`.
+fn mul_hi_128(a: u128, b: u128) -> u128 {
+    let (a_lo, a_hi) = (a as u64 as u128, a >> 64);
+    let (b_lo, b_hi) = (b as u64 as u128, b >> 64);
+
+    let lo_lo = a_lo * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_lo = a_hi * b_lo;
+    let hi_hi = a_hi * b_hi;
+
+    // The sum of the middle 64-bit words, which cannot overflow 128 bits.
+    let cross = (lo_lo >> 64) + (lo_hi as u64 as u128) + (hi_lo as u64 as u128);
+
+    hi_hi + (lo_hi >> 64) + (hi_lo >> 64) + (cross >> 64)
 }
 
 /// Deterministically checks if a number is prime.
@@ -38,18 +173,31 @@ pub fn is_prime(n: u64) -> bool {
 /// # Panics
 ///
 /// Panics if the range is empty.
-pub fn gen_prime(range: Range<u64>) -> u64 {
-    let mut rng = rand::thread_rng();
-
+pub fn gen_prime<R: Rng + ?Sized>(rng: &mut R, range: Range<u64>) -> u64 {
     loop {
         let attempt = rng.gen_range(range.clone());
 
-        if miller_rabin::is_prime(&attempt, ITERATIONS) {
+        if is_probable_prime(attempt) {
             return attempt;
         }
     }
 }
 
+/// Checks if a number is (potentially) prime with the Miller-Rabin primality test.
+pub fn is_probable_prime(n: u64) -> bool {
+    miller_rabin::is_prime(&n, ITERATIONS)
+}
+
+/// Returns the position of the `rank`-th set bit (counting from zero) of `word`, which must have
+/// more than `rank` set bits.
+pub fn select_in_word(mut word: u64, rank: usize) -> usize {
+    // Clear the lowest `rank` set bits, after which the lowest set bit is the answer.
+    for _ in 0..rank {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +208,53 @@ mod tests {
 
         assert!(primes.iter().copied().all(is_prime));
     }
+
+    #[test]
+    fn test_mod_mersenne_61() {
+        const P: u128 = (1 << 61) - 1;
+
+        let inputs = [
+            0,
+            1,
+            P - 1,
+            P,
+            P + 1,
+            2 * P,
+            u64::MAX as u128,
+            (1 << 125) - 1,
+        ];
+        for y in inputs {
+            assert_eq!(mod_mersenne_61(y) as u128, y % P);
+        }
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let y = rng.gen::<u128>() >> 3;
+            assert_eq!(mod_mersenne_61(y) as u128, y % P);
+        }
+    }
+
+    #[test]
+    fn test_barrett() {
+        let mut rng = rand::thread_rng();
+
+        let mut divisors = vec![1, 2, 3, 7, (1 << 61) - 1, u64::MAX - 1, u64::MAX];
+        divisors.extend((0..100).map(|_| rng.gen_range(1..u64::MAX)));
+
+        for d in divisors {
+            let b64 = Barrett64::new(d);
+            let b128 = Barrett128::new(d);
+
+            let mut inputs = vec![0, 1, d as u128 - 1, d as u128, u64::MAX as u128, u128::MAX];
+            inputs.extend([(d as u128) << 64, ((d as u128) << 64) - 1]);
+            inputs.extend((0..100).map(|_| rng.gen::<u128>()));
+
+            for y in inputs {
+                assert_eq!(b128.rem(y) as u128, y % d as u128);
+
+                let x = y as u64;
+                assert_eq!(b64.div_rem(x), (x / d, x % d));
+            }
+        }
+    }
 }
@@ -0,0 +1,61 @@
+//! Support for range filtering [`Uuid`] keys by their timestamp, with the `uuid` feature.
+//!
+//! UUIDv7 identifiers and ULIDs both start with a 48-bit big-endian Unix timestamp in
+//! milliseconds, so their order is the order of their creation time. A ULID can be used as a
+//! [`Uuid`] with `Uuid::from_u128(ulid.0)`, since both are 128-bit values with the same layout.
+
+use std::ops::RangeBounds;
+
+use uuid::Uuid;
+
+use crate::filter::range_endpoints;
+use crate::{KeyedRangeFilter, RangeFilterBackend, ToOrderedU64};
+
+/// The largest timestamp in milliseconds that fits in the 48 timestamp bits of a UUID.
+const MAX_TIMESTAMP_MILLIS: u64 = (1 << 48) - 1;
+
+/// Maps the UUID onto its leading 48 bits, which are the Unix timestamp in milliseconds of UUIDv7
+/// identifiers and ULIDs.
+///
+/// Every UUID created in the same millisecond is mapped onto the same key, so a filter over UUIDs
+/// answers whether any key was created in a range of time, and the maximum range interval of its
+/// hasher is measured in milliseconds. This is also monotone for every other UUID version, whose
+/// leading bits are random or derived from a hash.
+impl ToOrderedU64 for Uuid {
+    fn to_ordered_u64(&self) -> u64 {
+        (self.as_u128() >> 80) as u64
+    }
+}
+
+impl<B: RangeFilterBackend> KeyedRangeFilter<Uuid, B> {
+    /// Checks if any UUIDv7 or ULID key among the original input set may have been created in the
+    /// given range of Unix timestamps in milliseconds.
+    ///
+    /// Timestamps that do not fit in 48 bits are clamped to the largest one that does. Empty and
+    /// reversed ranges always return `false`.
+    ///
+    /// ```
+    /// use grafite::{KeyedRangeFilter, OrderPreservingHasher};
+    /// use uuid::Uuid;
+    ///
+    /// // A UUIDv7 created at the given timestamp, with arbitrary random bits.
+    /// let v7 = |millis: u64| Uuid::from_u128(((millis as u128) << 80) | (0x7 << 76) | 0x1234);
+    ///
+    /// let keys = [v7(1_700_000_000_000), v7(1_700_000_060_000)];
+    /// let hasher = OrderPreservingHasher::new(keys.len(), 0.01, 1000).unwrap();
+    /// let rf = KeyedRangeFilter::new(keys.into_iter(), hasher);
+    ///
+    /// assert!(rf.query_millis(1_700_000_059_500..1_700_000_060_500));
+    /// ```
+    pub fn query_millis<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        match range_endpoints(&range) {
+            Some((start, end)) if start <= MAX_TIMESTAMP_MILLIS => {
+                self.filter().query(start..=end.min(MAX_TIMESTAMP_MILLIS))
+            }
+            _ => false,
+        }
+    }
+}
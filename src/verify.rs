@@ -0,0 +1,162 @@
+//! This module contains the [`VerifiedRangeFilter`] type, a [`RangeFilter`] that keeps the exact
+//! set of its keys to cross-check its answers.
+
+use std::ops::{Deref, RangeBounds};
+
+use crate::filter::range_endpoints;
+use crate::{BuildError, OrderPreservingHasher, RangeFilter};
+
+/// A [`RangeFilter`] along with a sorted copy of the keys it was built over, for qualifying the
+/// filter before it is used in production.
+///
+/// [`Self::query_verified`] answers a query with [`RangeFilter::query_chunked`] and checks the
+/// answer against the exact keys, panicking on any false negative. Keeping the keys takes 64 bits
+/// per key, far more than the filter itself, so this is only meant for testing. Every method of
+/// [`RangeFilter`] that takes `&self` is available through [`Deref`].
+///
+/// ```
+/// use grafite::RangeFilterBuilder;
+///
+/// let rf = RangeFilterBuilder::new()
+///     .epsilon(0.01)
+///     .max_interval(20)
+///     .build_verified([1, 2, 3, 7, 8, 9, 15, 20])
+///     .unwrap();
+///
+/// assert!(rf.query_verified(3..5));
+/// assert!(rf.is_false_positive(10..15) || !rf.query_verified(10..15));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VerifiedRangeFilter {
+    /// The verified filter.
+    filter: RangeFilter,
+    /// The sorted and deduplicated keys that the filter was built over.
+    keys: Vec<u64>,
+}
+
+impl VerifiedRangeFilter {
+    /// Wraps a filter along with the keys it was built over, which are sorted and deduplicated.
+    pub fn new(filter: RangeFilter, mut keys: Vec<u64>) -> Self {
+        keys.sort_unstable();
+        keys.dedup();
+
+        Self { filter, keys }
+    }
+
+    /// Builds a filter over the given keys with the given hasher, keeping a sorted copy of them.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new(keys: Vec<u64>, hasher: OrderPreservingHasher) -> Result<Self, BuildError> {
+        let filter = RangeFilter::try_new(keys.iter().copied(), hasher)?;
+
+        Ok(Self::new(filter, keys))
+    }
+
+    /// Checks if there are any elements within the given range among the original input set, and
+    /// verifies the answer against the exact keys.
+    ///
+    /// The range is answered with [`RangeFilter::query_chunked`], which is the same as
+    /// [`RangeFilter::query`] for ranges of at most [`RangeFilter::max_interval`] keys within a
+    /// single block of the reduced universe. Other ranges are split into sub-queries of at most
+    /// that many keys that each lie within a single block, so the panic message can point at the
+    /// sub-query and the single window of hashes that should have contained the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter answers `false` for a range that contains a key. The panic message
+    /// contains the range, the offending key and the hashes involved, along with the
+    /// [`RangeFilter::query_explain`] of the sub-query that contains the key.
+    pub fn query_verified<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        let answer = self.filter.query_chunked(start..=end);
+        if !answer {
+            if let Some(key) = self.first_key_in(start, end) {
+                let (chunk_start, chunk_end) = self.chunk_of(start, end, key);
+                let hasher = &self.filter.hasher;
+                panic!(
+                    "false negative for the range {start}..={end}, which contains the key {key}: \
+                     the key hashes to {}, the sub-query {chunk_start}..={chunk_end} hashes to \
+                     {}..={} with {hasher:?}, and it was explained as {:?}",
+                    hasher.hash(key),
+                    hasher.hash(chunk_start),
+                    hasher.hash(chunk_end),
+                    self.filter.query_explain(chunk_start..=chunk_end),
+                );
+            }
+        }
+
+        answer
+    }
+
+    /// Returns `true` if the filter answers `true` for the given range, but it contains none of
+    /// the keys. Like in [`Self::query_verified`], the range is answered with
+    /// [`RangeFilter::query_chunked`].
+    pub fn is_false_positive<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u64>,
+    {
+        let Some((start, end)) = range_endpoints(&range) else {
+            return false;
+        };
+
+        self.filter.query_chunked(start..=end) && self.first_key_in(start, end).is_none()
+    }
+
+    /// Returns the sub-query of [`RangeFilter::query_chunked`] over `start..=end` that contains
+    /// `key`.
+    fn chunk_of(&self, start: u64, end: u64, key: u64) -> (u64, u64) {
+        let Some(max_interval) = self.filter.max_interval().filter(|&l| l > 0) else {
+            return (start, end);
+        };
+
+        // The sub-queries start over at the first key of every block, so the sub-query of the key
+        // is found by stepping from the first key of its block that lies within the range.
+        let r = self.filter.hasher.reduced_universe();
+        let block_start = (key - key % r).max(start);
+        let block_end = (key - key % r).saturating_add(r - 1);
+
+        let chunk_start = block_start + (key - block_start) / max_interval * max_interval;
+        let chunk_end = chunk_start
+            .saturating_add(max_interval - 1)
+            .min(block_end)
+            .min(end);
+        (chunk_start, chunk_end)
+    }
+
+    /// Returns the smallest key within the inclusive range `start..=end`, if any.
+    fn first_key_in(&self, start: u64, end: u64) -> Option<u64> {
+        let i = self.keys.partition_point(|&key| key < start);
+        self.keys.get(i).copied().filter(|&key| key <= end)
+    }
+
+    /// Returns the sorted and deduplicated keys that the filter was built over.
+    pub fn keys(&self) -> &[u64] {
+        &self.keys
+    }
+
+    /// Consumes this `VerifiedRangeFilter`, returning the filter and discarding the keys.
+    pub fn into_filter(self) -> RangeFilter {
+        self.filter
+    }
+}
+
+impl Deref for VerifiedRangeFilter {
+    type Target = RangeFilter;
+
+    fn deref(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
+
+impl AsRef<RangeFilter> for VerifiedRangeFilter {
+    fn as_ref(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
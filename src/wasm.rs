@@ -0,0 +1,117 @@
+//! JavaScript bindings for building, serializing, and querying filters with `wasm-bindgen`.
+//!
+//! These bindings let browser and edge-runtime applications ship filters that were built ahead of
+//! time, and probe them client-side. Filters built through the bindings always derive their hash
+//! function from a caller-provided seed, so neither building nor querying a filter requires a
+//! source of operating system randomness.
+//!
+//! All keys are `u64` values, which are passed as `BigInt`s from JavaScript.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{OrderPreservingHasher, RangeFilter};
+
+/// A [`RangeFilter`] over `u64` keys that can be used from JavaScript, where it is exported as
+/// `RangeFilter`.
+#[wasm_bindgen(js_name = RangeFilter)]
+#[derive(Debug, Clone)]
+pub struct WasmRangeFilter {
+    /// The wrapped filter.
+    filter: RangeFilter,
+}
+
+#[wasm_bindgen(js_class = RangeFilter)]
+impl WasmRangeFilter {
+    /// Builds a filter over `keys` for the given false positive rate and maximum range interval.
+    ///
+    /// The hash function is derived deterministically from `seed`, so building the same keys with
+    /// the same parameters always produces the same filter.
+    pub fn build(
+        keys: &[u64],
+        epsilon: f64,
+        max_interval: u64,
+        seed: u64,
+    ) -> Result<WasmRangeFilter, JsError> {
+        let hasher = OrderPreservingHasher::new_seeded(keys.len(), epsilon, max_interval, seed)
+            .map_err(|e| JsError::new(&format!("invalid filter parameters: {e:?}")))?;
+        let filter = RangeFilter::try_new(keys.iter().copied(), hasher)
+            .map_err(|e| JsError::new(&format!("unable to build the range filter: {e:?}")))?;
+
+        Ok(Self { filter })
+    }
+
+    /// Reads a filter that was written with [`Self::serialize`] or [`RangeFilter::write_to`].
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmRangeFilter, JsError> {
+        let filter = RangeFilter::read_from(bytes).map_err(|e| JsError::new(&e.to_string()))?;
+
+        Ok(Self { filter })
+    }
+
+    /// Writes the filter into a byte array in the format of [`RangeFilter::write_to`].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.filter
+            .write_to(&mut bytes)
+            .expect("writing into a `Vec` cannot fail");
+
+        bytes
+    }
+
+    /// Checks if there may be any keys in the inclusive range `start..=end`.
+    pub fn query(&self, start: u64, end: u64) -> bool {
+        self.filter.query(start..=end)
+    }
+
+    /// Checks if the given key may be among the original input set.
+    pub fn contains(&self, key: u64) -> bool {
+        self.filter.contains(key)
+    }
+
+    /// Checks many inclusive ranges at once, given as parallel arrays of their endpoints.
+    ///
+    /// Returns one byte per range, which is `1` if the range may contain a key and `0` otherwise.
+    #[wasm_bindgen(js_name = queryMany)]
+    pub fn query_many(&self, starts: &[u64], ends: &[u64]) -> Result<Vec<u8>, JsError> {
+        if starts.len() != ends.len() {
+            return Err(JsError::new(
+                "`starts` and `ends` must have the same length",
+            ));
+        }
+
+        Ok(starts
+            .iter()
+            .zip(ends)
+            .map(|(&start, &end)| self.filter.query(start..=end) as u8)
+            .collect())
+    }
+
+    /// Returns the number of distinct hash values stored in the filter.
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.filter.ef.len()
+    }
+
+    /// Returns `true` if the filter stores no hash values.
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.filter.ef.is_empty()
+    }
+
+    /// Returns the amount of space required to store the filter on the heap.
+    #[wasm_bindgen(js_name = heapSize)]
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+    }
+}
+
+impl WasmRangeFilter {
+    /// Wraps a filter so it can be handed to JavaScript.
+    pub fn from_filter(filter: RangeFilter) -> Self {
+        Self { filter }
+    }
+
+    /// Returns the wrapped filter.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+}
@@ -0,0 +1,138 @@
+//! This module contains the [`RangeFilter128`] type, a range filter over 128-bit keys.
+
+use std::ops::{Bound, RangeBounds};
+
+use crate::hash::HashedRange;
+use crate::{BuildError, OrderPreservingHasher, RangeFilter};
+
+/// A range filter over 128-bit keys, such as composite timestamps or UUIDv7 identifiers.
+///
+/// Truncating such keys to 64 bits destroys their ordering, so this filter hashes the full keys
+/// with [`OrderPreservingHasher::hash_u128`] instead. The hash values still fit in 64 bits, since
+/// the reduced universe size only depends on the number of keys, the false positive rate, and the
+/// maximum range interval, so they are stored in an ordinary [`RangeFilter`].
+///
+/// The hasher should be created with [`OrderPreservingHasher::new_u128`], which checks the maximum
+/// range interval against the 128-bit universe of keys.
+///
+/// ```
+/// use grafite::{OrderPreservingHasher, RangeFilter128};
+///
+/// let keys = [1u128 << 100, (1 << 100) + 7, u128::MAX - 3];
+/// let hasher = OrderPreservingHasher::new_u128(keys.len(), 0.01, 64).unwrap();
+/// let rf = RangeFilter128::new(keys.into_iter(), hasher);
+///
+/// assert!(rf.query((1 << 100) + 5..(1 << 100) + 10));
+/// assert!(rf.query(u128::MAX - 10..));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RangeFilter128 {
+    /// The filter that the 128-bit hash values are stored in.
+    filter: RangeFilter,
+}
+
+impl RangeFilter128 {
+    /// Creates a new `RangeFilter128` given an iterator of 128-bit keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter could not be built, for example if there are no input values. See
+    /// [`Self::try_new`] for a fallible version of this function.
+    pub fn new<I>(values: I, hasher: OrderPreservingHasher) -> Self
+    where
+        I: Iterator<Item = u128>,
+    {
+        match Self::try_new(values, hasher) {
+            Ok(filter) => filter,
+            Err(e) => panic!("Unable to build the range filter: {e:?}"),
+        }
+    }
+
+    /// Creates a new `RangeFilter128` given an iterator of 128-bit keys.
+    ///
+    /// If the filter could not be built for any reason, this function will return a
+    /// [`BuildError`].
+    pub fn try_new<I>(values: I, hasher: OrderPreservingHasher) -> Result<Self, BuildError>
+    where
+        I: Iterator<Item = u128>,
+    {
+        if hasher.reduced_universe() == 0 {
+            return Err(BuildError::InvalidHasher);
+        }
+
+        let hashes = values.map(|x| hasher.hash_u128(x)).collect();
+        let filter = RangeFilter::from_hashes(hashes, hasher)?;
+
+        Ok(Self { filter })
+    }
+
+    /// Wraps a filter whose hash values were produced by [`OrderPreservingHasher::hash_u128`], for
+    /// example one that was loaded with [`RangeFilter::read_from`] after being written from
+    /// [`Self::filter`].
+    ///
+    /// If the hash values were produced in any other way, queries may return false negatives.
+    pub fn from_filter(filter: RangeFilter) -> Self {
+        Self { filter }
+    }
+
+    /// Checks if there are any elements within the given range among the original input set.
+    ///
+    /// Empty and reversed ranges always return `false`.
+    pub fn query<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u128>,
+    {
+        let Some((start, end)) = range_endpoints_128(&range) else {
+            return false;
+        };
+
+        let filter = &self.filter;
+        match filter.hasher.hash_range_u128(start, end) {
+            HashedRange::One(start_hash, end_hash) => filter.query_prehashed(start_hash, end_hash),
+            HashedRange::Two((start1, end1), (start2, end2)) => {
+                filter.query_prehashed(start1, end1) || filter.query_prehashed(start2, end2)
+            }
+            HashedRange::All => true,
+        }
+    }
+
+    /// Returns the hash function used to hash the keys of this filter.
+    pub fn hasher(&self) -> &OrderPreservingHasher {
+        &self.filter.hasher
+    }
+
+    /// Returns the filter that the hash values are stored in.
+    ///
+    /// Its queries take 64-bit keys, so it should only be used to inspect or persist the hash
+    /// values, and not to answer queries directly.
+    pub fn filter(&self) -> &RangeFilter {
+        &self.filter
+    }
+
+    /// Returns the amount of space required to store this `RangeFilter128` on the heap.
+    pub fn heap_size(&self) -> usize {
+        self.filter.heap_size()
+    }
+}
+
+/// Returns the inclusive endpoints of a range of 128-bit keys, or `None` if it is empty.
+///
+/// See [`range_endpoints`](crate::range_endpoints) for more information.
+fn range_endpoints_128<R>(range: &R) -> Option<(u128, u128)>
+where
+    R: RangeBounds<u128>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&s) => s.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&e) => e.checked_sub(1)?,
+        Bound::Unbounded => u128::MAX,
+    };
+
+    (start <= end).then_some((start, end))
+}
@@ -0,0 +1,201 @@
+//! Generators for the evaluation workloads of the Grafite paper.
+//!
+//! A [`Workload`] is a set of keys together with a set of range queries that are all _empty_, so
+//! every query that a filter answers with `true` is a false positive. Comparing the fraction of
+//! such queries with [`RangeFilter::false_positive_rate`] reproduces the false positive rate and
+//! space trade-offs of the paper on arbitrary parameters.
+//!
+//! Keys are always drawn uniformly at random from the whole `u64` universe. Queries can start
+//! uniformly at random, or be correlated with the keys by starting shortly after one of them, which
+//! is the case that heuristic range filters struggle with. The length of the ranges can be fixed,
+//! or vary uniformly between two bounds.
+//!
+//! ```
+//! use grafite::workloads::{QueryDistribution, RangeLength, WorkloadGenerator};
+//! use grafite::{OrderPreservingHasher, RangeFilter};
+//! use rand::SeedableRng;
+//! use rand_chacha::ChaCha8Rng;
+//!
+//! let mut rng = ChaCha8Rng::seed_from_u64(42);
+//! let workload = WorkloadGenerator::new(10_000, 10_000)
+//!     .query_distribution(QueryDistribution::Adversarial)
+//!     .range_length(RangeLength::Fixed(32))
+//!     .generate(&mut rng);
+//!
+//! let hasher = OrderPreservingHasher::new_with_rng(10_000, 0.01, 32, &mut rng).unwrap();
+//! let rf = RangeFilter::new(workload.keys.iter().copied(), hasher);
+//!
+//! // The measured false positive rate is close to the expected one.
+//! assert!(workload.false_positive_rate(&rf) < 0.02);
+//! ```
+
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+
+use crate::{RangeFilter, RangeFilterBackend};
+
+/// The number of attempts made per requested query before a [`WorkloadGenerator`] gives up on
+/// finding more empty queries.
+const ATTEMPTS_PER_QUERY: usize = 100;
+
+/// Where the queries of a [`Workload`] start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryDistribution {
+    /// Queries start uniformly at random, so they are almost never close to a key.
+    #[default]
+    Uniform,
+    /// Queries start after a random key, at a distance chosen uniformly at random between `1` and
+    /// `max_distance`. Smaller distances correspond to a higher degree of correlation.
+    Correlated {
+        /// The largest distance between a key and the start of a query.
+        max_distance: u64,
+    },
+    /// Queries start immediately after a random key, which is the maximum degree of correlation.
+    Adversarial,
+}
+
+/// The lengths of the ranges of a [`Workload`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeLength {
+    /// Every range contains exactly this many keys. A length of `1` produces point queries.
+    Fixed(u64),
+    /// Every range has a length chosen uniformly at random from the given bounds.
+    Uniform(RangeInclusive<u64>),
+}
+
+impl Default for RangeLength {
+    fn default() -> Self {
+        Self::Fixed(1 << 5)
+    }
+}
+
+impl RangeLength {
+    /// Returns a random length, which is always at least `1`.
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u64 {
+        let len = match self {
+            Self::Fixed(len) => *len,
+            Self::Uniform(lengths) if lengths.is_empty() => *lengths.start(),
+            Self::Uniform(lengths) => rng.gen_range(lengths.clone()),
+        };
+
+        len.max(1)
+    }
+}
+
+/// A generator of [`Workload`]s.
+///
+/// Defaults to uniformly distributed queries of length 32, the short ranges of the paper.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkloadGenerator {
+    num_keys: usize,
+    num_queries: usize,
+    query_distribution: QueryDistribution,
+    range_length: RangeLength,
+}
+
+impl WorkloadGenerator {
+    /// Creates a generator of workloads with the given number of keys and queries.
+    pub fn new(num_keys: usize, num_queries: usize) -> Self {
+        Self {
+            num_keys,
+            num_queries,
+            query_distribution: QueryDistribution::default(),
+            range_length: RangeLength::default(),
+        }
+    }
+
+    /// Sets where the queries start.
+    pub fn query_distribution(mut self, query_distribution: QueryDistribution) -> Self {
+        self.query_distribution = query_distribution;
+        self
+    }
+
+    /// Sets the lengths of the ranges.
+    pub fn range_length(mut self, range_length: RangeLength) -> Self {
+        self.range_length = range_length;
+        self
+    }
+
+    /// Generates a workload.
+    ///
+    /// Candidate queries that contain a key are discarded. If the keys are so dense that empty
+    /// queries are rare, the workload may contain fewer queries than requested.
+    pub fn generate<R: Rng + ?Sized>(&self, rng: &mut R) -> Workload {
+        let mut keys: Vec<u64> = (0..self.num_keys).map(|_| rng.gen()).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut queries = Vec::with_capacity(self.num_queries);
+        let mut attempts = self.num_queries.saturating_mul(ATTEMPTS_PER_QUERY);
+
+        while queries.len() < self.num_queries && attempts > 0 {
+            attempts -= 1;
+
+            let Some(start) = self.sample_start(&keys, rng) else {
+                continue;
+            };
+            let Some(end) = start.checked_add(self.range_length.sample(rng) - 1) else {
+                continue;
+            };
+
+            if is_empty(&keys, start, end) {
+                queries.push((start, end));
+            }
+        }
+
+        Workload { keys, queries }
+    }
+
+    /// Returns a random start of a query, or `None` if it would overflow.
+    fn sample_start<R: Rng + ?Sized>(&self, keys: &[u64], rng: &mut R) -> Option<u64> {
+        let distance = match self.query_distribution {
+            QueryDistribution::Uniform => return Some(rng.gen()),
+            QueryDistribution::Correlated { max_distance } => {
+                rng.gen_range(1..=max_distance.max(1))
+            }
+            QueryDistribution::Adversarial => 1,
+        };
+
+        if keys.is_empty() {
+            return None;
+        }
+
+        keys[rng.gen_range(0..keys.len())].checked_add(distance)
+    }
+}
+
+/// A set of keys together with a set of empty range queries.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Workload {
+    /// The sorted and distinct keys.
+    pub keys: Vec<u64>,
+    /// The inclusive endpoints of the queries, none of which contain any of the keys.
+    pub queries: Vec<(u64, u64)>,
+}
+
+impl Workload {
+    /// Returns the fraction of queries that the filter answers with `true`.
+    ///
+    /// Since every query is empty, this is the false positive rate measured on this workload. An
+    /// empty set of queries has a false positive rate of `0`.
+    pub fn false_positive_rate<B: RangeFilterBackend>(&self, filter: &RangeFilter<B>) -> f64 {
+        if self.queries.is_empty() {
+            return 0.0;
+        }
+
+        let false_positives = self
+            .queries
+            .iter()
+            .filter(|&&(start, end)| filter.query(start..=end))
+            .count();
+
+        false_positives as f64 / self.queries.len() as f64
+    }
+}
+
+/// Checks if none of the sorted `keys` are within `start..=end`.
+fn is_empty(keys: &[u64], start: u64, end: u64) -> bool {
+    let i = keys.partition_point(|&key| key < start);
+    keys.get(i).is_none_or(|&key| key > end)
+}
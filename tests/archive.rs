@@ -0,0 +1,50 @@
+#![cfg(feature = "rkyv")]
+
+use grafite::{ArchivedRangeFilter, OrderPreservingHasher, RangeFilter};
+use rkyv::rancor::Error;
+
+#[test]
+fn test_archived_query() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let bytes = rkyv::to_bytes::<Error>(&rf).unwrap();
+    let archived = rkyv::access::<ArchivedRangeFilter, Error>(&bytes).unwrap();
+
+    assert_eq!(archived.len(), rf.backend().len());
+    for start in 0..25 {
+        for end in start + 1..30 {
+            assert_eq!(archived.query(start..end), rf.query(start..end));
+        }
+    }
+
+    let deserialized = rkyv::deserialize::<RangeFilter, Error>(archived).unwrap();
+    assert_eq!(
+        deserialized.backend().iter().collect::<Vec<_>>(),
+        rf.backend().iter().collect::<Vec<_>>()
+    );
+    assert_eq!(deserialized.max_interval(), Some(20));
+    assert!(deserialized.query(3..5));
+    assert!(!deserialized.query(10..15));
+}
+
+#[test]
+fn test_archived_query_across_blocks() {
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let bytes = rkyv::to_bytes::<Error>(&rf).unwrap();
+    let archived = rkyv::access::<ArchivedRangeFilter, Error>(&bytes).unwrap();
+
+    for start in (r - 20..51 * r).step_by(13) {
+        for end in [start, start + 15, start + r, start + 3 * r] {
+            assert_eq!(archived.query(start..=end), rf.query(start..=end));
+        }
+    }
+    assert!(archived.query(r - 3..=r + 3));
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "arrow")]
+
+use arrow_array::{Array, UInt64Array};
+use grafite::{OrderPreservingHasher, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_arrow_batch() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+
+    let mut starts: Vec<Option<u64>> = keys.iter().map(|&k| Some(k.saturating_sub(10))).collect();
+    let mut ends: Vec<Option<u64>> = keys.iter().map(|&k| Some(k)).collect();
+    starts.extend((0..1000).map(|_| Some(rng.gen())));
+    ends.extend(
+        starts[keys.len()..]
+            .iter()
+            .map(|s| s.map(|s: u64| s.saturating_add(20))),
+    );
+    starts[3] = None;
+    ends[7] = None;
+
+    let results = rf
+        .query_arrow(
+            &UInt64Array::from(starts.clone()),
+            &UInt64Array::from(ends.clone()),
+        )
+        .unwrap();
+    assert_eq!(results.len(), starts.len());
+    assert_eq!(results.null_count(), 2);
+
+    for (i, (start, end)) in starts.iter().zip(&ends).enumerate() {
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                assert!(results.is_valid(i));
+                assert_eq!(results.value(i), rf.query(start..=end));
+            }
+            _ => assert!(results.is_null(i)),
+        }
+    }
+}
+
+#[test]
+fn test_arrow_length_mismatch() {
+    let hasher = OrderPreservingHasher::new(1, 0.01, 16).unwrap();
+    let rf = RangeFilter::new([1].into_iter(), hasher);
+
+    let starts = UInt64Array::from(vec![1, 2]);
+    let ends = UInt64Array::from(vec![3]);
+    assert!(rf.query_arrow(&starts, &ends).is_err());
+}
@@ -0,0 +1,174 @@
+use grafite::{
+    BuildError, DenseBitVector, EliasFano, Encoding, OrderPreservingHasher, RangeFilter,
+    RangeFilterBackend,
+};
+
+/// A backend that stores the hash values in a plain sorted vector, relying on the provided
+/// methods of the trait.
+#[derive(Debug, Clone)]
+struct SortedVec(Vec<u64>);
+
+impl RangeFilterBackend for SortedVec {
+    fn from_sorted(hashes: &[u64]) -> Self {
+        Self(hashes.to_vec())
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get(&self, index: usize) -> u64 {
+        self.0[index]
+    }
+
+    fn predecessor(&self, value: u64) -> Option<u64> {
+        match self.0.partition_point(|&h| h <= value) {
+            0 => None,
+            i => Some(self.0[i - 1]),
+        }
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        self.0.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+#[test]
+fn test_custom_backend() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 37 + x % 7).collect();
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    let custom: RangeFilter<SortedVec> =
+        RangeFilter::try_with_backend(values.iter().copied(), hasher).unwrap();
+
+    assert!(rf.iter_hashes().eq(custom.iter_hashes()));
+    assert_eq!(custom.heap_size(), values.len() * 8);
+
+    for start in (0..40_000).step_by(7) {
+        let range = start..start + 50;
+        assert_eq!(rf.query(range.clone()), custom.query(range.clone()));
+        assert_eq!(rf.rank_range(range.clone()), custom.rank_range(range));
+        assert_eq!(rf.successor_hash(start), custom.successor_hash(start));
+    }
+
+    let merged = RangeFilter::merge(&[custom.clone(), custom.clone()]).unwrap();
+    assert!(merged.iter_hashes().eq(custom.iter_hashes()));
+}
+
+#[test]
+#[cfg(feature = "sucds")]
+fn test_sucds_backend() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 37 + x % 7).collect();
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    let sucds: RangeFilter<sucds::mii_sequences::EliasFano> =
+        RangeFilter::try_with_backend(values.iter().copied(), hasher).unwrap();
+
+    assert!(rf.iter_hashes().eq(sucds.iter_hashes()));
+
+    for start in (0..40_000).step_by(7) {
+        let range = start..start + 50;
+        assert_eq!(rf.query(range.clone()), sucds.query(range.clone()));
+        assert_eq!(rf.rank_range(range.clone()), sucds.rank_range(range));
+    }
+
+    // Hashes beyond the largest stored hash are outside of the universe of `sucds`.
+    for hash in [0, u64::MAX - 1, u64::MAX] {
+        assert_eq!(rf.predecessor_hash(hash), sucds.predecessor_hash(hash));
+        assert_eq!(rf.successor_hash(hash), sucds.successor_hash(hash));
+    }
+}
+
+#[test]
+fn test_reencode() {
+    // Dense hash values, which are smaller in a bit vector.
+    let values: Vec<u64> = (0..10_000).map(|x| x * 37 + x % 7).collect();
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 14);
+
+    let rf: RangeFilter<EliasFano> =
+        RangeFilter::try_with_backend(values.iter().copied(), hasher).unwrap();
+    let dense: RangeFilter<DenseBitVector> = rf.reencode().unwrap();
+    let custom: RangeFilter<SortedVec> = dense.reencode().unwrap();
+    let encoding: RangeFilter<Encoding> = custom.reencode().unwrap();
+
+    assert_eq!(dense.hasher().hash(12345), rf.hasher().hash(12345));
+    assert!(rf.iter_hashes().eq(dense.iter_hashes()));
+    assert!(rf.iter_hashes().eq(custom.iter_hashes()));
+    assert!(rf.iter_hashes().eq(encoding.iter_hashes()));
+    assert_eq!(
+        encoding.backend().is_dense(),
+        Encoding::prefers_dense(&custom.iter_hashes().collect::<Vec<_>>())
+    );
+
+    for start in (0..40_000).step_by(7) {
+        let range = start..start + 50;
+        assert_eq!(rf.query(range.clone()), dense.query(range.clone()));
+        assert_eq!(rf.query(range.clone()), custom.query(range.clone()));
+        assert_eq!(rf.query(range.clone()), encoding.query(range));
+    }
+
+    // The estimated sizes match the built backends.
+    let (len, max) = (rf.backend().len(), rf.iter_hashes().last().unwrap());
+    assert_eq!(
+        EliasFano::<Vec<u64>>::estimated_size_in_bytes(len, max),
+        Some(rf.backend().size_in_bytes())
+    );
+    assert_eq!(
+        DenseBitVector::estimated_size_in_bytes(len, max),
+        Some(dense.backend().size_in_bytes())
+    );
+    assert_eq!(SortedVec::estimated_size_in_bytes(len, max), None);
+}
+
+#[test]
+fn test_reencode_larger() {
+    // Sparse hash values, which a bit vector over the reduced universe would waste space on.
+    let values: Vec<u64> = (0..1000).map(|x| x * 37 + x % 7).collect();
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf: RangeFilter<EliasFano> =
+        RangeFilter::try_with_backend(values.iter().copied(), hasher).unwrap();
+
+    match rf.reencode::<DenseBitVector>() {
+        Err(BuildError::LargerEncoding { size, elias_fano }) => {
+            assert_eq!(elias_fano, rf.backend().size_in_bytes());
+            assert!(size > 10 * elias_fano);
+        }
+        other => panic!("expected `LargerEncoding`, got {:?}", other.map(|_| ())),
+    }
+
+    // Backends that never take more space than Elias-Fano are always allowed.
+    let encoding: RangeFilter<Encoding> = rf.reencode().unwrap();
+    assert!(!encoding.backend().is_dense());
+    assert!(rf.reencode::<SortedVec>().is_ok());
+}
+
+#[test]
+fn test_from_sorted_interruptible() {
+    let hashes: Vec<u64> = (0..1000).map(|x| x * 7 + 3).collect();
+
+    fn check<B: RangeFilterBackend>(hashes: &[u64]) {
+        // Checkpoints come after every 100 hash values, except after the last one.
+        let mut checkpoints = Vec::new();
+        let mut checkpoint = |done| {
+            checkpoints.push(done);
+            true
+        };
+        let backend = B::from_sorted_interruptible(hashes, 100, &mut checkpoint).unwrap();
+        assert!(backend.iter().eq(hashes.iter().copied()));
+        assert_eq!(checkpoints, (1..10).map(|i| i * 100).collect::<Vec<_>>());
+
+        let mut checkpoint = |done| done < 500;
+        assert!(B::from_sorted_interruptible(hashes, 100, &mut checkpoint).is_none());
+    }
+
+    check::<EliasFano>(&hashes);
+    check::<DenseBitVector>(&hashes);
+    check::<Encoding>(&hashes);
+
+    // Backends that do not override it encode every hash value without any checkpoints.
+    let mut checkpoint = |_| false;
+    let backend = SortedVec::from_sorted_interruptible(&hashes, 100, &mut checkpoint).unwrap();
+    assert_eq!(backend.0, hashes);
+}
@@ -0,0 +1,114 @@
+use grafite::{
+    BlockFilterBuilder, BlockFilterReader, BuildError, OrderPreservingHasher, ParamError,
+    RangeFilterBlockBuilder, RangeFilterBlockReader,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_sstable_blocks() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    // Simulate a sorted table with contiguous blocks of keys.
+    let mut keys: Vec<u64> = (0..10_000).map(|_| rng.gen_range(0..1 << 40)).collect();
+    keys.sort_unstable();
+    let blocks: Vec<&[u64]> = keys.chunks(256).collect();
+
+    let mut builder = RangeFilterBlockBuilder::new(0.01, 1 << 10).with_seed(7);
+    let mut filters = Vec::new();
+    for block in &blocks {
+        for &key in block.iter() {
+            builder.add_key(key);
+        }
+        assert_eq!(builder.num_keys(), block.len());
+
+        let mut bytes = Vec::new();
+        builder.finish_block(&mut bytes).unwrap();
+        assert_eq!(builder.num_keys(), 0);
+        filters.push(bytes);
+    }
+
+    let readers: Vec<RangeFilterBlockReader> = filters
+        .iter()
+        .map(|bytes| RangeFilterBlockReader::open(bytes).unwrap())
+        .collect();
+    for (block, reader) in blocks.iter().zip(&readers) {
+        for &key in block.iter() {
+            assert!(reader.may_contain(key));
+            assert!(reader.may_contain_range(key.saturating_sub(100), key));
+        }
+    }
+
+    // A point lookup only has to read a few blocks besides the one that contains the key.
+    let key = blocks[10][5];
+    let candidates = readers.iter().filter(|r| r.may_contain(key)).count();
+    assert!((1..=3).contains(&candidates));
+}
+
+#[test]
+fn test_wide_ranges() {
+    let keys = [1_000, 50_000, 1_000_000, 1 << 40];
+
+    let mut builder = RangeFilterBlockBuilder::new(0.01, 16).with_seed(7);
+    for key in keys {
+        builder.add_key(key);
+    }
+    let mut bytes = Vec::new();
+    builder.finish_block(&mut bytes).unwrap();
+    let reader = RangeFilterBlockReader::open(&bytes).unwrap();
+
+    // Ranges much wider than the maximum interval of 16 keys must not skip the block.
+    for key in keys {
+        for width in [100, 10_000, 1 << 20] {
+            assert!(reader.may_contain_range(key.saturating_sub(width), key + width));
+        }
+    }
+}
+
+#[test]
+fn test_ranges_across_hash_blocks() {
+    // The blocks of the reduced universe that the builder's hasher will have.
+    let r = OrderPreservingHasher::new_seeded(100, 0.01, 16, 7)
+        .unwrap()
+        .reduced_universe();
+    let keys: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+
+    let mut builder = RangeFilterBlockBuilder::new(0.01, 16).with_seed(7);
+    for &key in &keys {
+        builder.add_key(key);
+    }
+    let mut bytes = Vec::new();
+    builder.finish_block(&mut bytes).unwrap();
+    let reader = RangeFilterBlockReader::open(&bytes).unwrap();
+    assert_eq!(reader.filter().unwrap().hasher().reduced_universe(), r);
+
+    // Every range around a key is checked in both of the windows it hashes onto.
+    for &key in &keys {
+        for start in key - 15..=key {
+            assert!(reader.may_contain_range(start, start + 15));
+        }
+    }
+}
+
+#[test]
+fn test_empty_and_invalid_blocks() {
+    let mut builder = RangeFilterBlockBuilder::new(0.01, 64);
+
+    let mut bytes = Vec::new();
+    builder.finish_block(&mut bytes).unwrap();
+    assert!(bytes.is_empty());
+
+    let reader = RangeFilterBlockReader::open(&bytes).unwrap();
+    assert!(reader.filter().is_none());
+    assert!(!reader.may_contain(0));
+    assert!(!reader.may_contain_range(0, u64::MAX));
+
+    assert!(RangeFilterBlockReader::open(b"garbage").is_err());
+
+    let mut builder = RangeFilterBlockBuilder::new(1.5, 64);
+    builder.add_key(1);
+    assert!(matches!(
+        builder.finish_block(&mut bytes),
+        Err(BuildError::Param(ParamError::InvalidEpsilon(_)))
+    ));
+}
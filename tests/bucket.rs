@@ -0,0 +1,41 @@
+use grafite::{BucketRangeFilter, BuildError};
+
+#[test]
+fn test_bucket() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let rf = BucketRangeFilter::new(values, 4).unwrap();
+    assert_eq!(rf.bucket_size(), 4);
+
+    // The non-empty buckets are `[0, 3]`, `[4, 7]`, `[8, 11]`, `[12, 15]`, and `[20, 23]`.
+    assert!(rf.query(0..20));
+    assert!(rf.query(3..5));
+    assert!(rf.query(4..5));
+    assert!(!rf.query(16..20));
+    assert!(rf.query(16..=20));
+    assert!(!rf.query(24..));
+    assert!(!rf.query(5..5));
+
+    for start in 0..30 {
+        for end in start..35 {
+            if values.iter().any(|&x| start <= x && x <= end) {
+                assert!(rf.query(start..=end));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_bucket_budget() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1_000_000).collect();
+
+    let rf = BucketRangeFilter::with_budget(values.iter().copied(), 10).unwrap();
+    assert!(rf.bucket_size() > 1);
+    assert!(values.iter().all(|&x| rf.query(x..=x)));
+
+    assert!(matches!(
+        BucketRangeFilter::with_budget([], 10),
+        Err(BuildError::EmptyInput)
+    ));
+    assert!(BucketRangeFilter::with_budget([1, 2, 3], 2).is_err());
+}
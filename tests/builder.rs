@@ -0,0 +1,275 @@
+use grafite::{
+    BuildError, BuildObserver, BuildPhase, InsertBuilder, IoBuildError, OrderPreservingHasher,
+    ParamError, PrimeStrategy, RangeFilter, RangeFilterBuilder, MERSENNE_61,
+};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const VALUES: [u64; 8] = [1, 2, 3, 7, 8, 9, 15, 20];
+
+#[test]
+fn test_builder() {
+    let rf = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(20)
+        .build(VALUES)
+        .unwrap();
+
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+    assert!(rf.query(10..16));
+
+    let rf = RangeFilterBuilder::new()
+        .bits_per_key(16)
+        .max_interval(20)
+        .build(VALUES)
+        .unwrap();
+
+    assert!(rf.query(3..5));
+    assert!(rf.query(10..16));
+}
+
+#[test]
+fn test_builder_seeded() {
+    let builder = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(20)
+        .seed(42);
+
+    let a = builder.build(VALUES).unwrap();
+    let b = builder.build(VALUES).unwrap();
+
+    assert_eq!(
+        a.backend().iter().collect::<Vec<_>>(),
+        b.backend().iter().collect::<Vec<_>>()
+    );
+    assert_eq!(a.hasher().hash(12345), b.hasher().hash(12345));
+}
+
+#[test]
+fn test_builder_duplicates() {
+    let builder = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(20)
+        .seed(42);
+
+    // The hasher is sized for the distinct keys only.
+    let repeated = VALUES.iter().flat_map(|&x| [x; 100]);
+    let a = builder.build(repeated).unwrap();
+    let b = builder.build(VALUES).unwrap();
+
+    assert_eq!(a.num_elements(), Some(VALUES.len()));
+    assert_eq!(a.hasher().reduced_universe(), b.hasher().reduced_universe());
+    assert!(a.iter_hashes().eq(b.iter_hashes()));
+}
+
+#[test]
+fn test_builder_invalid() {
+    assert!(matches!(
+        RangeFilterBuilder::new().epsilon(0.01).build(VALUES),
+        Err(BuildError::Param(ParamError::MissingParameter(
+            "max_interval"
+        )))
+    ));
+    assert!(matches!(
+        RangeFilterBuilder::new().max_interval(20).build(VALUES),
+        Err(BuildError::Param(ParamError::MissingParameter("epsilon")))
+    ));
+    assert!(matches!(
+        RangeFilterBuilder::new()
+            .epsilon(0.01)
+            .bits_per_key(16)
+            .max_interval(20)
+            .build(VALUES),
+        Err(BuildError::Param(ParamError::ConflictingParameters))
+    ));
+    assert!(matches!(
+        RangeFilterBuilder::new()
+            .epsilon(0.01)
+            .max_interval(20)
+            .build([]),
+        Err(BuildError::EmptyInput)
+    ));
+}
+
+#[test]
+fn test_insert_builder() {
+    let mut builder = InsertBuilder::new();
+    assert!(builder.is_empty());
+
+    for &x in &VALUES[..4] {
+        builder.push(x);
+    }
+    builder.extend(VALUES[4..].iter().copied());
+    assert_eq!(builder.len(), VALUES.len());
+
+    let hasher = OrderPreservingHasher::new(builder.len(), 0.01, 20).unwrap();
+    let rf = builder.finish(hasher).unwrap();
+
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+    assert!(rf.query(10..16));
+
+    let hasher = OrderPreservingHasher::new(1, 0.01, 20).unwrap();
+    assert!(matches!(
+        InsertBuilder::new().finish(hasher),
+        Err(BuildError::EmptyInput)
+    ));
+}
+
+#[test]
+fn test_fallible_build() {
+    let keys = || Ok::<_, io::Error>(VALUES.into_iter().map(Ok));
+
+    let rf = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(20)
+        .build_two_pass(keys)
+        .unwrap();
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+
+    let failing = [Ok(1), Ok(2), Err(io::Error::other("disk on fire")), Ok(3)];
+    let hasher = OrderPreservingHasher::new(VALUES.len(), 0.01, 20).unwrap();
+    assert!(matches!(
+        RangeFilter::try_from_fallible(failing.into_iter(), hasher),
+        Err(IoBuildError::Io(e)) if e.to_string() == "disk on fire"
+    ));
+}
+
+#[test]
+fn test_build_progress() {
+    let hasher = OrderPreservingHasher::new(VALUES.len(), 0.01, 20).unwrap();
+
+    let mut reports = Vec::new();
+    let mut observer = |phase, done, total| reports.push((phase, done, total));
+    let rf = RangeFilter::try_new_with_observer(VALUES.into_iter(), hasher, &mut observer).unwrap();
+    assert!(rf.query(3..5));
+
+    assert_eq!(
+        reports,
+        [
+            (BuildPhase::Hashing, 0, 8),
+            (BuildPhase::Hashing, 8, 8),
+            (BuildPhase::Sorting, 0, 8),
+            (BuildPhase::Sorting, 8, 8),
+            (BuildPhase::Encoding, 0, 8),
+            (BuildPhase::Encoding, 8, 8),
+        ]
+    );
+}
+
+#[test]
+fn test_build_progress_matches_build() {
+    // Both a reduced universe that fits in 32 bits and one that does not.
+    for hasher in [
+        OrderPreservingHasher::new(VALUES.len(), 0.01, 20).unwrap(),
+        OrderPreservingHasher::new_with_reduced(u64::MAX / 2),
+    ] {
+        let mut observer = |_, _, _| {};
+        let observed =
+            RangeFilter::try_new_with_observer(VALUES.into_iter(), hasher, &mut observer).unwrap();
+        let mut hashes: Vec<u64> = VALUES.iter().map(|&x| hasher.hash(x)).collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        assert!(observed.iter_hashes().eq(hashes));
+    }
+}
+
+#[test]
+fn test_build_cancelled() {
+    let hasher = OrderPreservingHasher::new(VALUES.len(), 0.01, 20).unwrap();
+
+    let cancel = AtomicBool::new(false);
+    assert!(RangeFilter::try_new_cancellable(VALUES.into_iter(), hasher, &cancel).is_ok());
+
+    cancel.store(true, Ordering::Relaxed);
+    assert!(matches!(
+        RangeFilter::try_new_cancellable(VALUES.into_iter(), hasher, &cancel),
+        Err(BuildError::Cancelled)
+    ));
+}
+
+#[test]
+fn test_build_cancelled_while_encoding() {
+    /// Cancels the build at the first report from within the encoding phase.
+    #[derive(Default)]
+    struct CancelWhileEncoding {
+        reports: Vec<(BuildPhase, usize, usize)>,
+    }
+
+    impl BuildObserver for CancelWhileEncoding {
+        fn on_progress(&mut self, phase: BuildPhase, items_done: usize, items_total: usize) {
+            self.reports.push((phase, items_done, items_total));
+        }
+
+        fn is_cancelled(&self) -> bool {
+            matches!(
+                self.reports.last(),
+                Some(&(BuildPhase::Encoding, done, total)) if 0 < done && done < total
+            )
+        }
+    }
+
+    // Enough keys for the encoding to report its progress in between.
+    let n = 5 << 19;
+    let hasher = OrderPreservingHasher::new_seeded(n, 0.5, 2, 7).unwrap();
+    let mut observer = CancelWhileEncoding::default();
+    assert!(matches!(
+        RangeFilter::try_new_with_observer((0..n as u64).map(|x| x * 8), hasher, &mut observer),
+        Err(BuildError::Cancelled)
+    ));
+
+    let (phase, done, total) = *observer.reports.last().unwrap();
+    assert_eq!(phase, BuildPhase::Encoding);
+    assert_eq!(done, 1 << 20);
+    assert!(done < total);
+}
+
+#[test]
+fn test_builder_prime_strategy() {
+    let builder = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(20)
+        .prime_strategy(PrimeStrategy::Mersenne61)
+        .seed(5);
+
+    let rf = builder.build(VALUES).unwrap();
+    assert_eq!(rf.hasher().p(), MERSENNE_61);
+    assert_eq!(rf.seed(), Some(5));
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+}
+
+#[test]
+fn test_builder_power_of_two() {
+    let rf = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(20)
+        .power_of_two(true)
+        .build(VALUES)
+        .unwrap();
+
+    assert!(rf.hasher().reduced_universe().is_power_of_two());
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+}
+
+#[test]
+fn test_builder_universe_size() {
+    let builder = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(1 << 20)
+        .universe_size(1 << 24);
+
+    // With only 8 keys in `[0, 2^24)`, the maximum interval is at most `2^24 * 0.01 / 8`.
+    assert!(matches!(
+        builder.build(VALUES),
+        Err(BuildError::Param(ParamError::InvalidMaxInterval(20971)))
+    ));
+
+    let rf = builder.max_interval(20).seed(3).build(VALUES).unwrap();
+    let hasher = OrderPreservingHasher::new_with_universe_seeded(1 << 24, 8, 0.01, 20, 3).unwrap();
+    assert_eq!(rf.hasher().hash(12345), hasher.hash(12345));
+    assert!(rf.query(3..5));
+}
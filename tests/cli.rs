@@ -0,0 +1,80 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use grafite::RangeFilter;
+
+const CLI: &str = env!("CARGO_BIN_EXE_grafite-cli");
+
+#[test]
+fn test_cli() {
+    let dir = std::env::temp_dir().join(format!("grafite-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (keys, filter) = (dir.join("keys.txt"), dir.join("filter.bin"));
+
+    fs::write(&keys, "1 2 3\n7 8 9\n\n15\n20\n").unwrap();
+    let status = Command::new(CLI)
+        .args(["build", "-m", "20", "-s", "7", "-o"])
+        .args([&filter, &keys])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let loaded = RangeFilter::read_from(fs::read(&filter).unwrap().as_slice()).unwrap();
+    assert_eq!(loaded.seed(), Some(7));
+    assert_eq!(loaded.num_elements(), Some(8));
+
+    // Queries are read from standard input if no query file is given.
+    let mut child = Command::new(CLI)
+        .arg("query")
+        .arg(&filter)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"3\n3 5\n\n9 16\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"true\ntrue\ntrue\n");
+
+    let queries = dir.join("queries.txt");
+    fs::write(&queries, "10 12\n10 not-a-key\n").unwrap();
+    let output = Command::new(CLI)
+        .arg("query")
+        .arg(&filter)
+        .arg("--queries")
+        .arg(&queries)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2"));
+
+    let output = Command::new(CLI)
+        .arg("inspect")
+        .arg(&filter)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("keys:                8"));
+    assert!(stdout.contains("max interval:        20"));
+    assert!(stdout.contains("source fingerprint:"));
+
+    // Building a filter without keys fails.
+    fs::write(&keys, "").unwrap();
+    let status = Command::new(CLI)
+        .args(["build", "-o"])
+        .args([&filter, &keys])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,113 @@
+use grafite::{
+    CompositeEncoder, CompositeError, OrderPreservingHasher, RangeFilter, RangeFilter128,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_invalid_encoders() {
+    assert_eq!(CompositeEncoder::new(&[]), Err(CompositeError::NoColumns));
+    assert_eq!(
+        CompositeEncoder::new(&[8, 0]),
+        Err(CompositeError::ZeroWidth { column: 1 })
+    );
+    assert_eq!(
+        CompositeEncoder::new(&[64, 65]),
+        Err(CompositeError::TooWide {
+            bits: 129,
+            max: 128
+        })
+    );
+
+    let encoder = CompositeEncoder::new(&[32, 64]).unwrap();
+    assert!(!encoder.fits_in_u64());
+    assert_eq!(
+        encoder.encode_u64(&[1, 2]),
+        Err(CompositeError::TooWide { bits: 96, max: 64 })
+    );
+    assert_eq!(
+        encoder.encode(&[1]),
+        Err(CompositeError::ColumnCount {
+            expected: 2,
+            found: 1
+        })
+    );
+    assert_eq!(
+        encoder.encode(&[1 << 32, 0]),
+        Err(CompositeError::ValueOutOfRange {
+            column: 0,
+            value: 1 << 32
+        })
+    );
+    assert!(encoder.prefix_range(&[1, 2], ..).is_err());
+}
+
+#[test]
+fn test_encode_order() {
+    let encoder = CompositeEncoder::new(&[8, 16, 8]).unwrap();
+    assert_eq!(encoder.bits(), 32);
+    assert_eq!(encoder.widths(), &[8, 16, 8]);
+
+    let key = encoder.encode(&[0xab, 0x1234, 0xcd]).unwrap();
+    assert_eq!(key, 0xab1234cd);
+    assert_eq!(encoder.decode(key), vec![0xab, 0x1234, 0xcd]);
+
+    // Keys are ordered by their leading columns first.
+    let a = encoder.encode_u64(&[1, 0xffff, 0xff]).unwrap();
+    let b = encoder.encode_u64(&[2, 0, 0]).unwrap();
+    assert!(a < b);
+
+    let full = CompositeEncoder::new(&[128]).unwrap();
+    assert_eq!(full.encode(&[u128::MAX]), Ok(u128::MAX));
+}
+
+#[test]
+fn test_prefix_ranges() {
+    let encoder = CompositeEncoder::new(&[8, 16, 8]).unwrap();
+
+    let range = encoder.prefix_range_u64(&[3], 10..20).unwrap();
+    assert_eq!(range, 0x03000a00..=0x030013ff);
+
+    let range = encoder.prefix_range_u64(&[3, 7], ..).unwrap();
+    assert_eq!(range, 0x03000700..=0x030007ff);
+
+    // Ranges are clamped to their column, and may be empty.
+    let range = encoder.prefix_range(&[], 0xfe..).unwrap();
+    assert_eq!(range, 0xfe000000..=0xffffffff);
+    assert!(encoder.prefix_range(&[], 0x100..).unwrap().is_empty());
+    assert!(encoder.prefix_range(&[3], 5..5).unwrap().is_empty());
+}
+
+#[test]
+fn test_composite_filters() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let encoder = CompositeEncoder::new(&[16, 48]).unwrap();
+    let rows: Vec<[u64; 2]> = (0..10_000)
+        .map(|_| [rng.gen_range(0..100), rng.gen_range(0..1 << 48)])
+        .collect();
+    let keys = rows.iter().map(|row| encoder.encode_u64(row).unwrap());
+    let hasher = OrderPreservingHasher::new_with_rng(rows.len(), 0.01, 1 << 10, &mut rng).unwrap();
+    let rf = RangeFilter::new(keys, hasher);
+
+    for &[tenant, ts] in &rows {
+        let range = encoder
+            .prefix_range_u64(&[tenant], ts.saturating_sub(500)..=ts)
+            .unwrap();
+        assert!(rf.query(range));
+    }
+
+    let encoder = CompositeEncoder::new(&[64, 64]).unwrap();
+    let rows: Vec<[u128; 2]> = (0..10_000)
+        .map(|_| [rng.gen::<u64>() as u128, rng.gen::<u64>() as u128])
+        .collect();
+    let keys = rows.iter().map(|row| encoder.encode(row).unwrap());
+    let hasher =
+        OrderPreservingHasher::new_u128_with_rng(rows.len(), 0.01, 1 << 10, &mut rng).unwrap();
+    let rf = RangeFilter128::new(keys, hasher);
+
+    for &[id, ts] in &rows {
+        let range = encoder.prefix_range(&[id], ts..ts + 100).unwrap();
+        assert!(rf.query(range));
+    }
+}
@@ -0,0 +1,58 @@
+use grafite::{BuildError, CountingRangeFilter, OrderPreservingHasher};
+
+#[test]
+fn test_counting() {
+    let values: Vec<u64> = (0..1000)
+        .flat_map(|x| [x * 50, x * 50, x * 50 + 1])
+        .collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 100).unwrap();
+    let rf = CountingRangeFilter::new(values.iter().copied(), hasher).unwrap();
+    assert_eq!(rf.total_count(), 3000);
+
+    for start in (0..60_000).step_by(31) {
+        let end = start + 99;
+        let count = values.iter().filter(|&&x| start <= x && x <= end).count() as u64;
+
+        let estimate = rf.count_range(start..=end);
+        assert!(estimate >= count);
+        assert_eq!(estimate > 0, rf.query(start..=end));
+        assert_eq!(estimate > 0, rf.filter().rank_range(start..=end) > 0);
+    }
+
+    assert_eq!(rf.count_range(5..5), 0);
+}
+
+#[test]
+fn test_counting_across_blocks() {
+    // With a single key, `L = 8` and `epsilon = 0.1`, every block has `r = 80` keys.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = CountingRangeFilter::new([81, 81].into_iter(), hasher).unwrap();
+    assert_eq!(rf.count_range(78..=85), 2);
+
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(200, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50)
+        .flat_map(|k| [k * r - 1, k * r - 1, k * r + 5, k * r + 5])
+        .collect();
+    let rf = CountingRangeFilter::new(values.iter().copied(), hasher).unwrap();
+
+    for k in 1..=50 {
+        let estimate = rf.count_range(k * r - 8..=k * r + 8);
+        assert!((4..=rf.total_count()).contains(&estimate));
+    }
+
+    // Ranges of at least `r` keys count every key.
+    assert_eq!(rf.count_range(..), rf.total_count());
+    assert_eq!(rf.count_range(r / 2..r / 2 + r), rf.total_count());
+}
+
+#[test]
+fn test_counting_empty() {
+    let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    assert!(matches!(
+        CountingRangeFilter::new(std::iter::empty(), hasher),
+        Err(BuildError::EmptyInput)
+    ));
+}
@@ -0,0 +1,69 @@
+use grafite::{DeletableRangeFilter, OrderPreservingHasher};
+
+#[test]
+fn test_remove() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 100).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len() + 1, 0.01, 64).unwrap();
+    let mut rf = DeletableRangeFilter::new(values.iter().copied().chain([500]), hasher).unwrap();
+
+    assert!(values.iter().all(|&x| rf.contains(x)));
+
+    // Remove every other key.
+    for &x in values.iter().step_by(2) {
+        assert!(rf.remove(x));
+    }
+
+    for (i, &x) in values.iter().enumerate() {
+        if i % 2 == 1 || x == 500 {
+            assert!(rf.query(x..x + 10));
+        }
+    }
+
+    let false_positives = values
+        .iter()
+        .step_by(2)
+        .filter(|&&x| x != 500 && rf.query(x..=x))
+        .count();
+    assert!(false_positives < 10);
+
+    // The key `500` was inserted twice, and `0` was already removed.
+    assert!(rf.remove(500));
+    assert!(rf.contains(500));
+    assert!(!rf.remove(0));
+    assert!(rf.filter().contains(0));
+}
+
+#[test]
+fn test_remove_across_blocks() {
+    // With a single key, `L = 8` and `epsilon = 0.1`, every block has `r = 80` keys.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let mut rf = DeletableRangeFilter::new(std::iter::once(81), hasher).unwrap();
+    assert!(rf.query(78..=85));
+    assert!(rf.remove(81));
+    assert!(!rf.query(78..=85));
+
+    // Keys on both sides of many block boundaries, spread over several words of the bitmap.
+    let hasher = OrderPreservingHasher::new_seeded(200, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=100).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let mut rf = DeletableRangeFilter::new(values.iter().copied(), hasher).unwrap();
+
+    // Remove the keys after every boundary of an even block.
+    for k in (2..=100).step_by(2) {
+        assert!(rf.remove(k * r + 5));
+    }
+    for k in 1..=100 {
+        assert!(rf.query(k * r - 8..=k * r + 8));
+        assert!(rf.query(k * r - 1..=k * r));
+        if k % 2 == 1 {
+            assert!(rf.query(k * r + 5..=k * r + 10));
+        }
+    }
+
+    let false_positives = (2..=100)
+        .step_by(2)
+        .filter(|&k| rf.query(k * r + 5..=k * r + 10))
+        .count();
+    assert!(false_positives < 10);
+}
@@ -0,0 +1,79 @@
+use grafite::{DenseBitVector, EliasFano, Encoding, OrderPreservingHasher, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Checks every query of the bit vector over `values` against a plain sorted slice.
+fn check(values: &[u64]) {
+    let bits = DenseBitVector::from_slice(values);
+    assert_eq!(bits.len(), values.len());
+    assert!(bits.iter().eq(values.iter().copied()));
+    assert_eq!(bits.iter().len(), values.len());
+    assert_eq!(bits.get(values.len()), None);
+
+    let max = values.last().copied().unwrap_or(0);
+    for x in (0..max + 130).chain([u64::MAX]) {
+        let rank = values.partition_point(|&v| v < x);
+        assert_eq!(bits.rank(x), rank, "rank of {x}");
+        assert_eq!(bits.contains(x), values.binary_search(&x).is_ok());
+
+        let predecessor = values.iter().rev().find(|&&v| v <= x).copied();
+        assert_eq!(bits.predecessor(x), predecessor, "predecessor of {x}");
+        assert_eq!(
+            bits.successor(x),
+            values.get(rank).copied(),
+            "successor of {x}"
+        );
+    }
+
+    for (i, &x) in values.iter().enumerate() {
+        assert_eq!(bits.get(i), Some(x));
+    }
+}
+
+#[test]
+fn test_dense_bit_vector() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    check(&[]);
+    check(&[0]);
+    check(&[63, 64]);
+    check(&[1, 5000]);
+
+    for (max, density) in [(100, 0.5), (1000, 0.9), (10_000, 0.3), (50_000, 0.01)] {
+        let values: Vec<u64> = (0..max).filter(|_| rng.gen_bool(density)).collect();
+        check(&values);
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_dense_bit_vector_unsorted() {
+    DenseBitVector::from_slice(&[1, 3, 3]);
+}
+
+#[test]
+fn test_encoding_selection() {
+    assert!(!Encoding::prefers_dense(&[]));
+    assert!(!Encoding::prefers_dense(&[0, 1 << 20]));
+    assert!(Encoding::prefers_dense(&(0..1000).collect::<Vec<_>>()));
+
+    let sparse: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new(sparse.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(sparse.iter().copied(), hasher);
+    assert!(!rf.backend().is_dense());
+
+    // With a reduced universe that is barely larger than the key set, most of it is occupied.
+    let dense: Vec<u64> = (0..10_000).collect();
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 14);
+    let rf = RangeFilter::new(dense.iter().copied(), hasher);
+    assert!(rf.backend().is_dense());
+
+    // The same filter with an Elias-Fano encoding is larger, but answers queries identically.
+    let ef: RangeFilter<EliasFano> =
+        RangeFilter::try_with_backend(dense.iter().copied(), *rf.hasher()).unwrap();
+    assert!(rf.heap_size() < ef.heap_size());
+
+    for start in 0..80_000 {
+        assert_eq!(rf.query(start..start + 3), ef.query(start..start + 3));
+    }
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "derive")]
+
+use grafite::{KeyedRangeFilter, OrderPreservingHasher, RangeKey, ToOrderedU64};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[derive(RangeKey, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+#[derive(RangeKey, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Record {
+    level: Level,
+    shard: i8,
+    offset: u32,
+}
+
+#[derive(RangeKey, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Wide(u64, u32, bool);
+
+#[derive(RangeKey)]
+struct Pair<T>(T, T);
+
+#[test]
+fn test_derived_widths() {
+    assert_eq!(<Level as RangeKey>::BITS, 3);
+    assert_eq!(<Record as RangeKey>::BITS, 3 + 8 + 32);
+    assert_eq!(<Wide as RangeKey>::BITS, 97);
+    assert_eq!(<Pair<u16> as RangeKey>::BITS, 32);
+
+    assert_eq!(Level::Warn.to_range_key(), 2);
+    assert_eq!(Pair(1u16, 2u16).to_range_key(), 0x0001_0002);
+    assert_eq!((-1i8).to_range_key(), 0x7f);
+}
+
+#[test]
+fn test_derived_order() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let levels = [
+        Level::Debug,
+        Level::Info,
+        Level::Warn,
+        Level::Error,
+        Level::Fatal,
+    ];
+
+    let mut records: Vec<Record> = (0..1000)
+        .map(|_| Record {
+            level: levels[rng.gen_range(0..levels.len())],
+            shard: rng.gen(),
+            offset: rng.gen(),
+        })
+        .collect();
+    records.sort();
+    for pair in records.windows(2) {
+        assert!(pair[0].to_range_key() <= pair[1].to_range_key());
+        assert!(pair[0].to_ordered_u64() <= pair[1].to_ordered_u64());
+    }
+
+    // Keys wider than 64 bits are truncated to their most significant bits.
+    let mut wide: Vec<Wide> = (0..1000)
+        .map(|_| Wide(rng.gen(), rng.gen(), rng.gen()))
+        .collect();
+    wide.sort();
+    for pair in wide.windows(2) {
+        assert!(pair[0].to_range_key() <= pair[1].to_range_key());
+        assert!(pair[0].to_ordered_u64() <= pair[1].to_ordered_u64());
+    }
+    assert_eq!(Wide(u64::MAX, 0, false).to_ordered_u64(), u64::MAX);
+}
+
+#[test]
+fn test_derived_filter() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+
+    let records: Vec<Record> = (0..10_000)
+        .map(|_| Record {
+            level: Level::Error,
+            shard: rng.gen_range(-4..4),
+            offset: rng.gen(),
+        })
+        .collect();
+    let hasher =
+        OrderPreservingHasher::new_with_rng(records.len(), 0.01, 1 << 10, &mut rng).unwrap();
+    let rf = KeyedRangeFilter::new(records.iter().copied(), hasher);
+
+    for &record in &records {
+        let start = Record {
+            offset: record.offset.saturating_sub(100),
+            ..record
+        };
+        assert!(rf.query(start..=record));
+    }
+}
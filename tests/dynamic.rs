@@ -0,0 +1,78 @@
+use grafite::{DynamicRangeFilter, OrderPreservingHasher, RangeFilter};
+
+#[test]
+fn test_dynamic() {
+    let values: Vec<u64> = (0..2000).map(|x| x * 100).collect();
+    let (initial, inserted) = values.split_at(500);
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(initial.iter().copied(), hasher);
+
+    let mut dynamic = DynamicRangeFilter::with_merge_threshold(rf, 100);
+    for &x in inserted {
+        dynamic.insert(x);
+        assert!(dynamic.delta_len() <= 100);
+        assert!(dynamic.query(x..=x));
+    }
+    assert!(values
+        .iter()
+        .all(|&x| dynamic.query(x.saturating_sub(5)..x + 5)));
+
+    // Merging produces the same filter as building over every key at once.
+    let full = RangeFilter::new(values.iter().copied(), hasher);
+    let merged = dynamic.into_filter();
+    assert!(merged.iter_hashes().eq(full.iter_hashes()));
+}
+
+#[test]
+fn test_dynamic_across_blocks() {
+    // Keys on both sides of many block boundaries, half of which are only buffered.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let (initial, inserted) = values.split_at(50);
+
+    let rf = RangeFilter::new(initial.iter().copied(), hasher);
+    let mut dynamic = DynamicRangeFilter::with_merge_threshold(rf, 1000);
+    inserted.iter().for_each(|&x| dynamic.insert(x));
+
+    for &x in &values {
+        assert!(dynamic.query(x - 10..=x + 5));
+    }
+    assert!(dynamic.query(r..=3 * r));
+}
+
+#[test]
+fn test_dynamic_background_merge() {
+    let values: Vec<u64> = (0..5000).map(|x| x * 100).collect();
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(values[..1000].iter().copied(), hasher);
+
+    // Filling the buffer freezes it, and the merge is only swapped in by a later insert.
+    let mut dynamic = DynamicRangeFilter::with_merge_threshold(rf, 1000);
+    values[1000..2000].iter().for_each(|&x| dynamic.insert(x));
+    assert!(dynamic.is_merging());
+    assert_eq!(dynamic.delta_len(), 0);
+    assert!(values[..2000].iter().all(|&x| dynamic.query(x..=x)));
+
+    // A clone does not wait for the merge, and buffers its hashes instead.
+    let clone = dynamic.clone();
+    assert!(!clone.is_merging());
+    assert_eq!(clone.delta_len(), 1000);
+    assert!(values[..2000].iter().all(|&x| clone.query(x..=x)));
+
+    values[2000..].iter().for_each(|&x| dynamic.insert(x));
+    assert!(values.iter().all(|&x| dynamic.query(x..=x)));
+
+    dynamic.merge();
+    assert!(!dynamic.is_merging());
+    assert_eq!(dynamic.delta_len(), 0);
+
+    let full = RangeFilter::new(values.iter().copied(), hasher);
+    assert!(dynamic.filter().iter_hashes().eq(full.iter_hashes()));
+    assert!(clone.into_filter().iter_hashes().eq(RangeFilter::new(
+        values[..2000].iter().copied(),
+        hasher
+    )
+    .iter_hashes()));
+}
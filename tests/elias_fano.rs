@@ -0,0 +1,104 @@
+use grafite::{EliasFano, OrderPreservingHasher, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::borrow::Cow;
+
+/// Checks every query of the encoding of `values` against a plain sorted slice.
+fn check(values: &[u64]) {
+    let ef = EliasFano::from_slice(values);
+    assert_eq!(ef.len(), values.len());
+    assert!(ef.iter().eq(values.iter().copied()));
+    assert_eq!(ef.get(values.len()), None);
+
+    let mut probes: Vec<u64> = vec![0, 1, u64::MAX - 1, u64::MAX];
+    for &x in values.iter().step_by(values.len() / 500 + 1) {
+        probes.extend([x.wrapping_sub(1), x, x.wrapping_add(1)]);
+    }
+
+    for x in probes {
+        let rank = values.partition_point(|&v| v < x);
+        assert_eq!(ef.rank(x), rank, "rank of {x}");
+
+        let predecessor = values.iter().rev().find(|&&v| v <= x).copied();
+        assert_eq!(ef.predecessor(x), predecessor, "predecessor of {x}");
+        assert_eq!(
+            ef.successor(x),
+            values.get(rank).copied(),
+            "successor of {x}"
+        );
+    }
+
+    for (i, &x) in values.iter().enumerate().step_by(values.len() / 1000 + 1) {
+        assert_eq!(ef.get(i), Some(x));
+    }
+}
+
+#[test]
+fn test_elias_fano() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    check(&[]);
+    check(&[0]);
+    check(&[u64::MAX]);
+    check(&[0, u64::MAX]);
+    check(&[5, 5, 5, 5]);
+
+    for (len, max) in [
+        (10, 100),
+        (1000, 1000),
+        (10_000, 1 << 20),
+        (100_000, 1 << 40),
+        (5000, u64::MAX),
+    ] {
+        let mut values: Vec<u64> = (0..len).map(|_| rng.gen_range(0..max)).collect();
+        values.sort_unstable();
+        check(&values);
+
+        values.dedup();
+        check(&values);
+    }
+
+    // Dense values with long runs of ones in the high bits.
+    check(&(0..100_000).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_elias_fano_words() {
+    let values: Vec<u64> = (0..10_000).map(|x| x * 37).collect();
+    let ef = EliasFano::from_slice(&values);
+
+    let borrowed = EliasFano::from_words(ef.as_words()).unwrap();
+    assert!(borrowed.iter().eq(values.iter().copied()));
+
+    // Truncated or invalid words are rejected.
+    let words = ef.as_words();
+    assert!(EliasFano::from_words(&words[..words.len() - 1]).is_none());
+    assert!(EliasFano::from_words(&words[..3]).is_none());
+
+    let mut invalid = words.to_vec();
+    invalid[0] = 2;
+    assert!(EliasFano::from_words(invalid).is_none());
+}
+
+#[test]
+fn test_borrowed_backend() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    // A filter that queries words owned elsewhere without copying them.
+    let hashes: Vec<u64> = rf.iter_hashes().collect();
+    let ef = EliasFano::from_slice(&hashes);
+    let borrowed = RangeFilter::from_parts(
+        *rf.hasher(),
+        EliasFano::from_words(Cow::Borrowed(ef.as_words())).unwrap(),
+    )
+    .unwrap();
+
+    for start in (0..1_000_000).step_by(333) {
+        assert_eq!(
+            rf.query(start..start + 64),
+            borrowed.query(start..start + 64)
+        );
+    }
+}
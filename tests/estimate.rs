@@ -0,0 +1,34 @@
+use grafite::{OrderPreservingHasher, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_estimate_false_positive_rate() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let mut keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+    keys.sort_unstable();
+
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.05, 64, &mut rng).unwrap();
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+
+    let estimate = rf.estimate_false_positive_rate(&mut rng, 100_000, 64, &keys);
+    assert_eq!(estimate.empty_probes, 100_000);
+    assert!(estimate.lower <= estimate.rate && estimate.rate <= estimate.upper);
+    assert!(estimate.upper - estimate.lower < 0.01);
+
+    // The bound on the false positive rate holds for ranges of at most the maximum interval.
+    assert!(estimate.lower <= rf.false_positive_rate().unwrap());
+    assert!(estimate.false_positives > 0);
+
+    // Probes that contain a key are not counted.
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 10);
+    let keys = [0, 1 << 62, u64::MAX];
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+    let estimate = rf.estimate_false_positive_rate(&mut rng, 1000, u64::MAX, &keys);
+    assert_eq!(estimate.empty_probes, 0);
+    assert_eq!(
+        (estimate.rate, estimate.lower, estimate.upper),
+        (0.0, 0.0, 1.0)
+    );
+    assert!(estimate.contains(0.5));
+}
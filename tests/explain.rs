@@ -0,0 +1,118 @@
+use grafite::{OrderPreservingHasher, QueryExplanation, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Checks if `hash` is inside the hashed window `start_hash..=end_hash`, which may wrap around.
+fn in_window(hash: u64, start_hash: u64, end_hash: u64) -> bool {
+    if start_hash > end_hash {
+        hash >= start_hash || hash <= end_hash
+    } else {
+        start_hash <= hash && hash <= end_hash
+    }
+}
+
+#[test]
+fn test_query_explain() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..100).map(|_| rng.gen()).collect();
+
+    // A small reduced universe gives plenty of wrapped windows, and short ranges, which rarely
+    // span a whole block of it, give plenty of negatives.
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 12);
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+    let hashes: Vec<u64> = rf.iter_hashes().collect();
+
+    let (mut positives, mut negatives, mut wrapped) = (0, 0, 0);
+    for _ in 0..10_000 {
+        let start: u64 = rng.gen();
+        let end = start.saturating_add(rng.gen_range(0..32));
+
+        let explanation = rf.query_explain(start..=end);
+        assert_eq!(explanation.is_positive(), rf.query(start..=end));
+
+        match explanation {
+            QueryExplanation::Positive {
+                start_hash,
+                end_hash,
+                witness,
+            } => {
+                positives += 1;
+                assert_eq!(hashes[witness.index], witness.hash);
+                assert!(in_window(witness.hash, start_hash, end_hash));
+            }
+            QueryExplanation::Negative {
+                start_hash,
+                end_hash,
+                predecessor,
+            } => {
+                negatives += 1;
+                assert!(hashes.iter().all(|&h| !in_window(h, start_hash, end_hash)));
+
+                if start_hash > end_hash {
+                    wrapped += 1;
+                    assert_eq!(predecessor.unwrap().index, hashes.len() - 1);
+                } else if let Some(predecessor) = predecessor {
+                    assert_eq!(hashes[predecessor.index], predecessor.hash);
+                    assert!(predecessor.hash < start_hash);
+                    assert!(hashes
+                        .get(predecessor.index + 1)
+                        .is_none_or(|&h| h > end_hash));
+                } else {
+                    assert!(hashes[0] > end_hash);
+                }
+            }
+            QueryExplanation::Empty => unreachable!(),
+        }
+    }
+
+    assert!(positives > 0 && negatives > 0);
+    assert!(wrapped <= negatives);
+
+    assert_eq!(rf.query_explain(5..5), QueryExplanation::Empty);
+}
+
+#[test]
+fn test_query_explain_keys() {
+    let keys = [1, 2, 3, 7, 8, 9, 15, 20];
+    let hasher = OrderPreservingHasher::new(keys.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+
+    // Every query for a single key is positive, and the witness is the hash of that key.
+    for key in keys {
+        match rf.query_explain(key..=key) {
+            QueryExplanation::Positive { witness, .. } => {
+                assert_eq!(witness.hash, hasher.hash(key))
+            }
+            explanation => panic!("false negative for {key}: {explanation:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_query_explain_across_blocks() {
+    // Every block has `r = 80` keys, so `78..=85` crosses from the first block into the second.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = RangeFilter::new(std::iter::once(81), hasher);
+
+    match rf.query_explain(78..=85) {
+        QueryExplanation::Positive {
+            start_hash,
+            end_hash,
+            witness,
+        } => {
+            assert_eq!(witness.hash, hasher.hash(81));
+            assert_eq!((start_hash, end_hash), (hasher.hash(80), hasher.hash(85)));
+        }
+        explanation => panic!("false negative: {explanation:?}"),
+    }
+
+    // A range that contains an entire block is explained with the whole reduced universe.
+    match rf.query_explain(1_000..=1_200) {
+        QueryExplanation::Positive {
+            start_hash,
+            end_hash,
+            ..
+        } => assert_eq!((start_hash, end_hash), (0, 79)),
+        explanation => panic!("false negative: {explanation:?}"),
+    }
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "ffi")]
+
+use grafite::ffi::*;
+use grafite::RangeFilter;
+use std::ptr;
+
+#[test]
+fn test_ffi_roundtrip() {
+    let keys: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+
+    let mut filter = ptr::null_mut();
+    let status =
+        unsafe { grafite_filter_build(keys.as_ptr(), keys.len(), 0.01, 64, 7, &mut filter) };
+    assert_eq!(status, GrafiteStatus::Ok);
+    assert!(!filter.is_null());
+
+    let mut found = false;
+    for &key in &keys {
+        unsafe {
+            assert_eq!(
+                grafite_filter_contains(filter, key, &mut found),
+                GrafiteStatus::Ok
+            );
+            assert!(found);
+            assert_eq!(
+                grafite_filter_query(filter, key.saturating_sub(10), key, &mut found),
+                GrafiteStatus::Ok
+            );
+            assert!(found);
+        }
+    }
+
+    // Query the required size first, then serialize into a buffer of that size.
+    let mut len = 0;
+    let status = unsafe { grafite_filter_serialize(filter, ptr::null_mut(), &mut len) };
+    assert_eq!(status, GrafiteStatus::BufferTooSmall);
+
+    let mut buf = vec![0u8; len];
+    let status = unsafe { grafite_filter_serialize(filter, buf.as_mut_ptr(), &mut len) };
+    assert_eq!(status, GrafiteStatus::Ok);
+    assert_eq!(len, buf.len());
+    assert!(RangeFilter::read_from(buf.as_slice())
+        .unwrap()
+        .contains(5000));
+
+    let mut loaded = ptr::null_mut();
+    let status = unsafe { grafite_filter_deserialize(buf.as_ptr(), buf.len(), &mut loaded) };
+    assert_eq!(status, GrafiteStatus::Ok);
+    unsafe {
+        assert_eq!(
+            grafite_filter_contains(loaded, 5000, &mut found),
+            GrafiteStatus::Ok
+        );
+        assert!(found);
+        grafite_filter_free(loaded);
+        grafite_filter_free(filter);
+    }
+}
+
+#[test]
+fn test_ffi_errors() {
+    let mut filter = ptr::null_mut();
+    let keys = [1u64, 2, 3];
+
+    unsafe {
+        assert_eq!(
+            grafite_filter_build(keys.as_ptr(), keys.len(), 0.01, 64, 0, ptr::null_mut()),
+            GrafiteStatus::NullPointer
+        );
+        assert_eq!(
+            grafite_filter_build(ptr::null(), 0, 0.01, 64, 0, &mut filter),
+            GrafiteStatus::EmptyInput
+        );
+        assert_eq!(
+            grafite_filter_build(keys.as_ptr(), keys.len(), 2.0, 64, 0, &mut filter),
+            GrafiteStatus::InvalidParameters
+        );
+        assert!(filter.is_null());
+
+        let bytes = b"not a filter";
+        assert_eq!(
+            grafite_filter_deserialize(bytes.as_ptr(), bytes.len(), &mut filter),
+            GrafiteStatus::InvalidData
+        );
+
+        let mut found = false;
+        assert_eq!(
+            grafite_filter_query(ptr::null(), 0, 1, &mut found),
+            GrafiteStatus::NullPointer
+        );
+        grafite_filter_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,53 @@
+use grafite::{FloatRangeFilter, OrderPreservingHasher, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_float_keys() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let keys: Vec<f64> = (0..10_000).map(|_| rng.gen_range(-1e6..1e6)).collect();
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 1 << 16, &mut rng).unwrap();
+    let rf = FloatRangeFilter::new(keys.iter().copied(), hasher);
+
+    for &key in &keys {
+        assert!(rf.query(key..=key));
+
+        // A range of a few hundred representable values around the key.
+        let bits = key.abs().to_bits();
+        let below = f64::from_bits(bits - 100).copysign(key);
+        let above = f64::from_bits(bits + 100).copysign(key);
+        assert!(rf.query(below.min(above)..=below.max(above)));
+    }
+
+    assert!(!rf.query(1.0..1.0));
+    assert!(!rf.query(2.0..=1.0));
+}
+
+#[test]
+fn test_special_values() {
+    let hasher = OrderPreservingHasher::new(4, 0.01, 16).unwrap();
+    let rf = FloatRangeFilter::new(
+        [-0.0, f64::NEG_INFINITY, f64::INFINITY, -f64::NAN].into_iter(),
+        hasher,
+    );
+
+    // Negative zero is treated as positive zero.
+    assert!(rf.query(0.0..=0.0));
+    assert!(rf.query(-0.0..=-0.0));
+
+    assert!(rf.query(..=f64::NEG_INFINITY));
+    assert!(rf.query(f64::INFINITY..=f64::INFINITY));
+
+    // Every NaN is the same NaN, which is greater than infinity.
+    assert!(rf.query(f64::NAN..=f64::NAN));
+    assert!(rf.query((-f64::NAN)..=(-f64::NAN)));
+    assert!(rf.query(f64::INFINITY..=f64::NAN));
+
+    // The mapped keys can be persisted through the inner filter.
+    let mut bytes = Vec::new();
+    rf.filter().write_to(&mut bytes).unwrap();
+    let loaded = FloatRangeFilter::from_filter(RangeFilter::read_from(bytes.as_slice()).unwrap());
+    assert!(loaded.query(0.0..=0.0));
+    assert!(loaded.query(f64::NAN..=f64::NAN));
+}
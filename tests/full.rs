@@ -29,7 +29,7 @@ fn bench(num_elements: usize, bits_per_key: u8, max_interval: u64) {
     );
     println!(
         "Expected false positive rate: {}",
-        rf.false_positive_rate(num_elements, max_interval)
+        rf.false_positive_rate().unwrap()
     );
 
     values.sort_unstable();
@@ -0,0 +1,61 @@
+use grafite::{BuildError, HybridRangeFilter, OrderPreservingHasher, ParamError};
+
+#[test]
+fn test_hybrid() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 100).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.1, 64).unwrap();
+    let rf = HybridRangeFilter::new(values.iter().copied(), hasher, 0.001).unwrap();
+
+    assert!(values.iter().all(|&x| rf.contains(x)));
+    assert!(values.iter().all(|&x| rf.query(x..=x)));
+    assert!(values
+        .iter()
+        .all(|&x| rf.query(x.saturating_sub(10)..x + 10)));
+    assert!(!rf.query(5..5));
+
+    // The Bloom filter has a far lower false positive rate on point queries than the range filter.
+    let point_false_positives = (0..100_000)
+        .filter(|&i| i % 100 != 0 && rf.contains(i))
+        .count();
+    assert!(point_false_positives < 1_000);
+
+    let range = rf.range_filter();
+    assert!((0..1000).all(|i| range.query(i * 100..=i * 100)));
+}
+
+#[test]
+fn test_hybrid_long_ranges() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 100_000).collect();
+
+    let hasher = OrderPreservingHasher::new_seeded(values.len(), 0.01, 64, 7).unwrap();
+    let rf = HybridRangeFilter::new(values.iter().copied(), hasher, 0.001).unwrap();
+
+    // Ranges longer than the maximum range interval are split into sub-queries.
+    let range = rf.range_filter();
+    for &x in values.iter().step_by(10) {
+        assert!(rf.query(x.saturating_sub(5_000)..x + 5_000));
+        for (start, end) in [(x + 10, x + 1_000), (x + 1_000, x + 99_000)] {
+            assert_eq!(rf.query(start..end), range.query_chunked(start..end));
+        }
+    }
+
+    // Ranges that cross a block boundary are not missed.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = HybridRangeFilter::new([81], hasher, 0.001).unwrap();
+    assert!(rf.query(78..=85));
+    assert!(rf.query(..));
+}
+
+#[test]
+fn test_hybrid_invalid() {
+    let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    assert!(matches!(
+        HybridRangeFilter::new([1, 2, 3], hasher, 0.0),
+        Err(BuildError::Param(ParamError::InvalidEpsilon(_)))
+    ));
+    assert!(matches!(
+        HybridRangeFilter::new([], hasher, 0.01),
+        Err(BuildError::EmptyInput)
+    ));
+}
@@ -0,0 +1,65 @@
+use grafite::{ipv4_cidr, ipv6_cidr, KeyedRangeFilter, OrderPreservingHasher, RangeFilter128};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[test]
+fn test_cidr_ranges() {
+    let addr = Ipv4Addr::new(172, 16, 5, 4);
+    assert_eq!(ipv4_cidr(addr, 32), addr..=addr);
+    assert_eq!(
+        ipv4_cidr(addr, 12),
+        Ipv4Addr::new(172, 16, 0, 0)..=Ipv4Addr::new(172, 31, 255, 255)
+    );
+    assert_eq!(
+        ipv4_cidr(addr, 0),
+        Ipv4Addr::UNSPECIFIED..=Ipv4Addr::BROADCAST
+    );
+
+    let addr: Ipv6Addr = "2001:db8::1".parse().unwrap();
+    let block = ipv6_cidr(addr, 32);
+    assert_eq!(
+        *block.start(),
+        u128::from("2001:db8::".parse::<Ipv6Addr>().unwrap())
+    );
+    assert_eq!(
+        *block.end(),
+        u128::from(
+            "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"
+                .parse::<Ipv6Addr>()
+                .unwrap()
+        )
+    );
+    assert_eq!(ipv6_cidr(addr, 128), u128::from(addr)..=u128::from(addr));
+}
+
+#[test]
+#[should_panic]
+fn test_invalid_prefix() {
+    ipv4_cidr(Ipv4Addr::LOCALHOST, 33);
+}
+
+#[test]
+fn test_ip_filters() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let addrs: Vec<Ipv4Addr> = (0..10_000)
+        .map(|_| Ipv4Addr::from(rng.gen::<u32>()))
+        .collect();
+    let hasher = OrderPreservingHasher::new_with_rng(addrs.len(), 0.01, 1 << 8, &mut rng).unwrap();
+    let rf = KeyedRangeFilter::new(addrs.iter().copied(), hasher);
+    for &addr in &addrs {
+        assert!(rf.query(addr..=addr));
+        assert!(rf.query_cidr(addr, 24));
+    }
+
+    let addrs: Vec<Ipv6Addr> = (0..10_000)
+        .map(|_| Ipv6Addr::from(rng.gen::<u128>()))
+        .collect();
+    let hasher =
+        OrderPreservingHasher::new_u128_with_rng(addrs.len(), 0.01, 1 << 16, &mut rng).unwrap();
+    let rf = RangeFilter128::new(addrs.iter().map(|&addr| u128::from(addr)), hasher);
+    for &addr in &addrs {
+        assert!(rf.query_ipv6_cidr(addr, 112));
+    }
+}
@@ -0,0 +1,102 @@
+use grafite::{EliasFano, KeyedRangeFilter, OrderPreservingHasher, ToOrderedU64};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::cmp::Reverse;
+
+/// Checks that the mapping of every pair of keys has the same order as the keys themselves.
+fn check_order<K: ToOrderedU64 + PartialOrd>(keys: &[K]) {
+    for a in keys {
+        for b in keys {
+            if a <= b {
+                assert!(a.to_ordered_u64() <= b.to_ordered_u64());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ordered_mappings() {
+    check_order(&[0u8, 1, 127, 128, 255]);
+    check_order(&[i8::MIN, -1, 0, 1, i8::MAX]);
+    check_order(&[i32::MIN, -70000, -1, 0, 1, 70000, i32::MAX]);
+    check_order(&[false, true]);
+    check_order(&['\0', 'a', 'z', '\u{10FFFF}']);
+    check_order(&[
+        f32::NEG_INFINITY,
+        f32::MIN,
+        -1.0,
+        -f32::MIN_POSITIVE,
+        -0.0,
+        0.0,
+        f32::MIN_POSITIVE,
+        1.0,
+        f32::MAX,
+        f32::INFINITY,
+    ]);
+    check_order(&[Reverse(5u64), Reverse(3), Reverse(0)]);
+
+    // Different widths of the same signedness are mapped onto the same integers.
+    assert_eq!((-5i8).to_ordered_u64(), (-5i64).to_ordered_u64());
+    assert_eq!(1.5f32.to_ordered_u64(), 1.5f64.to_ordered_u64());
+}
+
+#[test]
+fn test_keyed_filters() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let keys: Vec<i32> = (0..10_000).map(|_| rng.gen()).collect();
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let rf = KeyedRangeFilter::new(keys.iter().copied(), hasher);
+    for &key in &keys {
+        assert!(rf.query(key..=key));
+        assert!(rf.query(key.saturating_sub(10)..key.saturating_add(10)));
+    }
+
+    // Reversed keys are queried with reversed ranges.
+    let rf = KeyedRangeFilter::new(keys.iter().map(|&k| Reverse(k)), hasher);
+    for &key in &keys {
+        assert!(rf.query(Reverse(key.saturating_add(5))..=Reverse(key)));
+    }
+
+    // Any backend can store the mapped keys.
+    let rf: KeyedRangeFilter<i32, EliasFano> =
+        KeyedRangeFilter::try_with_backend(keys.iter().copied(), hasher).unwrap();
+    for &key in &keys {
+        assert!(rf.query(key..=key));
+    }
+    assert!(rf.filter().heap_size() > 0);
+}
+
+#[test]
+fn test_byte_string_keys() {
+    check_order(&[
+        &b""[..],
+        b"\0",
+        b"a",
+        b"ab",
+        b"abcdefgh",
+        b"abd",
+        b"b",
+        b"\xff\xff",
+    ]);
+    check_order(&["", "a", "apple", "banana", "\u{e9}t\u{e9}"]);
+
+    // Only the first eight bytes are mapped.
+    assert_eq!("abcdefgh1".to_ordered_u64(), "abcdefgh2".to_ordered_u64());
+    assert_eq!(b"ab".to_vec().to_ordered_u64(), "ab".to_ordered_u64());
+
+    let keys: Vec<String> = (0..1000).map(|i| format!("key:{i:04}")).collect();
+    let hasher = OrderPreservingHasher::new(keys.len(), 0.01, 1 << 24).unwrap();
+    let rf = KeyedRangeFilter::new(keys.iter().cloned(), hasher);
+
+    for key in &keys {
+        assert!(rf.query(key.clone()..=key.clone()));
+        assert!(rf.query_prefix(key.as_bytes()));
+        assert!(rf.query_prefix(&key.as_bytes()[..6]));
+    }
+
+    // Borrowed keys can be queried with borrowed ranges.
+    let rf = KeyedRangeFilter::new(keys.iter().map(String::as_str), hasher);
+    assert!(rf.query("key:0500"..="key:0500"));
+    assert!(rf.query_prefix(b"key:05"));
+}
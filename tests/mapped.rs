@@ -0,0 +1,105 @@
+#![cfg(feature = "mmap")]
+
+use grafite::{MappedRangeFilter, OrderPreservingHasher, PersistError, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fs::File;
+use std::path::PathBuf;
+
+/// Writes `bytes` to a file in the temporary directory that is unique to this test process.
+fn write_temp(name: &str, bytes: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("grafite-{}-{name}", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+#[test]
+fn test_mapped_queries() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen()).collect();
+
+    let compact = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let wide = OrderPreservingHasher::new_with_reduced_rng(1 << 40, &mut rng);
+
+    for (name, hasher) in [("compact", compact), ("wide", wide)] {
+        let rf = RangeFilter::new(keys.iter().copied(), hasher);
+        let mut bytes = Vec::new();
+        rf.write_to(&mut bytes).unwrap();
+        let path = write_temp(name, &bytes);
+
+        let file = File::open(&path).unwrap();
+        let mapped = unsafe { MappedRangeFilter::map(&file) }.unwrap();
+        assert_eq!(mapped.len(), rf.iter_hashes().count());
+        assert_eq!(
+            mapped.hasher().reduced_universe(),
+            rf.hasher().reduced_universe()
+        );
+        mapped.verify().unwrap();
+
+        for &key in keys.iter().take(10_000) {
+            mapped.prefetch(key..=key);
+            assert!(mapped.query(key..=key));
+            assert!(mapped.query(key.saturating_sub(20)..=key));
+        }
+        for _ in 0..10_000 {
+            let start = rng.gen::<u64>();
+            let end = start.saturating_add(rng.gen_range(0..100));
+            mapped.prefetch(start..=end);
+            assert_eq!(mapped.query(start..=end), rf.query(start..=end));
+        }
+        assert!(!mapped.query(5..5));
+        assert_eq!(mapped.query(..), rf.query(..));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn test_mapped_across_blocks() {
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+    let path = write_temp("blocks", &bytes);
+    let file = File::open(&path).unwrap();
+    let mapped = unsafe { MappedRangeFilter::map(&file) }.unwrap();
+
+    for start in (r - 20..51 * r).step_by(13) {
+        for end in [start, start + 15, start + r, start + 3 * r] {
+            mapped.prefetch(start..=end);
+            assert_eq!(mapped.query(start..=end), rf.query(start..=end));
+        }
+    }
+    assert!(mapped.query(r - 3..=r + 3));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_mapped_truncated() {
+    let hasher = OrderPreservingHasher::new(1000, 0.01, 64).unwrap();
+    let rf = RangeFilter::new(0..1000, hasher);
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+
+    let path = write_temp("truncated", &bytes[..bytes.len() - 10]);
+    let file = File::open(&path).unwrap();
+    let mapped = unsafe { MappedRangeFilter::map(&file) };
+    assert!(matches!(mapped, Err(PersistError::Io(_))));
+
+    // A corrupted hash value is only detected by verifying the whole file.
+    let mut corrupted = bytes.clone();
+    let last = corrupted.len() - 5;
+    corrupted[last] ^= 0xff;
+    let path_corrupted = write_temp("corrupted", &corrupted);
+    let file = File::open(&path_corrupted).unwrap();
+    let mapped = unsafe { MappedRangeFilter::map(&file) }.unwrap();
+    assert!(mapped.verify().is_err());
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(path_corrupted).unwrap();
+}
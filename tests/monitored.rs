@@ -0,0 +1,133 @@
+use grafite::{HashedWindow, MonitoredRangeFilter, OrderPreservingHasher, RangeFilter};
+
+#[test]
+fn test_metrics() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new_seeded(values.len(), 0.01, 64, 7).unwrap();
+    let rf = MonitoredRangeFilter::new(RangeFilter::new(values.iter().copied(), hasher));
+
+    let metrics = rf.metrics();
+    assert_eq!(metrics.queries, 0);
+    assert_eq!(metrics.positive_rate(), 0.0);
+    assert_eq!(metrics.false_discovery_rate(), 0.0);
+
+    // Every query is counted, and the caller reports the positives that contain no keys.
+    let mut expected_positives = 0;
+    let mut expected_false_positives = 0;
+    for start in (0..1_000_000).step_by(10) {
+        let range = start..start + 10;
+        if rf.query(range.clone()) {
+            expected_positives += 1;
+            if !values.iter().any(|v| range.contains(v)) {
+                rf.report_false_positive(range.clone());
+                expected_false_positives += 1;
+            }
+        }
+    }
+    assert!(rf.contains(1000));
+
+    let metrics = rf.metrics();
+    assert_eq!(metrics.queries, 100_001);
+    assert_eq!(metrics.positives, expected_positives + 1);
+    assert_eq!(metrics.false_positives, expected_false_positives);
+    assert!(metrics.false_positives < metrics.positives);
+    assert_eq!(
+        metrics.false_discovery_rate(),
+        metrics.false_positives as f64 / metrics.positives as f64
+    );
+
+    // Queries made directly on the filter are not counted.
+    rf.filter().query(0..10);
+    assert_eq!(rf.take_metrics(), metrics);
+    assert_eq!(rf.metrics(), Default::default());
+}
+
+#[test]
+fn test_metrics_shared() {
+    let hasher = OrderPreservingHasher::new(4, 0.01, 20).unwrap();
+    let rf = MonitoredRangeFilter::new(RangeFilter::new([1, 2, 3, 7].into_iter(), hasher));
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    assert!(rf.query(3..5));
+                }
+            });
+        }
+    });
+
+    let metrics = rf.metrics();
+    assert_eq!((metrics.queries, metrics.positives), (4000, 4000));
+    assert_eq!(metrics.positive_rate(), 1.0);
+}
+
+#[test]
+fn test_false_positive_windows() {
+    let hasher = OrderPreservingHasher::new_seeded(4, 0.01, 20, 3).unwrap();
+    let rf = MonitoredRangeFilter::with_feedback_capacity(
+        RangeFilter::new([1, 2, 3, 7].into_iter(), hasher),
+        2,
+    );
+    let hasher = *rf.filter().hasher();
+
+    rf.report_false_positive(10..20);
+    rf.report_false_positive(30..=40);
+    rf.report_false_positive(50..);
+    // Empty ranges are ignored.
+    rf.report_false_positive(60..60);
+
+    assert_eq!(rf.metrics().false_positives, 3);
+    assert_eq!(
+        rf.false_positive_windows(),
+        [
+            HashedWindow {
+                start: 30,
+                end: 40,
+                start_hash: hasher.hash(30),
+                end_hash: hasher.hash(40),
+            },
+            // The range contains entire blocks, so it covers every hash.
+            HashedWindow {
+                start: 50,
+                end: u64::MAX,
+                start_hash: 0,
+                end_hash: hasher.reduced_universe() - 1,
+            },
+        ]
+    );
+
+    // Only the count is kept when the capacity is zero.
+    let rf = MonitoredRangeFilter::with_feedback_capacity(rf.into_filter(), 0);
+    rf.report_false_positive(10..20);
+    assert_eq!(rf.metrics().false_positives, 1);
+    assert!(rf.false_positive_windows().is_empty());
+}
+
+#[test]
+fn test_false_positive_windows_across_blocks() {
+    // The reduced universe is 80, so keys 0..80 and 80..160 are in different blocks.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let r = hasher.reduced_universe();
+    assert_eq!(r, 80);
+    let rf = MonitoredRangeFilter::new(RangeFilter::new([1_000].into_iter(), hasher));
+
+    rf.report_false_positive(78..=85);
+    assert_eq!(
+        rf.false_positive_windows(),
+        [
+            HashedWindow {
+                start: 78,
+                end: 79,
+                start_hash: hasher.hash(78),
+                end_hash: hasher.hash(79),
+            },
+            HashedWindow {
+                start: 80,
+                end: 85,
+                start_hash: hasher.hash(80),
+                end_hash: hasher.hash(85),
+            },
+        ]
+    );
+}
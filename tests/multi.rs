@@ -0,0 +1,85 @@
+use grafite::{BuildError, MultiFilter, PersistError};
+
+fn groups() -> Vec<(u64, Vec<u64>)> {
+    (0..10)
+        .map(|id| (id, (0..100).map(|x| id * 1_000_000 + x * 100).collect()))
+        .collect()
+}
+
+#[test]
+fn test_multi() {
+    let filters: MultiFilter = MultiFilter::build(groups(), 0.01, 64).unwrap();
+    assert_eq!(filters.len(), 10);
+
+    for (id, keys) in groups() {
+        for &key in &keys {
+            assert_eq!(filters.query(&id, key..=key), Some(true));
+            assert!(filters.candidates(key..=key).any(|&c| c == id));
+        }
+    }
+    assert_eq!(filters.query(&10, 0..100), None);
+    assert_eq!(filters.candidates(5..5).count(), 0);
+}
+
+#[test]
+fn test_multi_wide_range() {
+    let filters: MultiFilter = MultiFilter::build(groups(), 0.01, 64).unwrap();
+
+    // Ranges much wider than the maximum interval of 64 keys.
+    for (id, keys) in groups() {
+        for &key in &keys {
+            for width in [1_000, 100_000] {
+                let range = key.saturating_sub(width)..=key + width;
+                assert!(filters.candidates(range).any(|&c| c == id));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_multi_string_ids() {
+    let groups = [("default", vec![1, 2, 3]), ("users", vec![100, 200, 300])];
+    let mut filters: MultiFilter<String> =
+        MultiFilter::build(groups.map(|(id, keys)| (id.to_string(), keys)), 0.01, 20).unwrap();
+
+    assert_eq!(filters.query("users", 200..201), Some(true));
+    assert!(filters.remove("default").is_some());
+    assert_eq!(filters.query("default", 1..2), None);
+}
+
+#[test]
+fn test_multi_roundtrip() {
+    let filters: MultiFilter = MultiFilter::build(groups(), 0.01, 64).unwrap();
+
+    let mut bytes = Vec::new();
+    filters.write_to(&mut bytes).unwrap();
+    let loaded: MultiFilter = MultiFilter::read_from(bytes.as_slice()).unwrap();
+
+    assert_eq!(loaded.len(), filters.len());
+    for ((id, filter), (loaded_id, loaded_filter)) in filters.iter().zip(loaded.iter()) {
+        assert_eq!(id, loaded_id);
+        assert!(filter.iter_hashes().eq(loaded_filter.iter_hashes()));
+    }
+
+    let strings: MultiFilter<String> =
+        MultiFilter::build([("a".to_string(), [1, 2, 3])], 0.01, 20).unwrap();
+    let mut bytes = Vec::new();
+    strings.write_to(&mut bytes).unwrap();
+    let loaded: MultiFilter<String> = MultiFilter::read_from(bytes.as_slice()).unwrap();
+    assert_eq!(loaded.query("a", 2..3), Some(true));
+
+    // A corrupted id is detected by the checksum of the ids.
+    bytes[20] ^= 1;
+    assert!(matches!(
+        MultiFilter::<String>::read_from(bytes.as_slice()),
+        Err(PersistError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_multi_invalid() {
+    assert!(matches!(
+        MultiFilter::<u64>::build([(1, vec![])], 0.01, 20),
+        Err(BuildError::Param(_) | BuildError::EmptyInput)
+    ));
+}
@@ -0,0 +1,64 @@
+use grafite::{BuildError, ParamError, RangeFilterBuilder};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_narrowed() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let base = 1 << 50;
+    let keys: Vec<u64> = (0..1000)
+        .map(|_| base + rng.gen_range(0..1 << 30))
+        .collect();
+
+    let builder = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(64)
+        .seed(1);
+    let narrowed = builder.build_narrowed(keys.iter().copied()).unwrap();
+    let plain = builder.build(keys.iter().copied()).unwrap();
+
+    assert_eq!(narrowed.min_key(), *keys.iter().min().unwrap());
+    assert_eq!(narrowed.max_key(), *keys.iter().max().unwrap());
+    assert_eq!(
+        narrowed.filter().iter_hashes().count(),
+        plain.iter_hashes().count()
+    );
+
+    for &key in &keys {
+        assert!(narrowed.contains(key));
+        assert!(narrowed.query(key - 10..key + 10));
+    }
+    assert!(narrowed.query(..));
+    assert!(!narrowed.query(..narrowed.min_key()));
+    assert!(!narrowed.query(narrowed.max_key() + 1..));
+
+    // Ranges outside of the span of the keys are never false positives.
+    let (mut narrowed_positives, mut plain_positives) = (0, 0);
+    for _ in 0..100_000 {
+        let start: u64 = rng.gen_range(0..u64::MAX - 64);
+        if !(base..base + (1 << 31)).contains(&start) {
+            narrowed_positives += narrowed.query(start..start + 64) as usize;
+            plain_positives += plain.query(start..start + 64) as usize;
+        }
+    }
+    assert_eq!(narrowed_positives, 0);
+    assert!(plain_positives > 0);
+}
+
+#[test]
+fn test_narrowed_invalid() {
+    let builder = RangeFilterBuilder::new().epsilon(0.01).max_interval(64);
+    assert!(matches!(
+        builder.build_narrowed([]),
+        Err(BuildError::EmptyInput)
+    ));
+
+    // The maximum interval is checked against the span of the keys.
+    assert!(builder.build_narrowed([5, 10, 15]).is_err());
+    assert!(builder.build_narrowed([0, u64::MAX]).is_ok());
+
+    assert!(matches!(
+        builder.build_narrowed([7]),
+        Err(BuildError::Param(ParamError::InvalidMaxInterval(0)))
+    ));
+}
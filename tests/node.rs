@@ -0,0 +1,62 @@
+#![cfg(feature = "node")]
+
+use grafite::{NodeRangeFilter, OrderPreservingHasher, RangeFilter};
+use napi::bindgen_prelude::{BigInt, BigUint64Array};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_node_filter() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+
+    let build = || {
+        NodeRangeFilter::build(
+            BigUint64Array::new(keys.clone()),
+            0.01,
+            BigInt::from(64u64),
+            BigInt::from(7u64),
+        )
+        .unwrap()
+    };
+
+    let rf = build();
+    assert_eq!(rf.len() as usize, rf.filter().iter_hashes().count());
+    assert!(!rf.is_empty());
+
+    for &key in &keys {
+        assert!(rf.contains(BigInt::from(key)).unwrap());
+        assert!(rf
+            .query(BigInt::from(key.saturating_sub(10)), BigInt::from(key))
+            .unwrap());
+    }
+
+    // Keys must be unsigned 64-bit integers.
+    let negative = BigInt {
+        sign_bit: true,
+        words: vec![1],
+    };
+    assert!(rf.contains(negative).is_err());
+    let wide = BigInt {
+        sign_bit: false,
+        words: vec![1, 1],
+    };
+    assert!(rf.contains(wide).is_err());
+
+    // The same seed produces the same filter, which survives a round trip.
+    let bytes = rf.serialize();
+    assert_eq!(bytes.as_ref(), build().serialize().as_ref());
+
+    let loaded = NodeRangeFilter::deserialize(bytes.to_vec().into()).unwrap();
+    assert_eq!(loaded.filter().seed(), Some(7));
+    assert_eq!(
+        RangeFilter::read_from(bytes.as_ref()).unwrap().heap_size() as f64,
+        loaded.heap_size()
+    );
+
+    assert!(NodeRangeFilter::deserialize(b"NOPE".to_vec().into()).is_err());
+
+    let hasher = OrderPreservingHasher::new_seeded(keys.len(), 0.01, 64, 7).unwrap();
+    let wrapped = NodeRangeFilter::from_filter(RangeFilter::new(keys.iter().copied(), hasher));
+    assert_eq!(wrapped.serialize().as_ref(), bytes.as_ref());
+}
@@ -0,0 +1,34 @@
+use grafite::{BuildError, PartitionedRangeFilter};
+
+#[test]
+fn test_partitioned() {
+    let values: Vec<u64> = (0..5000).map(|x| x * 1000).collect();
+
+    let rf = PartitionedRangeFilter::new(values.iter().copied(), 0.01, 1 << 12, 512).unwrap();
+    assert_eq!(rf.partitions().len(), 10);
+    assert_eq!(rf.index()[1], values[512]);
+
+    // There are no false negatives, including for ranges that span several partitions.
+    for start in (0..5_000_000).step_by(997) {
+        for len in [1, 100, 1 << 12, 1 << 20] {
+            let end = start + len - 1;
+            if values.iter().any(|&x| start <= x && x <= end) {
+                assert!(rf.query(start..=end));
+            }
+        }
+    }
+
+    // Keys before the first partition are never present.
+    let rf = PartitionedRangeFilter::new([100, 200, 300], 0.01, 32, 2).unwrap();
+    assert!(!rf.query(0..100));
+    assert!(rf.query(0..=100));
+    assert!(!rf.query(5..5));
+}
+
+#[test]
+fn test_partitioned_invalid() {
+    assert!(matches!(
+        PartitionedRangeFilter::new([], 0.01, 32, 16),
+        Err(BuildError::EmptyInput)
+    ));
+}
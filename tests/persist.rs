@@ -0,0 +1,267 @@
+use grafite::persist::{read_metadata, serialized_len, LEN_PREFIX_BYTES};
+use grafite::{FilterMetadata, OrderPreservingHasher, PersistError, RangeFilter};
+
+fn build() -> RangeFilter {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    RangeFilter::new(values.iter().copied(), hasher)
+}
+
+#[test]
+fn test_roundtrip() {
+    let rf = build();
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+    let loaded = RangeFilter::read_from(bytes.as_slice()).unwrap();
+
+    assert_eq!(
+        loaded.backend().iter().collect::<Vec<_>>(),
+        rf.backend().iter().collect::<Vec<_>>()
+    );
+    for start in 0..25 {
+        for end in start + 1..30 {
+            assert_eq!(loaded.query(start..end), rf.query(start..end));
+        }
+    }
+
+    assert_eq!(loaded.num_elements(), Some(8));
+    assert_eq!(loaded.max_interval(), Some(20));
+    assert_eq!(loaded.false_positive_rate(), rf.false_positive_rate());
+}
+
+#[test]
+fn test_unsupported_version() {
+    let rf = build();
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+    bytes[4..8].copy_from_slice(&2u32.to_le_bytes());
+
+    assert!(matches!(
+        RangeFilter::read_from(bytes.as_slice()),
+        Err(PersistError::UnsupportedVersion(2))
+    ));
+}
+
+#[test]
+fn test_dense_roundtrip() {
+    let values: Vec<u64> = (0..10_000).collect();
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 14);
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    assert!(rf.backend().is_dense());
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+    let loaded = RangeFilter::read_from(bytes.as_slice()).unwrap();
+    assert!(loaded.backend().is_dense());
+    assert!(loaded.iter_hashes().eq(rf.iter_hashes()));
+}
+
+#[test]
+fn test_compact_hashes() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+
+    let small = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let large = OrderPreservingHasher::new_with_reduced(1 << 40);
+    assert!(small.fits_in_u32());
+    assert!(!large.fits_in_u32());
+
+    for (hasher, compact) in [(small, true), (large, false)] {
+        let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+        let mut bytes = Vec::new();
+        rf.write_to(&mut bytes).unwrap();
+        let loaded = RangeFilter::read_from(bytes.as_slice()).unwrap();
+        assert!(loaded.iter_hashes().eq(rf.iter_hashes()));
+
+        // 32-bit hash values take half as many bytes as 64-bit ones.
+        let n = rf.iter_hashes().count();
+        assert_eq!(bytes.len() < 8 * n, compact);
+    }
+}
+
+#[test]
+fn test_corruption_detected() {
+    let rf = build();
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+
+    // Flip a bit inside the stored hash values.
+    let len = bytes.len();
+    bytes[len - 8] ^= 1;
+
+    assert!(matches!(
+        RangeFilter::read_from(bytes.as_slice()),
+        Err(PersistError::ChecksumMismatch { .. })
+    ));
+
+    assert!(matches!(
+        RangeFilter::read_from(&b"NOPE"[..]),
+        Err(PersistError::InvalidMagic)
+    ));
+
+    bytes.truncate(len - 2);
+    assert!(matches!(
+        RangeFilter::read_from(bytes.as_slice()),
+        Err(PersistError::Io(_))
+    ));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compressed_roundtrip() {
+    let rf = build();
+
+    let mut bytes = Vec::new();
+    rf.write_to_compressed(&mut bytes, 0).unwrap();
+    let loaded = RangeFilter::read_from_compressed(bytes.as_slice()).unwrap();
+
+    assert_eq!(
+        loaded.backend().iter().collect::<Vec<_>>(),
+        rf.backend().iter().collect::<Vec<_>>()
+    );
+    assert!(loaded.query(3..5));
+    assert!(!loaded.query(10..15));
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn test_postcard_roundtrip() {
+    let rf = build();
+
+    let bytes = rf.to_postcard().unwrap();
+    let loaded = RangeFilter::from_postcard(&bytes).unwrap();
+    assert_eq!(
+        loaded.backend().iter().collect::<Vec<_>>(),
+        rf.backend().iter().collect::<Vec<_>>()
+    );
+
+    let mut buf = [0; 256];
+    let used = rf.to_postcard_slice(&mut buf).unwrap();
+    assert_eq!(used, bytes.as_slice());
+
+    assert_eq!(loaded.max_interval(), Some(20));
+
+    assert!(RangeFilter::from_postcard(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn test_metadata() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+    let rf = build();
+
+    let metadata = FilterMetadata::new(FilterMetadata::fingerprint(values));
+    assert_eq!(
+        metadata.source_fingerprint,
+        FilterMetadata::fingerprint(values.into_iter().rev())
+    );
+
+    let mut bytes = Vec::new();
+    rf.write_to_with_metadata(&mut bytes, &metadata).unwrap();
+
+    assert_eq!(read_metadata(bytes.as_slice()).unwrap(), Some(metadata));
+    let (loaded, loaded_metadata) = RangeFilter::read_from_with_metadata(bytes.as_slice()).unwrap();
+    assert_eq!(loaded_metadata, Some(metadata));
+    assert!(loaded.query(3..5));
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+    assert_eq!(read_metadata(bytes.as_slice()).unwrap(), None);
+}
+
+#[test]
+fn test_seed_persisted() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+    let hasher = OrderPreservingHasher::new_seeded(values.len(), 0.01, 20, 99).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let mut bytes = Vec::new();
+    rf.write_to_with_metadata(&mut bytes, &FilterMetadata::new(0))
+        .unwrap();
+    let loaded = RangeFilter::read_from(bytes.as_slice()).unwrap();
+
+    assert_eq!(loaded.seed(), Some(99));
+    assert_eq!(loaded.hasher().hash(12345), rf.hasher().hash(12345));
+}
+
+#[test]
+fn test_serialized_len() {
+    let values: Vec<u64> = (0..10_000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new_seeded(values.len(), 0.01, 64, 7).unwrap();
+    let large = RangeFilter::new(values.into_iter(), hasher);
+
+    for filter in [build(), large] {
+        let mut bytes = Vec::new();
+        filter
+            .write_to_with_metadata(&mut bytes, &FilterMetadata::new(1))
+            .unwrap();
+
+        let prefix = &bytes[..bytes.len().min(LEN_PREFIX_BYTES)];
+        assert_eq!(serialized_len(prefix).unwrap(), bytes.len() as u64);
+    }
+
+    assert!(matches!(serialized_len(b"GRAF"), Err(PersistError::Io(_))));
+    assert!(matches!(
+        serialized_len(b"NOPE"),
+        Err(PersistError::InvalidMagic)
+    ));
+}
+
+#[test]
+fn test_keyed_persisted() {
+    let key = [7; 32];
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+    let hasher = OrderPreservingHasher::new_keyed(values.len(), 0.01, 20, &key).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let mut bytes = Vec::new();
+    rf.write_to(&mut bytes).unwrap();
+
+    // The constants are not written, so the filter can only be read with the key.
+    for param in [hasher.c1(), hasher.c2(), hasher.p()] {
+        assert!(!bytes.windows(8).any(|w| w == param.to_le_bytes()));
+    }
+    assert!(matches!(
+        RangeFilter::read_from(bytes.as_slice()),
+        Err(PersistError::KeyRequired)
+    ));
+    assert!(matches!(
+        RangeFilter::read_from_keyed(bytes.as_slice(), &[8; 32]),
+        Err(PersistError::KeyMismatch)
+    ));
+
+    let loaded = RangeFilter::read_from_keyed(bytes.as_slice(), &key).unwrap();
+    assert!(loaded.hasher().is_keyed());
+    assert!(loaded.iter_hashes().eq(rf.iter_hashes()));
+    assert_eq!(loaded.hasher().hash(12345), hasher.hash(12345));
+    assert_eq!(loaded.max_interval(), Some(20));
+    assert_eq!(serialized_len(&bytes).unwrap(), bytes.len() as u64);
+
+    // The key is ignored for filters whose hash function is not keyed.
+    let mut bytes = Vec::new();
+    build().write_to(&mut bytes).unwrap();
+    assert!(RangeFilter::read_from_keyed(bytes.as_slice(), &key).is_ok());
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn test_keyed_postcard() {
+    let key = [7; 32];
+    let hasher = OrderPreservingHasher::new_with_reduced_keyed(1 << 40, &key);
+    let rf = RangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), hasher);
+
+    let bytes = rf.to_postcard().unwrap();
+    assert!(matches!(
+        RangeFilter::from_postcard(&bytes),
+        Err(PersistError::KeyRequired)
+    ));
+    assert!(matches!(
+        RangeFilter::from_postcard_keyed(&bytes, &[8; 32]),
+        Err(PersistError::KeyMismatch)
+    ));
+
+    let loaded = RangeFilter::from_postcard_keyed(&bytes, &key).unwrap();
+    assert!(loaded.iter_hashes().eq(rf.iter_hashes()));
+}
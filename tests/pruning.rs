@@ -0,0 +1,183 @@
+#![cfg(feature = "datafusion")]
+
+use datafusion::arrow::array::BooleanArray;
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::common::{Column, ScalarValue};
+use datafusion::logical_expr::Operator;
+use datafusion::physical_expr::expressions::{self, BinaryExpr, Literal};
+use datafusion::physical_expr::PhysicalExpr;
+use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+use grafite::{OrderPreservingHasher, RangeFilter, RowGroupFilters, ToOrderedU64};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Builds one filter per row group over signed keys, where an empty row group has no filter.
+fn filters(groups: &[&[i64]], max_interval: u64) -> RowGroupFilters {
+    let filters = groups
+        .iter()
+        .map(|keys| {
+            (!keys.is_empty()).then(|| {
+                let hasher =
+                    OrderPreservingHasher::new_seeded(keys.len(), 0.01, max_interval, 7).unwrap();
+                RangeFilter::new(keys.iter().map(ToOrderedU64::to_ordered_u64), hasher)
+            })
+        })
+        .collect();
+
+    RowGroupFilters::from_filters(filters)
+}
+
+fn column(name: &str) -> Arc<dyn PhysicalExpr> {
+    let index = if name == "ts" { 0 } else { 1 };
+    Arc::new(expressions::Column::new(name, index))
+}
+
+fn lit(value: ScalarValue) -> Arc<dyn PhysicalExpr> {
+    Arc::new(Literal::new(value))
+}
+
+fn binary(
+    left: Arc<dyn PhysicalExpr>,
+    op: Operator,
+    right: Arc<dyn PhysicalExpr>,
+) -> Arc<dyn PhysicalExpr> {
+    Arc::new(BinaryExpr::new(left, op, right))
+}
+
+fn predicate(expr: Arc<dyn PhysicalExpr>) -> PruningPredicate {
+    let schema = Schema::new(vec![
+        Field::new("ts", DataType::Int64, false),
+        Field::new("id", DataType::UInt32, true),
+    ]);
+    PruningPredicate::try_new(expr, Arc::new(schema)).unwrap()
+}
+
+#[test]
+fn test_contained() {
+    let filters = filters(&[&[-5, 0, 5], &[1_000, 2_000], &[]], 16);
+    let statistics = filters.pruning_statistics("ts");
+    assert_eq!(statistics.num_containers(), 3);
+
+    let ts = Column::from_name("ts");
+    let values = |values: &[i64]| {
+        values
+            .iter()
+            .map(|&v| ScalarValue::Int64(Some(v)))
+            .collect::<HashSet<_>>()
+    };
+
+    // A filter never proves that a row group only has the given values.
+    let contained = statistics.contained(&ts, &values(&[-5])).unwrap();
+    assert_eq!(
+        contained,
+        BooleanArray::from(vec![None, Some(false), Some(false)])
+    );
+
+    let contained = statistics.contained(&ts, &values(&[0, 2_000])).unwrap();
+    assert_eq!(contained, BooleanArray::from(vec![None, None, Some(false)]));
+
+    // Other columns and literals that are not integers are not known.
+    assert!(statistics
+        .contained(&Column::from_name("id"), &values(&[0]))
+        .is_none());
+    let strings = HashSet::from([ScalarValue::Utf8(Some("a".to_string()))]);
+    assert!(statistics.contained(&ts, &strings).is_none());
+    assert!(statistics.min_values(&ts).is_none());
+}
+
+#[test]
+fn test_prune_ranges() {
+    let filters = filters(&[&[-5, 0, 5], &[1_000, 2_000], &[]], 16);
+    let statistics = filters.pruning_statistics("ts");
+    let prune = |expr| statistics.prune(&predicate(expr)).unwrap();
+    let ts = |op, v| binary(column("ts"), op, lit(ScalarValue::Int64(Some(v))));
+    let and = |l, r| binary(l, Operator::And, r);
+
+    assert_eq!(
+        prune(and(ts(Operator::GtEq, 900), ts(Operator::Lt, 1_001))),
+        vec![false, true, false]
+    );
+    assert_eq!(prune(ts(Operator::Eq, -5)), vec![true, false, false]);
+
+    // The literal may be on either side of the comparison.
+    let swapped = binary(lit(ScalarValue::Int64(Some(3))), Operator::Lt, column("ts"));
+    assert_eq!(
+        prune(and(swapped, ts(Operator::LtEq, 4))),
+        vec![false, false, false]
+    );
+
+    // Disjoint bounds and bounds beyond every key leave no range.
+    assert_eq!(
+        prune(and(ts(Operator::Gt, 5), ts(Operator::Lt, 0))),
+        vec![false, false, false]
+    );
+    assert_eq!(prune(ts(Operator::Lt, i64::MIN)), vec![false, false, false]);
+
+    // Disjunctions and comparisons on other columns are left to the predicate.
+    let id = binary(
+        column("id"),
+        Operator::Eq,
+        lit(ScalarValue::UInt32(Some(1))),
+    );
+    assert_eq!(prune(id.clone()), vec![true, true, true]);
+    assert_eq!(
+        prune(binary(
+            ts(Operator::Lt, -10),
+            Operator::Or,
+            ts(Operator::Gt, 5_000)
+        )),
+        vec![true, true, true]
+    );
+    assert_eq!(
+        prune(and(id, ts(Operator::Eq, 0))),
+        vec![true, false, false]
+    );
+}
+
+#[test]
+fn test_prune_wide_range() {
+    // The range spans far more keys than the maximum interval, so it is split into sub-queries.
+    let filters = filters(&[&[1_000], &[50_000], &[1 << 40]], 16);
+    let statistics = filters.pruning_statistics("ts");
+
+    let range = binary(
+        binary(
+            column("ts"),
+            Operator::GtEq,
+            lit(ScalarValue::Int64(Some(0))),
+        ),
+        Operator::And,
+        binary(
+            column("ts"),
+            Operator::LtEq,
+            lit(ScalarValue::Int64(Some(1 << 20))),
+        ),
+    );
+    let keep = statistics.prune(&predicate(range)).unwrap();
+    assert!(keep[0] && keep[1]);
+}
+
+#[test]
+fn test_prune_across_hash_blocks() {
+    // The ordered keys right before and after a block boundary of the reduced universe, mapped
+    // back onto signed values.
+    let r = OrderPreservingHasher::new_seeded(2, 0.01, 16, 7)
+        .unwrap()
+        .reduced_universe();
+    let keys = [3 * r - 1, 3 * r + 5].map(|key| (key ^ 1 << 63) as i64);
+    let filters = filters(&[&keys, &[0, 1]], 16);
+    let statistics = filters.pruning_statistics("ts");
+    let ts = |op, v| binary(column("ts"), op, lit(ScalarValue::Int64(Some(v))));
+
+    // Every range around a key is checked in both of the windows it hashes onto.
+    for key in keys {
+        for start in key - 15..=key {
+            let range = binary(
+                ts(Operator::GtEq, start),
+                Operator::And,
+                ts(Operator::LtEq, start + 15),
+            );
+            assert!(statistics.prune(&predicate(range)).unwrap()[0]);
+        }
+    }
+}
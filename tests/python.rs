@@ -0,0 +1,57 @@
+#![cfg(feature = "python")]
+
+use grafite::{OrderPreservingHasher, PyRangeFilter, RangeFilter};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+#[test]
+fn test_python_filter() {
+    let keys = [3, 17, 1000, 1 << 40];
+    let hasher = OrderPreservingHasher::new_seeded(keys.len(), 0.01, 16, 7).unwrap();
+    let rf = RangeFilter::new(keys.into_iter(), hasher);
+
+    Python::initialize();
+    Python::attach(|py| {
+        let filter = Py::new(py, PyRangeFilter::from_filter(rf.clone())).unwrap();
+        let filter = filter.bind(py);
+
+        for key in keys {
+            let found: bool = filter
+                .call_method1("query", (key, key + 3))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert!(found);
+            assert!(filter.contains(key).unwrap());
+        }
+        assert_eq!(filter.len().unwrap(), keys.len());
+
+        let hasher = filter.getattr("hasher").unwrap();
+        let seed: Option<u64> = hasher.getattr("seed").unwrap().extract().unwrap();
+        assert_eq!(seed, Some(7));
+        let hash: u64 = hasher
+            .call_method1("hash", (17,))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(hash, rf.hasher().hash(17));
+
+        // Filters written from Python can be read back, both from Python and from Rust.
+        let bytes = filter.call_method0("to_bytes").unwrap();
+        let bytes = bytes.cast::<PyBytes>().unwrap().as_bytes().to_vec();
+        let loaded = RangeFilter::read_from(bytes.as_slice()).unwrap();
+        assert!(loaded.contains(1000));
+
+        let loaded = filter
+            .get_type()
+            .call_method1("from_bytes", (PyBytes::new(py, &bytes),))
+            .unwrap();
+        let loaded = loaded.extract::<PyRef<'_, PyRangeFilter>>().unwrap();
+        assert!(loaded.filter().contains(1 << 40));
+
+        let invalid = filter
+            .get_type()
+            .call_method1("from_bytes", (PyBytes::new(py, b"nope"),));
+        assert!(invalid.is_err());
+    });
+}
@@ -1,4 +1,8 @@
-use grafite::{OrderPreservingHasher, RangeFilter};
+use grafite::{
+    BuildError, Encoding, MergeError, OrderPreservingHasher, ParamError, PrimeStrategy, QueryError,
+    RangeFilter, RangeFilterBackend, RangeFilterBuilder, MERSENNE_61,
+};
+use std::num::NonZeroU64;
 
 #[test]
 fn test_basic() {
@@ -21,3 +25,881 @@ fn test_basic() {
     assert!(!rf.query(10..15));
     assert!(rf.query(10..16));
 }
+
+#[test]
+#[allow(clippy::reversed_empty_ranges)]
+fn test_empty_ranges() {
+    use std::ops::Bound::{Excluded, Included, Unbounded};
+
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    for x in values {
+        assert!(!rf.query(x..x));
+        assert!(!rf.query(x + 1..=x));
+        assert!(!rf.query(x + 5..x));
+        assert_eq!(rf.rank_range(x..x), 0);
+        assert_eq!(rf.selectivity(x..x), 0.0);
+    }
+
+    assert!(rf.query((Excluded(0), Excluded(2))));
+    assert!(!rf.query((Excluded(3), Excluded(7))));
+    assert!(rf.query((Excluded(3), Included(7))));
+    assert!(!rf.query((Excluded(1), Excluded(2))));
+    assert!(!rf.query((Excluded(u64::MAX), Unbounded)));
+
+    assert!(!rf.query(..0));
+    assert!(!rf.query(0..0));
+    assert_eq!(rf.rank_range(..0), 0);
+
+    assert_eq!(
+        rf.query_many(&[(3, 1), (20, 8), (1, 3)]),
+        [false, false, true]
+    );
+    assert_eq!(
+        rf.query_sorted_batch(&[(3, 1), (20, 8), (1, 3)]),
+        [false, false, true]
+    );
+}
+
+#[test]
+fn test_try_new() {
+    let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    assert!(matches!(
+        RangeFilter::try_new(std::iter::empty(), hasher),
+        Err(BuildError::EmptyInput)
+    ));
+
+    let hasher = OrderPreservingHasher::new_with_reduced(0);
+    assert!(matches!(
+        RangeFilter::try_new([1, 2, 3].into_iter(), hasher),
+        Err(BuildError::InvalidHasher)
+    ));
+
+    let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+    assert!(RangeFilter::try_new([1, 2, 3].into_iter(), hasher).is_ok());
+}
+
+#[test]
+fn test_from_sorted_unchecked() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let hashes: Vec<u64> = rf.backend().iter().collect();
+    let fast = RangeFilter::from_sorted_unchecked(&hashes, hasher);
+
+    for start in 0..25 {
+        for end in start + 1..30 {
+            assert_eq!(fast.query(start..end), rf.query(start..end));
+        }
+    }
+}
+
+#[test]
+fn test_from_hashes() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let hashes: Vec<u64> = values.iter().rev().map(|&x| hasher.hash(x)).collect();
+    let cached = RangeFilter::from_hashes(hashes, hasher).unwrap();
+    assert_eq!(
+        cached.backend().iter().collect::<Vec<_>>(),
+        rf.backend().iter().collect::<Vec<_>>()
+    );
+
+    let r = hasher.reduced_universe();
+    assert!(matches!(
+        RangeFilter::from_hashes(vec![0, r], hasher),
+        Err(BuildError::HashOutOfRange {
+            hash,
+            reduced_universe,
+        }) if hash == r && reduced_universe == r
+    ));
+}
+
+#[test]
+fn test_filter_from_parts() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let max_hash = rf.iter_hashes().last().unwrap();
+    let (parts_hasher, backend) = rf.clone().into_parts();
+    let rebuilt = RangeFilter::from_parts(parts_hasher, backend.clone()).unwrap();
+    assert!(rebuilt.iter_hashes().eq(rf.iter_hashes()));
+    assert_eq!(rebuilt.hasher().hash(12345), rf.hasher().hash(12345));
+
+    // A hasher whose reduced universe does not cover the stored hashes is rejected.
+    let mismatched = OrderPreservingHasher::new_with_reduced(max_hash);
+    assert!(matches!(
+        RangeFilter::from_parts(mismatched, backend),
+        Err(BuildError::HashOutOfRange { hash, .. }) if hash == max_hash
+    ));
+
+    assert!(matches!(
+        RangeFilter::from_parts(hasher, Encoding::from_sorted(&[])),
+        Err(BuildError::EmptyInput)
+    ));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_new() {
+    let values: Vec<u64> = (0..10_000).map(|x| x * 7).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    let par = RangeFilter::par_new(values, hasher);
+
+    assert!(rf.backend().iter().eq(par.backend().iter()));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_par_query_many() {
+    let values: Vec<u64> = (0..10_000).map(|x| x * 7).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let ranges: Vec<(u64, u64)> = (0..100_000).step_by(3).map(|x| (x, x + 5)).collect();
+    assert_eq!(rf.par_query_many(&ranges), rf.query_many(&ranges));
+}
+
+#[test]
+fn test_build_external() {
+    let values: Vec<u64> = (0..10_000).map(|x| x * 7 % 5_003).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let temp_dir = std::env::temp_dir();
+
+    let external =
+        RangeFilter::build_external(values.iter().copied(), hasher, &temp_dir, 1_000).unwrap();
+    assert!(rf.backend().iter().eq(external.backend().iter()));
+
+    let single =
+        RangeFilter::build_external(values.iter().copied(), hasher, &temp_dir, 20_000).unwrap();
+    assert!(rf.backend().iter().eq(single.backend().iter()));
+
+    assert!(RangeFilter::build_external(std::iter::empty(), hasher, &temp_dir, 10).is_err());
+
+    // The merged hashes are streamed into the same encoding that an in-memory build chooses.
+    let dense: Vec<u64> = (0..10_000).rev().collect();
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 14);
+    let rf = RangeFilter::new(dense.iter().copied(), hasher);
+    let external =
+        RangeFilter::build_external(dense.iter().copied(), hasher, &temp_dir, 1_000).unwrap();
+    assert!(external.backend().is_dense());
+    assert!(rf.backend().iter().eq(external.backend().iter()));
+}
+
+#[test]
+fn test_seeded() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let a = OrderPreservingHasher::new_seeded(values.len(), 0.01, 20, 7).unwrap();
+    let b = OrderPreservingHasher::new_seeded(values.len(), 0.01, 20, 7).unwrap();
+    let c = OrderPreservingHasher::new_with_budget_seeded(values.len(), 16, 20, 7).unwrap();
+    assert!((0..1000).all(|x| a.hash(x * 12345) == b.hash(x * 12345)));
+    assert_eq!(a.seed(), Some(7));
+    assert_eq!(c.seed(), Some(7));
+
+    let rf = RangeFilter::new(values.iter().copied(), a);
+    assert_eq!(rf.seed(), Some(7));
+
+    let unseeded = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    assert_eq!(unseeded.seed(), None);
+
+    let reduced = OrderPreservingHasher::new_with_reduced_seeded(1 << 20, 3);
+    assert_eq!(
+        reduced.hash(1 << 40),
+        OrderPreservingHasher::new_with_reduced_seeded(1 << 20, 3).hash(1 << 40)
+    );
+}
+
+#[test]
+fn test_from_parts() {
+    let hasher = OrderPreservingHasher::new(8, 0.01, 20).unwrap();
+
+    let copy = OrderPreservingHasher::from_parts(
+        hasher.c1(),
+        hasher.c2(),
+        hasher.p(),
+        hasher.reduced_universe(),
+    )
+    .unwrap();
+    assert!((0..1000).all(|x| copy.hash(x * 4321) == hasher.hash(x * 4321)));
+
+    // `p` must be a prime greater than `r`.
+    assert!(OrderPreservingHasher::from_parts(1, 1, 15, 10).is_err());
+    assert!(OrderPreservingHasher::from_parts(1, 1, 7, 10).is_err());
+    // `c1` must be nonzero and `c1, c2 < p`.
+    assert!(OrderPreservingHasher::from_parts(0, 1, 13, 10).is_err());
+    assert!(OrderPreservingHasher::from_parts(1, 13, 13, 10).is_err());
+    assert!(OrderPreservingHasher::from_parts(1, 12, 13, 10).is_ok());
+}
+
+#[test]
+fn test_keyed() {
+    let key = [42; 32];
+    let a = OrderPreservingHasher::new_keyed(8, 0.01, 20, &key).unwrap();
+    let b = OrderPreservingHasher::new_keyed(8, 0.01, 20, &key).unwrap();
+    let other = OrderPreservingHasher::new_keyed(8, 0.01, 20, &[43; 32]).unwrap();
+
+    // The same key always derives the same constants, and a different key derives others.
+    assert!(a.is_keyed());
+    assert_eq!((a.c1(), a.c2(), a.p()), (b.c1(), b.c2(), b.p()));
+    assert_ne!((a.c1(), a.c2(), a.p()), (other.c1(), other.c2(), other.p()));
+    assert_eq!(a.seed(), None);
+    assert_eq!(a.max_interval(), Some(20));
+
+    // The constants are left out of the debug output.
+    let debug = format!("{a:?}");
+    assert!(debug.contains("<redacted>"));
+    for param in [a.c1(), a.c2(), a.p()] {
+        assert!(!debug.contains(&param.to_string()));
+    }
+
+    let unkeyed = OrderPreservingHasher::new_seeded(8, 0.01, 20, 1).unwrap();
+    assert!(!unkeyed.is_keyed());
+    assert!(format!("{unkeyed:?}").contains(&unkeyed.p().to_string()));
+
+    let rf = RangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), a);
+    assert!(rf.query(3..5));
+}
+
+#[test]
+fn test_inverse_epsilon() {
+    let k = NonZeroU64::new(100).unwrap();
+    let exact = OrderPreservingHasher::new_with_inverse_epsilon_seeded(1000, k, 32, 9).unwrap();
+    let float = OrderPreservingHasher::new_seeded(1000, 0.01, 32, 9).unwrap();
+
+    assert_eq!(exact.reduced_universe(), 1000 * 32 * 100);
+    assert_eq!(
+        (exact.c1(), exact.c2(), exact.p()),
+        (float.c1(), float.c2(), float.p())
+    );
+    assert_eq!(exact.seed(), Some(9));
+    assert_eq!(exact.num_elements(), Some(1000));
+    assert_eq!(exact.max_interval(), Some(32));
+
+    // The maximum range interval is `u / (n * k)`.
+    let k = NonZeroU64::new(2).unwrap();
+    let max = u64::MAX / 2;
+    assert!(matches!(
+        OrderPreservingHasher::new_with_inverse_epsilon(1, k, max + 1),
+        Err(ParamError::InvalidMaxInterval(m)) if m == max
+    ));
+
+    assert!(matches!(
+        OrderPreservingHasher::new_with_inverse_epsilon(10, NonZeroU64::MIN, 1),
+        Err(ParamError::InvalidEpsilon(e)) if e == 1.0
+    ));
+    assert!(matches!(
+        OrderPreservingHasher::new_with_inverse_epsilon(0, k, 1),
+        Err(ParamError::NoElements)
+    ));
+}
+
+#[test]
+fn test_budget_for_epsilon() {
+    for epsilon in [0.5, 0.1, 0.01, 0.001, 1e-6] {
+        for max_interval in [1, 20, 1000] {
+            let (bits_per_key, budget_epsilon) =
+                OrderPreservingHasher::budget_for_epsilon(epsilon, max_interval).unwrap();
+            assert!(budget_epsilon <= epsilon);
+            assert_eq!(
+                OrderPreservingHasher::epsilon_with_budget(bits_per_key, max_interval).unwrap(),
+                budget_epsilon
+            );
+
+            // One bit less per key is not enough.
+            if bits_per_key > 3 {
+                let fewer =
+                    OrderPreservingHasher::epsilon_with_budget(bits_per_key - 1, max_interval);
+                assert!(fewer.unwrap() > epsilon);
+            }
+        }
+    }
+
+    assert_eq!(
+        OrderPreservingHasher::budget_for_epsilon(0.5, 1).unwrap(),
+        (3, 0.5)
+    );
+    assert!(matches!(
+        OrderPreservingHasher::budget_for_epsilon(1.0, 20),
+        Err(ParamError::InvalidEpsilon(_))
+    ));
+    assert!(matches!(
+        OrderPreservingHasher::budget_for_epsilon(0.0, 20),
+        Err(ParamError::InvalidEpsilon(_))
+    ));
+    assert!(matches!(
+        OrderPreservingHasher::budget_for_epsilon(1e-20, u64::MAX),
+        Err(ParamError::Overflow)
+    ));
+}
+
+#[test]
+fn test_with_universe() {
+    let hasher =
+        OrderPreservingHasher::new_with_universe_seeded(1 << 40, 1000, 0.01, 1 << 20, 5).unwrap();
+    assert_eq!(hasher.reduced_universe(), 1000 * (1 << 20) * 100);
+    assert_eq!(hasher.seed(), Some(5));
+
+    // The maximum range interval is `u * e / n`, which is tighter than for every 64-bit key.
+    let max = ((1u64 << 40) as f64 * 0.01) as u64 / 1000;
+    assert!(OrderPreservingHasher::new_with_universe(1 << 40, 1000, 0.01, max).is_ok());
+    assert!(matches!(
+        OrderPreservingHasher::new_with_universe(1 << 40, 1000, 0.01, max + 1),
+        Err(ParamError::InvalidMaxInterval(m)) if m == max
+    ));
+    assert!(OrderPreservingHasher::new(1000, 0.01, max + 1).is_ok());
+
+    let keys: Vec<u64> = (0..1000).map(|x| x << 30).collect();
+    let hasher = OrderPreservingHasher::new_with_universe(1 << 40, keys.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+    assert!(keys.iter().all(|&key| rf.contains(key)));
+}
+
+#[test]
+fn test_custom_rng() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let a =
+        OrderPreservingHasher::new_with_rng(8, 0.01, 20, &mut StdRng::seed_from_u64(1)).unwrap();
+    let b = OrderPreservingHasher::new_with_budget_rng(8, 16, 20, &mut StdRng::seed_from_u64(1))
+        .unwrap();
+    let c = OrderPreservingHasher::new_with_reduced_rng(1 << 20, &mut StdRng::seed_from_u64(1));
+
+    let a2 =
+        OrderPreservingHasher::new_with_rng(8, 0.01, 20, &mut StdRng::seed_from_u64(1)).unwrap();
+    assert_eq!(a.c1(), a2.c1());
+    assert_eq!(a.c2(), a2.c2());
+    assert_eq!(a.p(), a2.p());
+    assert_eq!(a.seed(), None);
+
+    assert!(b.reduced_universe() > 0);
+    assert_eq!(c.reduced_universe(), 1 << 20);
+}
+
+#[test]
+fn test_mersenne() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new_mersenne(values.len(), 0.01, 20).unwrap();
+    assert_eq!(hasher.p(), MERSENNE_61);
+
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    assert!(rf.query(0..20));
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+    assert!(rf.query(4..8));
+    assert!(!rf.query(10..15));
+    assert!(rf.query(10..16));
+
+    assert!(matches!(
+        OrderPreservingHasher::new_with_strategy(
+            1 << 20,
+            0.5,
+            1 << 42,
+            PrimeStrategy::Mersenne61,
+            &mut rand::thread_rng()
+        ),
+        Err(ParamError::ReducedUniverseTooLarge(_))
+    ));
+}
+
+#[test]
+fn test_hash_matches_reference() {
+    // `((c1 * (x / r) + c2) mod p) mod r + x) mod r`, computed without any overflow.
+    fn reference(hasher: &OrderPreservingHasher, x: u64) -> u64 {
+        let (c1, c2, p) = (hasher.c1() as u128, hasher.c2() as u128, hasher.p() as u128);
+        let r = hasher.reduced_universe() as u128;
+        let q = ((c1 * (x as u128 / r) + c2) % p) % r;
+        ((q + x as u128) % r) as u64
+    }
+
+    let hashers = [
+        OrderPreservingHasher::new(1 << 20, 0.01, 1 << 10).unwrap(),
+        OrderPreservingHasher::new_mersenne(1 << 20, 0.01, 1 << 10).unwrap(),
+        OrderPreservingHasher::new_with_reduced((1 << 63) + 12345),
+        OrderPreservingHasher::new_power_of_two(1 << 20, 0.01, 1 << 10).unwrap(),
+        OrderPreservingHasher::new_with_reduced(1 << 63),
+    ];
+
+    for hasher in &hashers {
+        for x in (0..1000)
+            .map(|i: u64| i.wrapping_mul(0x9e3779b97f4a7c15))
+            .chain([u64::MAX, u64::MAX - 1])
+        {
+            assert_eq!(hasher.hash(x), reference(hasher, x));
+        }
+    }
+}
+
+#[test]
+fn test_power_of_two() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let exact = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let hasher = OrderPreservingHasher::new_power_of_two(values.len(), 0.01, 20).unwrap();
+    assert!(hasher.reduced_universe().is_power_of_two());
+    assert_eq!(
+        hasher.reduced_universe(),
+        exact.reduced_universe().next_power_of_two()
+    );
+
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    assert!(rf.query(3..5));
+    assert!(!rf.query(4..7));
+    assert!(rf.query(10..16));
+}
+
+#[test]
+fn test_hash_many() {
+    // A length that is not a multiple of any vector width, and keys at the ends of the universe.
+    let mut keys: Vec<u64> = (0..1001)
+        .map(|i: u64| i.wrapping_mul(0x9e3779b97f4a7c15))
+        .collect();
+    keys.extend([0, 1, u64::MAX - 1, u64::MAX]);
+
+    let hashers = [
+        OrderPreservingHasher::new(1 << 20, 0.01, 1 << 10).unwrap(),
+        OrderPreservingHasher::new_mersenne(1 << 20, 0.01, 1 << 10).unwrap(),
+        OrderPreservingHasher::new_power_of_two(1 << 20, 0.01, 1 << 10).unwrap(),
+        OrderPreservingHasher::new_with_reduced_seeded(3, 1),
+        OrderPreservingHasher::new_with_reduced_seeded(u64::MAX / 3 * 2, 2),
+        OrderPreservingHasher::new_with_reduced_seeded(1 << 63, 3),
+        *RangeFilterBuilder::new()
+            .epsilon(0.01)
+            .max_interval(1 << 10)
+            .prime_strategy(PrimeStrategy::Mersenne61)
+            .power_of_two(true)
+            .build(0..1 << 10)
+            .unwrap()
+            .hasher(),
+    ];
+
+    for hasher in &hashers {
+        let mut out = vec![0; keys.len()];
+        hasher.hash_many(&keys, &mut out);
+        assert!(keys.iter().zip(&out).all(|(&x, &h)| hasher.hash(x) == h));
+    }
+}
+
+#[test]
+fn test_query_checked() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    assert_eq!(rf.query_checked(0..20), Ok(true));
+    assert_eq!(rf.query_checked(4..7), Ok(rf.query(4..7)));
+    assert_eq!(rf.query_checked(5..5), Ok(false));
+    assert_eq!(
+        rf.query_checked(0..=20),
+        Err(QueryError::IntervalTooLong {
+            length: 21,
+            max_interval: 20
+        })
+    );
+    assert_eq!(
+        rf.query_checked(..),
+        Err(QueryError::IntervalTooLong {
+            length: u64::MAX,
+            max_interval: 20
+        })
+    );
+
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 20);
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    assert_eq!(rf.query_checked(0..20), Err(QueryError::UnknownMaxInterval));
+}
+
+#[test]
+fn test_query_across_blocks() {
+    // With a single key, `L = 8` and `epsilon = 0.1`, every block has `r = 80` keys.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    assert_eq!(hasher.reduced_universe(), 80);
+    let rf = RangeFilter::try_new(std::iter::once(81), hasher).unwrap();
+    assert!(rf.query(78..=85));
+    assert_eq!(rf.query_checked(78..=85), Ok(true));
+
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    for &x in &values {
+        for start in x - 15..=x {
+            for end in x..start + 16 {
+                assert!(rf.query(start..=end));
+                assert_eq!(rf.query_checked(start..=end), Ok(true));
+            }
+        }
+    }
+
+    // Ranges that are longer than a block, with and without an entire block.
+    assert!(rf.query(r - 1..=2 * r - 2));
+    assert!(rf.query(r + 10..=3 * r));
+}
+
+#[test]
+fn test_query_chunked() {
+    let values: Vec<u64> = (0..100).map(|x| x * 1000).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 16).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    for start in (0..100_000).step_by(37) {
+        for len in [1, 16, 17, 100, 999, 2000] {
+            let end = start + len - 1;
+            let expected = values.iter().any(|&x| start <= x && x <= end);
+            if expected {
+                assert!(rf.query_chunked(start..=end));
+            }
+            if len <= 16 {
+                assert_eq!(rf.query_chunked(start..=end), rf.query(start..=end));
+            }
+        }
+    }
+
+    assert!(!rf.query_chunked(5..5));
+    assert!(rf.query_chunked(..));
+}
+
+#[test]
+fn test_query_chunked_across_blocks() {
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = RangeFilter::try_new(std::iter::once(81), hasher).unwrap();
+    assert!(rf.query_chunked(78..=85));
+
+    // Blocks of `r = 1600` keys, with keys next to many block boundaries, so that long ranges
+    // cross block boundaries in the middle of a chunk.
+    let hasher = OrderPreservingHasher::new_seeded(10, 0.1, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=5).flat_map(|k| [k * r - 1, k * r]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    for &x in &values {
+        for start in (x.saturating_sub(3 * r)..=x).step_by(7) {
+            for len in [1, 15, 16, 17, 33, 100, 1000, r - 1, r, 2 * r] {
+                let end = start + len - 1;
+                if end >= x {
+                    assert!(rf.query_chunked(start..=end));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_build_params() {
+    let hasher = OrderPreservingHasher::new_seeded(1000, 0.01, 64, 3).unwrap();
+    assert_eq!(hasher.num_elements(), Some(1000));
+    assert_eq!(hasher.max_interval(), Some(64));
+
+    let rf = RangeFilter::new(0..1000, hasher);
+    assert_eq!(rf.num_elements(), Some(1000));
+    assert_eq!(rf.max_interval(), Some(64));
+
+    let epsilon = rf.false_positive_rate().unwrap();
+    assert!(0.009 < epsilon && epsilon <= 0.01);
+    assert_eq!(epsilon, rf.false_positive_rate_for(1000, 64));
+
+    let unknown = OrderPreservingHasher::new_with_reduced(1 << 20);
+    assert_eq!(unknown.num_elements(), None);
+    assert_eq!(
+        RangeFilter::new(0..1000, unknown).false_positive_rate(),
+        None
+    );
+}
+
+#[test]
+fn test_contains() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    assert!(values.iter().all(|&x| rf.contains(x)));
+    for x in 0..100 {
+        assert_eq!(rf.contains(x), rf.query(x..=x));
+    }
+
+    assert!(rf.iter_hashes().all(|hash| rf.contains_hash(hash)));
+    assert!(!rf.contains_hash(hasher.reduced_universe()));
+}
+
+#[test]
+fn test_query_prehashed() {
+    let hasher = OrderPreservingHasher::new(16, 0.01, 20).unwrap();
+    let a = RangeFilter::new([1, 2, 3, 7, 8, 9, 15, 20].into_iter(), hasher);
+    let b = RangeFilter::new([30, 31, 45, 50].into_iter(), hasher);
+
+    for start in 0..50 {
+        for end in start..start + 20 {
+            let (start_hash, end_hash) = (hasher.hash(start), hasher.hash(end));
+            assert_eq!(
+                a.query_prehashed(start_hash, end_hash),
+                a.query(start..=end)
+            );
+            assert_eq!(
+                b.query_prehashed(start_hash, end_hash),
+                b.query(start..=end)
+            );
+        }
+    }
+
+    // A range that crosses the block boundary at `r = 80` is split into one query per block.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = RangeFilter::new(std::iter::once(81), hasher);
+    assert!(!rf.query_prehashed(hasher.hash(78), hasher.hash(79)));
+    assert!(rf.query_prehashed(hasher.hash(80), hasher.hash(85)));
+    assert!(rf.query(78..=85));
+}
+
+#[test]
+fn test_predecessor_successor_hash() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let hashes: Vec<u64> = rf.backend().iter().collect();
+    let (min, max) = (hashes[0], hashes[hashes.len() - 1]);
+
+    assert_eq!(rf.predecessor_hash(min), Some(min));
+    assert_eq!(rf.successor_hash(max), Some(max));
+    assert_eq!(rf.successor_hash(max + 1), None);
+    if min > 0 {
+        assert_eq!(rf.predecessor_hash(min - 1), None);
+    }
+
+    for w in hashes.windows(2) {
+        assert_eq!(rf.predecessor_hash(w[1] - 1), Some(w[0]));
+        assert_eq!(rf.successor_hash(w[0] + 1), Some(w[1]));
+    }
+}
+
+#[test]
+fn test_rank_range() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 13).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    for start in (0..13_000).step_by(101) {
+        let end = start + 63;
+        let keys = values.iter().filter(|&&x| start <= x && x <= end).count();
+
+        let rank = rf.rank_range(start..=end);
+        assert!(rank >= keys);
+        assert_eq!(rank > 0, rf.query(start..=end));
+    }
+}
+
+#[test]
+fn test_rank_range_across_blocks() {
+    // With a single key, `L = 8` and `epsilon = 0.1`, every block has `r = 80` keys.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = RangeFilter::try_new(std::iter::once(81), hasher).unwrap();
+    assert_eq!(rf.rank_range(78..=85), 1);
+
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+    let len = rf.iter_hashes().count();
+
+    for k in 1..=50 {
+        let (start, end) = (k * r - 8, k * r + 8);
+        assert!(rf.rank_range(start..=end) >= 2);
+        assert!(rf.rank_range(start..=end) <= len);
+    }
+
+    // Ranges of at least `r` keys count every stored hash.
+    assert_eq!(rf.rank_range(..), len);
+    assert_eq!(rf.rank_range(r / 2..r / 2 + r), len);
+    assert_eq!(rf.rank_range(r + 10..=3 * r), len);
+}
+
+#[test]
+fn test_selectivity() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 13).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 1 << 10).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    // About 78 of the 1000 keys lie within `[0, 1023]`.
+    let selectivity = rf.selectivity(0..1024);
+    assert!((0.07..=0.09).contains(&selectivity));
+
+    for start in (0..13_000).step_by(997) {
+        let selectivity = rf.selectivity(start..start + 1024);
+        assert!((0.0..=1.0).contains(&selectivity));
+    }
+
+    // Every key lies within the full range.
+    assert_eq!(rf.selectivity(..), 1.0);
+    assert_eq!(rf.selectivity(0..=u64::MAX / 2), 1.0);
+}
+
+#[test]
+fn test_iter_hashes() {
+    let values = [20, 15, 9, 8, 7, 3, 2, 1, 1];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let hashes: Vec<u64> = rf.iter_hashes().collect();
+    assert!(hashes.windows(2).all(|w| w[0] < w[1]));
+    assert!(values.iter().all(|&x| hashes.contains(&hasher.hash(x))));
+
+    let copy = RangeFilter::from_sorted_unchecked(&hashes, hasher);
+    assert!(copy.iter_hashes().eq(rf.iter_hashes()));
+}
+
+#[test]
+fn test_query_many() {
+    let values = [1, 2, 3, 7, 8, 9, 15, 20];
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 20).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let ranges: Vec<(u64, u64)> = (0..25)
+        .flat_map(|start| (start..30).map(move |end| (start, end)))
+        .collect();
+
+    let results = rf.query_many(&ranges);
+    assert_eq!(results.len(), ranges.len());
+    for (&(start, end), &result) in ranges.iter().zip(&results) {
+        assert_eq!(result, rf.query(start..=end));
+    }
+
+    let mut out = vec![false; 2];
+    rf.query_many_into(&[(4, 6), (10, 15)], &mut out);
+    assert_eq!(out, [false, true]);
+}
+
+#[test]
+fn test_query_sorted_batch() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 37).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let ranges: Vec<(u64, u64)> = (0..40_000).step_by(7).map(|x| (x, x + 20)).collect();
+    assert_eq!(rf.query_sorted_batch(&ranges), rf.query_many(&ranges));
+
+    let unsorted: Vec<(u64, u64)> = ranges.iter().rev().copied().collect();
+    assert_eq!(rf.query_sorted_batch(&unsorted), rf.query_many(&unsorted));
+
+    // Ranges that cross the block boundaries around keys next to them, and longer ranges.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let values: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let ranges: Vec<(u64, u64)> = (r - 20..51 * r)
+        .step_by(97)
+        .flat_map(|x| [(x, x + 15), (x, x + r), (x, x + 3 * r)])
+        .collect();
+    let answers = rf.query_sorted_batch(&ranges);
+    assert_eq!(answers, rf.query_many(&ranges));
+    for (&(start, end), answer) in ranges.iter().zip(answers) {
+        assert!(answer || !values.iter().any(|&x| start <= x && x <= end));
+    }
+}
+
+#[test]
+fn test_union() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 37).collect();
+    let (left, right) = values.split_at(600);
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 32).unwrap();
+    let a = RangeFilter::new(left.iter().copied(), hasher);
+    // The two filters share some keys.
+    let b = RangeFilter::new(right.iter().chain(&left[..100]).copied(), hasher);
+
+    let full = RangeFilter::new(values.iter().copied(), hasher);
+    let union = a.union(&b).unwrap();
+    assert!(union.iter_hashes().eq(full.iter_hashes()));
+    assert!(b.union(&a).unwrap().iter_hashes().eq(full.iter_hashes()));
+
+    let other = OrderPreservingHasher::new(values.len(), 0.01, 32).unwrap();
+    let c = RangeFilter::new(right.iter().copied(), other);
+    assert_eq!(a.union(&c).unwrap_err(), MergeError::HasherMismatch);
+}
+
+#[test]
+fn test_merge() {
+    let values: Vec<u64> = (0..3000).map(|x| x * 11).collect();
+
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 32).unwrap();
+    // Overlapping chunks, so that some hashes appear in more than one filter.
+    let filters: Vec<RangeFilter> = (0..values.len())
+        .step_by(500)
+        .map(|i| {
+            let chunk = &values[i..(i + 700).min(values.len())];
+            RangeFilter::new(chunk.iter().copied(), hasher)
+        })
+        .collect();
+
+    let full = RangeFilter::new(values.iter().copied(), hasher);
+    let merged = RangeFilter::merge(&filters).unwrap();
+    assert!(merged.iter_hashes().eq(full.iter_hashes()));
+
+    let single = RangeFilter::merge(&filters[..1]).unwrap();
+    assert!(single.iter_hashes().eq(filters[0].iter_hashes()));
+
+    let no_filters: [RangeFilter; 0] = [];
+    assert_eq!(
+        RangeFilter::merge(&no_filters).unwrap_err(),
+        MergeError::NoFilters
+    );
+
+    let other = OrderPreservingHasher::new(values.len(), 0.01, 32).unwrap();
+    let mut mixed = filters.clone();
+    mixed.push(RangeFilter::new(values.iter().copied(), other));
+    assert_eq!(
+        RangeFilter::merge(&mixed).unwrap_err(),
+        MergeError::HasherMismatch
+    );
+}
+
+#[test]
+fn test_query_any() {
+    let levels: Vec<RangeFilter> = (0..5)
+        .map(|level| {
+            let values: Vec<u64> = (0..100).map(|x| level * 100_000 + x * 1000).collect();
+            let hasher = OrderPreservingHasher::new(values.len(), 0.01, 32).unwrap();
+            RangeFilter::new(values.into_iter(), hasher)
+        })
+        .collect();
+
+    for level in 0..5u64 {
+        let key = level * 100_000 + 5000;
+        assert!(RangeFilter::query_any(&levels, key..=key));
+        assert!(RangeFilter::query_matching(&levels, key..=key).any(|i| i == level as usize));
+    }
+
+    assert!(!RangeFilter::query_any(&levels, 10..10));
+    let no_filters: [RangeFilter; 0] = [];
+    assert!(!RangeFilter::query_any(&no_filters, 0..10));
+    assert_eq!(RangeFilter::query_matching(&levels, 5..5).count(), 0);
+}
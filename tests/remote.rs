@@ -0,0 +1,52 @@
+#![cfg(feature = "object_store")]
+
+use futures::executor::block_on;
+use grafite::{OrderPreservingHasher, PersistError, RangeFilter, RemoteError};
+use object_store::memory::InMemory;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+
+fn build(num_keys: u64) -> RangeFilter {
+    let hasher = OrderPreservingHasher::new_seeded(num_keys as usize, 0.01, 64, 7).unwrap();
+    RangeFilter::new((0..num_keys).map(|x| x * 1000), hasher)
+}
+
+#[test]
+fn test_read_from_object_store() {
+    let store = InMemory::new();
+
+    block_on(async {
+        for (name, filter) in [("small", build(1)), ("large", build(10_000))] {
+            let mut bytes = Vec::new();
+            filter.write_to(&mut bytes).unwrap();
+            // Trailing bytes after the filter are never fetched.
+            bytes.extend_from_slice(b"trailing data");
+
+            let path = Path::from(format!("filters/{name}.graf"));
+            store.put(&path, PutPayload::from(bytes)).await.unwrap();
+
+            let loaded = RangeFilter::read_from_object_store(&store, &path)
+                .await
+                .unwrap();
+            assert_eq!(
+                loaded.iter_hashes().collect::<Vec<_>>(),
+                filter.iter_hashes().collect::<Vec<_>>()
+            );
+        }
+
+        let missing = RangeFilter::read_from_object_store(&store, &Path::from("missing")).await;
+        assert!(matches!(missing, Err(RemoteError::ObjectStore(_))));
+
+        // A truncated filter is detected before fetching the rest of it.
+        let mut bytes = Vec::new();
+        build(10_000).write_to(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() / 2);
+        let path = Path::from("truncated");
+        store.put(&path, PutPayload::from(bytes)).await.unwrap();
+        let truncated = RangeFilter::read_from_object_store(&store, &path).await;
+        assert!(matches!(
+            truncated,
+            Err(RemoteError::Persist(PersistError::Io(_)))
+        ));
+    });
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "parquet")]
+
+use bytes::Bytes;
+use grafite::{OrderPreservingHasher, RangeFilter, RowGroupError, RowGroupFilters};
+use parquet::data_type::{Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::FileReader;
+use parquet::file::serialized_reader::SerializedFileReader;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::sync::Arc;
+
+/// Writes a Parquet file with one row group per entry of `groups`, each holding the values of a
+/// signed `ts` column, an unsigned `id` column with nulls, and a `score` column of doubles.
+fn write_file(groups: &[Vec<i64>]) -> Bytes {
+    let schema = parse_message_type(
+        "message schema {
+            REQUIRED INT64 ts;
+            OPTIONAL INT32 id (INTEGER(32, false));
+            REQUIRED DOUBLE score;
+        }",
+    )
+    .unwrap();
+    let props = Arc::new(WriterProperties::builder().build());
+
+    let mut buf = Vec::new();
+    let mut writer = SerializedFileWriter::new(&mut buf, Arc::new(schema), props).unwrap();
+    for values in groups {
+        let mut row_group = writer.next_row_group().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(values, None, None)
+            .unwrap();
+        column.close().unwrap();
+
+        // Every other id is null, and the others are large unsigned values.
+        let ids: Vec<i32> = values
+            .iter()
+            .step_by(2)
+            .map(|&v| (v as u32 | 1 << 31) as i32)
+            .collect();
+        let def_levels: Vec<i16> = (0..values.len()).map(|i| (i % 2 == 0) as i16).collect();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int32Type>()
+            .write_batch(&ids, Some(&def_levels), None)
+            .unwrap();
+        column.close().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<parquet::data_type::DoubleType>()
+            .write_batch(&vec![0.5; values.len()], None, None)
+            .unwrap();
+        column.close().unwrap();
+
+        row_group.close().unwrap();
+    }
+    writer.close().unwrap();
+
+    Bytes::from(buf)
+}
+
+#[test]
+fn test_row_group_filters() {
+    let groups: Vec<Vec<i64>> = (0..8)
+        .map(|g| (0..1000).map(|i| (g - 4) * 1_000_000 + i * 37).collect())
+        .collect();
+    let reader = SerializedFileReader::new(write_file(&groups)).unwrap();
+    assert_eq!(reader.metadata().num_row_groups(), groups.len());
+
+    let filters = RowGroupFilters::build(&reader, 0, 0.01, 64).unwrap();
+    assert_eq!(filters.num_row_groups(), groups.len());
+
+    for (g, values) in groups.iter().enumerate() {
+        for &v in values {
+            let matching = filters.matching_row_groups(v - 10..=v);
+            assert!(matching.contains(&g));
+            assert!(matching.len() <= 3);
+        }
+    }
+    assert!(filters.matching_row_groups(10i64..10).is_empty());
+
+    // The unsigned column is queried with unsigned keys, and its nulls are skipped.
+    let filters = RowGroupFilters::build(&reader, 1, 0.01, 64).unwrap();
+    for (g, values) in groups.iter().enumerate() {
+        let id = (values[2] as u32 | 1 << 31) as u64;
+        assert!(filters.matching_row_groups(id..=id).contains(&g));
+    }
+
+    // Filters can be persisted and reassembled.
+    let filters: Vec<Option<RangeFilter>> = (0..filters.num_row_groups())
+        .map(|g| {
+            let mut bytes = Vec::new();
+            filters.filter(g).unwrap().write_to(&mut bytes).unwrap();
+            Some(RangeFilter::read_from(bytes.as_slice()).unwrap())
+        })
+        .collect();
+    let filters = RowGroupFilters::from_filters(filters);
+    assert!(filters.heap_size() > 0);
+}
+
+#[test]
+fn test_wide_predicate() {
+    // A few values per row group, spread far apart.
+    let groups: Vec<Vec<i64>> = (0..4)
+        .map(|g| vec![g * 1_000, 50_000 + g, 1_000_000 * (g + 1), -(1 << 40) + g])
+        .collect();
+    let reader = SerializedFileReader::new(write_file(&groups)).unwrap();
+    let filters = RowGroupFilters::build(&reader, 0, 0.01, 16).unwrap();
+
+    // Predicates much wider than the maximum interval of 16 keys must not skip any row group.
+    for (g, values) in groups.iter().enumerate() {
+        for &v in values {
+            for width in [100, 10_000, 1 << 20] {
+                assert!(filters
+                    .matching_row_groups(v - width..=v + width)
+                    .contains(&g));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ranges_across_hash_blocks() {
+    // The ordered keys right before and after block boundaries of the reduced universe, mapped
+    // back onto signed values and repeated, so the filter is sized for the 100 distinct values.
+    let r = OrderPreservingHasher::new(100, 0.01, 16)
+        .unwrap()
+        .reduced_universe();
+    let keys: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let values: Vec<i64> = keys
+        .iter()
+        .flat_map(|&key| [(key ^ 1 << 63) as i64; 3])
+        .collect();
+    let reader = SerializedFileReader::new(write_file(std::slice::from_ref(&values))).unwrap();
+
+    let filters = RowGroupFilters::build(&reader, 0, 0.01, 16).unwrap();
+    assert_eq!(filters.filter(0).unwrap().hasher().reduced_universe(), r);
+
+    // Every range around a value is checked in both of the windows it hashes onto.
+    for &v in &values {
+        for start in v - 15..=v {
+            assert_eq!(filters.matching_row_groups(start..=start + 15), [0]);
+        }
+    }
+}
+
+#[test]
+fn test_unsupported_column() {
+    let reader = SerializedFileReader::new(write_file(&[vec![1, 2, 3]])).unwrap();
+    assert!(matches!(
+        RowGroupFilters::build(&reader, 2, 0.01, 64),
+        Err(RowGroupError::UnsupportedColumn(_))
+    ));
+}
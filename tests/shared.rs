@@ -0,0 +1,18 @@
+use grafite::{OrderPreservingHasher, RangeFilter, SharedRangeFilter};
+
+#[test]
+fn test_shared() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 100).collect();
+    let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+    let rf = SharedRangeFilter::from(RangeFilter::new(values.iter().copied(), hasher));
+
+    let clone = rf.clone();
+    assert!(SharedRangeFilter::ptr_eq(&rf, &clone));
+    assert!(values.iter().all(|&x| clone.query(x..=x)));
+
+    // The filter is only moved out once it is no longer shared.
+    let rf = rf.try_unwrap().unwrap_err();
+    drop(clone);
+    let owned = rf.try_unwrap().unwrap();
+    assert!(values.iter().all(|&x| owned.query(x..=x)));
+}
@@ -0,0 +1,47 @@
+use grafite::{OrderPreservingHasher, RangeFilter, SignedRangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_signed_keys() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let keys: Vec<i64> = (0..10_000)
+        .map(|_| rng.gen_range(-1_000_000..1_000_000))
+        .collect();
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let rf = SignedRangeFilter::new(keys.iter().copied(), hasher);
+
+    for &key in &keys {
+        assert!(rf.query(key..=key));
+        assert!(rf.query(key - 10..key + 10));
+    }
+
+    // Ranges that cross zero are not wrapped.
+    let rf = SignedRangeFilter::new([-5, 5].into_iter(), hasher);
+    assert!(rf.query(-1..=1) == rf.filter().query((1 << 63) - 1..=(1 << 63) + 1));
+    assert!(rf.query(-6..-4));
+    assert!(rf.query(4..6));
+
+    assert!(!rf.query(3..3));
+    assert!(!rf.query((
+        std::ops::Bound::Excluded(i64::MAX),
+        std::ops::Bound::Unbounded
+    )));
+}
+
+#[test]
+fn test_signed_extremes() {
+    let hasher = OrderPreservingHasher::new(2, 0.01, 16).unwrap();
+    let rf = SignedRangeFilter::new([i64::MIN, i64::MAX].into_iter(), hasher);
+
+    assert!(rf.query(..i64::MIN + 1));
+    assert!(rf.query(i64::MAX..));
+
+    // The mapped keys can be persisted through the inner filter.
+    let mut bytes = Vec::new();
+    rf.filter().write_to(&mut bytes).unwrap();
+    let loaded = SignedRangeFilter::from_filter(RangeFilter::read_from(bytes.as_slice()).unwrap());
+    assert!(loaded.query(i64::MIN..=i64::MIN));
+    assert!(loaded.query(i64::MAX..=i64::MAX));
+}
@@ -0,0 +1,141 @@
+use grafite::{EliasFano, OrderPreservingHasher, RangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_stats() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new_seeded(values.len(), 0.01, 64, 7).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let stats = rf.stats();
+    let hashes: Vec<u64> = rf.iter_hashes().collect();
+    assert_eq!(stats.num_hashes, hashes.len());
+    assert_eq!(stats.min_hash, hashes.first().copied());
+    assert_eq!(stats.max_hash, hashes.last().copied());
+    assert!(stats.max_hash.unwrap() < stats.reduced_universe);
+
+    assert_eq!(stats.reduced_universe, hasher.reduced_universe());
+    assert_eq!(
+        (stats.prime, stats.c1, stats.c2),
+        (Some(hasher.p()), Some(hasher.c1()), Some(hasher.c2()))
+    );
+
+    assert_eq!(stats.encoded_bits, 8 * rf.heap_size());
+    assert_eq!(
+        stats.bits_per_key,
+        stats.encoded_bits as f64 / stats.num_hashes as f64
+    );
+
+    // The statistics do not depend on the backend other than through the encoded size.
+    let rf = RangeFilter::<EliasFano>::try_with_backend(values.iter().copied(), hasher).unwrap();
+    let ef_stats = rf.stats();
+    assert_eq!(ef_stats.num_hashes, stats.num_hashes);
+    assert_eq!(ef_stats.min_hash, stats.min_hash);
+    assert_eq!(ef_stats.max_hash, stats.max_hash);
+}
+
+#[test]
+fn test_stats_keyed() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new_keyed(values.len(), 0.01, 64, &[7; 32]).unwrap();
+    let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+    let stats = rf.stats();
+    assert_eq!((stats.prime, stats.c1, stats.c2), (None, None, None));
+
+    // The secret constants do not leak through the debug output.
+    let debug = format!("{stats:?}");
+    for constant in [hasher.p(), hasher.c1(), hasher.c2()] {
+        assert!(!debug.contains(&constant.to_string()), "{debug}");
+    }
+}
+
+#[test]
+fn test_bits_per_key() {
+    let values: Vec<u64> = (0..100_000).map(|x| x * 1_000_003).collect();
+
+    for budget in [8, 12, 16, 24] {
+        let hasher =
+            OrderPreservingHasher::new_with_budget_seeded(values.len(), budget, 32, 7).unwrap();
+        let rf = RangeFilter::new(values.iter().copied(), hasher);
+
+        // The realized space usage is close to the budget.
+        let bits_per_key = rf.bits_per_key();
+        assert!((bits_per_key - budget as f64).abs() < 1.0, "{bits_per_key}");
+        assert_eq!(bits_per_key, rf.stats().bits_per_key);
+    }
+}
+
+#[test]
+fn test_memory_breakdown() {
+    let sparse: Vec<u64> = (0..10_000).map(|x| x * 1000).collect();
+    let hasher = OrderPreservingHasher::new_seeded(sparse.len(), 0.01, 64, 7).unwrap();
+    let rf = RangeFilter::new(sparse.iter().copied(), hasher);
+    assert!(!rf.backend().is_dense());
+
+    let breakdown = rf.memory_breakdown();
+    assert_eq!(breakdown.total() - breakdown.hasher, rf.heap_size());
+    assert!(breakdown.low_bits > 0 && breakdown.high_bits > 0 && breakdown.index > 0);
+    assert_eq!(breakdown.bit_vector, 0);
+    assert!(breakdown.hasher > 0);
+
+    // The high bits take at most 3 bits per value.
+    assert!(breakdown.high_bits * 8 <= 3 * rf.iter_hashes().count() + 64);
+
+    let dense: Vec<u64> = (0..10_000).collect();
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 14);
+    let rf = RangeFilter::new(dense.iter().copied(), hasher);
+    assert!(rf.backend().is_dense());
+
+    let breakdown = rf.memory_breakdown();
+    assert_eq!(breakdown.total() - breakdown.hasher, rf.heap_size());
+    assert_eq!((breakdown.low_bits, breakdown.high_bits), (0, 0));
+    assert!(breakdown.bit_vector > 0 && breakdown.index > 0);
+
+    let rf = RangeFilter::<EliasFano>::try_with_backend(dense.iter().copied(), hasher).unwrap();
+    let breakdown = rf.memory_breakdown();
+    assert_eq!(breakdown.total() - breakdown.hasher, rf.heap_size());
+    assert_eq!(breakdown.other, 32);
+}
+
+#[test]
+fn test_gap_histogram() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    // Random keys produce evenly spread hash values.
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+
+    let even = rf.gap_histogram();
+    let hashes: Vec<u64> = rf.iter_hashes().collect();
+    assert_eq!(even.num_gaps, hashes.len() - 1);
+    assert_eq!(even.buckets.iter().sum::<usize>(), even.num_gaps);
+    assert_eq!(even.min_gap, hashes.windows(2).map(|w| w[1] - w[0]).min());
+    assert_eq!(even.max_gap, hashes.windows(2).map(|w| w[1] - w[0]).max());
+    assert!((even.coefficient_of_variation() - 1.0).abs() < 0.1);
+
+    // Keys in tight clusters produce clustered hash values.
+    let keys: Vec<u64> = (0..100)
+        .flat_map(|_| {
+            let center: u64 = rng.gen_range(0..u64::MAX - 1000);
+            (0..100).map(move |i| center + i * 8)
+        })
+        .collect();
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let rf = RangeFilter::new(keys.iter().copied(), hasher);
+
+    let clustered = rf.gap_histogram();
+    assert!(clustered.coefficient_of_variation() > 3.0);
+    assert!(clustered.fraction_below(even.mean as u64) > 0.9);
+    assert!(even.fraction_below(even.mean as u64) < 0.9);
+
+    // A single hash value has no gaps.
+    let rf = RangeFilter::new([5].into_iter(), hasher);
+    let single = rf.gap_histogram();
+    assert_eq!(single.num_gaps, 0);
+    assert_eq!((single.min_gap, single.max_gap), (None, None));
+    assert_eq!(single.coefficient_of_variation(), 0.0);
+    assert_eq!(single.fraction_below(100), 0.0);
+}
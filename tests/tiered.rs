@@ -0,0 +1,47 @@
+use grafite::{BuildError, ParamError, TieredRangeFilter};
+
+#[test]
+fn test_tiered() {
+    let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+
+    let rf =
+        TieredRangeFilter::new(values.iter().copied(), 0.01, &[1 << 16, 1 << 5, 1 << 5]).unwrap();
+    assert_eq!(rf.tiers().len(), 2);
+    assert_eq!(rf.tiers()[0].max_interval(), Some(1 << 5));
+    assert_eq!(rf.tiers()[1].max_interval(), Some(1 << 16));
+
+    for start in (0..1_000_000).step_by(997) {
+        for len in [1, 32, 33, 1 << 16, (1 << 16) + 1] {
+            let end = start + len - 1;
+            if values.iter().any(|&x| start <= x && x <= end) {
+                assert!(rf.query(start..=end));
+            }
+        }
+    }
+
+    // Short ranges are routed to the short tier.
+    for start in (0..100_000).step_by(13) {
+        assert_eq!(
+            rf.query(start..start + 20),
+            rf.tiers()[0].query(start..start + 20)
+        );
+    }
+
+    assert!(!rf.query(5..5));
+}
+
+#[test]
+fn test_tiered_invalid() {
+    assert!(matches!(
+        TieredRangeFilter::new([1, 2, 3], 0.01, &[]),
+        Err(BuildError::Param(ParamError::MissingParameter(_)))
+    ));
+    assert!(matches!(
+        TieredRangeFilter::new([], 0.01, &[32]),
+        Err(BuildError::EmptyInput)
+    ));
+    assert!(matches!(
+        TieredRangeFilter::new([1, 2, 3], 2.0, &[32]),
+        Err(BuildError::Param(ParamError::InvalidEpsilon(_)))
+    ));
+}
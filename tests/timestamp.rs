@@ -0,0 +1,99 @@
+use grafite::{OrderPreservingHasher, RangeFilter, TimeUnit, TimestampRangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[test]
+fn test_time_units() {
+    let t = Duration::from_nanos(1_234_567_891);
+    for unit in [
+        TimeUnit::Seconds,
+        TimeUnit::Millis,
+        TimeUnit::Micros,
+        TimeUnit::Nanos,
+    ] {
+        assert_eq!(unit.encode(&t) ^ (1 << 63), 1_234_567_891 / unit.nanos());
+    }
+
+    // Timestamps before the epoch are ordered before the epoch, and rounded down.
+    let before = UNIX_EPOCH - Duration::from_millis(1);
+    assert!(TimeUnit::Seconds.encode(&before) < TimeUnit::Seconds.encode(&UNIX_EPOCH));
+    assert_eq!(TimeUnit::Millis.encode(&before), (-1i64 as u64) ^ (1 << 63));
+    assert_eq!(
+        TimeUnit::Millis.encode(&UNIX_EPOCH),
+        TimeUnit::Millis.encode(&Duration::ZERO)
+    );
+}
+
+#[test]
+fn test_timestamp_filter() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    let events: Vec<SystemTime> = (0..10_000)
+        .map(|_| base + Duration::from_nanos(rng.gen_range(0..1 << 50)))
+        .collect();
+    let hasher = OrderPreservingHasher::new_with_rng(events.len(), 0.01, 1000, &mut rng).unwrap();
+    let rf = TimestampRangeFilter::new(events.iter().copied(), TimeUnit::Millis, hasher);
+    assert_eq!(rf.unit(), TimeUnit::Millis);
+
+    for &event in &events {
+        assert!(rf.query(event..=event));
+        assert!(rf.query(event - Duration::from_millis(500)..event + Duration::from_millis(1)));
+    }
+
+    // Excluded bounds are exact to the nanosecond before they are rounded.
+    let rf = TimestampRangeFilter::new(
+        [Duration::from_secs(10)].into_iter(),
+        TimeUnit::Nanos,
+        hasher,
+    );
+    assert!(rf.query(Duration::from_secs(9)..=Duration::from_secs(10)));
+    assert!(!rf.query(Duration::from_secs(10)..Duration::from_secs(10)));
+    assert!(!rf.query(Duration::from_secs(11)..Duration::from_secs(10)));
+
+    // The scaled timestamps can be persisted through the inner filter.
+    let mut bytes = Vec::new();
+    rf.filter().write_to(&mut bytes).unwrap();
+    let filter = RangeFilter::read_from(bytes.as_slice()).unwrap();
+    let loaded = TimestampRangeFilter::<Duration>::from_filter(filter, TimeUnit::Nanos);
+    assert!(loaded.query(Duration::from_secs(10)..=Duration::from_secs(10)));
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono_timestamps() {
+    use chrono::{DateTime, Utc};
+
+    let events: Vec<DateTime<Utc>> = [1_700_000_000, 1_700_000_060, 1_700_003_600]
+        .into_iter()
+        .map(|s| DateTime::from_timestamp(s, 0).unwrap())
+        .collect();
+    let hasher = OrderPreservingHasher::new(events.len(), 0.01, 60).unwrap();
+    let rf = TimestampRangeFilter::new(events.iter().copied(), TimeUnit::Seconds, hasher);
+
+    for &event in &events {
+        assert!(rf.query(event..=event));
+    }
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_time_timestamps() {
+    use time::OffsetDateTime;
+
+    let events: Vec<OffsetDateTime> = [1_700_000_000, 1_700_000_060, 1_700_003_600]
+        .into_iter()
+        .map(|s| OffsetDateTime::from_unix_timestamp(s).unwrap())
+        .collect();
+    let hasher = OrderPreservingHasher::new(events.len(), 0.01, 60).unwrap();
+    let rf = TimestampRangeFilter::new(events.iter().copied(), TimeUnit::Seconds, hasher);
+
+    for &event in &events {
+        assert!(rf.query(event..=event));
+    }
+    assert_eq!(
+        TimeUnit::Seconds.encode(&events[0]),
+        TimeUnit::Seconds.encode(&(UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000)))
+    );
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use grafite::{OrderPreservingHasher, RangeFilter};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// A subscriber that records the message of every event and the name of every span.
+#[derive(Default, Clone)]
+struct Recorder {
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl Visit for Recorder {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.messages.lock().unwrap().push(format!("{value:?}"));
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == "grafite"
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let name = format!("span {}", span.metadata().name());
+        self.messages.lock().unwrap().push(name);
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        event.record(&mut self.clone());
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[test]
+fn test_tracing() {
+    let recorder = Recorder::default();
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let values: Vec<u64> = (0..1000).map(|x| x * 1000).collect();
+        let hasher = OrderPreservingHasher::new(values.len(), 0.01, 64).unwrap();
+        let rf = RangeFilter::new(values.into_iter(), hasher);
+
+        for x in 0..10_000 {
+            rf.query(x..x + 10);
+        }
+    });
+
+    let messages = recorder.messages.lock().unwrap();
+    let count = |message: &str| messages.iter().filter(|m| *m == message).count();
+
+    assert_eq!(count("derived hash function parameters"), 1);
+    assert_eq!(count("span build"), 1);
+    assert_eq!(count("finished build phase"), 3);
+
+    // Only a sample of the queries is traced.
+    let sampled = count("sampled query");
+    assert!(0 < sampled && sampled < 100);
+}
@@ -0,0 +1,102 @@
+use grafite::{IntervalChoice, ParamError, Tuning};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_tuning() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let mut keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+    keys.sort_unstable();
+
+    let lengths: Vec<u64> = (0..1000).map(|_| rng.gen_range(1..=64)).collect();
+    let tuning = Tuning::recommend(16 * 1024, keys.len(), &lengths).unwrap();
+    assert_eq!(tuning.bits_per_key, 12);
+    assert_eq!(tuning.max_interval, 64);
+    assert_eq!(tuning.epsilon, 64.0 / 1024.0);
+    assert!(tuning.expected_false_positive_rate < tuning.epsilon);
+
+    let rf = tuning
+        .builder()
+        .seed(7)
+        .build(keys.iter().copied())
+        .unwrap();
+    assert!(rf.heap_size() <= 16 * 1024);
+    for &key in &keys {
+        assert!(rf.contains(key));
+    }
+
+    let estimate = rf.estimate_false_positive_rate(&mut rng, 100_000, 64, &keys);
+    assert!(estimate.lower <= tuning.epsilon);
+}
+
+#[test]
+fn test_tuning_limits() {
+    // The encoding of the hash values fits in the budget, and lengths that would need a false
+    // positive rate of at least `1` are not covered.
+    let tuning = Tuning::recommend(1000, 1000, &[0, 1, 2, 10, 100]).unwrap();
+    assert_eq!(tuning.bits_per_key, 7);
+    assert_eq!(tuning.max_interval, 10);
+    assert_eq!(
+        tuning.expected_false_positive_rate,
+        (1.0 + 2.0 + 10.0 + 32.0) / 32.0 / 4.0
+    );
+
+    // Bits beyond the 64-bit universe are not spent.
+    let tuning = Tuning::recommend(usize::MAX, 1 << 10, &[1]).unwrap();
+    assert_eq!(tuning.bits_per_key, 55);
+
+    assert!(matches!(
+        Tuning::recommend(1000, 0, &[1]),
+        Err(ParamError::NoElements)
+    ));
+    assert!(matches!(
+        Tuning::recommend(1000, 4000, &[1]),
+        Err(ParamError::Overflow)
+    ));
+    assert!(matches!(
+        Tuning::recommend(1000, 1000, &[0]),
+        Err(ParamError::MissingParameter("query_lengths"))
+    ));
+    assert!(matches!(
+        Tuning::recommend(1000, 1000, &[32, 100]),
+        Err(ParamError::InvalidMaxInterval(31))
+    ));
+}
+
+#[test]
+fn test_interval_choice() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let mut queries: Vec<(u64, u64)> = (0..990)
+        .map(|_| {
+            let start = rng.gen_range(0..1 << 40);
+            (start, start + rng.gen_range(0..32))
+        })
+        .collect();
+    queries.extend((0..10).map(|i| (i << 20, (i << 20) + 999)));
+    queries.extend([(5, 4), (u64::MAX, 0)]);
+
+    let choice = IntervalChoice::from_queries(queries.iter().copied(), 0.99).unwrap();
+    assert!(choice.max_interval <= 32);
+    assert_eq!(choice.num_queries, 1000);
+    assert_eq!(choice.num_longer, 10);
+    assert_eq!(choice.covered_fraction(), 0.99);
+    assert_eq!(choice.longest, 1000);
+    assert_eq!(choice.max_chunks, 1000u64.div_ceil(choice.max_interval));
+    assert!(choice.mean_chunks > 1.0);
+
+    let choice = IntervalChoice::from_queries(queries.iter().copied(), 1.0).unwrap();
+    assert_eq!(choice.max_interval, 1000);
+    assert_eq!((choice.num_longer, choice.max_chunks), (0, 1));
+    assert_eq!(choice.mean_chunks, 1.0);
+
+    let choice = IntervalChoice::from_queries([(0, u64::MAX)], 0.5).unwrap();
+    assert_eq!((choice.max_interval, choice.longest), (u64::MAX, u64::MAX));
+
+    assert_eq!(IntervalChoice::from_queries([(5, 4)], 0.99), None);
+}
+
+#[test]
+#[should_panic(expected = "quantile must be in (0, 1]")]
+fn test_interval_choice_invalid_quantile() {
+    IntervalChoice::from_queries([(0, 0)], 0.0);
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "uuid")]
+
+use grafite::{KeyedRangeFilter, OrderPreservingHasher, ToOrderedU64};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use uuid::Uuid;
+
+/// Creates a UUIDv7 with the given timestamp and random bits.
+fn v7(millis: u64, random: u128) -> Uuid {
+    let random = random & ((1 << 76) - 1);
+    Uuid::from_u128(((millis as u128) << 80) | (0x7 << 76) | random)
+}
+
+#[test]
+fn test_uuid_keys() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let start = 1_700_000_000_000;
+    let millis: Vec<u64> = (0..10_000)
+        .map(|_| start + rng.gen_range(0..86_400_000))
+        .collect();
+    let keys: Vec<Uuid> = millis.iter().map(|&ms| v7(ms, rng.gen())).collect();
+
+    // The order of the mapped keys is the order of the timestamps.
+    assert_eq!(keys[0].to_ordered_u64(), millis[0]);
+    assert_eq!(v7(5, 0).to_ordered_u64(), v7(5, u128::MAX).to_ordered_u64());
+
+    let hasher = OrderPreservingHasher::new_with_rng(keys.len(), 0.01, 1000, &mut rng).unwrap();
+    let rf = KeyedRangeFilter::new(keys.iter().copied(), hasher);
+
+    for (&key, &ms) in keys.iter().zip(&millis) {
+        assert!(rf.query(key..=key));
+        assert!(rf.query_millis(ms..=ms));
+        assert!(rf.query_millis(ms - 500..ms + 500));
+    }
+
+    // Timestamps after the end of the 48-bit range cannot match any key.
+    assert!(!rf.query_millis(1 << 48..));
+    assert!(!rf.query_millis(10..10));
+}
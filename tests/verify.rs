@@ -0,0 +1,119 @@
+#![cfg(feature = "verify")]
+
+use grafite::VerifiedRangeFilter;
+use grafite::{InsertBuilder, OrderPreservingHasher, RangeFilter, RangeFilterBuilder};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_query_verified() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen_range(0..1 << 40)).collect();
+
+    let rf = RangeFilterBuilder::new()
+        .epsilon(0.01)
+        .max_interval(1 << 10)
+        .seed(7)
+        .build_verified(keys.iter().copied())
+        .unwrap();
+
+    let mut sorted = keys.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    assert_eq!(rf.keys(), sorted);
+
+    let mut false_positives = 0;
+    for _ in 0..10_000 {
+        let start = rng.gen_range(0..1 << 40);
+        let end = start + rng.gen_range(0..1 << 10);
+
+        let answer = rf.query_verified(start..=end);
+        assert_eq!(answer, rf.query(start..=end));
+        if rf.is_false_positive(start..=end) {
+            false_positives += 1;
+        }
+    }
+    assert!(false_positives < 1000);
+
+    for &key in &keys {
+        assert!(rf.query_verified(key..=key));
+        assert!(!rf.is_false_positive(key..=key));
+    }
+    assert!(!rf.query_verified(5..5));
+
+    // Ranges much longer than the maximum interval are verified against their sub-queries.
+    for &key in keys.iter().take(100) {
+        for width in [1 << 12, 1 << 20] {
+            let range = key.saturating_sub(width)..=key + width;
+            assert!(rf.query_verified(range.clone()));
+            assert!(!rf.is_false_positive(range));
+        }
+    }
+}
+
+#[test]
+fn test_query_verified_across_blocks() {
+    // With a single key, `L = 8` and `epsilon = 0.1`, every block has `r = 80` keys.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let rf = VerifiedRangeFilter::try_new(vec![81], hasher).unwrap();
+    assert!(rf.query_verified(78..=85));
+    assert!(rf.query_verified(0..=100));
+    assert!(!rf.is_false_positive(78..=85));
+
+    // Keys on both sides of many block boundaries.
+    let hasher = OrderPreservingHasher::new_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe();
+    let keys: Vec<u64> = (1..=50).flat_map(|k| [k * r - 1, k * r + 5]).collect();
+    let rf = VerifiedRangeFilter::try_new(keys.clone(), hasher).unwrap();
+
+    for &key in &keys {
+        for start in key - 15..=key {
+            assert!(rf.query_verified(start..=start + 15));
+        }
+        assert!(rf.query_verified(key - 100..=key + 100));
+    }
+}
+
+#[test]
+#[should_panic(expected = "the sub-query 1040..=1047")]
+fn test_false_negative_after_block_boundary_panics() {
+    // Every block has `r = 80` keys, so the sub-queries of `1030..=1050` are `1030..=1037`,
+    // `1038..=1039` and `1040..=1047`.
+    let hasher = OrderPreservingHasher::new_seeded(1, 0.1, 8, 7).unwrap();
+    let filter = RangeFilter::new([1].into_iter(), hasher);
+
+    let rf = VerifiedRangeFilter::new(filter, vec![1, 1_045]);
+    rf.query_verified(1_030..=1_050);
+}
+
+#[test]
+fn test_finish_verified() {
+    let builder: InsertBuilder = [20, 1, 2, 3, 7, 7].into_iter().collect();
+    let hasher = OrderPreservingHasher::new(builder.len(), 0.01, 20).unwrap();
+    let rf = builder.finish_verified(hasher).unwrap();
+
+    assert_eq!(rf.keys(), [1, 2, 3, 7, 20]);
+    assert!(rf.query_verified(3..5));
+    assert!(rf.into_filter().query(20..=20));
+}
+
+#[test]
+#[should_panic(expected = "false negative for the range 1000000..=1000000")]
+fn test_false_negative_panics() {
+    let hasher = OrderPreservingHasher::new_seeded(3, 0.0001, 1, 7).unwrap();
+    let filter = RangeFilter::new([1, 2, 3].into_iter(), hasher);
+
+    // The filter was not built over the last key, so querying it is a false negative.
+    let rf = VerifiedRangeFilter::new(filter, vec![1, 2, 3, 1_000_000]);
+    rf.query_verified(1_000_000..=1_000_000);
+}
+
+#[test]
+#[should_panic(expected = "the sub-query 1000000..=1000000")]
+fn test_false_negative_in_long_range_panics() {
+    let hasher = OrderPreservingHasher::new_seeded(3, 0.0001, 1, 7).unwrap();
+    let filter = RangeFilter::new([1, 2, 3].into_iter(), hasher);
+
+    let rf = VerifiedRangeFilter::new(filter, vec![1, 2, 3, 1_000_000]);
+    rf.query_verified(999_990..=1_000_009);
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "wasm")]
+
+use grafite::{RangeFilter, WasmRangeFilter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_wasm_filter() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen()).collect();
+
+    let rf = WasmRangeFilter::build(&keys, 0.01, 64, 7).unwrap();
+    assert_eq!(rf.len(), rf.filter().iter_hashes().count());
+    assert!(!rf.is_empty());
+
+    for &key in &keys {
+        assert!(rf.contains(key));
+        assert!(rf.query(key.saturating_sub(10), key));
+    }
+
+    let starts: Vec<u64> = keys.iter().map(|&k| k.saturating_sub(5)).collect();
+    assert!(rf
+        .query_many(&starts, &keys)
+        .unwrap()
+        .iter()
+        .all(|&b| b == 1));
+
+    // The same seed produces the same filter, which survives a round trip.
+    let bytes = rf.serialize();
+    assert_eq!(
+        bytes,
+        WasmRangeFilter::build(&keys, 0.01, 64, 7)
+            .unwrap()
+            .serialize()
+    );
+
+    let loaded = WasmRangeFilter::deserialize(&bytes).unwrap();
+    assert_eq!(loaded.filter().seed(), Some(7));
+    assert_eq!(
+        RangeFilter::read_from(bytes.as_slice())
+            .unwrap()
+            .heap_size(),
+        loaded.heap_size()
+    );
+}
@@ -0,0 +1,93 @@
+use grafite::{OrderPreservingHasher, RangeFilter, RangeFilter128};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_hash_u128() {
+    let hasher = OrderPreservingHasher::new_u128_seeded(1000, 0.01, 64, 7).unwrap();
+    assert_eq!(hasher.seed(), Some(7));
+
+    // Keys that fit in 64 bits hash exactly like 64-bit keys.
+    for x in [0, 1, 12345, u64::MAX as u128 - 1, u64::MAX as u128] {
+        assert_eq!(hasher.hash_u128(x), hasher.hash(x as u64));
+    }
+
+    // Consecutive keys within a block of the reduced universe have consecutive hashes.
+    let r = hasher.reduced_universe() as u128;
+    let base = (1u128 << 100) / r * r;
+    for offset in 0..100 {
+        let hash = hasher.hash_u128(base + offset);
+        assert!(hash < hasher.reduced_universe());
+        assert_eq!(
+            hasher.hash_u128(base + offset + 1),
+            (hash + 1) % hasher.reduced_universe()
+        );
+    }
+}
+
+#[test]
+fn test_no_false_negatives() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    let keys: Vec<u128> = (0..10_000)
+        .map(|_| (rng.gen::<u128>() >> 4) + (1 << 120))
+        .collect();
+    let hasher = OrderPreservingHasher::new_u128_with_rng(keys.len(), 0.01, 64, &mut rng).unwrap();
+    let rf = RangeFilter128::new(keys.iter().copied(), hasher);
+
+    for &key in &keys {
+        assert!(rf.query(key..=key));
+        assert!(rf.query(key.saturating_sub(10)..key.saturating_add(10)));
+    }
+    assert!(rf.query(..));
+    assert!(!rf.query(5..5));
+
+    // The hash values can be persisted through the inner filter.
+    let mut bytes = Vec::new();
+    rf.filter().write_to(&mut bytes).unwrap();
+    let loaded = RangeFilter128::from_filter(RangeFilter::read_from(bytes.as_slice()).unwrap());
+    for &key in keys.iter().step_by(10) {
+        assert!(loaded.query(key..=key));
+    }
+}
+
+#[test]
+fn test_false_positive_rate() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+
+    let keys: Vec<u128> = (0..10_000).map(|_| rng.gen()).collect();
+    let hasher = OrderPreservingHasher::new_u128_with_rng(keys.len(), 0.01, 32, &mut rng).unwrap();
+    let rf = RangeFilter128::new(keys.iter().copied(), hasher);
+
+    let trials = 100_000;
+    let false_positives = (0..trials)
+        .filter(|_| {
+            let start: u128 = rng.gen();
+            rf.query(start..start.saturating_add(32))
+        })
+        .count();
+
+    assert!((false_positives as f64) < 0.02 * trials as f64);
+}
+
+#[test]
+fn test_query_across_blocks() {
+    let hasher = OrderPreservingHasher::new_u128_seeded(100, 0.01, 16, 3).unwrap();
+    let r = hasher.reduced_universe() as u128;
+
+    // Keys right before and after block boundaries far beyond 64 bits.
+    let base = (1u128 << 100) / r * r;
+    let keys: Vec<u128> = (1..=50)
+        .flat_map(|k| [base + k * r - 1, base + k * r + 5])
+        .collect();
+    let rf = RangeFilter128::new(keys.iter().copied(), hasher);
+
+    // Every range of up to 16 keys around a key contains it, whichever block it starts in.
+    for &key in &keys {
+        for start in key - 15..=key {
+            for end in key..start + 16 {
+                assert!(rf.query(start..=end), "{start}..={end}");
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use grafite::workloads::{QueryDistribution, RangeLength, WorkloadGenerator};
+use grafite::{OrderPreservingHasher, RangeFilter};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn test_workloads() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+
+    for (distribution, length) in [
+        (QueryDistribution::Uniform, RangeLength::Fixed(1)),
+        (
+            QueryDistribution::Correlated {
+                max_distance: 1 << 10,
+            },
+            RangeLength::Fixed(1 << 5),
+        ),
+        (
+            QueryDistribution::Adversarial,
+            RangeLength::Uniform(1..=1 << 10),
+        ),
+    ] {
+        let workload = WorkloadGenerator::new(10_000, 10_000)
+            .query_distribution(distribution)
+            .range_length(length.clone())
+            .generate(&mut rng);
+
+        assert_eq!(workload.keys.len(), 10_000);
+        assert_eq!(workload.queries.len(), 10_000);
+        assert!(workload.keys.windows(2).all(|w| w[0] < w[1]));
+
+        for &(start, end) in &workload.queries {
+            assert!(start <= end);
+            match length {
+                RangeLength::Fixed(len) => assert_eq!(end - start + 1, len),
+                RangeLength::Uniform(ref lengths) => assert!(lengths.contains(&(end - start + 1))),
+            }
+
+            // No key is within the range.
+            let i = workload.keys.partition_point(|&key| key < start);
+            assert!(workload.keys.get(i).is_none_or(|&key| key > end));
+
+            match distribution {
+                QueryDistribution::Uniform => {}
+                QueryDistribution::Correlated { max_distance } => {
+                    assert!(start - workload.keys[i - 1] <= max_distance)
+                }
+                QueryDistribution::Adversarial => assert_eq!(start - workload.keys[i - 1], 1),
+            }
+        }
+
+        let hasher = OrderPreservingHasher::new_with_rng(10_000, 0.01, 1 << 10, &mut rng).unwrap();
+        let rf = RangeFilter::new(workload.keys.iter().copied(), hasher);
+        assert!(workload.false_positive_rate(&rf) < 0.02);
+    }
+}
+
+#[test]
+fn test_dense_workload() {
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+
+    // Queries covering the whole universe always contain a key, so none can be generated.
+    let workload = WorkloadGenerator::new(100, 10)
+        .range_length(RangeLength::Fixed(u64::MAX))
+        .generate(&mut rng);
+    assert!(workload.queries.is_empty());
+
+    let hasher = OrderPreservingHasher::new_with_reduced(1 << 20);
+    let rf = RangeFilter::new(workload.keys.iter().copied(), hasher);
+    assert_eq!(workload.false_positive_rate(&rf), 0.0);
+}